@@ -1,35 +1,682 @@
-use crate::config::Config;
-use anyhow::Result;
-use tracing::info;
+use crate::analysis::AnalysisEngine;
+use crate::backup::{BackupManager, BackupReason};
+use crate::candidates::{self, Candidate};
+use crate::categorize::{
+    self, CategorizationReport, Categorizer, CategorySuggestion, ReviewItem, ReviewStatus,
+};
+use crate::classifier::{self, LocalCategorizer, NaiveBayes, TrainingReport};
+use crate::config::{CategorizerEngine, Config};
+use crate::data::{FinancialData, Transaction};
+use crate::events::{AgentEvent, EventBus};
+use crate::merge::{MergeEngine, MergeOptions, MergeResult};
+use crate::notifications::NotificationCenter;
+use crate::plugin::{Plugin, PluginRegistry};
+use crate::quicken::{InvalidQif, QifImporter};
+use crate::scheduler::{Clock, Scheduler, SystemClock};
+use crate::storage::Database;
+use crate::watcher::{shutdown_signal, ImportWatcher};
+use anyhow::{bail, Context, Result};
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info, warn};
+use uuid::Uuid;
 
 /// Main Financial Agent that orchestrates all financial operations
 pub struct FinancialAgent {
     config: Config,
+    clock: Arc<dyn Clock>,
+    events: EventBus,
+    plugins: PluginRegistry,
+    notifications: NotificationCenter,
+    categorizer: Option<Arc<dyn Categorizer>>,
 }
 
 impl FinancialAgent {
     /// Create a new Financial Agent instance
     pub async fn new() -> Result<Self> {
         let config = Config::load().await?;
+        Ok(Self::with_config(config))
+    }
 
+    /// Create a Financial Agent from an explicit configuration
+    pub fn with_config(config: Config) -> Self {
         info!("Initializing QSpec Financial Agent");
 
-        Ok(Self { config })
+        Self {
+            notifications: NotificationCenter::from_config(&config.notifications),
+            categorizer: Self::default_categorizer(&config),
+            config,
+            clock: Arc::new(SystemClock),
+            events: EventBus::default(),
+            plugins: PluginRegistry::new(),
+        }
+    }
+
+    /// Categorizer chosen by `categorization.engine`
+    fn default_categorizer(config: &Config) -> Option<Arc<dyn Categorizer>> {
+        let local = || Arc::new(LocalCategorizer::new()) as Arc<dyn Categorizer>;
+        match config.categorization.engine {
+            CategorizerEngine::Local => Some(local()),
+            CategorizerEngine::Llm => Self::llm_categorizer(config),
+            CategorizerEngine::Auto => Self::llm_categorizer(config).or_else(|| Some(local())),
+        }
+    }
+
+    #[cfg(feature = "ai-analysis")]
+    fn llm_categorizer(config: &Config) -> Option<Arc<dyn Categorizer>> {
+        match crate::ai::provider_from_config(config) {
+            Ok(provider) => provider.map(|provider| {
+                Arc::new(crate::ai::LlmCategorizer::new(
+                    provider,
+                    config.categorization.history_examples,
+                )) as Arc<dyn Categorizer>
+            }),
+            Err(e) => {
+                warn!("AI categorization unavailable: {:#}", e);
+                None
+            }
+        }
+    }
+
+    #[cfg(not(feature = "ai-analysis"))]
+    fn llm_categorizer(_config: &Config) -> Option<Arc<dyn Categorizer>> {
+        None
+    }
+
+    /// Use a specific categorizer instead of the one chosen by `categorization.engine`
+    pub fn with_categorizer(mut self, categorizer: Arc<dyn Categorizer>) -> Self {
+        self.categorizer = Some(categorizer);
+        self
+    }
+
+    /// Load a plugin; it receives every event published after this point
+    pub fn with_plugin(mut self, plugin: impl Plugin + 'static) -> Self {
+        self.plugins.register(plugin);
+        self
+    }
+
+    /// Replace the loaded plugins
+    pub fn with_plugins(mut self, plugins: PluginRegistry) -> Self {
+        self.plugins = plugins;
+        self
+    }
+
+    /// Use a different time source for scheduling
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Agent configuration
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Time source used for scheduled jobs and backups
+    pub fn clock(&self) -> Arc<dyn Clock> {
+        self.clock.clone()
+    }
+
+    /// Event stream; call `subscribe` to receive events
+    pub fn events(&self) -> &EventBus {
+        &self.events
+    }
+
+    pub fn plugins(&self) -> &PluginRegistry {
+        &self.plugins
+    }
+
+    /// Categorizer used by `categorize`; `None` when the LLM engine is chosen but unavailable
+    pub fn categorizer(&self) -> Option<&Arc<dyn Categorizer>> {
+        self.categorizer.as_ref()
+    }
+
+    /// Send an event to subscribers, each plugin in turn and the notification channels
+    pub async fn publish(&self, event: AgentEvent) {
+        self.events.publish(event.clone());
+        self.plugins.dispatch(&event).await;
+        self.notifications.notify(&event, self.clock.now()).await;
+    }
+
+    /// Open the configured database
+    pub async fn open_database(&self) -> Result<Database> {
+        Database::open(&self.config.database).await
+    }
+
+    /// Load the books from the configured database
+    pub async fn load_data(&self) -> Result<FinancialData> {
+        let database = self.open_database().await?;
+        let data = database.load_data().await;
+        database.close().await;
+        data
+    }
+
+    /// Take a backup of the database if backups are enabled
+    pub async fn backup(&self, database: &Database, reason: BackupReason) -> Result<()> {
+        if self.config.database.backup.enabled {
+            BackupManager::new(&self.config.database)
+                .create_backup(database, reason, self.clock.now())
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Re-encrypt the database under a new passphrase, encrypting a plaintext store
+    pub async fn rotate_key(&self, new_passphrase: &str) -> Result<()> {
+        let mut database = self.open_for_rekey().await?;
+        let result = database
+            .rotate_key(new_passphrase, self.config.database.encryption.kdf)
+            .await;
+        database.close().await;
+        result
+    }
+
+    /// Decrypt the database in place
+    pub async fn remove_encryption(&self) -> Result<()> {
+        let mut database = self.open_for_rekey().await?;
+        let result = database.remove_encryption().await;
+        database.close().await;
+        result
     }
 
-    /// Run the main agent loop
+    /// Open the store as it is, whether or not the configuration expects it encrypted
+    async fn open_for_rekey(&self) -> Result<Database> {
+        let mut config = self.config.database.clone();
+        config.encryption.enabled = false;
+        let passphrase = config.encryption.resolve_passphrase()?;
+        Database::open_with_passphrase(&config, passphrase.as_deref()).await
+    }
+
+    /// Import a QIF file into the database, returning the number of transactions added
+    ///
+    /// Publishes `TransactionImported` for each new transaction and
+    /// `BudgetExceeded` for budgets the import pushed over, or `ImportFailed`.
+    pub async fn import_file<P: AsRef<Path>>(&self, path: P) -> Result<usize> {
+        let path = path.as_ref();
+        match self.import_into_database(path).await {
+            Ok(events) => {
+                let count = events
+                    .iter()
+                    .filter(|e| matches!(e, AgentEvent::TransactionImported { .. }))
+                    .count();
+                for event in events {
+                    self.publish(event).await;
+                }
+                info!("Imported {} transactions from {}", count, path.display());
+
+                if count > 0 && self.categorizer.is_some() {
+                    // The import itself succeeded; a categorizer outage only delays suggestions
+                    if let Err(e) = self.after_import().await {
+                        warn!("Categorization after import failed: {:#}", e);
+                    }
+                }
+                Ok(count)
+            }
+            Err(e) => {
+                self.publish(AgentEvent::ImportFailed {
+                    path: path.to_path_buf(),
+                    error: format!("{:#}", e),
+                })
+                .await;
+                Err(e)
+            }
+        }
+    }
+
+    async fn import_into_database(&self, path: &Path) -> Result<Vec<AgentEvent>> {
+        let passphrase = self.config.database.encryption.resolve_passphrase()?;
+        let imported =
+            QifImporter::import_file_with_passphrase(path, passphrase.as_deref()).await?;
+
+        let database = self.open_database().await?;
+        self.backup(&database, BackupReason::Import).await?;
+
+        // Re-importing a file matches its accounts and skips transactions already present;
+        // the file is the newer statement, so its balances win
+        let base = database.load_data().await?;
+        let options = MergeOptions {
+            take_incoming_balances: true,
+            ..MergeOptions::default()
+        };
+        let MergeResult { data, report } = MergeEngine::merge(&base, &imported, &options)?;
+        for conflict in &report.conflicts {
+            warn!(
+                "Import of {}: account {}: {}",
+                path.display(),
+                conflict.account,
+                conflict.detail
+            );
+        }
+        if report.duplicate_transactions > 0 {
+            info!(
+                "Skipped {} transactions already in the books",
+                report.duplicate_transactions
+            );
+        }
+        let incoming: HashSet<_> = imported.transactions.iter().map(|t| t.id).collect();
+        let added: Vec<&Transaction> = data
+            .transactions
+            .iter()
+            .filter(|t| incoming.contains(&t.id))
+            .collect();
+        let new_ids: HashSet<_> = added.iter().map(|t| t.id).collect();
+        let mut events: Vec<AgentEvent> = added
+            .into_iter()
+            .map(|transaction| AgentEvent::TransactionImported {
+                transaction: transaction.clone(),
+                source: path.to_path_buf(),
+            })
+            .collect();
+        database.save_data(&data).await?;
+        database.close().await;
+
+        events.extend(
+            AnalysisEngine::budget_overruns(&data, &self.config.budgets, &new_ids)
+                .into_iter()
+                .map(|overrun| AgentEvent::BudgetExceeded {
+                    category: overrun.category,
+                    year: overrun.year,
+                    month: overrun.month,
+                    budget: overrun.budget,
+                    spent: overrun.spent,
+                }),
+        );
+        Ok(events)
+    }
+
+    /// Let the categorizer learn from the import, then suggest if `on_import` is set
+    async fn after_import(&self) -> Result<()> {
+        let Some(categorizer) = self.categorizer.clone() else {
+            return Ok(());
+        };
+        let database = self.open_database().await?;
+        let result = async {
+            if self.config.categorization.on_import {
+                self.categorize_with(&database, categorizer.as_ref())
+                    .await?;
+            } else {
+                let data = database.load_data().await?;
+                categorizer.learn(&database, &data).await?;
+            }
+            Ok(())
+        }
+        .await;
+        database.close().await;
+        result
+    }
+
+    /// Retrain the local categorizer from scratch and measure its held-out accuracy
+    ///
+    /// Incremental updates keep the model current between runs; a full retrain
+    /// is only needed to refresh the accuracy figure.
+    pub async fn train_categorizer(&self) -> Result<TrainingReport> {
+        let database = self.open_database().await?;
+        let result = async {
+            let data = database.load_data().await?;
+            let model = NaiveBayes::train(
+                &data,
+                self.config.categorization.holdout_fraction,
+                self.clock.now(),
+            );
+            database.save_model(classifier::MODEL_NAME, &model).await?;
+            Ok(model.report())
+        }
+        .await;
+        database.close().await;
+        result
+    }
+
+    /// Suggest categories for uncategorized transactions
+    ///
+    /// Suggestions at or above `categorization.auto_apply_threshold` are applied
+    /// after a `RuleRun` backup; the rest wait in the review queue.
+    /// Transactions already reviewed are not sent again.
+    pub async fn categorize(&self) -> Result<CategorizationReport> {
+        let categorizer = self
+            .categorizer
+            .clone()
+            .context("No categorizer available; the llm engine needs a working [ai] setup")?;
+        let database = self.open_database().await?;
+        let result = self.categorize_with(&database, categorizer.as_ref()).await;
+        database.close().await;
+        result
+    }
+
+    async fn categorize_with(
+        &self,
+        database: &Database,
+        categorizer: &dyn Categorizer,
+    ) -> Result<CategorizationReport> {
+        let settings = &self.config.categorization;
+        let mut data = database.load_data().await?;
+        categorizer.learn(database, &data).await?;
+        let reviewed: HashSet<_> = database
+            .reviews(None)
+            .await?
+            .into_iter()
+            .map(|item| item.suggestion.transaction_id)
+            .collect();
+        let pending = categorize::uncategorized(&data, &reviewed);
+
+        let mut suggestions = Vec::new();
+        for batch in pending.chunks(settings.batch_size.max(1)) {
+            suggestions.extend(categorizer.suggest(&data, batch).await?);
+        }
+        let now = self.clock.now();
+        let items =
+            categorize::review_items(&data, suggestions, settings.auto_apply_threshold, now);
+
+        if items.iter().any(|i| i.status == ReviewStatus::AutoApplied) {
+            self.backup(database, BackupReason::RuleRun).await?;
+        }
+        for item in &items {
+            if item.status == ReviewStatus::AutoApplied {
+                if let Some(transaction) = categorize::apply(&mut data, &item.suggestion, now) {
+                    database.upsert_transaction(transaction).await?;
+                }
+            }
+            database.save_review(item).await?;
+        }
+
+        let report = CategorizationReport::new(categorizer.name(), pending.len(), items);
+        info!(
+            "{} suggested categories for {} transactions: {} applied, {} queued for review",
+            report.categorizer, report.considered, report.auto_applied, report.queued
+        );
+        Ok(report)
+    }
+
+    /// Suggestions waiting for review
+    pub async fn pending_reviews(&self) -> Result<Vec<ReviewItem>> {
+        let database = self.open_database().await?;
+        let pending = database.reviews(Some(ReviewStatus::Pending)).await;
+        database.close().await;
+        pending
+    }
+
+    /// Accept or reject suggestions from the review queue
+    ///
+    /// Accepting sets the categories after a `RuleRun` backup.
+    pub async fn decide_reviews(
+        &self,
+        mut items: Vec<ReviewItem>,
+        accept: bool,
+    ) -> Result<Vec<ReviewItem>> {
+        if items.is_empty() {
+            return Ok(items);
+        }
+        let database = self.open_database().await?;
+        let result = async {
+            let now = self.clock.now();
+            let status = if accept {
+                let mut data = database.load_data().await?;
+                self.backup(&database, BackupReason::RuleRun).await?;
+                for item in &items {
+                    if let Some(transaction) = categorize::apply(&mut data, &item.suggestion, now) {
+                        database.upsert_transaction(transaction).await?;
+                    }
+                }
+                ReviewStatus::Accepted
+            } else {
+                ReviewStatus::Rejected
+            };
+            for item in &mut items {
+                item.decide(status, now);
+                database.save_review(item).await?;
+            }
+            Ok(())
+        }
+        .await;
+        database.close().await;
+        result.map(|()| items)
+    }
+
+    /// Queue proposed transactions for review, noting likely duplicates
+    ///
+    /// Transactions whose splits do not add up to their amount are refused.
+    pub async fn queue_candidates(
+        &self,
+        transactions: Vec<Transaction>,
+        source: &str,
+        extractor: &str,
+    ) -> Result<Vec<Candidate>> {
+        for transaction in &transactions {
+            candidates::check_splits(transaction)?;
+        }
+        let database = self.open_database().await?;
+        let result = async {
+            let data = database.load_data().await?;
+            let now = self.clock.now();
+            let mut queued = Vec::new();
+            for transaction in transactions {
+                let candidate = Candidate::new(&data, transaction, source, extractor, now);
+                database.save_candidate(&candidate).await?;
+                queued.push(candidate);
+            }
+            Ok(queued)
+        }
+        .await;
+        database.close().await;
+        result
+    }
+
+    /// Proposed transactions waiting for review
+    pub async fn pending_candidates(&self) -> Result<Vec<Candidate>> {
+        let database = self.open_database().await?;
+        let pending = database.candidates(Some(ReviewStatus::Pending)).await;
+        database.close().await;
+        pending
+    }
+
+    /// Accept or reject proposed transactions
+    ///
+    /// Accepting merges them into the books after an `Import` backup, the same
+    /// way an import does; candidates already in the books are not added twice.
+    pub async fn decide_candidates(
+        &self,
+        mut items: Vec<Candidate>,
+        accept: bool,
+    ) -> Result<Vec<Candidate>> {
+        if items.is_empty() {
+            return Ok(items);
+        }
+        let database = self.open_database().await?;
+        let result: Result<Vec<Transaction>> = async {
+            let now = self.clock.now();
+            let mut added = Vec::new();
+            let status = if accept {
+                let base = database.load_data().await?;
+                let accounts: HashSet<_> = base.accounts.iter().map(|a| a.id).collect();
+                if let Some(item) = items
+                    .iter()
+                    .find(|item| !accounts.contains(&item.transaction.account_id))
+                {
+                    bail!(
+                        "Candidate {} belongs to an account that is not in the books",
+                        item.short_id()
+                    );
+                }
+                let mut incoming = FinancialData::new();
+                for item in &items {
+                    incoming.add_transaction(item.transaction.clone());
+                }
+                let MergeResult { data, report } =
+                    MergeEngine::merge(&base, &incoming, &MergeOptions::default())?;
+                if report.duplicate_transactions > 0 {
+                    info!(
+                        "Skipped {} candidates already in the books",
+                        report.duplicate_transactions
+                    );
+                }
+                let ids: HashSet<_> = items.iter().map(|item| item.transaction.id).collect();
+                added = data
+                    .transactions
+                    .iter()
+                    .filter(|t| ids.contains(&t.id))
+                    .cloned()
+                    .collect();
+                self.backup(&database, BackupReason::Import).await?;
+                database.save_data(&data).await?;
+                ReviewStatus::Accepted
+            } else {
+                ReviewStatus::Rejected
+            };
+            for item in &mut items {
+                item.decide(status, now);
+                database.save_candidate(item).await?;
+            }
+            Ok(added)
+        }
+        .await;
+        database.close().await;
+        let added = result?;
+
+        let sources: HashMap<Uuid, String> = items
+            .iter()
+            .map(|item| (item.transaction.id, item.source.clone()))
+            .collect();
+        for transaction in added {
+            let source = sources.get(&transaction.id).cloned().unwrap_or_default();
+            self.publish(AgentEvent::TransactionImported {
+                transaction,
+                source: source.into(),
+            })
+            .await;
+        }
+        Ok(items)
+    }
+
+    /// Set one transaction's category after a `RuleRun` backup
+    pub async fn set_category(&self, transaction_id: Uuid, category: &str) -> Result<()> {
+        let database = self.open_database().await?;
+        let result = async {
+            let mut data = database.load_data().await?;
+            let suggestion = CategorySuggestion {
+                transaction_id,
+                category: category.to_string(),
+                confidence: 1.0,
+                rationale: String::new(),
+                source: "manual".to_string(),
+            };
+            if !data.transactions.iter().any(|t| t.id == transaction_id) {
+                bail!("No transaction with id {}", transaction_id);
+            }
+            self.backup(&database, BackupReason::RuleRun).await?;
+            if let Some(transaction) = categorize::apply(&mut data, &suggestion, self.clock.now()) {
+                database.upsert_transaction(transaction).await?;
+            }
+            Ok(())
+        }
+        .await;
+        database.close().await;
+        result
+    }
+
+    /// Run the main agent loop until SIGINT or SIGTERM
     pub async fn run(&self) -> Result<()> {
+        self.run_until(shutdown_signal()).await
+    }
+
+    /// Run the main agent loop until `shutdown` resolves
+    ///
+    /// Watches the Quicken directory, imports matching files once their writes
+    /// have settled and archives them into `processed/` or `failed/`, and runs
+    /// scheduled jobs as they come due.
+    pub async fn run_until<F>(&self, shutdown: F) -> Result<()>
+    where
+        F: Future<Output = ()>,
+    {
         info!("Starting QSpec Financial Agent");
+        if !self.plugins.is_empty() {
+            info!("Loaded plugins: {}", self.plugins.names().join(", "));
+        }
+        tokio::pin!(shutdown);
 
-        // TODO: Implement main agent logic
-        // - Monitor for new Quicken data
-        // - Process financial transactions
-        // - Perform AI analysis
-        // - Generate reports and insights
+        self.config.check_paths()?;
+        let mut watcher = if self.config.quicken.auto_import {
+            Some(ImportWatcher::new(&self.config.quicken).await?)
+        } else {
+            info!("Auto-import disabled");
+            None
+        };
+        let scheduler = if self.config.scheduler.enabled {
+            Some(Scheduler::new(&self.config.scheduler, self.clock.clone())?)
+        } else {
+            None
+        };
+        let backups = BackupManager::new(&self.config.database);
+        let tick = Duration::from_millis(self.config.quicken.poll_interval_ms.max(1));
 
-        info!("QSpec Financial Agent running successfully");
+        loop {
+            if let Some(ref mut watcher) = watcher {
+                if let Err(e) = self.import_ready(watcher).await {
+                    warn!("Auto-import failed: {:#}", e);
+                }
+            }
+
+            if let Some(ref scheduler) = scheduler {
+                if let Err(e) = scheduler.run_due(self).await {
+                    warn!("Scheduled jobs failed: {:#}", e);
+                }
+            }
+
+            if let Err(e) = self.scheduled_backup(&backups).await {
+                warn!("Scheduled backup failed: {:#}", e);
+            }
+            self.notifications.flush_due(self.clock.now()).await;
+
+            tokio::select! {
+                _ = &mut shutdown => break,
+                _ = async {
+                    match watcher.as_mut() {
+                        Some(watcher) => watcher.wait().await,
+                        None => tokio::time::sleep(tick).await,
+                    }
+                } => {}
+            }
+        }
+
+        self.notifications.flush_all(self.clock.now()).await;
+        info!("QSpec Financial Agent stopped");
+        Ok(())
+    }
+
+    async fn import_ready(&self, watcher: &mut ImportWatcher) -> Result<()> {
+        for path in watcher.scan().await? {
+            let success = match self.import_file(&path).await {
+                Ok(_) => true,
+                Err(e) if e.downcast_ref::<InvalidQif>().is_some() => {
+                    error!("Failed to import {}: {:#}", path.display(), e);
+                    false
+                }
+                Err(e) => {
+                    // A missing passphrase or a busy database can clear up; keep the file
+                    warn!("Could not import {} yet: {:#}", path.display(), e);
+                    watcher.retry_later(&path);
+                    continue;
+                }
+            };
+            match watcher.archive(&path, success).await {
+                Ok(archived) => info!("Moved {} to {}", path.display(), archived.display()),
+                Err(e) => error!("Failed to archive {}: {:#}", path.display(), e),
+            }
+        }
         Ok(())
     }
+
+    async fn scheduled_backup(&self, backups: &BackupManager) -> Result<()> {
+        // Opening an encrypted store derives its key, so only open it when a backup is due
+        if !backups.is_due(self.clock.now()).await? {
+            return Ok(());
+        }
+        let database = self.open_database().await?;
+        let result = backups.backup_if_due(&database, self.clock.now()).await;
+        database.close().await;
+        result.map(|_| ())
+    }
 }
 
 #[cfg(test)]
@@ -38,20 +685,453 @@ mod tests {
 
     #[tokio::test]
     async fn test_financial_agent_new() {
-        // Test agent creation
-        let result = FinancialAgent::new().await;
-        assert!(
-            result.is_ok(),
-            "Failed to create FinancialAgent: {:?}",
-            result.err()
-        );
+        // Built-in defaults only, so the test never reads the real home directory
+        let config = crate::config::ConfigLoader::new().load().unwrap().config;
+        let agent = FinancialAgent::with_config(config);
+        assert!(agent.plugins().is_empty());
+        assert!(agent.config().quicken.auto_import);
+    }
+
+    #[tokio::test]
+    async fn test_import_file_takes_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            database: crate::storage::test_database_config(dir.path(), false),
+            ..Config::default()
+        };
+        let agent = FinancialAgent::with_config(config);
+
+        let qif = dir.path().join("import.qif");
+        tokio::fs::write(
+            &qif,
+            "!Account\nNChecking\nTBank\n^\n!Type:Bank\nD1/5/2026\nT-12.00\nPCafe\n^\n",
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(agent.import_file(&qif).await.unwrap(), 1);
+        // Importing the same file again matches the account and skips the transaction
+        assert_eq!(agent.import_file(&qif).await.unwrap(), 0);
+
+        // A newer statement for the same account brings its balance along
+        let newer = dir.path().join("newer.qif");
+        tokio::fs::write(
+            &newer,
+            "!Account\nNChecking\nTBank\n$488.00\n^\n!Type:Bank\nD1/5/2026\nT-12.00\nPCafe\n^\n",
+        )
+        .await
+        .unwrap();
+        assert_eq!(agent.import_file(&newer).await.unwrap(), 0);
+
+        let database = agent.open_database().await.unwrap();
+        let data = database.load_data().await.unwrap();
+        assert_eq!(data.accounts.len(), 1);
+        assert_eq!(data.accounts[0].balance, rust_decimal_macros::dec!(488.00));
+        assert_eq!(data.transactions.len(), 1);
+
+        let backups = BackupManager::new(&agent.config().database)
+            .list_backups()
+            .await
+            .unwrap();
+        assert_eq!(backups.len(), 3);
     }
 
     #[tokio::test]
     async fn test_financial_agent_run() {
         // Test agent execution
-        let agent = FinancialAgent::new().await.expect("Failed to create agent");
-        let result = agent.run().await;
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config {
+            database: crate::storage::test_database_config(dir.path(), false),
+            ..Config::default()
+        };
+        config.quicken.watch_directory = dir.path().join("inbox");
+        config.quicken.settle_ms = 0;
+        config.quicken.poll_interval_ms = 10;
+        let agent = FinancialAgent::with_config(config);
+
+        let missing = agent.run_until(async {}).await.unwrap_err();
+        assert!(missing.to_string().contains("quicken.watch_directory"));
+
+        std::fs::create_dir_all(dir.path().join("inbox")).unwrap();
+        let result = agent.run_until(async {}).await;
         assert!(result.is_ok(), "Agent run failed: {:?}", result.err());
     }
+
+    #[tokio::test]
+    async fn test_run_imports_and_archives_files() {
+        use crate::watcher::{FAILED_DIR, PROCESSED_DIR};
+        use std::time::Duration;
+
+        let dir = tempfile::tempdir().unwrap();
+        let inbox = dir.path().join("inbox");
+        let mut config = Config {
+            database: crate::storage::test_database_config(dir.path(), false),
+            ..Config::default()
+        };
+        config.quicken.watch_directory = inbox.clone();
+        config.quicken.settle_ms = 0;
+        config.quicken.poll_interval_ms = 20;
+        let agent = FinancialAgent::with_config(config);
+
+        std::fs::create_dir_all(&inbox).unwrap();
+        std::fs::write(
+            inbox.join("good.qif"),
+            "!Account\nNChecking\nTBank\n^\n!Type:Bank\nD1/5/2026\nT-12.00\nPCafe\n^\n",
+        )
+        .unwrap();
+        std::fs::write(
+            inbox.join("bad.qif"),
+            "!Type:Bank\nDnot a date\nT-1.00\n^\n",
+        )
+        .unwrap();
+        std::fs::write(inbox.join("notes.txt"), "ignored").unwrap();
+
+        let done = {
+            let inbox = inbox.clone();
+            async move {
+                while !(inbox.join(PROCESSED_DIR).join("good.qif").exists()
+                    && inbox.join(FAILED_DIR).join("bad.qif").exists())
+                {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(10), agent.run_until(done))
+            .await
+            .expect("files were not archived in time")
+            .unwrap();
+
+        assert!(inbox.join("notes.txt").exists());
+        let database = agent.open_database().await.unwrap();
+        assert_eq!(database.load_data().await.unwrap().transactions.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_run_leaves_files_it_cannot_import_yet() {
+        use crate::watcher::FAILED_DIR;
+        use std::time::Duration;
+
+        let dir = tempfile::tempdir().unwrap();
+        let inbox = dir.path().join("inbox");
+        let mut config = Config {
+            database: crate::storage::test_database_config(dir.path(), true),
+            ..Config::default()
+        };
+        config.database.encryption.passphrase_env = "QSPEC_TEST_UNSET_PASSPHRASE".to_string();
+        config.quicken.watch_directory = inbox.clone();
+        config.quicken.settle_ms = 0;
+        config.quicken.poll_interval_ms = 20;
+        let agent = FinancialAgent::with_config(config);
+        std::fs::create_dir_all(&inbox).unwrap();
+        std::fs::write(
+            inbox.join("bank.qif"),
+            "!Account\nNChecking\nTBank\n^\n!Type:Bank\nD1/5/2026\nT-12.00\nPCafe\n^\n",
+        )
+        .unwrap();
+
+        // No passphrase is set, so every attempt fails until one is provided
+        agent
+            .run_until(tokio::time::sleep(Duration::from_millis(300)))
+            .await
+            .unwrap();
+        assert!(inbox.join("bank.qif").exists());
+        assert_eq!(
+            std::fs::read_dir(inbox.join(FAILED_DIR)).unwrap().count(),
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_survives_unreadable_watch_directory() {
+        use crate::watcher::PROCESSED_DIR;
+        use std::time::Duration;
+
+        let dir = tempfile::tempdir().unwrap();
+        let inbox = dir.path().join("inbox");
+        let mut config = Config {
+            database: crate::storage::test_database_config(dir.path(), false),
+            ..Config::default()
+        };
+        config.quicken.watch_directory = inbox.clone();
+        config.quicken.settle_ms = 0;
+        config.quicken.poll_interval_ms = 20;
+        let agent = FinancialAgent::with_config(config);
+        std::fs::create_dir_all(&inbox).unwrap();
+
+        let done = {
+            let inbox = inbox.clone();
+            let away = dir.path().join("away");
+            async move {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                std::fs::rename(&inbox, &away).unwrap();
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                std::fs::rename(&away, &inbox).unwrap();
+                std::fs::write(
+                    inbox.join("late.qif"),
+                    "!Account\nNChecking\nTBank\n^\n!Type:Bank\nD1/5/2026\nT-12.00\nPCafe\n^\n",
+                )
+                .unwrap();
+                while !inbox.join(PROCESSED_DIR).join("late.qif").exists() {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(10), agent.run_until(done))
+            .await
+            .expect("the file was not imported after the directory came back")
+            .unwrap();
+    }
+
+    /// Records the names of the events it is given
+    struct Recorder(Arc<std::sync::Mutex<Vec<&'static str>>>);
+
+    #[async_trait::async_trait]
+    impl Plugin for Recorder {
+        fn name(&self) -> &str {
+            "recorder"
+        }
+
+        async fn on_event(&self, event: &AgentEvent) -> Result<()> {
+            self.0.lock().unwrap().push(event.name());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_import_publishes_events() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config {
+            database: crate::storage::test_database_config(dir.path(), false),
+            ..Config::default()
+        };
+        config
+            .budgets
+            .insert("Dining".to_string(), rust_decimal_macros::dec!(20));
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let agent = FinancialAgent::with_config(config).with_plugin(Recorder(seen.clone()));
+        let mut events = agent.events().subscribe();
+
+        let qif = dir.path().join("import.qif");
+        tokio::fs::write(
+            &qif,
+            "!Account\nNChecking\nTBank\n^\n!Type:Bank\nD1/5/2026\nT-12.00\nPCafe\nLDining\n^\nD1/9/2026\nT-15.00\nPBistro\nLDining\n^\n",
+        )
+        .await
+        .unwrap();
+        agent.import_file(&qif).await.unwrap();
+        assert!(agent
+            .import_file(dir.path().join("missing.qif"))
+            .await
+            .is_err());
+
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![
+                "transaction_imported",
+                "transaction_imported",
+                "budget_exceeded",
+                "import_failed"
+            ]
+        );
+        events.recv().await.unwrap();
+        events.recv().await.unwrap();
+        match events.recv().await.unwrap() {
+            AgentEvent::BudgetExceeded {
+                category, spent, ..
+            } => {
+                assert_eq!(category, "Dining");
+                assert_eq!(spent, rust_decimal_macros::dec!(27.00));
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+
+    /// Suggests Dining for cafes with high confidence and Home for anything else
+    struct FixedCategorizer;
+
+    #[async_trait::async_trait]
+    impl Categorizer for FixedCategorizer {
+        fn name(&self) -> &str {
+            "fixed"
+        }
+
+        async fn suggest(
+            &self,
+            _data: &FinancialData,
+            transactions: &[crate::data::Transaction],
+        ) -> Result<Vec<categorize::CategorySuggestion>> {
+            Ok(transactions
+                .iter()
+                .map(|t| {
+                    let cafe = categorize::payee_of(t).contains("Cafe");
+                    categorize::CategorySuggestion {
+                        transaction_id: t.id,
+                        category: if cafe { "Dining" } else { "Home" }.to_string(),
+                        confidence: if cafe { 0.95 } else { 0.5 },
+                        rationale: "fixed".to_string(),
+                        source: self.name().to_string(),
+                    }
+                })
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_import_categorizes_and_queues_reviews() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            database: crate::storage::test_database_config(dir.path(), false),
+            ..Config::default()
+        };
+        let agent =
+            FinancialAgent::with_config(config).with_categorizer(Arc::new(FixedCategorizer));
+
+        let qif = dir.path().join("import.qif");
+        tokio::fs::write(
+            &qif,
+            "!Account\nNChecking\nTBank\n^\n!Type:Bank\nD1/5/2026\nT-12.00\nPCafe Luna\nLDining\n^\n\
+             D1/9/2026\nT-8.00\nPCafe Luna\n^\nD1/10/2026\nT-40.00\nPHardware Co\n^\n",
+        )
+        .await
+        .unwrap();
+        agent.import_file(&qif).await.unwrap();
+
+        let category_of = |data: &FinancialData, payee: &str, amount| {
+            data.transactions
+                .iter()
+                .find(|t| t.payee.as_deref() == Some(payee) && t.amount == amount)
+                .and_then(|t| t.category.clone())
+        };
+        let data = agent.load_data().await.unwrap();
+        assert_eq!(
+            category_of(&data, "Cafe Luna", rust_decimal_macros::dec!(8.00)).as_deref(),
+            Some("Dining")
+        );
+        assert_eq!(
+            category_of(&data, "Hardware Co", rust_decimal_macros::dec!(40.00)),
+            None
+        );
+
+        let backups = BackupManager::new(&agent.config().database)
+            .list_backups()
+            .await
+            .unwrap();
+        assert_eq!(backups.len(), 2, "import and rule-run backups");
+
+        // Reviewed transactions are not sent again
+        let pending = agent.pending_reviews().await.unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(agent.categorize().await.unwrap().considered, 0);
+
+        let decided = agent.decide_reviews(pending, true).await.unwrap();
+        assert_eq!(decided[0].status, ReviewStatus::Accepted);
+        let data = agent.load_data().await.unwrap();
+        assert_eq!(
+            category_of(&data, "Hardware Co", rust_decimal_macros::dec!(40.00)).as_deref(),
+            Some("Home")
+        );
+        assert!(agent.pending_reviews().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_candidates_are_reviewed_before_joining_the_books() {
+        use crate::data::{Split, TransactionType};
+        use rust_decimal_macros::dec;
+
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            database: crate::storage::test_database_config(dir.path(), false),
+            ..Config::default()
+        };
+        let agent = FinancialAgent::with_config(config);
+        let account = crate::data::Account::new(
+            "Checking".to_string(),
+            crate::data::AccountType::Checking,
+            dec!(0),
+            "USD".to_string(),
+        );
+        let account_id = account.id;
+        let mut books = FinancialData::new();
+        books.add_account(account);
+        let database = agent.open_database().await.unwrap();
+        database.save_data(&books).await.unwrap();
+        database.close().await;
+
+        let date = chrono::Utc::now();
+        let receipt = |amount, items: &[rust_decimal::Decimal]| {
+            let mut t = Transaction::new(
+                account_id,
+                date,
+                amount,
+                "Market".to_string(),
+                TransactionType::Debit,
+            );
+            t.payee = Some("Market".to_string());
+            t.splits = items
+                .iter()
+                .map(|amount| Split {
+                    category: Some("Groceries".to_string()),
+                    memo: None,
+                    amount: *amount,
+                })
+                .collect();
+            t
+        };
+
+        let unbalanced = receipt(dec!(30.00), &[dec!(20.00)]);
+        assert!(agent
+            .queue_candidates(vec![unbalanced], "receipt.txt", "llm:mock")
+            .await
+            .is_err());
+
+        let queued = agent
+            .queue_candidates(
+                vec![
+                    receipt(dec!(30.00), &[dec!(18.00), dec!(12.00)]),
+                    receipt(dec!(5.00), &[]),
+                ],
+                "receipt.txt",
+                "llm:mock",
+            )
+            .await
+            .unwrap();
+        assert_eq!(queued.len(), 2);
+        assert!(agent.load_data().await.unwrap().transactions.is_empty());
+
+        let pending = agent.pending_candidates().await.unwrap();
+        let chosen = candidates::select(pending, &[queued[0].short_id()], false).unwrap();
+        agent.decide_candidates(chosen, true).await.unwrap();
+        let rest = agent.pending_candidates().await.unwrap();
+        agent.decide_candidates(rest, false).await.unwrap();
+
+        let data = agent.load_data().await.unwrap();
+        assert_eq!(data.transactions.len(), 1);
+        assert_eq!(data.transactions[0].splits.len(), 2);
+        assert!(data.payees.contains(&"Market".to_string()));
+        assert!(data.categories.contains(&"Groceries".to_string()));
+        assert!(agent.pending_candidates().await.unwrap().is_empty());
+
+        // The same receipt extracted again is not booked twice
+        let again = agent
+            .queue_candidates(
+                vec![receipt(dec!(30.00), &[dec!(18.00), dec!(12.00)])],
+                "receipt.txt",
+                "llm:mock",
+            )
+            .await
+            .unwrap();
+        agent.decide_candidates(again, true).await.unwrap();
+        assert_eq!(agent.load_data().await.unwrap().transactions.len(), 1);
+
+        let mut stray = receipt(dec!(9.00), &[]);
+        stray.account_id = Uuid::new_v4();
+        let stray = agent
+            .queue_candidates(vec![stray], "receipt.txt", "llm:mock")
+            .await
+            .unwrap();
+        let err = agent.decide_candidates(stray, true).await.unwrap_err();
+        assert!(err.to_string().contains("not in the books"));
+        assert_eq!(agent.pending_candidates().await.unwrap().len(), 1);
+        assert_eq!(agent.load_data().await.unwrap().transactions.len(), 1);
+    }
 }
@@ -0,0 +1,406 @@
+use super::provider::{ChatMessage, ChatRequest, LlmProvider};
+use crate::data::FinancialData;
+use crate::tools::{Books, ToolCall};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tracing::debug;
+
+const SYSTEM_PROMPT: &str = r#"You are a household finance assistant. You work on the user's books only through tools.
+Reply with exactly one JSON object per turn, either
+  {"tool": "<name>", "arguments": {...}, "reason": "<why, in a few words>"} to call a tool, or
+  {"answer": "<your answer to the user>"} once you can answer.
+Each tool result comes back as JSON in the next message. Call one tool at a time.
+Use only figures from tool results. Resolve relative dates against `today`.
+Tools:
+TOOLS"#;
+
+/// Asks the user before a tool changes the books
+#[async_trait]
+pub trait Confirm: Send + Sync {
+    async fn confirm(&self, action: &str) -> bool;
+}
+
+/// Answers every confirmation the same way, e.g. for `--yes` or non-interactive runs
+pub struct AutoConfirm(pub bool);
+
+/// How one step ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepOutcome {
+    Ok,
+    Error,
+    /// A write the user did not confirm
+    Declined,
+}
+
+/// One model turn that called a tool, or failed to
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TraceStep {
+    pub step: usize,
+    /// `None` when the reply could not be read as a tool call
+    pub tool: Option<String>,
+    pub arguments: Value,
+    pub reason: Option<String>,
+    pub outcome: StepOutcome,
+    /// What was sent back to the model
+    pub result: Value,
+}
+
+/// A complete assistant run: every step taken and the final answer
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AgentRun {
+    pub request: String,
+    pub steps: Vec<TraceStep>,
+    pub answer: Option<String>,
+    /// Why the run ended without an answer
+    pub stopped: Option<String>,
+}
+
+/// Tool-calling loop over the analysis API
+///
+/// The model chooses among the typed tools in [`ToolCall`], sees each result
+/// and continues until it answers or `max_steps` tool calls have been made.
+/// Tools that change the books run only after [`Confirm`] approves them.
+/// Without `share_data` the model sees only aggregates: tools that return or
+/// change individual transactions are refused.
+pub struct Assistant {
+    provider: Arc<dyn LlmProvider>,
+    confirm: Arc<dyn Confirm>,
+    max_steps: usize,
+    share_data: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Reply {
+    #[serde(default)]
+    tool: Option<String>,
+    #[serde(default)]
+    arguments: Option<Value>,
+    #[serde(default)]
+    reason: Option<String>,
+    #[serde(default)]
+    answer: Option<String>,
+}
+
+#[async_trait]
+impl Confirm for AutoConfirm {
+    async fn confirm(&self, _action: &str) -> bool {
+        self.0
+    }
+}
+
+impl Assistant {
+    pub fn new(
+        provider: Arc<dyn LlmProvider>,
+        confirm: Arc<dyn Confirm>,
+        max_steps: usize,
+        share_data: bool,
+    ) -> Self {
+        Self {
+            provider,
+            confirm,
+            max_steps,
+            share_data,
+        }
+    }
+
+    /// Work on `request` until the model answers or the step limit is reached
+    pub async fn run(
+        &self,
+        books: &dyn Books,
+        request: &str,
+        today: NaiveDate,
+    ) -> Result<AgentRun> {
+        let mut data = books.load().await?;
+        let mut messages = vec![
+            ChatMessage::system(
+                SYSTEM_PROMPT.replace("TOOLS", &crate::tools::prompt_descriptions(self.share_data)),
+            ),
+            ChatMessage::user(json!({"request": request, "today": today}).to_string()),
+        ];
+        let mut run = AgentRun {
+            request: request.to_string(),
+            steps: Vec::new(),
+            answer: None,
+            stopped: None,
+        };
+
+        while run.steps.len() < self.max_steps {
+            let chat = ChatRequest::new(messages.clone())
+                .with_temperature(0.0)
+                .json();
+            let response = self
+                .provider
+                .chat(&chat)
+                .await
+                .context("Assistant request failed")?;
+            messages.push(ChatMessage::assistant(response.content.clone()));
+
+            let reply = serde_json::from_str::<Reply>(response.content.trim());
+            if let Ok(Reply {
+                answer: Some(answer),
+                tool: None,
+                ..
+            }) = &reply
+            {
+                run.answer = Some(answer.trim().to_string());
+                return Ok(run);
+            }
+
+            let mut step = TraceStep {
+                step: run.steps.len() + 1,
+                tool: None,
+                arguments: Value::Null,
+                reason: None,
+                outcome: StepOutcome::Error,
+                result: Value::Null,
+            };
+            match reply {
+                Ok(Reply {
+                    tool: Some(tool),
+                    arguments,
+                    reason,
+                    answer: None,
+                }) => {
+                    let arguments = arguments.unwrap_or_else(|| json!({}));
+                    step.tool = Some(tool.clone());
+                    step.arguments = arguments.clone();
+                    step.reason = reason;
+                    match ToolCall::parse(&tool, arguments) {
+                        Ok(call) => {
+                            let (outcome, result) = self.call(books, &mut data, &call).await?;
+                            step.outcome = outcome;
+                            step.result = result;
+                        }
+                        Err(e) => {
+                            step.result = json!({"error": format!("invalid tool call: {}", e)})
+                        }
+                    }
+                }
+                Ok(_) => {
+                    step.result = json!({"error": "reply with either a tool call or an answer"})
+                }
+                Err(e) => {
+                    step.result = json!({"error": format!("reply is not the expected JSON: {}", e)})
+                }
+            }
+            debug!(
+                "Assistant step {}: {:?} -> {:?}",
+                step.step, step.tool, step.outcome
+            );
+            messages.push(ChatMessage::user(
+                json!({"tool": step.tool, "result": step.result}).to_string(),
+            ));
+            run.steps.push(step);
+        }
+
+        run.stopped = Some(format!("step limit of {} reached", self.max_steps));
+        Ok(run)
+    }
+
+    /// Run one tool; failures go back to the model rather than ending the run
+    async fn call(
+        &self,
+        books: &dyn Books,
+        data: &mut FinancialData,
+        call: &ToolCall,
+    ) -> Result<(StepOutcome, Value)> {
+        if !self.share_data && call.is_row_level() {
+            return Ok((
+                StepOutcome::Error,
+                json!({"error": format!(
+                    "{} works on individual transactions, which are not shared; use the aggregate tools",
+                    call.name()
+                )}),
+            ));
+        }
+        if !call.is_write() {
+            return Ok(match call.read(data) {
+                Ok(result) => (StepOutcome::Ok, result),
+                Err(e) => (StepOutcome::Error, json!({"error": format!("{:#}", e)})),
+            });
+        }
+
+        let action = match call.describe(data) {
+            Ok(action) => action,
+            Err(e) => return Ok((StepOutcome::Error, json!({"error": format!("{:#}", e)}))),
+        };
+        if !self.confirm.confirm(&action).await {
+            return Ok((
+                StepOutcome::Declined,
+                json!({"declined": action, "note": "the user did not confirm this change"}),
+            ));
+        }
+        let ToolCall::SetCategory {
+            transaction_id,
+            category,
+        } = call
+        else {
+            unreachable!("set_category is the only write");
+        };
+        books.set_category(*transaction_id, category).await?;
+        *data = books.load().await?;
+        Ok((StepOutcome::Ok, json!({"done": action})))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::MockProvider;
+    use crate::data::{Account, AccountType, Transaction, TransactionType};
+    use chrono::{TimeZone, Utc};
+    use rust_decimal_macros::dec;
+    use std::sync::Mutex;
+    use uuid::Uuid;
+
+    struct MemoryBooks(Mutex<FinancialData>);
+
+    #[async_trait]
+    impl Books for MemoryBooks {
+        async fn load(&self) -> Result<FinancialData> {
+            Ok(self.0.lock().unwrap().clone())
+        }
+
+        async fn set_category(&self, transaction_id: Uuid, category: &str) -> Result<()> {
+            let mut data = self.0.lock().unwrap();
+            let transaction = data
+                .transactions
+                .iter_mut()
+                .find(|t| t.id == transaction_id)
+                .context("missing")?;
+            transaction.category = Some(category.to_string());
+            Ok(())
+        }
+    }
+
+    fn books() -> (MemoryBooks, Uuid) {
+        let mut data = FinancialData::new();
+        let account = Account::new(
+            "Checking".to_string(),
+            AccountType::Checking,
+            dec!(900),
+            "USD".to_string(),
+        );
+        let t = Transaction::new(
+            account.id,
+            Utc.with_ymd_and_hms(2026, 9, 5, 12, 0, 0).unwrap(),
+            dec!(50.00),
+            "Grocer".to_string(),
+            TransactionType::Debit,
+        );
+        let id = t.id;
+        data.add_transaction(t);
+        data.add_account(account);
+        (MemoryBooks(Mutex::new(data)), id)
+    }
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, 18).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_multi_step_run_with_confirmed_write() {
+        let (books, id) = books();
+        let set = json!({
+            "tool": "set_category",
+            "arguments": {"transaction_id": id, "category": "Groceries"},
+            "reason": "uncategorized grocery charge"
+        });
+        let provider = Arc::new(
+            MockProvider::new()
+                .reply(r#"{"tool": "search_transactions", "arguments": {"query": "grocer"}}"#)
+                .reply(r#"{"tool": "transfer_money", "arguments": {}}"#)
+                .reply(set.to_string())
+                .reply(r#"{"answer": "Categorized the $50.00 Grocer charge as Groceries."}"#),
+        );
+        let assistant = Assistant::new(provider.clone(), Arc::new(AutoConfirm(true)), 8, true);
+        let run = assistant
+            .run(&books, "Categorize my grocery charges", today())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            run.answer.as_deref(),
+            Some("Categorized the $50.00 Grocer charge as Groceries.")
+        );
+        let outcomes: Vec<_> = run.steps.iter().map(|s| s.outcome).collect();
+        assert_eq!(
+            outcomes,
+            [StepOutcome::Ok, StepOutcome::Error, StepOutcome::Ok]
+        );
+        assert_eq!(run.steps[0].result[0]["payee"], "Grocer");
+        assert_eq!(
+            run.steps[2].reason.as_deref(),
+            Some("uncategorized grocery charge")
+        );
+        let data = books.load().await.unwrap();
+        assert_eq!(data.transactions[0].category.as_deref(), Some("Groceries"));
+
+        // Each tool result was fed back before the next turn
+        let requests = provider.requests();
+        let last = requests[3].messages.last().unwrap();
+        assert!(last.content.contains("\"done\""));
+    }
+
+    #[tokio::test]
+    async fn test_declined_writes_and_step_limit() {
+        let (books, id) = books();
+        let set = json!({
+            "tool": "set_category",
+            "arguments": {"transaction_id": id, "category": "Dining"}
+        });
+        let provider = Arc::new(MockProvider::new().with_responder(move |_| Ok(set.to_string())));
+        let assistant = Assistant::new(provider, Arc::new(AutoConfirm(false)), 3, true);
+        let run = assistant
+            .run(&books, "Recategorize", today())
+            .await
+            .unwrap();
+
+        assert_eq!(run.answer, None);
+        assert_eq!(run.stopped.as_deref(), Some("step limit of 3 reached"));
+        assert_eq!(run.steps.len(), 3);
+        assert!(run.steps.iter().all(|s| s.outcome == StepOutcome::Declined));
+        assert_eq!(books.load().await.unwrap().transactions[0].category, None);
+    }
+
+    #[tokio::test]
+    async fn test_without_share_data_only_aggregates_leave() {
+        let (books, id) = books();
+        let set = json!({
+            "tool": "set_category",
+            "arguments": {"transaction_id": id, "category": "Dining"}
+        });
+        let provider = Arc::new(
+            MockProvider::new()
+                .reply(r#"{"tool": "search_transactions", "arguments": {"query": "grocer"}}"#)
+                .reply(set.to_string())
+                .reply(r#"{"tool": "analyze_categories", "arguments": {}}"#)
+                .reply(r#"{"answer": "You spent $50.00, all uncategorized."}"#),
+        );
+        let assistant = Assistant::new(provider.clone(), Arc::new(AutoConfirm(true)), 8, false);
+        let run = assistant
+            .run(&books, "Where did my money go?", today())
+            .await
+            .unwrap();
+
+        let outcomes: Vec<_> = run.steps.iter().map(|s| s.outcome).collect();
+        assert_eq!(
+            outcomes,
+            [StepOutcome::Error, StepOutcome::Error, StepOutcome::Ok]
+        );
+        assert_eq!(books.load().await.unwrap().transactions[0].category, None);
+        let sent: String = provider.requests()[3]
+            .messages
+            .iter()
+            .map(|m| m.content.clone())
+            .collect();
+        assert!(!sent.contains("Grocer"));
+        assert!(!sent.contains("search_transactions {"));
+    }
+}
@@ -0,0 +1,226 @@
+use super::provider::{ChatMessage, ChatRequest, LlmProvider};
+use crate::categorize::{self, Categorizer, CategorySuggestion};
+use crate::data::{FinancialData, Transaction};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tracing::debug;
+
+const SYSTEM_PROMPT: &str = "You categorize household finance transactions. \
+Choose each category only from `categories`, guided by how this household categorized \
+the same payees before (`history`). Reply with one JSON object: \
+{\"suggestions\": [{\"id\": \"t1\", \"category\": \"...\", \"confidence\": 0.0, \"rationale\": \"...\"}]}. \
+`confidence` is between 0 and 1; keep `rationale` under 20 words. \
+Leave out transactions that fit no category.";
+
+/// Categorizer backed by a language model
+///
+/// Sends payee, memo, description, date and amount of each transaction along
+/// with the allowed categories and the household's past payee/category pairs.
+/// Answers naming a category outside the allowed list are discarded.
+pub struct LlmCategorizer {
+    provider: Arc<dyn LlmProvider>,
+    name: String,
+    history_examples: usize,
+}
+
+#[derive(Deserialize)]
+struct Reply {
+    #[serde(default)]
+    suggestions: Vec<ReplyItem>,
+}
+
+#[derive(Deserialize)]
+struct ReplyItem {
+    id: String,
+    category: String,
+    #[serde(default)]
+    confidence: f64,
+    #[serde(default)]
+    rationale: String,
+}
+
+impl LlmCategorizer {
+    pub fn new(provider: Arc<dyn LlmProvider>, history_examples: usize) -> Self {
+        Self {
+            name: format!("llm:{}", provider.model()),
+            provider,
+            history_examples,
+        }
+    }
+
+    fn prompt(&self, data: &FinancialData, transactions: &[Transaction]) -> String {
+        let batch_payees: HashSet<String> = transactions
+            .iter()
+            .map(|t| categorize::payee_of(t).to_lowercase())
+            .collect();
+        let all = categorize::history(data, usize::MAX);
+        let mut seen = HashSet::new();
+        let history: Vec<_> = all
+            .iter()
+            .filter(|(payee, ..)| batch_payees.contains(&payee.to_lowercase()))
+            .chain(all.iter().take(self.history_examples))
+            .filter(|pair| seen.insert(*pair))
+            .map(|(payee, category, count)| {
+                json!({"payee": payee, "category": category, "count": count})
+            })
+            .collect();
+
+        let batch: Vec<_> = transactions
+            .iter()
+            .enumerate()
+            .map(|(i, t)| {
+                json!({
+                    "id": format!("t{}", i + 1),
+                    "date": t.date.date_naive(),
+                    "amount": t.amount,
+                    "payee": categorize::payee_of(t),
+                    "memo": t.memo,
+                    "description": t.description,
+                })
+            })
+            .collect();
+
+        json!({
+            "categories": categorize::known_categories(data),
+            "history": history,
+            "transactions": batch,
+        })
+        .to_string()
+    }
+}
+
+#[async_trait]
+impl Categorizer for LlmCategorizer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn suggest(
+        &self,
+        data: &FinancialData,
+        transactions: &[Transaction],
+    ) -> Result<Vec<CategorySuggestion>> {
+        let categories = categorize::known_categories(data);
+        if transactions.is_empty() || categories.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let request = ChatRequest::new(vec![
+            ChatMessage::system(SYSTEM_PROMPT),
+            ChatMessage::user(self.prompt(data, transactions)),
+        ])
+        .with_temperature(0.0)
+        .json();
+        let response = self
+            .provider
+            .chat(&request)
+            .await
+            .context("Categorization request failed")?;
+        let reply: Reply = serde_json::from_str(strip_code_fence(&response.content))
+            .context("Categorization reply is not the expected JSON")?;
+
+        Ok(reply
+            .suggestions
+            .into_iter()
+            .filter_map(|item| {
+                let index: usize = item.id.trim_start_matches('t').parse().ok()?;
+                let transaction = transactions.get(index.checked_sub(1)?)?;
+                let Some(category) = categories
+                    .iter()
+                    .find(|c| c.eq_ignore_ascii_case(item.category.trim()))
+                else {
+                    debug!(
+                        "Dropping suggestion of unknown category '{}'",
+                        item.category
+                    );
+                    return None;
+                };
+                Some(CategorySuggestion {
+                    transaction_id: transaction.id,
+                    category: category.clone(),
+                    confidence: item.confidence.clamp(0.0, 1.0),
+                    rationale: item.rationale,
+                    source: self.name.clone(),
+                })
+            })
+            .collect())
+    }
+}
+
+/// Models sometimes wrap JSON in a Markdown code fence despite being asked not to
+pub(super) fn strip_code_fence(text: &str) -> &str {
+    let text = text.trim();
+    match text.strip_prefix("```") {
+        Some(rest) => rest
+            .trim_start_matches("json")
+            .trim_end()
+            .trim_end_matches("```")
+            .trim(),
+        None => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::MockProvider;
+    use crate::data::TransactionType;
+    use chrono::Utc;
+    use rust_decimal_macros::dec;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn test_llm_categorizer_parses_and_filters_reply() {
+        let mut data = FinancialData::new();
+        let mut past = Transaction::new(
+            Uuid::new_v4(),
+            Utc::now(),
+            dec!(-9.00),
+            "Cafe Luna".to_string(),
+            TransactionType::Debit,
+        );
+        past.category = Some("Dining".to_string());
+        data.add_transaction(past.clone());
+        data.categories.push("Utilities".to_string());
+
+        let new = vec![
+            Transaction {
+                id: Uuid::new_v4(),
+                category: None,
+                ..past.clone()
+            },
+            Transaction {
+                id: Uuid::new_v4(),
+                category: None,
+                description: "City Water".to_string(),
+                ..past
+            },
+        ];
+
+        let provider = Arc::new(MockProvider::new().reply(
+            "```json\n{\"suggestions\": [\
+             {\"id\": \"t1\", \"category\": \"dining\", \"confidence\": 1.4, \"rationale\": \"Past visits\"},\
+             {\"id\": \"t2\", \"category\": \"Water\", \"confidence\": 0.8},\
+             {\"id\": \"t9\", \"category\": \"Utilities\", \"confidence\": 0.8}]}\n```",
+        ));
+        let categorizer = LlmCategorizer::new(provider.clone(), 10);
+        let suggestions = categorizer.suggest(&data, &new).await.unwrap();
+
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].transaction_id, new[0].id);
+        assert_eq!(suggestions[0].category, "Dining");
+        assert_eq!(suggestions[0].confidence, 1.0);
+        assert_eq!(suggestions[0].source, "llm:mock");
+
+        let sent = &provider.requests()[0];
+        assert!(sent.json_response);
+        let prompt: serde_json::Value = serde_json::from_str(&sent.messages[1].content).unwrap();
+        assert_eq!(prompt["categories"], json!(["Dining", "Utilities"]));
+        assert_eq!(prompt["history"][0]["payee"], "Cafe Luna");
+        assert_eq!(prompt["transactions"][1]["payee"], "City Water");
+    }
+}
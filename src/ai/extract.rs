@@ -0,0 +1,395 @@
+use super::categorizer::strip_code_fence;
+use super::provider::{ChatMessage, ChatRequest, LlmProvider};
+use crate::candidates::{self, Candidate};
+use crate::data::{Account, Split, Transaction, TransactionType};
+use anyhow::{bail, Context, Result};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::debug;
+
+/// Longest text sent for extraction, in characters
+pub const MAX_TEXT_CHARS: usize = 20_000;
+
+const SYSTEM_PROMPT: &str = r#"You turn receipt and bank statement text into transactions.
+The text may come from a PDF text layer or OCR, so expect broken lines and stray characters.
+Reply with one JSON object:
+{"transactions": [{"date": "YYYY-MM-DD", "payee": "...", "total": "12.34", "type": "debit", "memo": null,
+  "items": [{"description": "...", "amount": "10.00", "category": "..."}]}]}
+A receipt is one transaction whose items are its lines, with tax, tips and discounts as their own
+items (discounts negative), so the items add up exactly to the total. A statement is one
+transaction per line, each without items. `type` is "debit" for money spent and "credit" for
+money received; totals are positive. Choose each category only from `categories`, or use null.
+Take every figure from the text; never estimate one. Resolve partial dates against `today`."#;
+
+/// A transaction the model proposed that failed validation
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RejectedCandidate {
+    pub date: NaiveDate,
+    pub payee: String,
+    pub total: Decimal,
+    pub reason: String,
+}
+
+/// Transactions read from one text, before review
+#[derive(Debug, Clone, PartialEq)]
+pub struct Extraction {
+    pub transactions: Vec<Transaction>,
+    pub rejected: Vec<RejectedCandidate>,
+}
+
+/// Outcome of `qspec extract text`: what was queued and what was refused
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExtractionReport {
+    pub source: String,
+    pub queued: Vec<Candidate>,
+    pub rejected: Vec<RejectedCandidate>,
+}
+
+/// Reads receipt and statement text into candidate transactions
+///
+/// Every candidate is checked so its line items add up to its total. If any
+/// fail, the model is shown the problems and asked once to correct them;
+/// those still failing are returned as rejected rather than queued.
+pub struct TextExtractor {
+    provider: Arc<dyn LlmProvider>,
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct Reply {
+    #[serde(default)]
+    transactions: Vec<ReplyTransaction>,
+}
+
+#[derive(Deserialize)]
+struct ReplyTransaction {
+    date: NaiveDate,
+    payee: String,
+    total: Decimal,
+    #[serde(default, rename = "type")]
+    kind: Option<String>,
+    #[serde(default)]
+    memo: Option<String>,
+    #[serde(default)]
+    items: Vec<ReplyItem>,
+}
+
+#[derive(Deserialize)]
+struct ReplyItem {
+    #[serde(default)]
+    description: Option<String>,
+    amount: Decimal,
+    #[serde(default)]
+    category: Option<String>,
+}
+
+impl TextExtractor {
+    pub fn new(provider: Arc<dyn LlmProvider>) -> Self {
+        Self {
+            name: format!("llm:{}", provider.model()),
+            provider,
+        }
+    }
+
+    /// Name recorded as the extractor of each candidate
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Propose transactions in `account` from `text`
+    pub async fn extract(
+        &self,
+        text: &str,
+        account: &Account,
+        categories: &[String],
+        today: NaiveDate,
+    ) -> Result<Extraction> {
+        if text.trim().is_empty() {
+            bail!("The text is empty");
+        }
+        if text.chars().count() > MAX_TEXT_CHARS {
+            bail!(
+                "The text is longer than {} characters; split it into smaller parts",
+                MAX_TEXT_CHARS
+            );
+        }
+
+        let mut messages = vec![
+            ChatMessage::system(SYSTEM_PROMPT),
+            ChatMessage::user(
+                json!({
+                    "today": today,
+                    "account": account.name,
+                    "categories": categories,
+                    "text": text,
+                })
+                .to_string(),
+            ),
+        ];
+
+        let mut extraction = Extraction {
+            transactions: Vec::new(),
+            rejected: Vec::new(),
+        };
+        for attempt in 0..2 {
+            let request = ChatRequest::new(messages.clone())
+                .with_temperature(0.0)
+                .json();
+            let response = self
+                .provider
+                .chat(&request)
+                .await
+                .context("Extraction request failed")?;
+            let reply = match serde_json::from_str::<Reply>(strip_code_fence(&response.content)) {
+                Ok(reply) => reply,
+                Err(e) if attempt == 0 => {
+                    messages.push(ChatMessage::assistant(response.content));
+                    messages.push(ChatMessage::user(format!(
+                        "That reply is not the expected JSON ({}). Reply again with the JSON object only.",
+                        e
+                    )));
+                    continue;
+                }
+                Err(e) => return Err(e).context("Extraction reply is not the expected JSON"),
+            };
+
+            extraction = Extraction {
+                transactions: Vec::new(),
+                rejected: Vec::new(),
+            };
+            for mut item in reply.transactions {
+                // Numbers like 8.0 arrive without cents; show them as 8.00
+                item.total = cents(item.total);
+                for line in &mut item.items {
+                    line.amount = cents(line.amount);
+                }
+                match build(&item, account, categories) {
+                    Ok(transaction) => extraction.transactions.push(transaction),
+                    Err(e) => extraction.rejected.push(RejectedCandidate {
+                        date: item.date,
+                        payee: item.payee,
+                        total: item.total,
+                        reason: format!("{:#}", e),
+                    }),
+                }
+            }
+            if extraction.rejected.is_empty() || attempt == 1 {
+                break;
+            }
+
+            debug!(
+                "Extraction has invalid candidates: {:?}",
+                extraction.rejected
+            );
+            let problems: Vec<String> = extraction
+                .rejected
+                .iter()
+                .map(|r| format!("{} {}: {}", r.date, r.payee, r.reason))
+                .collect();
+            messages.push(ChatMessage::assistant(response.content));
+            messages.push(ChatMessage::user(format!(
+                "These transactions do not check out: {}. Re-read the text and reply with the corrected JSON for all transactions.",
+                problems.join("; ")
+            )));
+        }
+        Ok(extraction)
+    }
+}
+
+/// `amount` with at least two decimal places
+fn cents(mut amount: Decimal) -> Decimal {
+    if amount.scale() < 2 {
+        amount.rescale(2);
+    }
+    amount
+}
+
+/// Transaction for one reply entry, checked so its items add up to the total
+fn build(item: &ReplyTransaction, account: &Account, categories: &[String]) -> Result<Transaction> {
+    let payee = item.payee.trim();
+    if payee.is_empty() {
+        bail!("payee is missing");
+    }
+    let transaction_type = match item.kind.as_deref().map(str::to_lowercase).as_deref() {
+        None | Some("debit") => TransactionType::Debit,
+        Some("credit") => TransactionType::Credit,
+        Some(other) => bail!("unknown type '{}'", other),
+    };
+    let known = |category: &Option<String>| {
+        let category = category.as_deref()?.trim();
+        categories
+            .iter()
+            .find(|c| c.eq_ignore_ascii_case(category))
+            .cloned()
+    };
+
+    let mut transaction = Transaction::new(
+        account.id,
+        item.date.and_hms_opt(0, 0, 0).unwrap().and_utc(),
+        item.total,
+        payee.to_string(),
+        transaction_type,
+    );
+    transaction.payee = Some(payee.to_string());
+    transaction.memo = item.memo.clone().filter(|m| !m.trim().is_empty());
+    if let [only] = item.items.as_slice() {
+        if only.amount != item.total {
+            bail!(
+                "line items add up to {} but the total is {}",
+                only.amount,
+                item.total
+            );
+        }
+        transaction.category = known(&only.category);
+    } else {
+        transaction.splits = item
+            .items
+            .iter()
+            .map(|line| Split {
+                category: known(&line.category),
+                memo: line.description.clone(),
+                amount: line.amount,
+            })
+            .collect();
+        // The largest line names the transaction's category
+        transaction.category = transaction
+            .splits
+            .iter()
+            .max_by_key(|s| s.amount)
+            .and_then(|s| s.category.clone());
+    }
+    candidates::check_splits(&transaction)?;
+    Ok(transaction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::MockProvider;
+    use crate::data::AccountType;
+    use rust_decimal_macros::dec;
+
+    fn account() -> Account {
+        Account::new(
+            "Checking".to_string(),
+            AccountType::Checking,
+            dec!(900),
+            "USD".to_string(),
+        )
+    }
+
+    fn categories() -> Vec<String> {
+        vec!["Groceries".to_string(), "Household".to_string()]
+    }
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, 18).unwrap()
+    }
+
+    const RECEIPT: &str = "GREEN MARKET\n10/02/26\nAPPLES 4.50\nSOAP 3.25\nTAX 0.25\nTOTAL 8.00";
+
+    #[tokio::test]
+    async fn test_receipt_with_line_items() {
+        let reply = json!({"transactions": [{
+            "date": "2026-10-02", "payee": "Green Market", "total": 8.00, "type": "debit",
+            "items": [
+                {"description": "Apples", "amount": 4.50, "category": "groceries"},
+                {"description": "Soap", "amount": "3.25", "category": "Household"},
+                {"description": "Tax", "amount": 0.25, "category": "Made Up"}
+            ]
+        }]});
+        let provider = Arc::new(MockProvider::new().reply(reply.to_string()));
+        let extractor = TextExtractor::new(provider.clone());
+        let extraction = extractor
+            .extract(RECEIPT, &account(), &categories(), today())
+            .await
+            .unwrap();
+
+        assert!(extraction.rejected.is_empty());
+        let t = &extraction.transactions[0];
+        assert_eq!(t.amount, dec!(8.00));
+        assert_eq!(t.transaction_type, TransactionType::Debit);
+        assert_eq!(
+            t.date.date_naive(),
+            NaiveDate::from_ymd_opt(2026, 10, 2).unwrap()
+        );
+        assert_eq!(t.category.as_deref(), Some("Groceries"));
+        let splits: Vec<_> = t
+            .splits
+            .iter()
+            .map(|s| (s.memo.as_deref(), s.category.as_deref(), s.amount))
+            .collect();
+        assert_eq!(
+            splits,
+            [
+                (Some("Apples"), Some("Groceries"), dec!(4.50)),
+                (Some("Soap"), Some("Household"), dec!(3.25)),
+                (Some("Tax"), None, dec!(0.25)),
+            ]
+        );
+        let request = &provider.requests()[0];
+        assert!(request.messages[1].content.contains("GREEN MARKET"));
+    }
+
+    #[tokio::test]
+    async fn test_items_must_add_up_after_one_correction() {
+        let wrong = json!({"transactions": [
+            {"date": "2026-10-02", "payee": "Green Market", "total": 8.00,
+             "items": [{"amount": 4.50}, {"amount": 3.25}]},
+            {"date": "2026-10-03", "payee": "Employer", "total": 1000, "type": "credit"}
+        ]});
+        let provider = Arc::new(
+            MockProvider::new()
+                .reply(wrong.to_string())
+                .reply(wrong.to_string()),
+        );
+        let extraction = TextExtractor::new(provider.clone())
+            .extract(RECEIPT, &account(), &categories(), today())
+            .await
+            .unwrap();
+
+        assert_eq!(extraction.transactions.len(), 1);
+        assert_eq!(
+            extraction.transactions[0].payee.as_deref(),
+            Some("Employer")
+        );
+        assert_eq!(
+            extraction.rejected,
+            vec![RejectedCandidate {
+                date: NaiveDate::from_ymd_opt(2026, 10, 2).unwrap(),
+                payee: "Green Market".to_string(),
+                total: dec!(8.00),
+                reason: "line items add up to 7.75 but the total is 8.00".to_string(),
+            }]
+        );
+        // The problem was put to the model before giving up
+        let requests = provider.requests();
+        assert_eq!(requests.len(), 2);
+        let correction = &requests[1].messages.last().unwrap().content;
+        assert!(correction.contains("line items add up to 7.75"));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_unusable_text() {
+        let extractor = TextExtractor::new(Arc::new(MockProvider::new()));
+        assert!(extractor
+            .extract("  ", &account(), &categories(), today())
+            .await
+            .is_err());
+        let long = "x".repeat(MAX_TEXT_CHARS + 1);
+        assert!(extractor
+            .extract(&long, &account(), &categories(), today())
+            .await
+            .is_err());
+
+        let provider = Arc::new(MockProvider::new().reply("not json").reply("still not"));
+        let err = TextExtractor::new(provider)
+            .extract(RECEIPT, &account(), &categories(), today())
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Extraction reply is not the expected JSON");
+    }
+}
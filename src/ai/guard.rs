@@ -0,0 +1,420 @@
+use super::ledger::{budget_exceeded, local_day, UsageLedger};
+use super::provider::{ChatRequest, ChatResponse, LlmError, LlmProvider, TokenUsage, UsageTotals};
+use super::redact::{PromptRedactor, Vault};
+use crate::config::{AiBudgetConfig, AiCacheConfig, AiConfig};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::warn;
+
+/// What happened to one audited request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditOutcome {
+    Sent,
+    Failed,
+    DryRun,
+    /// Answered from the response cache without contacting the endpoint
+    Cached,
+    /// Stopped by the token or cost budget
+    Blocked,
+}
+
+/// One line of the AI audit log: the prompt exactly as it left the machine
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub at: DateTime<Utc>,
+    pub provider: String,
+    pub model: String,
+    pub outcome: AuditOutcome,
+    /// Messages after redaction, as sent
+    pub request: ChatRequestRecord,
+    /// Values hidden, by kind; the values themselves are never recorded
+    pub redactions: BTreeMap<String, usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<TokenUsage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Serializable copy of a `ChatRequest`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatRequestRecord {
+    pub messages: Vec<super::provider::ChatMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    pub json_response: bool,
+}
+
+/// Provider wrapper that redacts prompts, audits them and honours dry runs
+///
+/// Every prompt passes through `PromptRedactor` before reaching the inner
+/// provider, and placeholders in the reply are swapped back for the original
+/// values, so callers work with real data while the endpoint only sees
+/// placeholders. With a [`UsageLedger`], identical redacted prompts are
+/// answered from its cache and calls stop once the budget is spent.
+pub struct GuardedProvider {
+    inner: Arc<dyn LlmProvider>,
+    redactor: PromptRedactor,
+    audit_log: Option<PathBuf>,
+    dry_run: bool,
+    /// Serializes appends to the audit log
+    audit_lock: Mutex<()>,
+    ledger: Option<UsageLedger>,
+    cache: AiCacheConfig,
+    budget: AiBudgetConfig,
+}
+
+impl GuardedProvider {
+    pub fn new(inner: Arc<dyn LlmProvider>, config: &AiConfig) -> Self {
+        Self {
+            inner,
+            redactor: PromptRedactor::new(&config.redaction),
+            audit_log: config.audit_log.clone(),
+            dry_run: config.dry_run,
+            audit_lock: Mutex::new(()),
+            ledger: None,
+            cache: config.cache.clone(),
+            budget: config.budget.clone(),
+        }
+    }
+
+    /// Cache answers and keep usage history in `ledger`
+    pub fn with_ledger(mut self, ledger: UsageLedger) -> Self {
+        self.ledger = Some(ledger);
+        self
+    }
+
+    fn redact(&self, request: &ChatRequest) -> (ChatRequest, Vault) {
+        let mut vault = Vault::default();
+        let mut redacted = request.clone();
+        for message in &mut redacted.messages {
+            message.content = self.redactor.redact(&message.content, &mut vault);
+        }
+        (redacted, vault)
+    }
+
+    /// Complete and write the audit record for a request that reached the endpoint
+    fn finish(&self, record: &mut AuditRecord, result: &Result<ChatResponse, LlmError>) {
+        match result {
+            Ok(response) => {
+                record.outcome = AuditOutcome::Sent;
+                record.usage = Some(response.usage);
+            }
+            Err(e) => {
+                record.outcome = AuditOutcome::Failed;
+                record.error = Some(e.to_string());
+            }
+        }
+        self.audit(record);
+    }
+
+    async fn log_usage(
+        &self,
+        ledger: &UsageLedger,
+        usage: TokenUsage,
+        cached: bool,
+        at: DateTime<Utc>,
+    ) {
+        let cost = self
+            .budget
+            .cost(usage.prompt_tokens, usage.completion_tokens);
+        if let Err(e) = ledger
+            .record(
+                self.inner.name(),
+                self.inner.model(),
+                usage,
+                cost,
+                cached,
+                at,
+            )
+            .await
+        {
+            warn!("Failed to record AI usage: {:#}", e);
+        }
+    }
+
+    fn audit(&self, record: &AuditRecord) {
+        let Some(path) = &self.audit_log else {
+            return;
+        };
+        let _guard = self.audit_lock.lock().unwrap();
+        // An unwritable audit log must not take AI features down with it
+        if let Err(e) = append_record(path, record) {
+            warn!("Failed to write AI audit log {}: {:#}", path.display(), e);
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for GuardedProvider {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn model(&self) -> &str {
+        self.inner.model()
+    }
+
+    async fn chat(&self, request: &ChatRequest) -> Result<ChatResponse, LlmError> {
+        let (redacted, vault) = self.redact(request);
+        let mut record = AuditRecord {
+            at: Utc::now(),
+            provider: self.inner.name().to_string(),
+            model: self.inner.model().to_string(),
+            outcome: AuditOutcome::DryRun,
+            request: ChatRequestRecord {
+                messages: redacted.messages.clone(),
+                temperature: redacted.temperature,
+                max_tokens: redacted.max_tokens,
+                json_response: redacted.json_response,
+            },
+            redactions: vault
+                .counts()
+                .iter()
+                .map(|(kind, count)| (kind.to_string(), *count))
+                .collect(),
+            usage: None,
+            error: None,
+        };
+
+        if self.dry_run {
+            eprintln!("{}", dry_run_text(&record));
+            self.audit(&record);
+            return Err(LlmError::DryRun);
+        }
+
+        let Some(ledger) = &self.ledger else {
+            let result = self.inner.chat(&redacted).await;
+            self.finish(&mut record, &result);
+            return result.map(|response| rehydrated(response, &vault));
+        };
+
+        let now = Utc::now();
+        let key = UsageLedger::key(self.inner.model(), &redacted);
+        if self.cache.enabled {
+            match ledger.cached(&key, self.cache.ttl_days, now).await {
+                Ok(Some(response)) => {
+                    record.outcome = AuditOutcome::Cached;
+                    self.audit(&record);
+                    self.log_usage(ledger, TokenUsage::default(), true, now)
+                        .await;
+                    return Ok(rehydrated(response, &vault));
+                }
+                Ok(None) => {}
+                Err(e) => warn!("AI response cache unavailable: {:#}", e),
+            }
+        }
+
+        if self.budget.is_limited() {
+            let blocked = match ledger.spend(local_day(now)).await {
+                Ok((today, month)) => budget_exceeded(
+                    &self.budget,
+                    &today,
+                    &month,
+                    TokenUsage::estimate(&redacted, ""),
+                ),
+                // Without the history there is no way to tell whether the budget allows this call
+                Err(e) => Some(format!("usage history unavailable: {:#}", e)),
+            };
+            if let Some(reason) = blocked {
+                record.outcome = AuditOutcome::Blocked;
+                record.error = Some(reason.clone());
+                self.audit(&record);
+                return Err(LlmError::BudgetExceeded(reason));
+            }
+        }
+
+        let result = self.inner.chat(&redacted).await;
+        self.finish(&mut record, &result);
+        if let Ok(response) = &result {
+            self.log_usage(ledger, response.usage, false, now).await;
+            if self.cache.enabled {
+                if let Err(e) = ledger.store(&key, response, now).await {
+                    warn!("Failed to cache AI response: {:#}", e);
+                }
+            }
+        }
+        result.map(|response| rehydrated(response, &vault))
+    }
+
+    fn usage(&self) -> UsageTotals {
+        self.inner.usage()
+    }
+}
+
+/// Whether an error chain ends in a dry run rather than a real failure
+pub fn is_dry_run(error: &anyhow::Error) -> bool {
+    error
+        .chain()
+        .any(|cause| matches!(cause.downcast_ref::<LlmError>(), Some(LlmError::DryRun)))
+}
+
+/// Read the audit log, oldest request first
+pub fn read_audit_log(path: &Path) -> Result<Vec<AuditRecord>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read AI audit log {}", path.display()))?;
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).context("Invalid AI audit record"))
+        .collect()
+}
+
+fn rehydrated(mut response: ChatResponse, vault: &Vault) -> ChatResponse {
+    response.content = vault.rehydrate(&response.content);
+    response
+}
+
+fn append_record(path: &Path, record: &AuditRecord) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.create(true).append(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path)?;
+    let mut line = serde_json::to_vec(record)?;
+    line.push(b'\n');
+    file.write_all(&line)?;
+    Ok(())
+}
+
+fn dry_run_text(record: &AuditRecord) -> String {
+    let mut text = format!("--- AI dry run: prompt for {} (not sent) ---", record.model);
+    for message in &record.request.messages {
+        let role = serde_json::to_value(message.role)
+            .ok()
+            .and_then(|v| v.as_str().map(str::to_string))
+            .unwrap_or_default();
+        text.push_str(&format!("\n[{}]\n{}", role, message.content));
+    }
+    if !record.redactions.is_empty() {
+        let hidden: Vec<String> = record
+            .redactions
+            .iter()
+            .map(|(kind, count)| format!("{} {}", count, kind))
+            .collect();
+        text.push_str(&format!("\n--- redacted: {} ---", hidden.join(", ")));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::{ChatMessage, MockProvider};
+    use crate::config::Config;
+
+    fn ai_config(dir: &Path) -> AiConfig {
+        let mut ai = Config::default().ai;
+        ai.enabled = true;
+        ai.audit_log = Some(dir.join("audit").join("ai_audit.jsonl"));
+        ai.redaction.names = vec!["Dana Whitfield".to_string()];
+        ai
+    }
+
+    #[tokio::test]
+    async fn test_redacts_audits_and_rehydrates() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = ai_config(dir.path());
+        let mock = Arc::new(
+            MockProvider::new()
+                .with_responder(|request| Ok(format!("Echo: {}", request.messages[0].content))),
+        );
+        let provider = GuardedProvider::new(mock.clone(), &config);
+
+        let request = ChatRequest::new(vec![ChatMessage::user(
+            "Dana Whitfield <dana@example.com> paid 4111111111111234",
+        )]);
+        let response = provider.chat(&request).await.unwrap();
+
+        let sent = &mock.requests()[0].messages[0].content;
+        assert_eq!(sent, "[PERSON_1] <[EMAIL_1]> paid [ACCOUNT_1]");
+        assert_eq!(
+            response.content,
+            "Echo: Dana Whitfield <dana@example.com> paid 4111111111111234"
+        );
+
+        let records = read_audit_log(config.audit_log.as_ref().unwrap()).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].outcome, AuditOutcome::Sent);
+        assert_eq!(records[0].request.messages[0].content, *sent);
+        assert_eq!(records[0].redactions["email"], 1);
+        let raw = std::fs::read_to_string(config.audit_log.as_ref().unwrap()).unwrap();
+        assert!(!raw.contains("dana@example.com"));
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_sends_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = ai_config(dir.path());
+        config.dry_run = true;
+        let mock = Arc::new(MockProvider::new().reply("never"));
+        let provider = GuardedProvider::new(mock.clone(), &config);
+
+        let request = ChatRequest::new(vec![ChatMessage::user("balance: 5120.33")]);
+        let error = provider.chat(&request).await.unwrap_err();
+        assert!(matches!(error, LlmError::DryRun));
+        assert!(is_dry_run(
+            &anyhow::Error::new(error).context("Categorization failed")
+        ));
+        assert!(mock.requests().is_empty());
+
+        let records = read_audit_log(config.audit_log.as_ref().unwrap()).unwrap();
+        assert_eq!(records[0].outcome, AuditOutcome::DryRun);
+        assert_eq!(
+            records[0].request.messages[0].content,
+            "balance: [BALANCE_1]"
+        );
+        assert!(dry_run_text(&records[0]).contains("[user]\nbalance: [BALANCE_1]"));
+    }
+
+    #[tokio::test]
+    async fn test_caches_answers_and_enforces_budget() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = ai_config(dir.path());
+        config.budget.daily_tokens = Some(40);
+        let mock = Arc::new(
+            MockProvider::new().with_responder(|_| Ok("Dining for [PERSON_1]".to_string())),
+        );
+        let ledger = UsageLedger::open(&dir.path().join("ai_usage.db")).unwrap();
+        let provider = GuardedProvider::new(mock.clone(), &config).with_ledger(ledger);
+
+        // Re-running the same prompt is answered from the cache, and rehydrated again
+        let request = ChatRequest::new(vec![ChatMessage::user("Dana Whitfield at Corner Bistro")]);
+        for _ in 0..2 {
+            let response = provider.chat(&request).await.unwrap();
+            assert_eq!(response.content, "Dining for Dana Whitfield");
+        }
+        assert_eq!(mock.requests().len(), 1);
+
+        // The first call used most of the 40-token day; a long new prompt is refused
+        let long = ChatRequest::new(vec![ChatMessage::user("x".repeat(200))]);
+        let error = provider.chat(&long).await.unwrap_err();
+        assert!(matches!(error, LlmError::BudgetExceeded(_)));
+        assert_eq!(mock.requests().len(), 1);
+
+        let records = read_audit_log(config.audit_log.as_ref().unwrap()).unwrap();
+        let outcomes: Vec<_> = records.iter().map(|r| r.outcome).collect();
+        assert_eq!(
+            outcomes,
+            [
+                AuditOutcome::Sent,
+                AuditOutcome::Cached,
+                AuditOutcome::Blocked
+            ]
+        );
+        assert_eq!(
+            records[2].error.as_deref(),
+            Some("daily token limit of 40 reached")
+        );
+    }
+}
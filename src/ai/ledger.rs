@@ -0,0 +1,437 @@
+use super::provider::{ChatRequest, ChatResponse, TokenUsage};
+use crate::config::AiBudgetConfig;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Local, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+use sqlx::Row;
+use std::path::{Path, PathBuf};
+use tokio::sync::OnceCell;
+
+const SCHEMA: &[&str] = &[
+    "CREATE TABLE IF NOT EXISTS responses (
+        key TEXT PRIMARY KEY,
+        model TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        content TEXT NOT NULL,
+        finish_reason TEXT
+    )",
+    "CREATE TABLE IF NOT EXISTS usage (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        at TEXT NOT NULL,
+        day TEXT NOT NULL,
+        provider TEXT NOT NULL,
+        model TEXT NOT NULL,
+        cached INTEGER NOT NULL,
+        prompt_tokens INTEGER NOT NULL,
+        completion_tokens INTEGER NOT NULL,
+        cost TEXT NOT NULL
+    )",
+    "CREATE INDEX IF NOT EXISTS idx_usage_day ON usage(day)",
+];
+
+/// Requests, tokens and cost over some period
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Spend {
+    pub requests: u64,
+    /// Requests answered from the cache, included in `requests`
+    pub cached: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub cost: Decimal,
+}
+
+/// Spend on one local calendar day
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DailyUsage {
+    pub day: NaiveDate,
+    #[serde(flatten)]
+    pub spend: Spend,
+}
+
+/// What `qspec usage` shows: recent days, today and this month against the budget
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UsageReport {
+    pub days: Vec<DailyUsage>,
+    pub today: Spend,
+    pub month: Spend,
+    pub budget: AiBudgetConfig,
+}
+
+/// Response cache and usage history for AI calls, kept in a SQLite file
+///
+/// Only redacted prompts reach the ledger: keys are hashes of the prompt as
+/// sent, and cached answers still hold placeholders rather than the values
+/// they stand for. Days are local calendar days.
+pub struct UsageLedger {
+    path: PathBuf,
+    pool: SqlitePool,
+    ready: OnceCell<()>,
+}
+
+impl Spend {
+    pub fn total_tokens(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+
+    fn add(&mut self, row: &sqlx::sqlite::SqliteRow) {
+        self.requests += 1;
+        if row.get::<bool, _>("cached") {
+            self.cached += 1;
+        }
+        self.prompt_tokens += row.get::<i64, _>("prompt_tokens") as u64;
+        self.completion_tokens += row.get::<i64, _>("completion_tokens") as u64;
+        self.cost += row
+            .get::<String, _>("cost")
+            .parse::<Decimal>()
+            .unwrap_or_default();
+    }
+}
+
+impl UsageLedger {
+    /// Use the ledger at `path`; the file is created on first use
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        let options = SqliteConnectOptions::new()
+            .filename(path)
+            .create_if_missing(true);
+        Ok(Self {
+            path: path.to_path_buf(),
+            pool: SqlitePoolOptions::new()
+                .max_connections(1)
+                .connect_lazy_with(options),
+            ready: OnceCell::new(),
+        })
+    }
+
+    /// Cache key for `request` sent to `model`: whitespace differences don't matter
+    pub fn key(model: &str, request: &ChatRequest) -> String {
+        let messages: Vec<_> = request
+            .messages
+            .iter()
+            .map(|m| {
+                let content: Vec<&str> = m.content.split_whitespace().collect();
+                json!({"role": m.role, "content": content.join(" ")})
+            })
+            .collect();
+        let normalized = json!({
+            "model": model,
+            "messages": messages,
+            "temperature": request.temperature,
+            "max_tokens": request.max_tokens,
+            "json": request.json_response,
+        });
+        Sha256::digest(normalized.to_string().as_bytes())
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    /// Cached answer for `key` written at most `ttl_days` ago (0 = any age)
+    pub async fn cached(
+        &self,
+        key: &str,
+        ttl_days: u32,
+        now: DateTime<Utc>,
+    ) -> Result<Option<ChatResponse>> {
+        let pool = self.pool().await?;
+        let row = sqlx::query(
+            "SELECT model, created_at, content, finish_reason FROM responses WHERE key = ?",
+        )
+        .bind(key)
+        .fetch_optional(pool)
+        .await?;
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        let created: DateTime<Utc> = row.get::<String, _>("created_at").parse()?;
+        if ttl_days > 0 && now - created > chrono::Duration::days(ttl_days as i64) {
+            return Ok(None);
+        }
+        Ok(Some(ChatResponse {
+            content: row.get("content"),
+            model: row.get("model"),
+            usage: TokenUsage::default(),
+            finish_reason: row.get("finish_reason"),
+        }))
+    }
+
+    /// Remember `response` as the answer to `key`
+    pub async fn store(
+        &self,
+        key: &str,
+        response: &ChatResponse,
+        now: DateTime<Utc>,
+    ) -> Result<()> {
+        let pool = self.pool().await?;
+        sqlx::query(
+            "INSERT OR REPLACE INTO responses (key, model, created_at, content, finish_reason)
+             VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(key)
+        .bind(&response.model)
+        .bind(now.to_rfc3339())
+        .bind(&response.content)
+        .bind(&response.finish_reason)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Add one request to the usage history
+    pub async fn record(
+        &self,
+        provider: &str,
+        model: &str,
+        usage: TokenUsage,
+        cost: Decimal,
+        cached: bool,
+        at: DateTime<Utc>,
+    ) -> Result<()> {
+        let pool = self.pool().await?;
+        sqlx::query(
+            "INSERT INTO usage (at, day, provider, model, cached, prompt_tokens, completion_tokens, cost)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(at.to_rfc3339())
+        .bind(local_day(at).to_string())
+        .bind(provider)
+        .bind(model)
+        .bind(cached)
+        .bind(usage.prompt_tokens as i64)
+        .bind(usage.completion_tokens as i64)
+        .bind(cost.to_string())
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Spend per day from `from` to `to`, oldest first; days without requests are left out
+    pub async fn daily(&self, from: NaiveDate, to: NaiveDate) -> Result<Vec<DailyUsage>> {
+        let pool = self.pool().await?;
+        let rows = sqlx::query(
+            "SELECT day, cached, prompt_tokens, completion_tokens, cost FROM usage
+             WHERE day >= ? AND day <= ? ORDER BY day",
+        )
+        .bind(from.to_string())
+        .bind(to.to_string())
+        .fetch_all(pool)
+        .await?;
+
+        let mut days: Vec<DailyUsage> = Vec::new();
+        for row in rows {
+            let day: NaiveDate = row.get::<String, _>("day").parse()?;
+            if days.last().is_none_or(|d| d.day != day) {
+                days.push(DailyUsage {
+                    day,
+                    spend: Spend::default(),
+                });
+            }
+            if let Some(last) = days.last_mut() {
+                last.spend.add(&row);
+            }
+        }
+        Ok(days)
+    }
+
+    /// Spend on `day` and in its calendar month
+    pub async fn spend(&self, day: NaiveDate) -> Result<(Spend, Spend)> {
+        let first = day.with_day(1).unwrap_or(day);
+        let days = self.daily(first, day).await?;
+        let mut month = Spend::default();
+        let mut today = Spend::default();
+        for entry in days {
+            let spend = entry.spend;
+            month.requests += spend.requests;
+            month.cached += spend.cached;
+            month.prompt_tokens += spend.prompt_tokens;
+            month.completion_tokens += spend.completion_tokens;
+            month.cost += spend.cost;
+            if entry.day == day {
+                today = spend;
+            }
+        }
+        Ok((today, month))
+    }
+
+    /// The last `days` days up to `today`, with today's and this month's totals
+    pub async fn report(
+        &self,
+        today: NaiveDate,
+        days: u32,
+        budget: &AiBudgetConfig,
+    ) -> Result<UsageReport> {
+        let from = today - chrono::Duration::days(days.saturating_sub(1) as i64);
+        let (today_spend, month) = self.spend(today).await?;
+        Ok(UsageReport {
+            days: self.daily(from, today).await?,
+            today: today_spend,
+            month,
+            budget: budget.clone(),
+        })
+    }
+
+    async fn pool(&self) -> Result<&SqlitePool> {
+        self.ready
+            .get_or_try_init(|| async {
+                for statement in SCHEMA {
+                    sqlx::query(statement).execute(&self.pool).await?;
+                }
+                Ok::<_, sqlx::Error>(())
+            })
+            .await
+            .with_context(|| format!("Failed to open AI usage database {}", self.path.display()))?;
+        Ok(&self.pool)
+    }
+}
+
+/// The limit that sending `next` would break, if any
+pub fn budget_exceeded(
+    budget: &AiBudgetConfig,
+    today: &Spend,
+    month: &Spend,
+    next: TokenUsage,
+) -> Option<String> {
+    let tokens = next.total();
+    let cost = budget.cost(next.prompt_tokens, next.completion_tokens);
+    let checks = [
+        (
+            "daily token",
+            budget.daily_tokens.map(Decimal::from),
+            Decimal::from(today.total_tokens() + tokens),
+        ),
+        (
+            "monthly token",
+            budget.monthly_tokens.map(Decimal::from),
+            Decimal::from(month.total_tokens() + tokens),
+        ),
+        ("daily cost", budget.daily_cost, today.cost + cost),
+        ("monthly cost", budget.monthly_cost, month.cost + cost),
+    ];
+    checks.into_iter().find_map(|(name, limit, total)| {
+        let limit = limit?;
+        (total > limit).then(|| format!("{} limit of {} reached", name, limit))
+    })
+}
+
+/// Local calendar day of `at`
+pub fn local_day(at: DateTime<Utc>) -> NaiveDate {
+    at.with_timezone(&Local).date_naive()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::ChatMessage;
+    use rust_decimal_macros::dec;
+
+    fn usage(prompt_tokens: u64, completion_tokens: u64) -> TokenUsage {
+        TokenUsage {
+            prompt_tokens,
+            completion_tokens,
+            estimated: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cache_and_usage_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let ledger = UsageLedger::open(&dir.path().join("ai").join("usage.db")).unwrap();
+        let now = Utc::now();
+
+        let request = ChatRequest::new(vec![ChatMessage::user("Categorize  these\n payees")]);
+        let same = ChatRequest::new(vec![ChatMessage::user("Categorize these payees")]);
+        let key = UsageLedger::key("gpt-4o-mini", &request);
+        assert_eq!(key, UsageLedger::key("gpt-4o-mini", &same));
+        assert_ne!(key, UsageLedger::key("llama3.1:8b", &same));
+        assert_ne!(key, UsageLedger::key("gpt-4o-mini", &same.clone().json()));
+
+        assert!(ledger.cached(&key, 30, now).await.unwrap().is_none());
+        let response = ChatResponse {
+            content: "{}".to_string(),
+            model: "gpt-4o-mini".to_string(),
+            usage: usage(100, 20),
+            finish_reason: Some("stop".to_string()),
+        };
+        ledger.store(&key, &response, now).await.unwrap();
+        let cached = ledger.cached(&key, 30, now).await.unwrap().unwrap();
+        assert_eq!(cached.content, "{}");
+        assert_eq!(cached.usage, TokenUsage::default());
+        let later = now + chrono::Duration::days(31);
+        assert!(ledger.cached(&key, 30, later).await.unwrap().is_none());
+        assert!(ledger.cached(&key, 0, later).await.unwrap().is_some());
+
+        let a_month_ago = now - chrono::Duration::days(40);
+        for (at, cached) in [(now, false), (now, true), (a_month_ago, false)] {
+            let (tokens, cost) = if cached {
+                (usage(0, 0), dec!(0))
+            } else {
+                (usage(1000, 200), dec!(0.01))
+            };
+            ledger
+                .record("openai", "gpt-4o-mini", tokens, cost, cached, at)
+                .await
+                .unwrap();
+        }
+        let (today, month) = ledger.spend(local_day(now)).await.unwrap();
+        assert_eq!(today.requests, 2);
+        assert_eq!(today.cached, 1);
+        assert_eq!(today.total_tokens(), 1200);
+        assert_eq!(today.cost, dec!(0.01));
+        assert_eq!(month, today);
+
+        let report = ledger
+            .report(local_day(now), 60, &AiBudgetConfig::default())
+            .await
+            .unwrap();
+        assert_eq!(report.days.len(), 2);
+        assert_eq!(report.days[0].day, local_day(a_month_ago));
+    }
+
+    #[test]
+    fn test_budget_limits() {
+        let budget = AiBudgetConfig {
+            daily_tokens: Some(10_000),
+            monthly_cost: Some(dec!(1.00)),
+            prompt_price: dec!(0.15),
+            completion_price: dec!(0.60),
+            ..AiBudgetConfig::default()
+        };
+        assert_eq!(budget.cost(1_000_000, 500_000), dec!(0.45));
+
+        let today = Spend {
+            requests: 3,
+            prompt_tokens: 8000,
+            completion_tokens: 1000,
+            cost: dec!(0.50),
+            ..Spend::default()
+        };
+        let month = Spend {
+            cost: dec!(0.90),
+            ..today
+        };
+        assert_eq!(
+            budget_exceeded(&budget, &today, &month, usage(500, 0)),
+            None
+        );
+        assert_eq!(
+            budget_exceeded(&budget, &today, &month, usage(1500, 0)).unwrap(),
+            "daily token limit of 10000 reached"
+        );
+        let month = Spend {
+            cost: dec!(1.00),
+            ..today
+        };
+        assert_eq!(
+            budget_exceeded(&budget, &today, &month, usage(10, 0)).unwrap(),
+            "monthly cost limit of 1.00 reached"
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use super::provider::{
+    ChatRequest, ChatResponse, LlmError, LlmProvider, TokenUsage, UsageMeter, UsageTotals,
+};
+use async_trait::async_trait;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+type Responder = Box<dyn Fn(&ChatRequest) -> Result<String, LlmError> + Send + Sync>;
+
+/// Deterministic provider for tests and offline runs
+///
+/// Scripted replies are returned in order; once they run out the responder,
+/// if any, answers. Every request is recorded for inspection.
+#[derive(Default)]
+pub struct MockProvider {
+    replies: Mutex<VecDeque<Result<String, LlmError>>>,
+    responder: Option<Responder>,
+    requests: Mutex<Vec<ChatRequest>>,
+    meter: UsageMeter,
+}
+
+impl MockProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue a reply
+    pub fn reply(self, content: impl Into<String>) -> Self {
+        self.replies.lock().unwrap().push_back(Ok(content.into()));
+        self
+    }
+
+    /// Queue a failure
+    pub fn fail(self, error: LlmError) -> Self {
+        self.replies.lock().unwrap().push_back(Err(error));
+        self
+    }
+
+    /// Answer requests that have no scripted reply
+    pub fn with_responder(
+        mut self,
+        responder: impl Fn(&ChatRequest) -> Result<String, LlmError> + Send + Sync + 'static,
+    ) -> Self {
+        self.responder = Some(Box::new(responder));
+        self
+    }
+
+    /// Requests received so far, oldest first
+    pub fn requests(&self) -> Vec<ChatRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl LlmProvider for MockProvider {
+    fn name(&self) -> &str {
+        "mock"
+    }
+
+    fn model(&self) -> &str {
+        "mock"
+    }
+
+    async fn chat(&self, request: &ChatRequest) -> Result<ChatResponse, LlmError> {
+        self.requests.lock().unwrap().push(request.clone());
+        let scripted = self.replies.lock().unwrap().pop_front();
+        let content = match (scripted, &self.responder) {
+            (Some(reply), _) => reply?,
+            (None, Some(responder)) => responder(request)?,
+            (None, None) => {
+                return Err(LlmError::NotConfigured(
+                    "mock provider has no reply scripted".to_string(),
+                ))
+            }
+        };
+
+        let usage = TokenUsage::estimate(request, &content);
+        self.meter.record(usage);
+        Ok(ChatResponse {
+            content,
+            model: self.model().to_string(),
+            usage,
+            finish_reason: Some("stop".to_string()),
+        })
+    }
+
+    fn usage(&self) -> UsageTotals {
+        self.meter.totals()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::ChatMessage;
+
+    #[tokio::test]
+    async fn test_mock_replies_in_order_then_responds() {
+        let provider = MockProvider::new()
+            .reply("first")
+            .fail(LlmError::InvalidResponse("garbled".to_string()))
+            .with_responder(|request| Ok(format!("echo {}", request.messages[0].content)));
+        let request = ChatRequest::new(vec![ChatMessage::user("ping")]);
+
+        assert_eq!(provider.chat(&request).await.unwrap().content, "first");
+        assert!(provider.chat(&request).await.is_err());
+        assert_eq!(provider.chat(&request).await.unwrap().content, "echo ping");
+
+        assert_eq!(provider.requests().len(), 3);
+        let usage = provider.usage();
+        assert_eq!(usage.requests, 2);
+        assert_eq!(usage.prompt_tokens, 2);
+
+        assert!(MockProvider::new().chat(&request).await.is_err());
+    }
+}
@@ -0,0 +1,86 @@
+//! Language-model access for AI-assisted analysis: the [`LlmProvider`] trait,
+//! an OpenAI-compatible client for hosted and self-hosted endpoints, a
+//! scripted provider for tests, the redaction, audit, caching and budget layer
+//! every prompt passes through, and the features built on them.
+
+mod assistant;
+mod categorizer;
+mod extract;
+mod guard;
+mod ledger;
+mod mock;
+mod narrative;
+mod openai;
+mod provider;
+mod question;
+mod redact;
+
+pub use assistant::{AgentRun, Assistant, AutoConfirm, Confirm, StepOutcome, TraceStep};
+pub use categorizer::LlmCategorizer;
+pub use extract::{Extraction, ExtractionReport, RejectedCandidate, TextExtractor, MAX_TEXT_CHARS};
+pub use guard::{is_dry_run, read_audit_log, AuditOutcome, AuditRecord, GuardedProvider};
+pub use ledger::{budget_exceeded, DailyUsage, Spend, UsageLedger, UsageReport};
+pub use mock::MockProvider;
+pub use narrative::{
+    AnomalyFact, CategoryChange, MonthFacts, Narrative, NarrativeSource, NarrativeWriter,
+    TRAILING_MONTHS,
+};
+pub use openai::OpenAiProvider;
+pub use provider::{
+    ChatMessage, ChatRequest, ChatResponse, LlmError, LlmProvider, Role, TokenUsage, UsageMeter,
+    UsageTotals,
+};
+pub use question::{Answer, QuestionAnswerer};
+pub use redact::{PiiKind, PromptRedactor, Vault};
+
+use crate::config::{AiConfig, Config};
+use anyhow::Result;
+use std::sync::Arc;
+
+/// Provider described by `config`, or `None` when AI is disabled
+///
+/// The client is always wrapped in a [`GuardedProvider`], so redaction, the
+/// audit log, dry runs, the response cache and budgets apply to every feature.
+pub fn provider_from_config(config: &Config) -> Result<Option<Arc<dyn LlmProvider>>> {
+    if !config.ai.enabled {
+        return Ok(None);
+    }
+    let config = guarded_config(config);
+    let client = Arc::new(OpenAiProvider::new(&config)?);
+    let mut provider = GuardedProvider::new(client, &config);
+    if let Some(path) = &config.usage_db {
+        provider = provider.with_ledger(UsageLedger::open(path)?);
+    }
+    Ok(Some(Arc::new(provider)))
+}
+
+/// AI settings for the configured store
+///
+/// The audit log and the ledger's response cache hold prompt and answer text
+/// in plaintext, so neither is kept for an encrypted store.
+fn guarded_config(config: &Config) -> AiConfig {
+    let mut ai = config.ai.clone();
+    if config.database.encryption.enabled {
+        ai.audit_log = None;
+        ai.cache.enabled = false;
+    }
+    ai
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypted_store_keeps_no_plaintext_prompts() {
+        let mut config = Config::default();
+        config.ai.cache.enabled = true;
+        let ai = guarded_config(&config);
+        assert!(ai.audit_log.is_some() && ai.cache.enabled);
+
+        config.database.encryption.enabled = true;
+        let ai = guarded_config(&config);
+        assert!(ai.audit_log.is_none() && !ai.cache.enabled);
+        assert_eq!(ai.usage_db, config.ai.usage_db);
+    }
+}
@@ -0,0 +1,567 @@
+use super::provider::{ChatMessage, ChatRequest, LlmProvider};
+use crate::analysis::{AnalysisEngine, MonthlyReport};
+use crate::categorize::payee_of;
+use crate::data::{FinancialData, TransactionType};
+use crate::utils::format_currency;
+use anyhow::{Context, Result};
+use chrono::{Datelike, NaiveDate};
+use regex::Regex;
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, OnceLock};
+use tracing::debug;
+
+/// Months the current month is compared against by default
+pub const TRAILING_MONTHS: u32 = 6;
+
+const SYSTEM_PROMPT: &str = "You write a short plain-English summary of a household's month \
+from the JSON `facts` you are given. Write two to four sentences with no headings or lists. \
+Mention the categories that moved most against their trailing average and the large charges \
+behind them. Every number you write must appear in `facts`: use `change_percent` as given \
+(you may round it to a whole number; a negative value means spending went down), write amounts exactly as given or rounded to whole units, \
+and never compute new figures such as differences or ratios.";
+
+/// Spending in one category against its trailing average
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CategoryChange {
+    pub category: String,
+    pub spent: Decimal,
+    pub trailing_average: Decimal,
+    /// Change against the average in percent, one decimal; `None` when the average is zero
+    pub change_percent: Option<Decimal>,
+    /// Anomalies this month in the category
+    pub anomalies: usize,
+}
+
+/// A charge flagged by anomaly detection
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnomalyFact {
+    pub date: NaiveDate,
+    pub payee: String,
+    pub category: Option<String>,
+    pub amount: Decimal,
+}
+
+/// Everything a narrative may say about a month, computed locally
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MonthFacts {
+    pub year: i32,
+    pub month: u32,
+    pub currency: String,
+    pub trailing_months: u32,
+    pub total_income: Decimal,
+    pub total_expenses: Decimal,
+    pub net_income: Decimal,
+    pub transaction_count: usize,
+    /// Spending categories, largest first
+    pub categories: Vec<CategoryChange>,
+    pub anomalies: Vec<AnomalyFact>,
+}
+
+/// Who wrote the summary
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NarrativeSource {
+    Model,
+    /// Built locally because the model's text could not be verified or was not requested
+    Template,
+}
+
+/// A month's summary with the figures it was checked against
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Narrative {
+    pub facts: MonthFacts,
+    pub summary: String,
+    pub source: NarrativeSource,
+    /// Numbers in the model's last draft that the facts do not support
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unsupported: Vec<String>,
+}
+
+/// Writes monthly summaries whose every number is checked against [`MonthFacts`]
+///
+/// The model sees the month's totals, each spending category against its
+/// trailing average and the month's anomalies. A draft quoting any number not
+/// backed by those facts is sent back once; if the second draft fails too, a
+/// locally built summary is used instead.
+pub struct NarrativeWriter {
+    provider: Arc<dyn LlmProvider>,
+}
+
+impl MonthFacts {
+    /// Compute the facts for `year`-`month` against the `trailing_months` before it
+    pub fn build(
+        data: &FinancialData,
+        year: i32,
+        month: u32,
+        trailing_months: u32,
+    ) -> Result<Self> {
+        let report: MonthlyReport = AnalysisEngine::generate_monthly_report(data, year, month)?;
+        let first = NaiveDate::from_ymd_opt(year, month, 1)
+            .with_context(|| format!("Invalid month {}-{:02}", year, month))?;
+
+        // (category, months back) -> spending; 0 is the reported month
+        let mut spending: BTreeMap<&str, Vec<Decimal>> = BTreeMap::new();
+        for transaction in &data.transactions {
+            if !matches!(transaction.transaction_type, TransactionType::Debit) {
+                continue;
+            }
+            let Some(category) = transaction.category.as_deref() else {
+                continue;
+            };
+            let date = transaction.date.date_naive();
+            let back =
+                (first.year() - date.year()) * 12 + first.month() as i32 - date.month() as i32;
+            if (0..=trailing_months as i32).contains(&back) {
+                spending
+                    .entry(category)
+                    .or_insert_with(|| vec![Decimal::ZERO; trailing_months as usize + 1])
+                    [back as usize] += transaction.amount;
+            }
+        }
+
+        let anomalies: Vec<AnomalyFact> = AnalysisEngine::detect_anomalies(data)?
+            .into_iter()
+            .filter(|t| t.date.year() == year && t.date.month() == month)
+            .map(|t| AnomalyFact {
+                date: t.date.date_naive(),
+                payee: payee_of(t).to_string(),
+                category: t.category.clone(),
+                amount: t.amount,
+            })
+            .collect();
+
+        let mut categories: Vec<CategoryChange> = spending
+            .into_iter()
+            .filter(|(_, months)| !months[0].is_zero())
+            .map(|(category, months)| {
+                let spent = months[0];
+                let trailing_average = if trailing_months == 0 {
+                    Decimal::ZERO
+                } else {
+                    (months[1..].iter().sum::<Decimal>() / Decimal::from(trailing_months))
+                        .round_dp(2)
+                };
+                let change_percent = (!trailing_average.is_zero()).then(|| {
+                    ((spent - trailing_average) / trailing_average * Decimal::ONE_HUNDRED)
+                        .round_dp(1)
+                });
+                CategoryChange {
+                    category: category.to_string(),
+                    spent,
+                    trailing_average,
+                    change_percent,
+                    anomalies: anomalies
+                        .iter()
+                        .filter(|a| a.category.as_deref() == Some(category))
+                        .count(),
+                }
+            })
+            .collect();
+        categories.sort_by(|a, b| {
+            b.spent
+                .cmp(&a.spent)
+                .then_with(|| a.category.cmp(&b.category))
+        });
+
+        Ok(Self {
+            year,
+            month,
+            currency: crate::output::display_currency(data).to_string(),
+            trailing_months,
+            total_income: report.total_income,
+            total_expenses: report.total_expenses,
+            net_income: report.net_income,
+            transaction_count: report.transaction_count,
+            categories,
+            anomalies,
+        })
+    }
+
+    /// Numbers in `text` that these facts do not support
+    ///
+    /// A number is supported when some figure here, ignoring its sign, equals
+    /// it after rounding to the number's own decimal places. Percentages must
+    /// match a `change_percent`, and a direction word next to one (up, fell,
+    /// lower, ...) must agree with its sign. Payee and category names are
+    /// skipped so digits inside them don't count.
+    pub fn unsupported_numbers(&self, text: &str) -> Vec<String> {
+        static NUMBER: OnceLock<Regex> = OnceLock::new();
+        let number = NUMBER
+            .get_or_init(|| Regex::new(r"\d{1,3}(?:,\d{3})+(?:\.\d+)?|\d+(?:\.\d+)?").unwrap());
+
+        let mut names: Vec<&str> = self
+            .categories
+            .iter()
+            .map(|c| c.category.as_str())
+            .chain(self.anomalies.iter().map(|a| a.payee.as_str()))
+            .collect();
+        names.sort_by_key(|name| std::cmp::Reverse(name.len()));
+        let mut text = text.to_string();
+        for name in names.into_iter().filter(|n| !n.is_empty()) {
+            if let Ok(pattern) = Regex::new(&format!("(?i){}", regex::escape(name))) {
+                text = pattern.replace_all(&text, " ").into_owned();
+            }
+        }
+
+        let figures = self.figures();
+        let changes: Vec<Decimal> = self
+            .categories
+            .iter()
+            .filter_map(|c| c.change_percent)
+            .collect();
+        number
+            .find_iter(&text)
+            .filter(|m| {
+                let Ok(value) = m.as_str().replace(',', "").parse::<Decimal>() else {
+                    return true;
+                };
+                let rest = text[m.end()..].trim_start();
+                let is_percent =
+                    rest.starts_with('%') || rest.to_lowercase().starts_with("percent");
+                if !is_percent {
+                    return !figures.iter().any(|figure| rounds_to(*figure, value));
+                }
+                let said = directions(&text[..m.start()], rest);
+                !changes.iter().any(|change| {
+                    rounds_to(change.abs(), value)
+                        && said.iter().all(|up| *up == change.is_sign_positive())
+                })
+            })
+            .map(|m| m.as_str().to_string())
+            .collect()
+    }
+
+    /// Every figure a summary may quote, as absolute values
+    fn figures(&self) -> Vec<Decimal> {
+        let mut figures = vec![
+            Decimal::from(self.year),
+            Decimal::from(self.month),
+            Decimal::from(self.trailing_months),
+            self.total_income,
+            self.total_expenses,
+            self.net_income,
+            Decimal::from(self.transaction_count),
+            Decimal::from(self.categories.len()),
+            Decimal::from(self.anomalies.len()),
+        ];
+        for category in &self.categories {
+            figures.extend([
+                category.spent,
+                category.trailing_average,
+                Decimal::from(category.anomalies),
+            ]);
+            figures.extend(category.change_percent);
+        }
+        for anomaly in &self.anomalies {
+            figures.extend([
+                anomaly.amount,
+                Decimal::from(anomaly.date.day()),
+                Decimal::from(anomaly.date.year()),
+            ]);
+        }
+        figures.into_iter().map(|f| f.abs()).collect()
+    }
+
+    /// Summary written from the facts alone, used when no verified model text is available
+    pub fn template_summary(&self) -> String {
+        let money = |amount: Decimal| format_currency(amount, &self.currency);
+        let month = NaiveDate::from_ymd_opt(self.year, self.month, 1)
+            .map(|d| d.format("%B %Y").to_string())
+            .unwrap_or_else(|| format!("{}-{:02}", self.year, self.month));
+        let mut summary = format!(
+            "In {} income was {} and spending {}, for a net of {}.",
+            month,
+            money(self.total_income),
+            money(self.total_expenses),
+            money(self.net_income)
+        );
+
+        let biggest = self
+            .categories
+            .iter()
+            .filter_map(|c| c.change_percent.map(|p| (c, p)))
+            .max_by_key(|(_, percent)| percent.abs());
+        if let Some((category, percent)) = biggest {
+            summary.push_str(&format!(
+                " {} was {} {}% against its {}-month average of {}.",
+                category.category,
+                if percent.is_sign_negative() {
+                    "down"
+                } else {
+                    "up"
+                },
+                percent.abs(),
+                self.trailing_months,
+                money(category.trailing_average)
+            ));
+        }
+        if let Some(largest) = self.anomalies.iter().max_by_key(|a| a.amount) {
+            summary.push_str(&format!(
+                " The largest unusual charge was {} at {} on {}.",
+                money(largest.amount),
+                largest.payee,
+                largest.date
+            ));
+        }
+        summary
+    }
+}
+
+/// Whether `value` is `figure` rounded to `value`'s decimal places
+fn rounds_to(figure: Decimal, value: Decimal) -> bool {
+    let scale = value.scale();
+    figure.round_dp(scale) == value
+        || figure.round_dp_with_strategy(scale, RoundingStrategy::MidpointAwayFromZero) == value
+}
+
+/// Directions stated within a few words of a percentage, `true` for up
+fn directions(before: &str, after: &str) -> Vec<bool> {
+    const UP: &[&str] = &[
+        "up",
+        "rose",
+        "risen",
+        "rising",
+        "increase",
+        "increased",
+        "higher",
+        "more",
+        "above",
+        "jumped",
+        "grew",
+        "climbed",
+    ];
+    const DOWN: &[&str] = &[
+        "down",
+        "fell",
+        "fallen",
+        "falling",
+        "decrease",
+        "decreased",
+        "lower",
+        "less",
+        "below",
+        "dropped",
+        "declined",
+    ];
+    let ends_clause = |word: &str| word.ends_with([',', '.', ';', ':', '!', '?']);
+
+    // Words back to the start of the clause, then forward to its end
+    let before = before
+        .split_whitespace()
+        .rev()
+        .take_while(|word| !ends_clause(word))
+        .take(3);
+    let mut after_words = Vec::new();
+    for word in after.trim_start_matches('%').split_whitespace().take(3) {
+        after_words.push(word);
+        if ends_clause(word) {
+            break;
+        }
+    }
+
+    before
+        .chain(after_words)
+        .filter_map(|word| {
+            let word = word
+                .trim_matches(|c: char| !c.is_alphabetic())
+                .to_lowercase();
+            if UP.contains(&word.as_str()) {
+                Some(true)
+            } else if DOWN.contains(&word.as_str()) {
+                Some(false)
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+impl NarrativeWriter {
+    pub fn new(provider: Arc<dyn LlmProvider>) -> Self {
+        Self { provider }
+    }
+
+    /// Summarize `facts`, falling back to the template if no draft can be verified
+    pub async fn write(&self, facts: MonthFacts) -> Result<Narrative> {
+        let mut messages = vec![
+            ChatMessage::system(SYSTEM_PROMPT),
+            ChatMessage::user(
+                serde_json::to_string(&facts).context("Failed to serialize month facts")?,
+            ),
+        ];
+
+        let mut unsupported = Vec::new();
+        for attempt in 0..2 {
+            let request = ChatRequest::new(messages.clone()).with_temperature(0.2);
+            let response = self
+                .provider
+                .chat(&request)
+                .await
+                .context("Narrative summary failed")?;
+            let draft = response.content.trim().to_string();
+
+            unsupported = facts.unsupported_numbers(&draft);
+            if unsupported.is_empty() && !draft.is_empty() {
+                return Ok(Narrative {
+                    facts,
+                    summary: draft,
+                    source: NarrativeSource::Model,
+                    unsupported,
+                });
+            }
+            if attempt == 0 {
+                debug!("Narrative quotes unsupported numbers: {:?}", unsupported);
+                messages.push(ChatMessage::assistant(draft));
+                messages.push(ChatMessage::user(format!(
+                    "These numbers are not in the facts: {}. Rewrite the summary using only figures from the facts.",
+                    unsupported.join(", ")
+                )));
+            }
+        }
+
+        let mut narrative = Narrative::template(facts);
+        narrative.unsupported = unsupported;
+        Ok(narrative)
+    }
+}
+
+impl Narrative {
+    /// Narrative built without the model, e.g. when figures may not be shared
+    pub fn template(facts: MonthFacts) -> Self {
+        Self {
+            summary: facts.template_summary(),
+            facts,
+            source: NarrativeSource::Template,
+            unsupported: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::MockProvider;
+    use crate::data::{Account, AccountType, Transaction};
+    use chrono::{TimeZone, Utc};
+    use rust_decimal_macros::dec;
+
+    fn books() -> FinancialData {
+        let mut data = FinancialData::new();
+        let account = Account::new(
+            "Checking".to_string(),
+            AccountType::Checking,
+            dec!(0),
+            "USD".to_string(),
+        );
+        let mut add = |month: u32, day: u32, amount: Decimal, payee: &str, kind| {
+            let category = if kind == TransactionType::Credit {
+                "Salary"
+            } else {
+                "Dining"
+            };
+            let mut t = Transaction::new(
+                account.id,
+                Utc.with_ymd_and_hms(2026, month, day, 12, 0, 0).unwrap(),
+                amount,
+                payee.to_string(),
+                kind,
+            );
+            t.payee = Some(payee.to_string());
+            t.category = Some(category.to_string());
+            data.add_transaction(t);
+        };
+        // Dining averages 100.00 over March to August, then September has a big night out
+        for month in 3..=8 {
+            add(
+                month,
+                10,
+                dec!(100.00),
+                "Corner Bistro",
+                TransactionType::Debit,
+            );
+        }
+        add(9, 4, dec!(40.00), "Corner Bistro", TransactionType::Debit);
+        add(9, 19, dec!(1800.00), "Hotel 1898", TransactionType::Debit);
+        add(9, 1, dec!(4000.00), "Acme Payroll", TransactionType::Credit);
+        data.add_account(account);
+        data
+    }
+
+    #[test]
+    fn test_facts_and_number_checks() {
+        let facts = MonthFacts::build(&books(), 2026, 9, TRAILING_MONTHS).unwrap();
+        assert_eq!(facts.total_expenses, dec!(1840.00));
+        assert_eq!(facts.net_income, dec!(2160.00));
+        let dining = &facts.categories[0];
+        assert_eq!(dining.category, "Dining");
+        assert_eq!(dining.trailing_average, dec!(100.00));
+        assert_eq!(dining.change_percent, Some(dec!(1740.0)));
+        assert_eq!(dining.anomalies, 1);
+        assert_eq!(facts.anomalies[0].payee, "Hotel 1898");
+
+        assert!(facts
+            .unsupported_numbers(
+                "Dining was up 1,740% vs your 6-month average of $100, driven by one $1,800.00 \
+                 charge at Hotel 1898 on September 19, 2026."
+            )
+            .is_empty());
+        assert_eq!(
+            facts.unsupported_numbers("You spent $1,700 more than usual, about 18x."),
+            ["1,700", "18"]
+        );
+
+        // Percentages must point the same way as the change they quote
+        assert_eq!(
+            facts.unsupported_numbers("Dining fell 1,740% against the average."),
+            ["1,740"]
+        );
+        assert_eq!(
+            facts.unsupported_numbers("Dining was 1740% lower, on 4000 of income."),
+            ["1740"]
+        );
+        assert_eq!(facts.unsupported_numbers("Income was 2160%."), ["2160"]);
+
+        // Rounding is accepted, truncation is not
+        let mut down = facts.clone();
+        down.categories[0].change_percent = Some(dec!(-12.7));
+        assert!(down
+            .unsupported_numbers("Dining was down 12.7%, or 13% less than usual.")
+            .is_empty());
+        assert_eq!(down.unsupported_numbers("Dining fell 12%."), ["12"]);
+        assert_eq!(down.unsupported_numbers("Dining rose 13%."), ["13"]);
+
+        let template = facts.template_summary();
+        assert!(template.contains("Dining was up 1740.0% against its 6-month average of $100.00"));
+        assert!(facts.unsupported_numbers(&template).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_unverifiable_drafts_fall_back_to_template() {
+        let facts = MonthFacts::build(&books(), 2026, 9, TRAILING_MONTHS).unwrap();
+
+        let provider = Arc::new(
+            MockProvider::new()
+                .reply("Dining cost $1,840 this month, 12 times the usual.")
+                .reply("Dining cost $1,840.00 this month, up 1740% on the 6-month average."),
+        );
+        let narrative = NarrativeWriter::new(provider.clone())
+            .write(facts.clone())
+            .await
+            .unwrap();
+        assert_eq!(narrative.source, NarrativeSource::Model);
+        assert!(provider.requests()[1].messages[3]
+            .content
+            .contains("not in the facts: 12."));
+
+        let provider = Arc::new(MockProvider::new().reply("Up 55%.").reply("Up 56%."));
+        let narrative = NarrativeWriter::new(provider)
+            .write(facts.clone())
+            .await
+            .unwrap();
+        assert_eq!(narrative.source, NarrativeSource::Template);
+        assert_eq!(narrative.unsupported, ["56"]);
+        assert_eq!(narrative.summary, facts.template_summary());
+    }
+}
@@ -0,0 +1,406 @@
+use super::provider::{
+    ChatMessage, ChatRequest, ChatResponse, LlmError, LlmProvider, TokenUsage, UsageMeter,
+    UsageTotals,
+};
+use crate::config::AiConfig;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// Error bodies longer than this are cut short in error messages
+const MAX_ERROR_BODY: usize = 500;
+
+/// Client for any endpoint speaking the OpenAI chat-completions protocol
+///
+/// Works with hosted APIs and with self-hosted servers such as llama.cpp,
+/// Ollama or vLLM. Timeouts, rate limits (429) and server errors are retried
+/// with exponential backoff, honouring `Retry-After` up to the request timeout.
+pub struct OpenAiProvider {
+    client: reqwest::Client,
+    url: String,
+    api_key: Option<String>,
+    model: String,
+    timeout: Duration,
+    max_retries: u32,
+    backoff: Duration,
+    meter: UsageMeter,
+}
+
+#[derive(Serialize)]
+struct CompletionRequest<'a> {
+    model: &'a str,
+    messages: &'a [ChatMessage],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<ResponseFormat>,
+}
+
+#[derive(Serialize)]
+struct ResponseFormat {
+    #[serde(rename = "type")]
+    kind: &'static str,
+}
+
+#[derive(Deserialize)]
+struct CompletionResponse {
+    model: Option<String>,
+    #[serde(default)]
+    choices: Vec<Choice>,
+    usage: Option<WireUsage>,
+}
+
+#[derive(Deserialize)]
+struct Choice {
+    message: WireMessage,
+    finish_reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct WireMessage {
+    content: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct WireUsage {
+    prompt_tokens: u64,
+    completion_tokens: u64,
+}
+
+impl OpenAiProvider {
+    /// Build a client from `[ai]`; `api_endpoint` is the base URL, e.g. `https://api.openai.com/v1`
+    pub fn new(config: &AiConfig) -> Result<Self> {
+        let endpoint = config
+            .api_endpoint
+            .as_deref()
+            .context("ai.api_endpoint is not set")?;
+        let client = reqwest::Client::builder()
+            .build()
+            .context("Failed to build HTTP client")?;
+
+        Ok(Self {
+            client,
+            url: completions_url(endpoint),
+            api_key: config.resolve_api_key(),
+            model: config.model.clone(),
+            timeout: Duration::from_secs(config.timeout_secs),
+            max_retries: config.max_retries,
+            backoff: Duration::from_millis(config.retry_backoff_ms),
+            meter: UsageMeter::default(),
+        })
+    }
+
+    async fn send(&self, request: &ChatRequest) -> Result<ChatResponse, LlmError> {
+        let body = CompletionRequest {
+            model: &self.model,
+            messages: &request.messages,
+            temperature: request.temperature,
+            max_tokens: request.max_tokens,
+            response_format: request.json_response.then_some(ResponseFormat {
+                kind: "json_object",
+            }),
+        };
+
+        let mut http = self
+            .client
+            .post(&self.url)
+            .timeout(self.timeout)
+            .json(&body);
+        if let Some(key) = &self.api_key {
+            http = http.bearer_auth(key);
+        }
+
+        let response = http.send().await.map_err(|e| self.transport_error(e))?;
+        let status = response.status();
+        if !status.is_success() {
+            let retry_after = response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.trim().parse::<u64>().ok())
+                .map(Duration::from_secs);
+            let mut body = response.text().await.unwrap_or_default();
+            if body.len() > MAX_ERROR_BODY {
+                let cut = (0..=MAX_ERROR_BODY)
+                    .rev()
+                    .find(|i| body.is_char_boundary(*i))
+                    .unwrap_or(0);
+                body.truncate(cut);
+                body.push('…');
+            }
+            return Err(LlmError::Status {
+                status: status.as_u16(),
+                body,
+                retry_after,
+            });
+        }
+
+        let parsed: CompletionResponse = response.json().await.map_err(|e| {
+            if e.is_timeout() {
+                LlmError::Timeout(self.timeout)
+            } else {
+                LlmError::InvalidResponse(e.to_string())
+            }
+        })?;
+        let choice = parsed
+            .choices
+            .into_iter()
+            .next()
+            .ok_or_else(|| LlmError::InvalidResponse("no choices returned".to_string()))?;
+        let content = choice.message.content.unwrap_or_default();
+        let usage = match parsed.usage {
+            Some(usage) => TokenUsage {
+                prompt_tokens: usage.prompt_tokens,
+                completion_tokens: usage.completion_tokens,
+                estimated: false,
+            },
+            None => TokenUsage::estimate(request, &content),
+        };
+
+        Ok(ChatResponse {
+            content,
+            model: parsed.model.unwrap_or_else(|| self.model.clone()),
+            usage,
+            finish_reason: choice.finish_reason,
+        })
+    }
+
+    fn transport_error(&self, error: reqwest::Error) -> LlmError {
+        if error.is_timeout() {
+            LlmError::Timeout(self.timeout)
+        } else {
+            LlmError::Transport(error.without_url().to_string())
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OpenAiProvider {
+    fn name(&self) -> &str {
+        "openai-compatible"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn chat(&self, request: &ChatRequest) -> Result<ChatResponse, LlmError> {
+        let mut attempt = 0;
+        loop {
+            match self.send(request).await {
+                Ok(response) => {
+                    debug!(
+                        "AI request to {} used {} prompt + {} completion tokens",
+                        self.model, response.usage.prompt_tokens, response.usage.completion_tokens
+                    );
+                    self.meter.record(response.usage);
+                    return Ok(response);
+                }
+                Err(e) if e.is_retryable() && attempt < self.max_retries => {
+                    // Every wait, however short it was capped, uses up one retry
+                    let delay = e
+                        .retry_after()
+                        .unwrap_or_else(|| self.backoff.saturating_mul(1 << attempt.min(16)))
+                        .min(self.timeout);
+                    attempt += 1;
+                    warn!(
+                        "AI request failed ({}); retry {} of {} in {:?}",
+                        e, attempt, self.max_retries, delay
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn usage(&self) -> UsageTotals {
+        self.meter.totals()
+    }
+}
+
+/// Chat-completions URL for a base endpoint; a full URL is used as given
+fn completions_url(endpoint: &str) -> String {
+    let endpoint = endpoint.trim_end_matches('/');
+    if endpoint.ends_with("/chat/completions") {
+        endpoint.to_string()
+    } else {
+        format!("{}/chat/completions", endpoint)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use serde_json::json;
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn ai_config(server: &MockServer) -> AiConfig {
+        let mut ai = Config::default().ai;
+        ai.enabled = true;
+        ai.api_endpoint = Some(format!("{}/v1/", server.uri()));
+        ai.api_key = Some("sk-test".to_string());
+        ai.model = "llama3.1:8b".to_string();
+        ai.retry_backoff_ms = 1;
+        ai
+    }
+
+    fn completion(content: &str, usage: bool) -> ResponseTemplate {
+        let mut body = json!({
+            "model": "llama3.1:8b",
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": content},
+                "finish_reason": "stop"
+            }]
+        });
+        if usage {
+            body["usage"] =
+                json!({"prompt_tokens": 12, "completion_tokens": 3, "total_tokens": 15});
+        }
+        ResponseTemplate::new(200).set_body_json(body)
+    }
+
+    #[tokio::test]
+    async fn test_chat_sends_openai_request_and_counts_tokens() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(header("authorization", "Bearer sk-test"))
+            .and(body_partial_json(json!({
+                "model": "llama3.1:8b",
+                "messages": [{"role": "system", "content": "Be brief"}, {"role": "user", "content": "Hi"}],
+                "temperature": 0.0,
+                "response_format": {"type": "json_object"}
+            })))
+            .respond_with(completion("{\"ok\":true}", true))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider = OpenAiProvider::new(&ai_config(&server)).unwrap();
+        let request = ChatRequest::new(vec![
+            ChatMessage::system("Be brief"),
+            ChatMessage::user("Hi"),
+        ])
+        .with_temperature(0.0)
+        .json();
+        let response = provider.chat(&request).await.unwrap();
+
+        assert_eq!(response.content, "{\"ok\":true}");
+        assert_eq!(response.finish_reason.as_deref(), Some("stop"));
+        assert_eq!(response.usage.total(), 15);
+        assert!(!response.usage.estimated);
+        assert_eq!(
+            provider.usage(),
+            UsageTotals {
+                requests: 1,
+                prompt_tokens: 12,
+                completion_tokens: 3
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_retries_transient_failures_only() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503).set_body_string("loading model"))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(429).insert_header("retry-after", "0"))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(completion("done", false))
+            .mount(&server)
+            .await;
+
+        let provider = OpenAiProvider::new(&ai_config(&server)).unwrap();
+        let request = ChatRequest::new(vec![ChatMessage::user("Summarize my month")]);
+        let response = provider.chat(&request).await.unwrap();
+        assert_eq!(response.content, "done");
+        assert!(response.usage.estimated);
+        assert_eq!(server.received_requests().await.unwrap().len(), 3);
+
+        // Authentication failures are returned at once
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(401).set_body_string("bad key"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let provider = OpenAiProvider::new(&ai_config(&server)).unwrap();
+        match provider.chat(&request).await {
+            Err(LlmError::Status { status, body, .. }) => {
+                assert_eq!(status, 401);
+                assert_eq!(body, "bad key");
+            }
+            other => panic!("expected HTTP error, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_is_capped_at_the_timeout() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(429).insert_header("retry-after", "3600"))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let mut config = ai_config(&server);
+        config.timeout_secs = 1;
+        config.max_retries = 1;
+        let provider = OpenAiProvider::new(&config).unwrap();
+        let request = ChatRequest::new(vec![ChatMessage::user("Hi")]);
+        let started = std::time::Instant::now();
+        assert!(matches!(
+            provider.chat(&request).await,
+            Err(LlmError::Status { status: 429, .. })
+        ));
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    #[tokio::test]
+    async fn test_timeout_is_reported_after_retries() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(completion("late", true).set_delay(Duration::from_millis(1500)))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let mut config = ai_config(&server);
+        config.timeout_secs = 1;
+        config.max_retries = 1;
+        let provider = OpenAiProvider::new(&config).unwrap();
+        let request = ChatRequest::new(vec![ChatMessage::user("Hi")]);
+        assert!(matches!(
+            provider.chat(&request).await,
+            Err(LlmError::Timeout(_))
+        ));
+        assert_eq!(provider.usage().requests, 0);
+    }
+
+    #[test]
+    fn test_completions_url() {
+        assert_eq!(
+            completions_url("http://localhost:11434/v1/"),
+            "http://localhost:11434/v1/chat/completions"
+        );
+        assert_eq!(
+            completions_url("http://localhost:8080/v1/chat/completions"),
+            "http://localhost:8080/v1/chat/completions"
+        );
+    }
+}
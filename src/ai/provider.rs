@@ -0,0 +1,220 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::ops::AddAssign;
+use std::sync::Mutex;
+use std::time::Duration;
+use thiserror::Error;
+
+/// Author of a chat message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    System,
+    User,
+    Assistant,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: Role,
+    pub content: String,
+}
+
+/// One chat-completion call; the model comes from the provider
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ChatRequest {
+    pub messages: Vec<ChatMessage>,
+    /// Sampling temperature; `None` uses the endpoint's default
+    pub temperature: Option<f32>,
+    /// Upper bound on completion tokens
+    pub max_tokens: Option<u32>,
+    /// Ask the endpoint to return a single JSON object
+    pub json_response: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChatResponse {
+    pub content: String,
+    /// Model that actually answered, as reported by the endpoint
+    pub model: String,
+    pub usage: TokenUsage,
+    pub finish_reason: Option<String>,
+}
+
+/// Tokens used by one request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// True when the endpoint did not report usage and it was estimated from text length
+    #[serde(default)]
+    pub estimated: bool,
+}
+
+/// Tokens used by every request a provider has made
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct UsageTotals {
+    pub requests: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+/// Thread-safe running total of token usage
+#[derive(Debug, Default)]
+pub struct UsageMeter {
+    totals: Mutex<UsageTotals>,
+}
+
+#[derive(Debug, Error)]
+pub enum LlmError {
+    #[error("AI request timed out after {0:?}")]
+    Timeout(Duration),
+
+    #[error("AI endpoint returned HTTP {status}: {body}")]
+    Status {
+        status: u16,
+        body: String,
+        retry_after: Option<Duration>,
+    },
+
+    #[error("Could not reach AI endpoint: {0}")]
+    Transport(String),
+
+    #[error("Unexpected AI response: {0}")]
+    InvalidResponse(String),
+
+    #[error("AI is not configured: {0}")]
+    NotConfigured(String),
+
+    #[error("AI dry run: the prompt was printed and not sent")]
+    DryRun,
+
+    #[error("AI budget exceeded: {0}")]
+    BudgetExceeded(String),
+}
+
+/// A chat-completion backend
+#[async_trait]
+pub trait LlmProvider: Send + Sync {
+    /// Short provider name for logs, e.g. `openai-compatible`
+    fn name(&self) -> &str;
+
+    /// Model requests are sent to
+    fn model(&self) -> &str;
+
+    /// Send one request, retrying transient failures as the provider sees fit
+    async fn chat(&self, request: &ChatRequest) -> Result<ChatResponse, LlmError>;
+
+    /// Usage accumulated since the provider was created
+    fn usage(&self) -> UsageTotals;
+}
+
+impl ChatMessage {
+    pub fn system(content: impl Into<String>) -> Self {
+        Self {
+            role: Role::System,
+            content: content.into(),
+        }
+    }
+
+    pub fn user(content: impl Into<String>) -> Self {
+        Self {
+            role: Role::User,
+            content: content.into(),
+        }
+    }
+
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self {
+            role: Role::Assistant,
+            content: content.into(),
+        }
+    }
+}
+
+impl ChatRequest {
+    pub fn new(messages: Vec<ChatMessage>) -> Self {
+        Self {
+            messages,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+
+    /// Request a JSON object response
+    pub fn json(mut self) -> Self {
+        self.json_response = true;
+        self
+    }
+}
+
+impl TokenUsage {
+    /// Rough count for endpoints that don't report usage: about four characters per token
+    pub fn estimate(request: &ChatRequest, completion: &str) -> Self {
+        let tokens = |text: &str| (text.chars().count() as u64).div_ceil(4);
+        Self {
+            prompt_tokens: request.messages.iter().map(|m| tokens(&m.content)).sum(),
+            completion_tokens: tokens(completion),
+            estimated: true,
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+}
+
+impl AddAssign<TokenUsage> for UsageTotals {
+    fn add_assign(&mut self, usage: TokenUsage) {
+        self.requests += 1;
+        self.prompt_tokens += usage.prompt_tokens;
+        self.completion_tokens += usage.completion_tokens;
+    }
+}
+
+impl UsageTotals {
+    pub fn total_tokens(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+}
+
+impl UsageMeter {
+    pub fn record(&self, usage: TokenUsage) {
+        *self.totals.lock().unwrap() += usage;
+    }
+
+    pub fn totals(&self) -> UsageTotals {
+        *self.totals.lock().unwrap()
+    }
+}
+
+impl LlmError {
+    /// Whether sending the same request again might succeed
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            LlmError::Timeout(_) | LlmError::Transport(_) => true,
+            LlmError::Status { status, .. } => *status == 429 || *status >= 500,
+            LlmError::InvalidResponse(_)
+            | LlmError::NotConfigured(_)
+            | LlmError::DryRun
+            | LlmError::BudgetExceeded(_) => false,
+        }
+    }
+
+    /// Delay requested by the endpoint, from a `Retry-After` header
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            LlmError::Status { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+}
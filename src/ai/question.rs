@@ -0,0 +1,250 @@
+use super::provider::{ChatMessage, ChatRequest, LlmProvider};
+use crate::data::FinancialData;
+use crate::query::{QueryEngine, QueryPlan, QueryResult, MAX_QUERIES};
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use serde::Serialize;
+use serde_json::json;
+use std::sync::Arc;
+use tracing::debug;
+
+const PLAN_PROMPT: &str = r#"You translate questions about household finances into queries.
+You never see the transactions; the queries run locally and their results are shown to the user.
+Reply with one JSON object {"queries": [...]} holding 1 to MAX queries. Each query may have:
+  "label": short name for the result, e.g. "Q2 2026"
+  "from", "to": inclusive dates, YYYY-MM-DD
+  "direction": "spending", "income" or "any" (default)
+  "categories": category names, taken from `categories`; a parent matches its subcategories
+  "payees": case-insensitive substrings of the payee
+  "accounts": account names, taken from `accounts`
+  "text": search words; word* for prefixes, "quoted phrases", ~word for fuzzy matches
+  "min_amount", "max_amount": absolute amounts as strings
+  "group_by": any of "category", "payee", "account", "month", "quarter", "year"
+  "aggregates": any of "sum", "count", "average", "min", "max" (default sum and count)
+  "limit": keep the first N rows; grouped rows are ordered by sum, largest first
+Use one query per period when the question compares periods. Resolve relative dates against `today`.
+Use no other fields."#;
+
+const SUMMARY_PROMPT: &str = "Answer the household's question in one to three sentences \
+using only the numbers in `results`. Amounts are absolute values in the household's currency. \
+Do not invent figures; say so if the results cannot answer the question.";
+
+/// The structured answer to a question
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Answer {
+    pub question: String,
+    /// Queries the model generated, shown so the answer can be checked
+    pub plan: QueryPlan,
+    pub results: Vec<QueryResult>,
+    /// Prose answer, only written when `ai.share_data` allows sending results
+    pub summary: Option<String>,
+}
+
+/// Answers questions by having the model write queries that run locally
+///
+/// Only the question, today's date and the names of categories and accounts
+/// are sent. With `share_data`, payee names go with the question and the
+/// query results are sent back for a prose answer.
+pub struct QuestionAnswerer {
+    provider: Arc<dyn LlmProvider>,
+    share_data: bool,
+}
+
+impl QuestionAnswerer {
+    pub fn new(provider: Arc<dyn LlmProvider>, share_data: bool) -> Self {
+        Self {
+            provider,
+            share_data,
+        }
+    }
+
+    /// Translate, run and, if allowed, summarize
+    pub async fn answer(
+        &self,
+        question: &str,
+        data: &FinancialData,
+        today: NaiveDate,
+    ) -> Result<Answer> {
+        let plan = self.plan(question, data, today).await?;
+        let results = QueryEngine::run_plan(data, &plan)?;
+        let summary = if self.share_data {
+            Some(self.summarize(question, &results).await?)
+        } else {
+            None
+        };
+
+        Ok(Answer {
+            question: question.to_string(),
+            plan,
+            results,
+            summary,
+        })
+    }
+
+    /// Ask the model for a query plan, giving it one chance to fix an invalid reply
+    pub async fn plan(
+        &self,
+        question: &str,
+        data: &FinancialData,
+        today: NaiveDate,
+    ) -> Result<QueryPlan> {
+        let mut messages = vec![
+            ChatMessage::system(PLAN_PROMPT.replace("MAX", &MAX_QUERIES.to_string())),
+            ChatMessage::user(self.context(question, data, today).to_string()),
+        ];
+
+        let mut attempts = 0;
+        loop {
+            let request = ChatRequest::new(messages.clone())
+                .with_temperature(0.0)
+                .json();
+            let response = self
+                .provider
+                .chat(&request)
+                .await
+                .context("Question translation failed")?;
+
+            let parsed = serde_json::from_str::<QueryPlan>(response.content.trim())
+                .map_err(anyhow::Error::from)
+                .and_then(|plan| plan.validate().map(|()| plan));
+            match parsed {
+                Ok(plan) => return Ok(plan),
+                Err(e) if attempts == 0 => {
+                    debug!("Asking the model to fix its query plan: {:#}", e);
+                    attempts += 1;
+                    messages.push(ChatMessage::assistant(response.content));
+                    messages.push(ChatMessage::user(format!(
+                        "That plan is invalid: {:#}. Reply with a corrected JSON object.",
+                        e
+                    )));
+                }
+                Err(e) => return Err(e.context("The model did not produce a valid query plan")),
+            }
+        }
+    }
+
+    fn context(&self, question: &str, data: &FinancialData, today: NaiveDate) -> serde_json::Value {
+        let accounts: Vec<&str> = data.accounts.iter().map(|a| a.name.as_str()).collect();
+        let mut context = json!({
+            "question": question,
+            "today": today,
+            "categories": crate::categorize::known_categories(data),
+            "accounts": accounts,
+        });
+        if self.share_data {
+            let mut payees: Vec<&str> = data.payees.iter().map(String::as_str).collect();
+            payees.sort_unstable();
+            payees.dedup();
+            context["payees"] = json!(payees);
+        }
+        context
+    }
+
+    async fn summarize(&self, question: &str, results: &[QueryResult]) -> Result<String> {
+        let request = ChatRequest::new(vec![
+            ChatMessage::system(SUMMARY_PROMPT),
+            ChatMessage::user(json!({"question": question, "results": results}).to_string()),
+        ])
+        .with_temperature(0.2);
+        let response = self
+            .provider
+            .chat(&request)
+            .await
+            .context("Answer summary failed")?;
+        Ok(response.content.trim().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::MockProvider;
+    use crate::data::{Account, AccountType, Transaction, TransactionType};
+    use chrono::{TimeZone, Utc};
+    use rust_decimal_macros::dec;
+
+    const PLAN: &str = r#"{"queries": [
+        {"label": "Q2 2026", "from": "2026-04-01", "to": "2026-06-30", "direction": "spending", "categories": ["Dining"]},
+        {"label": "Q2 2025", "from": "2025-04-01", "to": "2025-06-30", "direction": "spending", "categories": ["Dining"]}
+    ]}"#;
+
+    fn books() -> FinancialData {
+        let mut data = FinancialData::new();
+        let account = Account::new(
+            "Joint Checking".to_string(),
+            AccountType::Checking,
+            dec!(5120.33),
+            "USD".to_string(),
+        );
+        for (year, amount) in [(2026, dec!(84.20)), (2025, dec!(61.00))] {
+            let mut t = Transaction::new(
+                account.id,
+                Utc.with_ymd_and_hms(year, 5, 14, 19, 0, 0).unwrap(),
+                amount,
+                "Trattoria Rosa".to_string(),
+                TransactionType::Debit,
+            );
+            t.payee = Some("Trattoria Rosa".to_string());
+            t.category = Some("Dining".to_string());
+            data.add_transaction(t);
+        }
+        data.payees.push("Trattoria Rosa".to_string());
+        data.add_account(account);
+        data
+    }
+
+    #[tokio::test]
+    async fn test_answers_locally_without_sending_data() {
+        let provider = Arc::new(MockProvider::new().reply("not json").reply(PLAN));
+        let answerer = QuestionAnswerer::new(provider.clone(), false);
+        let today = NaiveDate::from_ymd_opt(2026, 10, 18).unwrap();
+        let answer = answerer
+            .answer("Restaurants in Q2 vs last year?", &books(), today)
+            .await
+            .unwrap();
+
+        assert_eq!(answer.plan.queries.len(), 2);
+        assert_eq!(answer.results[0].rows[0].sum, Some(dec!(84.20)));
+        assert_eq!(answer.results[1].rows[0].sum, Some(dec!(61.00)));
+        assert_eq!(answer.summary, None);
+
+        // The invalid reply was sent back for correction; nothing else was asked
+        let requests = provider.requests();
+        assert_eq!(requests.len(), 2);
+        for request in &requests {
+            for message in &request.messages {
+                assert!(!message.content.contains("Trattoria"));
+                assert!(!message.content.contains("84.2"));
+                assert!(!message.content.contains("5120"));
+            }
+        }
+        let context: serde_json::Value =
+            serde_json::from_str(&requests[0].messages[1].content).unwrap();
+        assert_eq!(context["categories"], json!(["Dining"]));
+        assert_eq!(context["today"], "2026-10-18");
+    }
+
+    #[tokio::test]
+    async fn test_shares_results_only_when_allowed() {
+        let provider = Arc::new(
+            MockProvider::new()
+                .reply(PLAN)
+                .reply("You spent $84.20 on dining in Q2, up from $61.00."),
+        );
+        let answerer = QuestionAnswerer::new(provider.clone(), true);
+        let today = NaiveDate::from_ymd_opt(2026, 10, 18).unwrap();
+        let answer = answerer
+            .answer("Restaurants in Q2 vs last year?", &books(), today)
+            .await
+            .unwrap();
+
+        assert!(answer.summary.unwrap().contains("84.20"));
+        let requests = provider.requests();
+        assert!(requests[0].messages[1].content.contains("Trattoria Rosa"));
+        assert!(requests[1].messages[1].content.contains("84.20"));
+
+        let failing = Arc::new(MockProvider::new().reply("{}").reply("{\"queries\": []}"));
+        let answerer = QuestionAnswerer::new(failing, false);
+        assert!(answerer.plan("?", &books(), today).await.is_err());
+    }
+}
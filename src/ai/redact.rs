@@ -0,0 +1,258 @@
+use crate::config::{RedactionConfig, RedactionMode};
+use regex::{Captures, Regex};
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+/// Kinds of personal detail found in prompts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PiiKind {
+    Email,
+    Address,
+    Phone,
+    Balance,
+    AccountNumber,
+    Person,
+}
+
+/// Placeholders issued for one request and the values they stand for
+///
+/// The same value gets the same placeholder everywhere in a request, so the
+/// model can still tell that two mentions are the same account or person.
+#[derive(Debug, Default)]
+pub struct Vault {
+    tokens: Vec<(String, String)>,
+    counts: BTreeMap<&'static str, usize>,
+}
+
+/// Hides account numbers, emails, phone numbers, addresses, known people and
+/// balances in prompt text according to `[ai.redaction]`
+pub struct PromptRedactor {
+    config: RedactionConfig,
+    people: Option<Regex>,
+}
+
+impl PiiKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PiiKind::Email => "email",
+            PiiKind::Address => "address",
+            PiiKind::Phone => "phone",
+            PiiKind::Balance => "balance",
+            PiiKind::AccountNumber => "account_number",
+            PiiKind::Person => "person",
+        }
+    }
+
+    fn marker(&self) -> &'static str {
+        match self {
+            PiiKind::Email => "EMAIL",
+            PiiKind::Address => "ADDRESS",
+            PiiKind::Phone => "PHONE",
+            PiiKind::Balance => "BALANCE",
+            PiiKind::AccountNumber => "ACCOUNT",
+            PiiKind::Person => "PERSON",
+        }
+    }
+}
+
+impl Vault {
+    /// Put the original values back in place of their placeholders
+    pub fn rehydrate(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        // Longest placeholder first so `[EMAIL_12]` is not read as `[EMAIL_1]`
+        let mut tokens: Vec<&(String, String)> = self.tokens.iter().collect();
+        tokens.sort_by_key(|(token, _)| std::cmp::Reverse(token.len()));
+        for (token, original) in tokens {
+            text = text.replace(token.as_str(), original);
+        }
+        text
+    }
+
+    /// Number of values hidden, by kind
+    pub fn counts(&self) -> &BTreeMap<&'static str, usize> {
+        &self.counts
+    }
+
+    fn hide(&mut self, kind: PiiKind, mode: RedactionMode, original: &str) -> String {
+        *self.counts.entry(kind.as_str()).or_default() += 1;
+        match mode {
+            RedactionMode::Keep => original.to_string(),
+            RedactionMode::Mask if kind == PiiKind::AccountNumber => {
+                let digits: String = original.chars().filter(char::is_ascii_digit).collect();
+                format!("****{}", &digits[digits.len().saturating_sub(4)..])
+            }
+            RedactionMode::Mask => format!("[{}]", kind.marker()),
+            RedactionMode::Tokenize => {
+                if let Some((token, _)) = self.tokens.iter().find(|(_, o)| o == original) {
+                    return token.clone();
+                }
+                let number = self
+                    .tokens
+                    .iter()
+                    .filter(|(token, _)| token.starts_with(&format!("[{}_", kind.marker())))
+                    .count()
+                    + 1;
+                let token = format!("[{}_{}]", kind.marker(), number);
+                self.tokens.push((token.clone(), original.to_string()));
+                token
+            }
+        }
+    }
+}
+
+impl PromptRedactor {
+    pub fn new(config: &RedactionConfig) -> Self {
+        let names: Vec<String> = config
+            .names
+            .iter()
+            .map(|n| n.trim())
+            .filter(|n| !n.is_empty())
+            .map(regex::escape)
+            .collect();
+        let people = (!names.is_empty())
+            .then(|| Regex::new(&format!(r"(?i)\b(?:{})\b", names.join("|"))).ok())
+            .flatten();
+        Self {
+            config: config.clone(),
+            people,
+        }
+    }
+
+    /// Hide personal details in `text`, recording placeholders in `vault`
+    pub fn redact(&self, text: &str, vault: &mut Vault) -> String {
+        static EMAIL: OnceLock<Regex> = OnceLock::new();
+        static ADDRESS: OnceLock<Regex> = OnceLock::new();
+        static PHONE: OnceLock<Regex> = OnceLock::new();
+        static BALANCE: OnceLock<Regex> = OnceLock::new();
+        static NUMBER: OnceLock<Regex> = OnceLock::new();
+        let email = EMAIL
+            .get_or_init(|| Regex::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}").unwrap());
+        let address = ADDRESS.get_or_init(|| {
+            Regex::new(
+                r"\b\d{1,6}(?:\s+[A-Z][A-Za-z]*\.?){1,4}\s+(?:St|Street|Ave|Avenue|Rd|Road|Blvd|Boulevard|Ln|Lane|Dr|Drive|Ct|Court|Way|Pl|Place|Ter|Terrace|Pkwy|Parkway|Hwy|Highway)\b\.?(?:,?\s+(?:Apt|Suite|Unit|#)\s*\w+)?",
+            )
+            .unwrap()
+        });
+        let phone = PHONE.get_or_init(|| {
+            Regex::new(r"(?:\+\d{1,3}[ .-]?)?\(?\b\d{3}\)?[ .-]\d{3}[ .-]\d{4}\b").unwrap()
+        });
+        let balance = BALANCE.get_or_init(|| {
+            Regex::new(r#"(?i)(\bbalance"?\s*(?:[:=]|of|is|was)?\s*"?\s*[$€£]?\s*)(-?\d[\d,]*(?:\.\d{1,2})?)"#)
+                .unwrap()
+        });
+        // Same shape the log redaction treats as account or card numbers
+        let number =
+            NUMBER.get_or_init(|| Regex::new(r"\b(?:\d{4}[ -]){2,3}\d{4}\b|\b\d{8,19}\b").unwrap());
+
+        let config = &self.config;
+        let mut text = replace(text, email, PiiKind::Email, config.emails, vault);
+        text = replace(&text, address, PiiKind::Address, config.addresses, vault);
+        text = replace(&text, phone, PiiKind::Phone, config.phone_numbers, vault);
+        if config.balances != RedactionMode::Keep {
+            text = balance
+                .replace_all(&text, |caps: &Captures| {
+                    format!(
+                        "{}{}",
+                        &caps[1],
+                        vault.hide(PiiKind::Balance, config.balances, &caps[2])
+                    )
+                })
+                .into_owned();
+        }
+        text = replace(
+            &text,
+            number,
+            PiiKind::AccountNumber,
+            config.account_numbers,
+            vault,
+        );
+        if let Some(people) = &self.people {
+            text = replace(&text, people, PiiKind::Person, config.people, vault);
+        }
+        text
+    }
+}
+
+fn replace(
+    text: &str,
+    regex: &Regex,
+    kind: PiiKind,
+    mode: RedactionMode,
+    vault: &mut Vault,
+) -> String {
+    if mode == RedactionMode::Keep {
+        return text.to_string();
+    }
+    regex
+        .replace_all(text, |caps: &Captures| vault.hide(kind, mode, &caps[0]))
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenizes_and_rehydrates() {
+        let config = RedactionConfig {
+            names: vec!["Dana Whitfield".to_string()],
+            ..RedactionConfig::default()
+        };
+        let redactor = PromptRedactor::new(&config);
+        let mut vault = Vault::default();
+        let text = "Dana Whitfield (dana@example.com, 555-867-5309) of 42 Maple Grove Ave, Apt 3 \
+                    moved $200 from 4111 1111 1111 1234 to 987654321; balance: $5,120.33. \
+                    dana@example.com paid on 2026-04-01 for 12.50";
+        let redacted = redactor.redact(text, &mut vault);
+
+        for secret in [
+            "Dana",
+            "dana@",
+            "867",
+            "Maple",
+            "4111",
+            "987654321",
+            "5,120.33",
+        ] {
+            assert!(
+                !redacted.contains(secret),
+                "{} leaked: {}",
+                secret,
+                redacted
+            );
+        }
+        // Repeated values share a placeholder; dates and ordinary amounts are untouched
+        assert_eq!(redacted.matches("[EMAIL_1]").count(), 2);
+        assert!(redacted.contains("[ACCOUNT_2]"));
+        assert!(redacted.contains("balance: $[BALANCE_1]"));
+        assert!(redacted.contains("2026-04-01") && redacted.contains("12.50"));
+        assert_eq!(vault.counts()["email"], 2);
+
+        let answer = "[PERSON_1] can be reached at [EMAIL_1]; [ACCOUNT_1] looks fine.";
+        assert_eq!(
+            vault.rehydrate(answer),
+            "Dana Whitfield can be reached at dana@example.com; 4111 1111 1111 1234 looks fine."
+        );
+    }
+
+    #[test]
+    fn test_mask_and_keep_modes() {
+        let config = RedactionConfig {
+            account_numbers: RedactionMode::Mask,
+            emails: RedactionMode::Keep,
+            balances: RedactionMode::Mask,
+            ..RedactionConfig::default()
+        };
+        let redactor = PromptRedactor::new(&config);
+        let mut vault = Vault::default();
+        let redacted = redactor.redact(
+            r#"{"email": "a@b.co", "account": "000123456789", "balance": "88.10"}"#,
+            &mut vault,
+        );
+        assert_eq!(
+            redacted,
+            r#"{"email": "a@b.co", "account": "****6789", "balance": "[BALANCE]"}"#
+        );
+        assert_eq!(vault.rehydrate("[BALANCE]"), "[BALANCE]");
+    }
+}
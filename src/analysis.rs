@@ -3,13 +3,15 @@ use anyhow::Result;
 use chrono::{Datelike, Utc};
 use rust_decimal::prelude::FromPrimitive;
 use rust_decimal::Decimal;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use uuid::Uuid;
 
 /// Financial analysis engine
 pub struct AnalysisEngine;
 
 /// Monthly spending report
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MonthlyReport {
     pub year: i32,
     pub month: u32,
@@ -21,7 +23,7 @@ pub struct MonthlyReport {
 }
 
 /// Category analysis
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CategoryAnalysis {
     pub category: String,
     pub total_amount: Decimal,
@@ -31,7 +33,7 @@ pub struct CategoryAnalysis {
 }
 
 /// Spending trend analysis
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SpendingTrend {
     pub category: String,
     pub monthly_amounts: Vec<(String, Decimal)>, // (Month, Amount)
@@ -39,7 +41,18 @@ pub struct SpendingTrend {
     pub average_monthly: Decimal,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// A category whose monthly spending went over budget
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BudgetOverrun {
+    pub category: String,
+    pub year: i32,
+    pub month: u32,
+    pub budget: Decimal,
+    pub spent: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum TrendDirection {
     Increasing,
     Decreasing,
@@ -79,10 +92,12 @@ impl AnalysisEngine {
                 _ => {} // Handle other types as needed
             }
 
-            // Add to category breakdown
-            if let Some(ref category) = transaction.category {
-                let current = category_breakdown.get(category).unwrap_or(&Decimal::ZERO);
-                category_breakdown.insert(category.clone(), current + amount);
+            // Add to category breakdown, line by line for split transactions
+            for (category, amount) in transaction.category_amounts() {
+                if let Some(category) = category {
+                    let current = category_breakdown.get(category).unwrap_or(&Decimal::ZERO);
+                    category_breakdown.insert(category.to_string(), current + amount);
+                }
             }
         }
 
@@ -104,25 +119,20 @@ impl AnalysisEngine {
         let mut category_totals: HashMap<String, (Decimal, usize)> = HashMap::new();
         let mut total_spending = Decimal::ZERO;
 
-        // Calculate totals per category
+        // Calculate totals per category, line by line for split transactions
         for transaction in &data.transactions {
             if matches!(transaction.transaction_type, TransactionType::Debit) {
-                let category = transaction
-                    .category
-                    .as_ref()
-                    .unwrap_or(&"Uncategorized".to_string())
-                    .clone();
-
-                let (current_amount, current_count) = category_totals
-                    .get(&category)
-                    .unwrap_or(&(Decimal::ZERO, 0));
-
-                category_totals.insert(
-                    category,
-                    (current_amount + transaction.amount, current_count + 1),
-                );
-
-                total_spending += transaction.amount;
+                for (category, amount) in transaction.category_amounts() {
+                    let category = category.unwrap_or("Uncategorized").to_string();
+
+                    let (current_amount, current_count) = category_totals
+                        .get(&category)
+                        .unwrap_or(&(Decimal::ZERO, 0));
+
+                    category_totals.insert(category, (current_amount + amount, current_count + 1));
+
+                    total_spending += amount;
+                }
             }
         }
 
@@ -151,7 +161,7 @@ impl AnalysisEngine {
         }
 
         // Sort by total amount (descending)
-        results.sort_by(|a, b| b.total_amount.cmp(&a.total_amount));
+        results.sort_by_key(|r| std::cmp::Reverse(r.total_amount));
 
         Ok(results)
     }
@@ -165,11 +175,11 @@ impl AnalysisEngine {
         let mut trends = HashMap::new();
 
         // Get all unique categories
-        let categories: std::collections::HashSet<String> = data
+        let categories: std::collections::HashSet<&str> = data
             .transactions
             .iter()
-            .filter_map(|t| t.category.as_ref())
-            .cloned()
+            .flat_map(|t| t.category_amounts())
+            .filter_map(|(category, _)| category)
             .collect();
 
         for category in categories {
@@ -188,10 +198,11 @@ impl AnalysisEngine {
                         let t_date = t.date;
                         t_date.year() == year
                             && t_date.month() == month
-                            && t.category.as_ref() == Some(&category)
                             && matches!(t.transaction_type, TransactionType::Debit)
                     })
-                    .map(|t| t.amount)
+                    .flat_map(|t| t.category_amounts())
+                    .filter(|(line_category, _)| *line_category == Some(category))
+                    .map(|(_, amount)| amount)
                     .sum();
 
                 monthly_amounts.push((format!("{}-{:02}", year, month), month_total));
@@ -212,9 +223,9 @@ impl AnalysisEngine {
             };
 
             trends.insert(
-                category.clone(),
+                category,
                 SpendingTrend {
-                    category,
+                    category: category.to_string(),
                     monthly_amounts,
                     trend_direction,
                     average_monthly,
@@ -260,45 +271,106 @@ impl AnalysisEngine {
         let mut anomalies = Vec::new();
 
         // Calculate average transaction amount per category
-        let mut category_stats: HashMap<String, (Decimal, usize)> = HashMap::new();
+        // Split transactions count once per line
+        let mut category_stats: HashMap<&str, (Decimal, usize)> = HashMap::new();
 
-        let uncategorized = "Uncategorized".to_string();
         for transaction in &data.transactions {
             if matches!(transaction.transaction_type, TransactionType::Debit) {
-                let category = transaction.category.as_ref().unwrap_or(&uncategorized);
+                for (category, amount) in transaction.category_amounts() {
+                    let category = category.unwrap_or("Uncategorized");
 
-                let (total, count) = category_stats.get(category).unwrap_or(&(Decimal::ZERO, 0));
-                category_stats.insert(category.clone(), (total + transaction.amount, count + 1));
+                    let (total, count) =
+                        category_stats.get(category).unwrap_or(&(Decimal::ZERO, 0));
+                    category_stats.insert(category, (total + amount, count + 1));
+                }
             }
         }
 
-        // Find transactions that are significantly above average for their category
+        // Find transactions with a line significantly above average for its category
         for transaction in &data.transactions {
             if matches!(transaction.transaction_type, TransactionType::Debit) {
-                let category = transaction.category.as_ref().unwrap_or(&uncategorized);
+                let unusual =
+                    transaction
+                        .category_amounts()
+                        .into_iter()
+                        .any(|(category, amount)| {
+                            let category = category.unwrap_or("Uncategorized");
+                            match category_stats.get(category) {
+                                Some((total, count)) if *count > 0 => {
+                                    let average = total / Decimal::from(*count);
+                                    amount > average * Decimal::from(3) // 3x average threshold
+                                }
+                                _ => false,
+                            }
+                        });
+
+                if unusual {
+                    anomalies.push(transaction);
+                }
+            }
+        }
 
-                if let Some((total, count)) = category_stats.get(category) {
-                    if *count > 0 {
-                        let average = total / Decimal::from(*count);
-                        let threshold = average * Decimal::from(3); // 3x average threshold
+        Ok(anomalies)
+    }
+
+    /// Budgets that the `new` transactions pushed over their monthly limit
+    ///
+    /// Months already over budget before these transactions are not reported
+    /// again, so repeated imports do not repeat the same overrun.
+    pub fn budget_overruns(
+        data: &FinancialData,
+        budgets: &BTreeMap<String, Decimal>,
+        new: &HashSet<Uuid>,
+    ) -> Vec<BudgetOverrun> {
+        // (category, year, month) -> (spent before, spent after)
+        let mut spending: BTreeMap<(&str, i32, u32), (Decimal, Decimal)> = BTreeMap::new();
+        let mut touched = HashSet::new();
+
+        for transaction in &data.transactions {
+            if !matches!(transaction.transaction_type, TransactionType::Debit) {
+                continue;
+            }
+            for (category, amount) in transaction.category_amounts() {
+                let Some(category) = category else {
+                    continue;
+                };
+                if !budgets.contains_key(category) {
+                    continue;
+                }
 
-                        if transaction.amount > threshold {
-                            anomalies.push(transaction);
-                        }
-                    }
+                let key = (category, transaction.date.year(), transaction.date.month());
+                let (before, after) = spending.entry(key).or_default();
+                *after += amount;
+                if new.contains(&transaction.id) {
+                    touched.insert(key);
+                } else {
+                    *before += amount;
                 }
             }
         }
 
-        Ok(anomalies)
+        spending
+            .into_iter()
+            .filter(|(key, _)| touched.contains(key))
+            .filter_map(|((category, year, month), (before, after))| {
+                let budget = budgets[category];
+                (before <= budget && after > budget).then(|| BudgetOverrun {
+                    category: category.to_string(),
+                    year,
+                    month,
+                    budget,
+                    spent: after,
+                })
+            })
+            .collect()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::data::{Account, AccountType, FinancialData};
-    use chrono::TimeZone;
+    use crate::data::{Account, AccountType, FinancialData, Split};
+    use chrono::{DateTime, TimeZone};
     use rust_decimal_macros::dec;
 
     fn create_test_data() -> FinancialData {
@@ -359,6 +431,28 @@ mod tests {
         data
     }
 
+    /// Add an uncategorized debit split into the given lines
+    fn add_split(data: &mut FinancialData, date: DateTime<Utc>, lines: &[(&str, Decimal)]) -> Uuid {
+        let mut transaction = Transaction::new(
+            data.accounts[0].id,
+            date,
+            lines.iter().map(|(_, amount)| *amount).sum(),
+            "Superstore".to_string(),
+            TransactionType::Debit,
+        );
+        transaction.splits = lines
+            .iter()
+            .map(|(category, amount)| Split {
+                category: Some(category.to_string()),
+                memo: None,
+                amount: *amount,
+            })
+            .collect();
+        let id = transaction.id;
+        data.add_transaction(transaction);
+        id
+    }
+
     #[test]
     fn test_monthly_report() {
         let data = create_test_data();
@@ -400,6 +494,37 @@ mod tests {
         assert_eq!(gas.total_amount, dec!(450.00));
         assert_eq!(gas.transaction_count, 2);
         assert_eq!(gas.average_amount, dec!(225.00));
+
+        // Each line of a split counts toward its own category
+        let mut data = data;
+        let feb = chrono::Utc.with_ymd_and_hms(2024, 2, 20, 0, 0, 0).unwrap();
+        add_split(
+            &mut data,
+            feb,
+            &[("Groceries", dec!(100.00)), ("Household", dec!(50.00))],
+        );
+        let analysis = AnalysisEngine::analyze_categories(&data).unwrap();
+        let find = |name: &str| analysis.iter().find(|c| c.category == name);
+        assert_eq!(find("Groceries").unwrap().total_amount, dec!(1200.00));
+        assert_eq!(find("Groceries").unwrap().transaction_count, 3);
+        assert_eq!(find("Household").unwrap().total_amount, dec!(50.00));
+        assert!(find("Uncategorized").is_none());
+    }
+
+    #[test]
+    fn test_spending_trends_follow_splits() {
+        let mut data = create_test_data();
+        add_split(
+            &mut data,
+            Utc::now(),
+            &[("Groceries", dec!(40.00)), ("Household", dec!(60.00))],
+        );
+
+        let trends = AnalysisEngine::analyze_spending_trends(&data, 1).unwrap();
+        let find = |name: &str| trends.iter().find(|t| t.category == name).unwrap();
+        assert_eq!(find("Groceries").monthly_amounts[0].1, dec!(40.00));
+        assert_eq!(find("Household").monthly_amounts[0].1, dec!(60.00));
+        assert_eq!(find("Gas").monthly_amounts[0].1, Decimal::ZERO);
     }
 
     #[test]
@@ -462,4 +587,73 @@ mod tests {
         // For now, just verify the function runs without error.
         assert!(anomalies.len() <= 1); // Should detect at most one anomaly
     }
+
+    #[test]
+    fn test_anomaly_detection_per_split_line() {
+        let mut data = FinancialData::new();
+        data.add_account(Account::new(
+            "Test Account".to_string(),
+            AccountType::Checking,
+            dec!(1000.00),
+            "USD".to_string(),
+        ));
+        let date = chrono::Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap();
+        for _ in 0..5 {
+            add_split(&mut data, date, &[("Dining", dec!(20.00))]);
+        }
+        // The dining line stands out even though the receipt as a whole is uncategorized
+        let unusual = add_split(
+            &mut data,
+            date,
+            &[("Dining", dec!(250.00)), ("Groceries", dec!(50.00))],
+        );
+
+        let anomalies = AnalysisEngine::detect_anomalies(&data).unwrap();
+        assert_eq!(anomalies.len(), 1);
+        assert_eq!(anomalies[0].id, unusual);
+    }
+
+    #[test]
+    fn test_budget_overruns() {
+        let data = create_test_data();
+        let budgets = BTreeMap::from([("Groceries".to_string(), dec!(550.00))]);
+        let id_of = |month: u32, category: &str| {
+            data.transactions
+                .iter()
+                .find(|t| t.date.month() == month && t.category.as_deref() == Some(category))
+                .unwrap()
+                .id
+        };
+
+        let overruns = AnalysisEngine::budget_overruns(
+            &data,
+            &budgets,
+            &HashSet::from([id_of(2, "Groceries"), id_of(1, "Groceries")]),
+        );
+        assert_eq!(overruns.len(), 1);
+        assert_eq!(overruns[0].month, 2);
+        assert_eq!(overruns[0].spent, dec!(600.00));
+
+        // Unbudgeted categories and months without new spending stay quiet
+        assert!(AnalysisEngine::budget_overruns(
+            &data,
+            &budgets,
+            &HashSet::from([id_of(2, "Gas")])
+        )
+        .is_empty());
+        assert!(AnalysisEngine::budget_overruns(&data, &budgets, &HashSet::new()).is_empty());
+
+        // Only the grocery line of a split counts against the grocery budget
+        let mut data = data;
+        let jan = chrono::Utc.with_ymd_and_hms(2024, 1, 28, 0, 0, 0).unwrap();
+        let receipt = add_split(
+            &mut data,
+            jan,
+            &[("Groceries", dec!(100.00)), ("Gas", dec!(400.00))],
+        );
+        let overruns = AnalysisEngine::budget_overruns(&data, &budgets, &HashSet::from([receipt]));
+        assert_eq!(overruns.len(), 1);
+        assert_eq!(overruns[0].month, 1);
+        assert_eq!(overruns[0].spent, dec!(600.00));
+    }
 }
@@ -0,0 +1,427 @@
+use crate::config::{BackupConfig, DatabaseConfig};
+use crate::storage::Database;
+use crate::utils::file_utils;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Datelike, Duration, NaiveDateTime, Utc};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Why a backup was taken
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackupReason {
+    Import,
+    RuleRun,
+    Migration,
+    Scheduled,
+    Restore,
+    Manual,
+}
+
+/// A backup file on disk
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BackupEntry {
+    pub path: PathBuf,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Takes, prunes and restores snapshots of the SQLite store
+pub struct BackupManager {
+    database_path: PathBuf,
+    directory: PathBuf,
+    policy: BackupConfig,
+}
+
+impl BackupManager {
+    /// Create a backup manager for the configured database
+    pub fn new(config: &DatabaseConfig) -> Self {
+        let directory = config.backup.directory.clone().unwrap_or_else(|| {
+            config
+                .path
+                .parent()
+                .unwrap_or_else(|| Path::new("."))
+                .join("backups")
+        });
+
+        Self {
+            database_path: config.path.clone(),
+            directory,
+            policy: config.backup.clone(),
+        }
+    }
+
+    /// Directory holding the backups
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// Take a consistent snapshot of the database stamped `now` and apply the retention policy
+    pub async fn create_backup(
+        &self,
+        database: &Database,
+        reason: BackupReason,
+        now: DateTime<Utc>,
+    ) -> Result<PathBuf> {
+        file_utils::ensure_dir_exists(&self.directory).await?;
+
+        let file_name = self
+            .database_path
+            .file_name()
+            .context("Database path has no file name")?;
+        // Several backups can be taken within the same second (e.g. import right after a migration)
+        let path = file_utils::unique_path(&file_utils::generate_backup_filename(
+            &self.directory.join(file_name),
+            now,
+        ));
+
+        database.backup_to(&path).await?;
+        info!("Created {:?} backup {}", reason, path.display());
+
+        self.prune(now).await?;
+        Ok(path)
+    }
+
+    /// Take a scheduled backup if the newest one is older than the configured interval
+    pub async fn backup_if_due(
+        &self,
+        database: &Database,
+        now: DateTime<Utc>,
+    ) -> Result<Option<PathBuf>> {
+        if !self.is_due(now).await? {
+            return Ok(None);
+        }
+        Ok(Some(
+            self.create_backup(database, BackupReason::Scheduled, now)
+                .await?,
+        ))
+    }
+
+    /// Whether a scheduled backup is due, judged from the backup directory alone
+    pub async fn is_due(&self, now: DateTime<Utc>) -> Result<bool> {
+        if !self.policy.enabled {
+            return Ok(false);
+        }
+
+        let interval = Duration::hours(self.policy.interval_hours as i64);
+        let latest = self.list_backups().await?.into_iter().next();
+        Ok(!matches!(latest, Some(entry) if now - entry.created_at < interval))
+    }
+
+    /// List existing backups, newest first
+    pub async fn list_backups(&self) -> Result<Vec<BackupEntry>> {
+        let mut entries = Vec::new();
+
+        if !self.directory.exists() {
+            return Ok(entries);
+        }
+
+        let stem = self
+            .database_path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let mut dir = tokio::fs::read_dir(&self.directory)
+            .await
+            .context("Failed to read backup directory")?;
+        while let Some(entry) = dir.next_entry().await? {
+            let path = entry.path();
+            if let Some(created_at) = parse_backup_timestamp(&path, &stem) {
+                entries.push(BackupEntry { path, created_at });
+            }
+        }
+
+        entries.sort_by(|a, b| b.created_at.cmp(&a.created_at).then(b.path.cmp(&a.path)));
+        Ok(entries)
+    }
+
+    /// Delete backups not selected by the retention policy
+    pub async fn prune(&self, now: DateTime<Utc>) -> Result<Vec<PathBuf>> {
+        let backups = self.list_backups().await?;
+        let timestamps: Vec<DateTime<Utc>> = backups.iter().map(|b| b.created_at).collect();
+        let keep = select_retained(&timestamps, now, &self.policy);
+
+        let mut removed = Vec::new();
+        for (index, backup) in backups.into_iter().enumerate() {
+            if !keep.contains(&index) {
+                tokio::fs::remove_file(&backup.path)
+                    .await
+                    .with_context(|| {
+                        format!("Failed to remove backup {}", backup.path.display())
+                    })?;
+                removed.push(backup.path);
+            }
+        }
+
+        Ok(removed)
+    }
+
+    /// Verify a backup and swap it in place of the live database
+    ///
+    /// The backup is copied aside and opened read-only, which checks the
+    /// passphrase for encrypted stores, and must pass SQLite's integrity check
+    /// before the current database is itself backed up and replaced. Older
+    /// schemas are migrated once the backup is in place. The live database must
+    /// not be open while restoring.
+    pub async fn restore(
+        &self,
+        config: &DatabaseConfig,
+        backup: &Path,
+        passphrase: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Result<()> {
+        if !backup.exists() {
+            bail!("Backup {} does not exist", backup.display());
+        }
+
+        let staging = self.database_path.with_extension("restore");
+        tokio::fs::copy(backup, &staging)
+            .await
+            .context("Failed to stage backup for restore")?;
+
+        let mut staged_config = config.clone();
+        staged_config.path = staging.clone();
+
+        let verified = async {
+            let database = Database::open_read_only(&staged_config, passphrase).await?;
+            let problems = database.integrity_check().await;
+            database.close().await;
+            let problems = problems?;
+            if !problems.is_empty() {
+                bail!("Backup failed integrity check: {}", problems.join("; "));
+            }
+            Ok(())
+        }
+        .await;
+
+        if let Err(e) = verified {
+            let _ = tokio::fs::remove_file(&staging).await;
+            return Err(e.context(format!("Refusing to restore {}", backup.display())));
+        }
+
+        if self.database_path.exists() {
+            let current = Database::open_with_passphrase(config, passphrase).await;
+            match current {
+                Ok(database) => {
+                    self.create_backup(&database, BackupReason::Restore, now)
+                        .await?;
+                    database.close().await;
+                }
+                Err(e) => warn!("Could not back up current database before restore: {}", e),
+            }
+        }
+
+        for suffix in ["-wal", "-shm"] {
+            let mut sidecar = self.database_path.clone().into_os_string();
+            sidecar.push(suffix);
+            let _ = tokio::fs::remove_file(PathBuf::from(sidecar)).await;
+        }
+
+        tokio::fs::rename(&staging, &self.database_path)
+            .await
+            .context("Failed to swap restored database into place")?;
+        Database::open_with_passphrase(config, passphrase)
+            .await
+            .context("Restored database could not be migrated")?
+            .close()
+            .await;
+
+        info!("Restored database from {}", backup.display());
+        Ok(())
+    }
+}
+
+/// Parse the timestamp that `generate_backup_filename` embeds in a backup name
+fn parse_backup_timestamp(path: &Path, database_stem: &str) -> Option<DateTime<Utc>> {
+    let stem = path.file_stem()?.to_string_lossy();
+    let rest = stem.strip_prefix(database_stem)?.strip_prefix('_')?;
+    let timestamp = rest.split('~').next()?;
+
+    NaiveDateTime::parse_from_str(timestamp, "%Y%m%d_%H%M%S")
+        .ok()
+        .map(|t| t.and_utc())
+}
+
+/// Select which backups to keep, given their timestamps sorted newest first
+///
+/// Keeps the newest `keep_last`, the newest of each day within `keep_daily_days`
+/// and the newest of each month within `keep_monthly_months`.
+pub fn select_retained(
+    timestamps: &[DateTime<Utc>],
+    now: DateTime<Utc>,
+    policy: &BackupConfig,
+) -> HashSet<usize> {
+    let mut keep: HashSet<usize> = (0..timestamps.len().min(policy.keep_last)).collect();
+
+    let daily_cutoff = now - Duration::days(policy.keep_daily_days as i64);
+    let mut seen_days = HashSet::new();
+    let months_now = now.year() * 12 + now.month0() as i32;
+    let mut seen_months = HashSet::new();
+
+    for (index, timestamp) in timestamps.iter().enumerate() {
+        if *timestamp > daily_cutoff && seen_days.insert(timestamp.date_naive()) {
+            keep.insert(index);
+        }
+
+        let months_ago = months_now - (timestamp.year() * 12 + timestamp.month0() as i32);
+        if months_ago < policy.keep_monthly_months as i32
+            && seen_months.insert((timestamp.year(), timestamp.month()))
+        {
+            keep.insert(index);
+        }
+    }
+
+    keep
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{AccountType, FinancialData};
+    use crate::storage::test_database_config;
+    use crate::Account;
+    use chrono::TimeZone;
+    use rust_decimal_macros::dec;
+    use tempfile::tempdir;
+
+    fn data_with_account(name: &str) -> FinancialData {
+        let mut data = FinancialData::new();
+        data.add_account(Account::new(
+            name.to_string(),
+            AccountType::Checking,
+            dec!(0),
+            "USD".to_string(),
+        ));
+        data
+    }
+
+    #[test]
+    fn test_retention_policy() {
+        let now = Utc.with_ymd_and_hms(2026, 10, 18, 12, 0, 0).unwrap();
+        let policy = BackupConfig {
+            keep_last: 2,
+            keep_daily_days: 7,
+            keep_monthly_months: 12,
+            ..BackupConfig::default()
+        };
+
+        let timestamps = vec![
+            now - Duration::hours(1),
+            now - Duration::hours(2),
+            now - Duration::hours(3), // same day as the two above, not kept
+            now - Duration::days(2),
+            now - Duration::days(2) - Duration::hours(1), // same day, not kept
+            Utc.with_ymd_and_hms(2026, 6, 3, 0, 0, 0).unwrap(),
+            Utc.with_ymd_and_hms(2026, 6, 1, 0, 0, 0).unwrap(), // same month, not kept
+            Utc.with_ymd_and_hms(2025, 9, 1, 0, 0, 0).unwrap(), // older than a year
+        ];
+
+        let keep = select_retained(&timestamps, now, &policy);
+        let mut kept: Vec<usize> = keep.into_iter().collect();
+        kept.sort();
+        assert_eq!(kept, vec![0, 1, 3, 5]);
+    }
+
+    #[test]
+    fn test_parse_backup_timestamp() {
+        let path = Path::new("/tmp/backups/test_20261018_101500~2.db");
+        let parsed = parse_backup_timestamp(path, "test").unwrap();
+        assert_eq!(
+            parsed,
+            Utc.with_ymd_and_hms(2026, 10, 18, 10, 15, 0).unwrap()
+        );
+        assert!(parse_backup_timestamp(Path::new("other_20261018_101500.db"), "test").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_backup_and_verified_restore() {
+        let dir = tempdir().unwrap();
+        let config = test_database_config(dir.path(), false);
+        let manager = BackupManager::new(&config);
+
+        let now = Utc.with_ymd_and_hms(2026, 10, 18, 10, 15, 0).unwrap();
+        let database = Database::open_with_passphrase(&config, None).await.unwrap();
+        database
+            .save_data(&data_with_account("Before"))
+            .await
+            .unwrap();
+        let backup = manager
+            .create_backup(&database, BackupReason::Manual, now)
+            .await
+            .unwrap();
+        assert_eq!(backup.file_name().unwrap(), "test_20261018_101500.db");
+        database
+            .save_data(&data_with_account("After"))
+            .await
+            .unwrap();
+        database.close().await;
+
+        // A corrupt file must never replace the live database
+        let corrupt = dir.path().join("corrupt.db");
+        std::fs::write(&corrupt, b"not a database").unwrap();
+        assert!(manager.restore(&config, &corrupt, None, now).await.is_err());
+
+        manager.restore(&config, &backup, None, now).await.unwrap();
+
+        let database = Database::open_with_passphrase(&config, None).await.unwrap();
+        let data = database.load_data().await.unwrap();
+        assert_eq!(data.accounts[0].name, "Before");
+
+        // The pre-restore state was itself backed up
+        assert_eq!(manager.list_backups().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_restore_checks_passphrase() {
+        let dir = tempdir().unwrap();
+        let config = test_database_config(dir.path(), true);
+        let manager = BackupManager::new(&config);
+
+        let database = Database::open_with_passphrase(&config, Some("pw"))
+            .await
+            .unwrap();
+        let now = Utc::now();
+        let backup = manager
+            .create_backup(&database, BackupReason::Manual, now)
+            .await
+            .unwrap();
+        database.close().await;
+
+        assert!(manager
+            .restore(&config, &backup, Some("nope"), now)
+            .await
+            .is_err());
+        manager
+            .restore(&config, &backup, Some("pw"), now)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_backup_if_due() {
+        let dir = tempdir().unwrap();
+        let config = test_database_config(dir.path(), false);
+        let manager = BackupManager::new(&config);
+        let database = Database::open_with_passphrase(&config, None).await.unwrap();
+
+        let now = Utc::now();
+        assert!(manager.is_due(now).await.unwrap());
+        assert!(manager
+            .backup_if_due(&database, now)
+            .await
+            .unwrap()
+            .is_some());
+        assert!(!manager.is_due(now).await.unwrap());
+        assert!(manager
+            .backup_if_due(&database, now)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(manager
+            .backup_if_due(&database, now + Duration::hours(25))
+            .await
+            .unwrap()
+            .is_some());
+    }
+}
@@ -0,0 +1,183 @@
+//! Review queue for transactions proposed from receipt and statement text.
+
+use crate::categorize::{self, ReviewStatus};
+use crate::data::{FinancialData, Transaction};
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A proposed transaction waiting to be added to the books
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Candidate {
+    /// The transaction as it would be added, splits included
+    pub transaction: Transaction,
+    /// Where the text came from, e.g. a file name
+    pub source: String,
+    /// Extractor that proposed it, e.g. `llm:gpt-4o-mini`
+    pub extractor: String,
+    /// An existing transaction with the same account, date and amount
+    pub possible_duplicate: Option<Uuid>,
+    pub status: ReviewStatus,
+    pub created_at: DateTime<Utc>,
+    pub decided_at: Option<DateTime<Utc>>,
+}
+
+impl Candidate {
+    pub fn new(
+        data: &FinancialData,
+        transaction: Transaction,
+        source: &str,
+        extractor: &str,
+        now: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            possible_duplicate: duplicate_of(data, &transaction),
+            transaction,
+            source: source.to_string(),
+            extractor: extractor.to_string(),
+            status: ReviewStatus::Pending,
+            created_at: now,
+            decided_at: None,
+        }
+    }
+
+    /// Short id shown in listings; any unique prefix selects the candidate
+    pub fn short_id(&self) -> String {
+        self.transaction.id.to_string()[..8].to_string()
+    }
+
+    pub fn payee(&self) -> &str {
+        categorize::payee_of(&self.transaction)
+    }
+
+    /// Move the candidate out of the queue
+    pub fn decide(&mut self, status: ReviewStatus, now: DateTime<Utc>) {
+        self.status = status;
+        self.decided_at = Some(now);
+    }
+}
+
+/// Check that a transaction's splits add up to its amount
+pub fn check_splits(transaction: &Transaction) -> Result<()> {
+    if transaction.amount <= Decimal::ZERO {
+        bail!("total must be positive, got {}", transaction.amount);
+    }
+    if transaction.splits.is_empty() {
+        return Ok(());
+    }
+    let sum: Decimal = transaction.splits.iter().map(|s| s.amount).sum();
+    if sum != transaction.amount {
+        bail!(
+            "line items add up to {} but the total is {}",
+            sum,
+            transaction.amount
+        );
+    }
+    Ok(())
+}
+
+/// Existing transaction the candidate probably repeats
+fn duplicate_of(data: &FinancialData, candidate: &Transaction) -> Option<Uuid> {
+    data.transactions
+        .iter()
+        .find(|t| {
+            t.account_id == candidate.account_id
+                && t.date.date_naive() == candidate.date.date_naive()
+                && t.amount == candidate.amount
+                && t.transaction_type == candidate.transaction_type
+        })
+        .map(|t| t.id)
+}
+
+/// Pick pending candidates by id prefix, or all of them
+pub fn select(pending: Vec<Candidate>, ids: &[String], all: bool) -> Result<Vec<Candidate>> {
+    if all {
+        return Ok(pending);
+    }
+    if ids.is_empty() {
+        bail!("Name the candidates to decide, or pass --all");
+    }
+    let mut chosen = Vec::new();
+    for id in ids {
+        let id = id.to_lowercase();
+        let matches: Vec<&Candidate> = pending
+            .iter()
+            .filter(|c| c.transaction.id.to_string().starts_with(&id))
+            .collect();
+        match matches.as_slice() {
+            [candidate] => chosen.push((*candidate).clone()),
+            [] => bail!("No pending candidate matches '{}'", id),
+            _ => bail!("'{}' matches several candidates; use more characters", id),
+        }
+    }
+    Ok(chosen)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Split, TransactionType};
+    use chrono::TimeZone;
+    use rust_decimal_macros::dec;
+
+    fn receipt(amount: Decimal, items: &[Decimal]) -> Transaction {
+        let mut transaction = Transaction::new(
+            Uuid::new_v4(),
+            Utc.with_ymd_and_hms(2026, 9, 5, 0, 0, 0).unwrap(),
+            amount,
+            "Market".to_string(),
+            TransactionType::Debit,
+        );
+        transaction.splits = items
+            .iter()
+            .map(|amount| Split {
+                category: None,
+                memo: None,
+                amount: *amount,
+            })
+            .collect();
+        transaction
+    }
+
+    #[test]
+    fn test_check_splits() {
+        assert!(check_splits(&receipt(dec!(30.00), &[dec!(18.00), dec!(12.00)])).is_ok());
+        assert!(check_splits(&receipt(dec!(30.00), &[])).is_ok());
+        let err = check_splits(&receipt(dec!(30.00), &[dec!(18.00), dec!(11.00)])).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line items add up to 29.00 but the total is 30.00"
+        );
+        assert!(check_splits(&receipt(dec!(0), &[])).is_err());
+    }
+
+    #[test]
+    fn test_duplicates_and_selection() {
+        let mut data = FinancialData::new();
+        let existing = receipt(dec!(30.00), &[]);
+        let mut repeat = receipt(dec!(30.00), &[dec!(30.00)]);
+        repeat.account_id = existing.account_id;
+        data.add_transaction(existing.clone());
+
+        let now = Utc::now();
+        let first = Candidate::new(&data, repeat, "receipt.txt", "llm:mock", now);
+        let second = Candidate::new(
+            &data,
+            receipt(dec!(9.00), &[]),
+            "receipt.txt",
+            "llm:mock",
+            now,
+        );
+        assert_eq!(first.possible_duplicate, Some(existing.id));
+        assert_eq!(second.possible_duplicate, None);
+
+        let pending = vec![first.clone(), second];
+        let chosen = select(pending.clone(), &[first.short_id()], false).unwrap();
+        assert_eq!(chosen, vec![first]);
+        assert_eq!(select(pending.clone(), &[], true).unwrap().len(), 2);
+        assert!(select(pending.clone(), &[], false).is_err());
+        assert!(select(pending, &["zzzz".to_string()], false).is_err());
+    }
+}
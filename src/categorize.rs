@@ -0,0 +1,353 @@
+use crate::data::{FinancialData, Transaction};
+use crate::storage::Database;
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// A proposed category for one transaction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CategorySuggestion {
+    pub transaction_id: Uuid,
+    pub category: String,
+    /// How sure the categorizer is, from 0 to 1
+    pub confidence: f64,
+    /// Short explanation shown during review
+    pub rationale: String,
+    /// Categorizer that made the suggestion, e.g. `llm:gpt-4o-mini`
+    pub source: String,
+}
+
+/// Proposes categories for uncategorized transactions
+///
+/// Implementations may call a language model or a local classifier; the agent
+/// treats them the same way.
+#[async_trait]
+pub trait Categorizer: Send + Sync {
+    /// Name recorded as the source of each suggestion
+    fn name(&self) -> &str;
+
+    /// Catch up on the books before suggesting; called after imports and before each pass
+    ///
+    /// Categorizers that learn from history keep their state in `database`. The
+    /// default does nothing.
+    async fn learn(&self, _database: &Database, _data: &FinancialData) -> Result<()> {
+        Ok(())
+    }
+
+    /// Suggest categories for `transactions`, learning from the rest of `data`
+    ///
+    /// Transactions the categorizer has no opinion about are left out.
+    async fn suggest(
+        &self,
+        data: &FinancialData,
+        transactions: &[Transaction],
+    ) -> Result<Vec<CategorySuggestion>>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewStatus {
+    /// Waiting for `qspec categorize accept` or `reject`
+    Pending,
+    /// Applied without review because of its confidence
+    AutoApplied,
+    Accepted,
+    Rejected,
+}
+
+/// A suggestion in the review queue, with enough of its transaction to judge it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReviewItem {
+    pub suggestion: CategorySuggestion,
+    pub status: ReviewStatus,
+    pub date: NaiveDate,
+    pub payee: String,
+    pub amount: Decimal,
+    pub created_at: DateTime<Utc>,
+    pub decided_at: Option<DateTime<Utc>>,
+}
+
+/// Outcome of one categorization pass
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CategorizationReport {
+    pub categorizer: String,
+    /// Uncategorized transactions that were sent to the categorizer
+    pub considered: usize,
+    pub auto_applied: usize,
+    pub queued: usize,
+    pub items: Vec<ReviewItem>,
+}
+
+impl ReviewStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReviewStatus::Pending => "pending",
+            ReviewStatus::AutoApplied => "auto_applied",
+            ReviewStatus::Accepted => "accepted",
+            ReviewStatus::Rejected => "rejected",
+        }
+    }
+}
+
+impl ReviewItem {
+    /// Short id shown in listings; any unique prefix selects the item
+    pub fn short_id(&self) -> String {
+        self.suggestion.transaction_id.to_string()[..8].to_string()
+    }
+
+    /// Move the item out of the queue
+    pub fn decide(&mut self, status: ReviewStatus, now: DateTime<Utc>) {
+        self.status = status;
+        self.decided_at = Some(now);
+    }
+}
+
+impl CategorizationReport {
+    pub fn new(categorizer: &str, considered: usize, items: Vec<ReviewItem>) -> Self {
+        let count = |status| items.iter().filter(|i| i.status == status).count();
+        Self {
+            categorizer: categorizer.to_string(),
+            considered,
+            auto_applied: count(ReviewStatus::AutoApplied),
+            queued: count(ReviewStatus::Pending),
+            items,
+        }
+    }
+}
+
+/// Transactions without a category that have not been through review
+pub fn uncategorized(data: &FinancialData, reviewed: &HashSet<Uuid>) -> Vec<Transaction> {
+    data.transactions
+        .iter()
+        .filter(|t| t.category.as_deref().is_none_or(|c| c.trim().is_empty()))
+        .filter(|t| !reviewed.contains(&t.id))
+        .cloned()
+        .collect()
+}
+
+/// Categories a suggestion may use: the known list, or those already in use
+pub fn known_categories(data: &FinancialData) -> Vec<String> {
+    if !data.categories.is_empty() {
+        return data.categories.clone();
+    }
+    let mut categories: Vec<String> = data
+        .transactions
+        .iter()
+        .filter_map(|t| t.category.clone())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    categories.sort();
+    categories
+}
+
+/// Most frequent payee → category pairs among categorized transactions
+pub fn history(data: &FinancialData, limit: usize) -> Vec<(String, String, usize)> {
+    let mut counts: HashMap<(String, String), usize> = HashMap::new();
+    for transaction in &data.transactions {
+        if let Some(category) = transaction.category.as_ref().filter(|c| !c.is_empty()) {
+            *counts
+                .entry((payee_of(transaction).to_string(), category.clone()))
+                .or_default() += 1;
+        }
+    }
+    let mut pairs: Vec<_> = counts
+        .into_iter()
+        .map(|((payee, category), count)| (payee, category, count))
+        .collect();
+    pairs.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| a.0.cmp(&b.0)));
+    pairs.truncate(limit);
+    pairs
+}
+
+/// Payee, falling back to the description for transactions without one
+pub fn payee_of(transaction: &Transaction) -> &str {
+    transaction
+        .payee
+        .as_deref()
+        .filter(|p| !p.trim().is_empty())
+        .unwrap_or(&transaction.description)
+}
+
+/// Turn suggestions into review items, auto-applying those at or above `threshold`
+///
+/// Suggestions for unknown transactions are dropped.
+pub fn review_items(
+    data: &FinancialData,
+    suggestions: Vec<CategorySuggestion>,
+    threshold: f64,
+    now: DateTime<Utc>,
+) -> Vec<ReviewItem> {
+    let transactions: HashMap<Uuid, &Transaction> =
+        data.transactions.iter().map(|t| (t.id, t)).collect();
+    suggestions
+        .into_iter()
+        .filter_map(|suggestion| {
+            let transaction = transactions.get(&suggestion.transaction_id)?;
+            let auto = suggestion.confidence >= threshold;
+            Some(ReviewItem {
+                status: if auto {
+                    ReviewStatus::AutoApplied
+                } else {
+                    ReviewStatus::Pending
+                },
+                date: transaction.date.date_naive(),
+                payee: payee_of(transaction).to_string(),
+                amount: transaction.amount,
+                created_at: now,
+                decided_at: auto.then_some(now),
+                suggestion,
+            })
+        })
+        .collect()
+}
+
+/// Set a suggested category on its transaction, returning the updated transaction
+pub fn apply<'a>(
+    data: &'a mut FinancialData,
+    suggestion: &CategorySuggestion,
+    now: DateTime<Utc>,
+) -> Option<&'a Transaction> {
+    if !data.categories.contains(&suggestion.category) {
+        data.categories.push(suggestion.category.clone());
+    }
+    let transaction = data
+        .transactions
+        .iter_mut()
+        .find(|t| t.id == suggestion.transaction_id)?;
+    transaction.category = Some(suggestion.category.clone());
+    transaction.updated_at = now;
+    Some(transaction)
+}
+
+/// Pick pending items by id prefix, or all of them, optionally above a confidence
+pub fn select(
+    pending: Vec<ReviewItem>,
+    ids: &[String],
+    all: bool,
+    min_confidence: Option<f64>,
+) -> Result<Vec<ReviewItem>> {
+    if !all && ids.is_empty() && min_confidence.is_none() {
+        bail!("Name the suggestions to decide, or pass --all");
+    }
+
+    let mut chosen: Vec<ReviewItem> = if ids.is_empty() {
+        pending
+    } else {
+        let mut chosen = Vec::new();
+        for id in ids {
+            let id = id.to_lowercase();
+            let matches: Vec<&ReviewItem> = pending
+                .iter()
+                .filter(|item| item.suggestion.transaction_id.to_string().starts_with(&id))
+                .collect();
+            match matches.as_slice() {
+                [item] => chosen.push((*item).clone()),
+                [] => bail!("No pending suggestion matches '{}'", id),
+                _ => bail!("'{}' matches several suggestions; use more characters", id),
+            }
+        }
+        chosen
+    };
+    if let Some(min) = min_confidence {
+        chosen.retain(|item| item.suggestion.confidence >= min);
+    }
+    Ok(chosen)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::TransactionType;
+    use chrono::TimeZone;
+    use rust_decimal_macros::dec;
+
+    fn transaction(payee: &str, category: Option<&str>) -> Transaction {
+        let mut t = Transaction::new(
+            Uuid::new_v4(),
+            Utc.with_ymd_and_hms(2026, 9, 1, 0, 0, 0).unwrap(),
+            dec!(-12.50),
+            payee.to_string(),
+            TransactionType::Debit,
+        );
+        t.payee = Some(payee.to_string());
+        t.category = category.map(str::to_string);
+        t
+    }
+
+    fn suggestion(transaction: &Transaction, confidence: f64) -> CategorySuggestion {
+        CategorySuggestion {
+            transaction_id: transaction.id,
+            category: "Dining".to_string(),
+            confidence,
+            rationale: "Same payee as past dining".to_string(),
+            source: "test".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_review_items_split_on_threshold_and_apply() {
+        let mut data = FinancialData::new();
+        data.add_transaction(transaction("Cafe", Some("Dining")));
+        data.add_transaction(transaction("Cafe", Some("Dining")));
+        let sure = transaction("Cafe", None);
+        let unsure = transaction("Hardware Co", None);
+        data.add_transaction(sure.clone());
+        data.add_transaction(unsure.clone());
+
+        let reviewed = HashSet::from([unsure.id]);
+        assert_eq!(uncategorized(&data, &reviewed), vec![sure.clone()]);
+        assert_eq!(
+            history(&data, 5),
+            vec![("Cafe".to_string(), "Dining".to_string(), 2)]
+        );
+
+        let now = Utc::now();
+        let items = review_items(
+            &data,
+            vec![
+                suggestion(&sure, 0.95),
+                suggestion(&unsure, 0.4),
+                suggestion(&transaction("Elsewhere", None), 0.99),
+            ],
+            0.9,
+            now,
+        );
+        let report = CategorizationReport::new("test", 2, items.clone());
+        assert_eq!((report.auto_applied, report.queued), (1, 1));
+        assert_eq!(items[0].status, ReviewStatus::AutoApplied);
+        assert_eq!(items[1].payee, "Hardware Co");
+
+        let updated = apply(&mut data, &items[0].suggestion, now).unwrap();
+        assert_eq!(updated.category.as_deref(), Some("Dining"));
+    }
+
+    #[test]
+    fn test_select_by_prefix() {
+        let mut data = FinancialData::new();
+        let first = transaction("A", None);
+        let second = transaction("B", None);
+        data.add_transaction(first.clone());
+        data.add_transaction(second.clone());
+        let pending = review_items(
+            &data,
+            vec![suggestion(&first, 0.5), suggestion(&second, 0.8)],
+            0.9,
+            Utc::now(),
+        );
+
+        let prefix = first.id.to_string()[..8].to_uppercase();
+        let chosen = select(pending.clone(), &[prefix], false, None).unwrap();
+        assert_eq!(chosen[0].suggestion.transaction_id, first.id);
+        assert_eq!(
+            select(pending.clone(), &[], true, Some(0.7)).unwrap().len(),
+            1
+        );
+        assert!(select(pending.clone(), &[], false, None).is_err());
+        assert!(select(pending, &["zzzz".to_string()], false, None).is_err());
+    }
+}
@@ -0,0 +1,557 @@
+use crate::categorize::{Categorizer, CategorySuggestion};
+use crate::data::{FinancialData, Transaction, TransactionType};
+use crate::storage::Database;
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Mutex;
+use tracing::info;
+use uuid::Uuid;
+
+/// Name the model is stored under in the database
+pub const MODEL_NAME: &str = "categorizer.naive_bayes";
+
+/// Upper bounds of the amount buckets used as features
+const AMOUNT_BUCKETS: &[Decimal] = &[
+    dec!(10),
+    dec!(25),
+    dec!(50),
+    dec!(100),
+    dec!(250),
+    dec!(500),
+    dec!(1000),
+];
+
+/// Multinomial naive Bayes over payee, memo and description words plus amount buckets
+///
+/// Every trained transaction is remembered with the features it contributed, so
+/// `update` can add, re-label or forget single transactions without retraining.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NaiveBayes {
+    classes: BTreeMap<String, ClassStats>,
+    /// Number of trained transactions each feature appears in
+    vocabulary: HashMap<String, u64>,
+    trained: HashMap<Uuid, Example>,
+    /// Held-out accuracy from the last full training run
+    pub evaluation: Option<Evaluation>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct ClassStats {
+    documents: u64,
+    features: HashMap<String, u64>,
+    total: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Example {
+    category: String,
+    features: Vec<String>,
+}
+
+/// Changes made by an incremental update
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct UpdateSummary {
+    pub added: usize,
+    pub relabeled: usize,
+    pub removed: usize,
+}
+
+/// Accuracy on categorized transactions held out of training
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Evaluation {
+    pub train_examples: usize,
+    pub test_examples: usize,
+    pub correct: usize,
+    /// Held-out transactions the model made no prediction for
+    pub abstained: usize,
+    /// `correct / test_examples`, or `None` when nothing was held out
+    pub accuracy: Option<f64>,
+    pub evaluated_at: DateTime<Utc>,
+}
+
+/// Outcome of `qspec categorize train`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TrainingReport {
+    pub examples: usize,
+    pub categories: usize,
+    pub vocabulary: usize,
+    pub evaluation: Option<Evaluation>,
+}
+
+/// A predicted category with its posterior probability
+#[derive(Debug, Clone, PartialEq)]
+pub struct Prediction {
+    pub category: String,
+    pub confidence: f64,
+    /// Words of the transaction most often seen with the category
+    pub evidence: Vec<String>,
+}
+
+impl NaiveBayes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Train from scratch on every categorized transaction and measure accuracy
+    ///
+    /// Accuracy comes from a separate model trained without the held-out share;
+    /// the returned model is trained on everything.
+    pub fn train(data: &FinancialData, holdout_fraction: f64, now: DateTime<Utc>) -> Self {
+        let mut model = Self::new();
+        model.update(data, now);
+        model.evaluation = Some(Self::evaluate(data, holdout_fraction, now));
+        model
+    }
+
+    /// Accuracy on a deterministic held-out share of the categorized transactions
+    pub fn evaluate(data: &FinancialData, holdout_fraction: f64, now: DateTime<Utc>) -> Evaluation {
+        let (test, train): (Vec<_>, Vec<_>) = labeled(data).partition(|(t, _)| {
+            (t.id.as_u128() % 1000) < (holdout_fraction * 1000.0).round() as u128
+        });
+
+        let mut model = Self::new();
+        for (transaction, category) in &train {
+            model.learn(transaction.id, category, features(transaction));
+        }
+        let mut correct = 0;
+        let mut abstained = 0;
+        for (transaction, category) in &test {
+            match model.predict(transaction) {
+                Some(prediction) if prediction.category == *category => correct += 1,
+                Some(_) => {}
+                None => abstained += 1,
+            }
+        }
+
+        Evaluation {
+            train_examples: train.len(),
+            test_examples: test.len(),
+            correct,
+            abstained,
+            accuracy: (!test.is_empty()).then(|| correct as f64 / test.len() as f64),
+            evaluated_at: now,
+        }
+    }
+
+    /// Learn newly categorized transactions and follow re-categorized or deleted ones
+    pub fn update(&mut self, data: &FinancialData, now: DateTime<Utc>) -> UpdateSummary {
+        let mut summary = UpdateSummary::default();
+        let current: HashMap<Uuid, (&Transaction, &str)> =
+            labeled(data).map(|(t, c)| (t.id, (t, c))).collect();
+
+        let stale: Vec<Uuid> = self
+            .trained
+            .iter()
+            .filter(|(id, example)| {
+                current
+                    .get(id)
+                    .is_none_or(|(t, c)| example.category != *c || example.features != features(t))
+            })
+            .map(|(id, _)| *id)
+            .collect();
+        for id in stale {
+            self.forget(id);
+            if current.contains_key(&id) {
+                summary.relabeled += 1;
+            } else {
+                summary.removed += 1;
+            }
+        }
+
+        for (id, (transaction, category)) in current {
+            if !self.trained.contains_key(&id) {
+                self.learn(id, category, features(transaction));
+                summary.added += 1;
+            }
+        }
+        summary.added -= summary.relabeled;
+
+        if summary != UpdateSummary::default() {
+            self.updated_at = Some(now);
+        }
+        summary
+    }
+
+    /// Most likely category, if the transaction shares any words with the training data
+    ///
+    /// With fewer than two categories there is nothing to choose between, so no
+    /// prediction is made.
+    pub fn predict(&self, transaction: &Transaction) -> Option<Prediction> {
+        if self.classes.len() < 2 {
+            return None;
+        }
+        let features: Vec<String> = features(transaction)
+            .into_iter()
+            .filter(|f| self.vocabulary.contains_key(f))
+            .collect();
+        if !features.iter().any(|f| is_word(f)) {
+            return None;
+        }
+
+        let documents: u64 = self.classes.values().map(|c| c.documents).sum();
+        let vocabulary = self.vocabulary.len() as f64;
+        let scores: Vec<(&String, f64)> = self
+            .classes
+            .iter()
+            .map(|(category, stats)| {
+                let prior = (stats.documents as f64 / documents as f64).ln();
+                let likelihood: f64 = features
+                    .iter()
+                    .map(|f| {
+                        let count = stats.features.get(f).copied().unwrap_or(0) as f64;
+                        ((count + 1.0) / (stats.total as f64 + vocabulary)).ln()
+                    })
+                    .sum();
+                (category, prior + likelihood)
+            })
+            .collect();
+
+        let best = scores
+            .iter()
+            .map(|(_, s)| *s)
+            .fold(f64::NEG_INFINITY, f64::max);
+        let total: f64 = scores.iter().map(|(_, s)| (s - best).exp()).sum();
+        let (category, _) = scores.iter().find(|(_, s)| *s == best)?;
+        let stats = &self.classes[*category];
+
+        let mut evidence: Vec<&String> = features
+            .iter()
+            .filter(|f| is_word(f) && stats.features.contains_key(*f))
+            .collect();
+        evidence.sort_by_key(|f| std::cmp::Reverse(stats.features[*f]));
+        evidence.truncate(3);
+
+        Some(Prediction {
+            category: category.to_string(),
+            confidence: 1.0 / total,
+            evidence: evidence.into_iter().cloned().collect(),
+        })
+    }
+
+    /// Transactions the model has learned from
+    pub fn examples(&self) -> usize {
+        self.trained.len()
+    }
+
+    pub fn categories(&self) -> usize {
+        self.classes.len()
+    }
+
+    pub fn vocabulary(&self) -> usize {
+        self.vocabulary.len()
+    }
+
+    pub fn report(&self) -> TrainingReport {
+        TrainingReport {
+            examples: self.examples(),
+            categories: self.categories(),
+            vocabulary: self.vocabulary(),
+            evaluation: self.evaluation.clone(),
+        }
+    }
+
+    fn learn(&mut self, id: Uuid, category: &str, features: Vec<String>) {
+        let stats = self.classes.entry(category.to_string()).or_default();
+        stats.documents += 1;
+        for feature in &features {
+            *stats.features.entry(feature.clone()).or_default() += 1;
+            stats.total += 1;
+            *self.vocabulary.entry(feature.clone()).or_default() += 1;
+        }
+        self.trained.insert(
+            id,
+            Example {
+                category: category.to_string(),
+                features,
+            },
+        );
+    }
+
+    fn forget(&mut self, id: Uuid) {
+        let Some(example) = self.trained.remove(&id) else {
+            return;
+        };
+        if let Some(stats) = self.classes.get_mut(&example.category) {
+            stats.documents -= 1;
+            for feature in &example.features {
+                decrement(&mut stats.features, feature);
+                stats.total -= 1;
+            }
+            if stats.documents == 0 {
+                self.classes.remove(&example.category);
+            }
+        }
+        for feature in &example.features {
+            decrement(&mut self.vocabulary, feature);
+        }
+    }
+}
+
+/// Categorizer backed by a `NaiveBayes` model kept in the database
+///
+/// Runs entirely offline. The model follows the books incrementally: each
+/// `learn` call picks up transactions categorized since the last one.
+#[derive(Default)]
+pub struct LocalCategorizer {
+    model: Mutex<NaiveBayes>,
+}
+
+impl LocalCategorizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start from an already trained model
+    pub fn with_model(model: NaiveBayes) -> Self {
+        Self {
+            model: Mutex::new(model),
+        }
+    }
+
+    /// Copy of the current model
+    pub fn model(&self) -> NaiveBayes {
+        self.model.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl Categorizer for LocalCategorizer {
+    fn name(&self) -> &str {
+        "local:naive-bayes"
+    }
+
+    async fn learn(&self, database: &Database, data: &FinancialData) -> Result<()> {
+        let mut model = match database.load_model::<NaiveBayes>(MODEL_NAME).await? {
+            Some(saved) => saved,
+            None => self.model(),
+        };
+        let summary = model.update(data, Utc::now());
+        if summary != UpdateSummary::default() {
+            info!(
+                "Local categorizer learned {} new, {} re-categorized and {} removed transactions",
+                summary.added, summary.relabeled, summary.removed
+            );
+            database.save_model(MODEL_NAME, &model).await?;
+        }
+        *self.model.lock().unwrap() = model;
+        Ok(())
+    }
+
+    async fn suggest(
+        &self,
+        data: &FinancialData,
+        transactions: &[Transaction],
+    ) -> Result<Vec<CategorySuggestion>> {
+        let mut model = self.model.lock().unwrap();
+        model.update(data, Utc::now());
+        Ok(transactions
+            .iter()
+            .filter_map(|transaction| {
+                let prediction = model.predict(transaction)?;
+                Some(CategorySuggestion {
+                    transaction_id: transaction.id,
+                    rationale: if prediction.evidence.is_empty() {
+                        format!("Similar to past {} transactions", prediction.category)
+                    } else {
+                        format!(
+                            "Words seen with {}: {}",
+                            prediction.category,
+                            prediction.evidence.join(", ")
+                        )
+                    },
+                    category: prediction.category,
+                    confidence: prediction.confidence,
+                    source: self.name().to_string(),
+                })
+            })
+            .collect())
+    }
+}
+
+/// Categorized transactions with their category
+fn labeled(data: &FinancialData) -> impl Iterator<Item = (&Transaction, &str)> {
+    data.transactions.iter().filter_map(|t| {
+        t.category
+            .as_deref()
+            .map(str::trim)
+            .filter(|c| !c.is_empty())
+            .map(|c| (t, c))
+    })
+}
+
+/// Words of the payee, memo and description, the amount bucket and the direction
+fn features(transaction: &Transaction) -> Vec<String> {
+    let text = [
+        transaction.payee.as_deref(),
+        transaction.memo.as_deref(),
+        Some(transaction.description.as_str()),
+    ];
+    let mut features: BTreeSet<String> = text
+        .into_iter()
+        .flatten()
+        .flat_map(|t| t.split(|c: char| !c.is_alphanumeric()))
+        .filter(|w| w.chars().count() > 1 && w.chars().any(char::is_alphabetic))
+        .map(str::to_lowercase)
+        .collect();
+
+    let amount = transaction.amount.abs();
+    let bucket = AMOUNT_BUCKETS
+        .iter()
+        .position(|limit| amount < *limit)
+        .unwrap_or(AMOUNT_BUCKETS.len());
+    features.insert(format!("amount:{}", bucket));
+    let direction = match transaction.transaction_type {
+        TransactionType::Credit | TransactionType::Interest | TransactionType::Dividend => "in",
+        _ => "out",
+    };
+    features.insert(format!("direction:{}", direction));
+    features.into_iter().collect()
+}
+
+fn is_word(feature: &str) -> bool {
+    !feature.contains(':')
+}
+
+fn decrement(counts: &mut HashMap<String, u64>, key: &str) {
+    if let Some(count) = counts.get_mut(key) {
+        *count -= 1;
+        if *count == 0 {
+            counts.remove(key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_database_config;
+    use rust_decimal_macros::dec;
+
+    fn transaction(payee: &str, amount: Decimal, category: Option<&str>) -> Transaction {
+        let mut t = Transaction::new(
+            Uuid::new_v4(),
+            Utc::now(),
+            amount,
+            payee.to_string(),
+            TransactionType::Debit,
+        );
+        t.payee = Some(payee.to_string());
+        t.category = category.map(str::to_string);
+        t
+    }
+
+    fn household() -> FinancialData {
+        let mut data = FinancialData::new();
+        for amount in [dec!(8.50), dec!(12.00), dec!(9.75)] {
+            data.add_transaction(transaction("Cafe Luna", amount, Some("Dining")));
+        }
+        data.add_transaction(transaction("Noodle Bar", dec!(21.00), Some("Dining")));
+        for amount in [dec!(64.10), dec!(58.90)] {
+            data.add_transaction(transaction("City Water Dept", amount, Some("Utilities")));
+        }
+        data.add_transaction(transaction("Fresh Market", dec!(143.20), Some("Groceries")));
+        data
+    }
+
+    #[test]
+    fn test_predicts_and_updates_incrementally() {
+        let mut data = household();
+        let mut model = NaiveBayes::new();
+        let summary = model.update(&data, Utc::now());
+        assert_eq!(summary.added, 7);
+        assert_eq!((model.categories(), model.examples()), (3, 7));
+
+        let prediction = model
+            .predict(&transaction("CAFE LUNA #12", dec!(10.00), None))
+            .unwrap();
+        assert_eq!(prediction.category, "Dining");
+        assert!(prediction.confidence > 0.8);
+        assert!(prediction.evidence.contains(&"luna".to_string()));
+        assert!(model
+            .predict(&transaction("Unheard Of", dec!(10.00), None))
+            .is_none());
+
+        // Re-categorize one, delete another and add a new one
+        data.transactions[3].category = Some("Takeout".to_string());
+        let removed = data.transactions.remove(6).id;
+        data.add_transaction(transaction("Corner Grocer", dec!(88.00), Some("Groceries")));
+        let summary = model.update(&data, Utc::now());
+        assert_eq!(
+            summary,
+            UpdateSummary {
+                added: 1,
+                relabeled: 1,
+                removed: 1
+            }
+        );
+        assert!(!model.trained.contains_key(&removed));
+
+        let mut fresh = NaiveBayes::new();
+        fresh.update(&data, Utc::now());
+        assert_eq!(model.classes, fresh.classes);
+        assert_eq!(model.vocabulary, fresh.vocabulary);
+        assert_eq!(model.update(&data, Utc::now()), UpdateSummary::default());
+    }
+
+    #[test]
+    fn test_evaluation_holds_out_a_share() {
+        let mut data = household();
+        for i in 0..40 {
+            let (payee, category) = if i % 2 == 0 {
+                ("Cafe Luna", "Dining")
+            } else {
+                ("City Water Dept", "Utilities")
+            };
+            data.add_transaction(transaction(payee, dec!(20.00), Some(category)));
+        }
+
+        let model = NaiveBayes::train(&data, 0.5, Utc::now());
+        let evaluation = model.evaluation.clone().unwrap();
+        assert_eq!(evaluation.train_examples + evaluation.test_examples, 47);
+        assert!(evaluation.test_examples > 0 && evaluation.train_examples > 0);
+        assert!(evaluation.accuracy.unwrap() > 0.8);
+        assert_eq!(model.examples(), 47);
+
+        let none_held = NaiveBayes::evaluate(&data, 0.0, Utc::now());
+        assert_eq!(none_held.test_examples, 0);
+        assert_eq!(none_held.accuracy, None);
+    }
+
+    #[tokio::test]
+    async fn test_local_categorizer_persists_its_model() {
+        let dir = tempfile::tempdir().unwrap();
+        let database =
+            Database::open_with_passphrase(&test_database_config(dir.path(), true), Some("pw"))
+                .await
+                .unwrap();
+        let mut data = household();
+
+        let categorizer = LocalCategorizer::new();
+        categorizer.learn(&database, &data).await.unwrap();
+        let saved: NaiveBayes = database.load_model(MODEL_NAME).await.unwrap().unwrap();
+        assert_eq!(saved.examples(), 7);
+
+        // A new process picks up the saved model and only learns what changed
+        data.add_transaction(transaction("Noodle Bar", dec!(18.00), Some("Dining")));
+        let restarted = LocalCategorizer::new();
+        restarted.learn(&database, &data).await.unwrap();
+        assert_eq!(restarted.model().examples(), 8);
+
+        let new = transaction("Noodle Bar", dec!(19.00), None);
+        data.add_transaction(new.clone());
+        let suggestions = restarted
+            .suggest(&data, std::slice::from_ref(&new))
+            .await
+            .unwrap();
+        assert_eq!(suggestions[0].transaction_id, new.id);
+        assert_eq!(suggestions[0].category, "Dining");
+        assert_eq!(suggestions[0].source, "local:naive-bayes");
+        database.close().await;
+    }
+}
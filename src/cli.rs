@@ -0,0 +1,1895 @@
+use crate::agent::FinancialAgent;
+use crate::analysis::AnalysisEngine;
+use crate::backup::BackupManager;
+use crate::candidates;
+use crate::categorize;
+use crate::config::{self, Config, ConfigLoader};
+use crate::crypto::CryptoError;
+use crate::data::FinancialData;
+use crate::export::ExportFormat;
+use crate::output::{self, display_currency, AccountBalance, Anomaly, OutputFormat};
+use crate::plugin::PluginRegistry;
+use crate::scheduler::Scheduler;
+use crate::storage::Database;
+use crate::utils::file_utils;
+use anyhow::{Context, Result};
+use chrono::{Datelike, Utc};
+use clap::{Parser, Subcommand};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Process exit codes shared by every `qspec` command
+pub mod exit_code {
+    /// Command completed successfully
+    pub const SUCCESS: u8 = 0;
+    /// Unexpected failure
+    pub const FAILURE: u8 = 1;
+    /// Invalid arguments (also used by clap for parse errors)
+    pub const USAGE: u8 = 2;
+    /// Configuration could not be loaded, parsed or updated
+    pub const CONFIG: u8 = 3;
+    /// Import, export or database operation failed
+    pub const DATA: u8 = 4;
+}
+
+/// QSpec Financial Agent command line
+#[derive(Debug, Parser)]
+#[command(name = "qspec", version, about)]
+pub struct Cli {
+    /// Configuration file to use instead of the system and user files
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Override a configuration value for this run, e.g. `--set logging.level=debug`
+    #[arg(long = "set", global = true, value_name = "KEY=VALUE", value_parser = parse_override)]
+    pub overrides: Vec<(String, String)>,
+
+    /// Output format for reports
+    #[arg(long, short, global = true, value_enum, default_value_t = OutputFormat::Table)]
+    pub output: OutputFormat,
+
+    /// Print AI prompts, after redaction, instead of sending them
+    #[arg(long, global = true)]
+    pub ai_dry_run: bool,
+
+    /// Command to run (defaults to `daemon`)
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Plugins for the agent, registered by binaries that embed the CLI
+    #[arg(skip)]
+    pub plugins: PluginRegistry,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Import one or more QIF files into the database
+    Import {
+        #[arg(required = true, value_name = "FILE")]
+        files: Vec<PathBuf>,
+    },
+
+    /// Export the books (encrypted when `encrypt_exports` is enabled)
+    Export {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = ExportFormat::Qif)]
+        format: ExportFormat,
+
+        /// Output file (standard output when omitted)
+        #[arg(value_name = "FILE")]
+        path: Option<PathBuf>,
+    },
+
+    /// Generate reports
+    Report {
+        #[command(subcommand)]
+        report: ReportCommand,
+    },
+
+    /// Analyze spending
+    Analyze {
+        #[command(subcommand)]
+        analysis: AnalyzeCommand,
+    },
+
+    /// List accounts and balances
+    Accounts,
+
+    /// Show or change configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommand,
+    },
+
+    /// Change or remove the database encryption key
+    Db {
+        #[command(subcommand)]
+        action: DbCommand,
+    },
+
+    /// List database backups or restore one
+    Backup {
+        #[command(subcommand)]
+        action: BackupCommand,
+    },
+
+    /// Show scheduled job status, history, or run a job now
+    Jobs {
+        #[command(subcommand)]
+        action: Option<JobsCommand>,
+    },
+
+    /// Suggest categories for uncategorized transactions and review them
+    Categorize {
+        #[command(subcommand)]
+        action: Option<CategorizeCommand>,
+    },
+
+    /// Propose transactions from receipt or statement text and review them
+    Extract {
+        #[command(subcommand)]
+        action: ExtractCommand,
+    },
+
+    /// Answer a question about the books, e.g. "dining in Q2 compared to last year"
+    #[cfg(feature = "ai-analysis")]
+    Ask {
+        /// The question, in plain words
+        #[arg(required = true, num_args = 1.., value_name = "QUESTION")]
+        question: Vec<String>,
+    },
+
+    /// Work on a multi-step request with the analysis tools, e.g. "categorize last month's Amazon orders"
+    #[cfg(feature = "ai-analysis")]
+    Agent {
+        /// The request, in plain words
+        #[arg(required = true, num_args = 1.., value_name = "REQUEST")]
+        request: Vec<String>,
+
+        /// Apply changes to the books without asking
+        #[arg(long)]
+        yes: bool,
+
+        /// Most tool calls to make (defaults to ai.max_steps)
+        #[arg(long)]
+        max_steps: Option<usize>,
+    },
+
+    /// AI requests, tokens and cost per day, against the configured budget
+    #[cfg(feature = "ai-analysis")]
+    Usage {
+        /// Number of days to list, ending today
+        #[arg(long, default_value_t = 30)]
+        days: u32,
+    },
+
+    /// Serve the books to AI assistants over the Model Context Protocol on stdio
+    Mcp {
+        /// Offer the set_category tool, which changes the books
+        #[arg(long)]
+        allow_writes: bool,
+    },
+
+    /// Watch the Quicken directory and import new files until stopped
+    Daemon,
+
+    /// Browse accounts and categorize transactions in a full-screen UI
+    #[cfg(feature = "tui")]
+    Tui,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ReportCommand {
+    /// Income, expenses and category breakdown for one month
+    Monthly {
+        /// Year (defaults to the current year)
+        #[arg(long)]
+        year: Option<i32>,
+
+        /// Month 1-12 (defaults to the current month)
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..=12))]
+        month: Option<u32>,
+
+        /// Add a plain-English summary checked against the report's figures
+        #[cfg(feature = "ai-analysis")]
+        #[arg(long)]
+        narrative: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AnalyzeCommand {
+    /// Spending totals per category
+    Categories,
+
+    /// Month-over-month spending trend per category
+    Trends {
+        /// Number of months to look back
+        #[arg(long, default_value_t = 6)]
+        months: usize,
+    },
+
+    /// Transactions well above their category average
+    Anomalies,
+
+    /// Findings from loaded plugins
+    Findings,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CategorizeCommand {
+    /// Suggest categories now, applying confident ones (the default)
+    Run,
+
+    /// List suggestions waiting for review
+    Review,
+
+    /// Apply suggested categories
+    Accept {
+        #[command(flatten)]
+        selection: ReviewSelection,
+    },
+
+    /// Dismiss suggestions; their transactions are not suggested again
+    Reject {
+        #[command(flatten)]
+        selection: ReviewSelection,
+    },
+
+    /// Retrain the local model from scratch and report its held-out accuracy
+    Train,
+}
+
+/// Which pending suggestions a review decision applies to
+#[derive(Debug, clap::Args)]
+pub struct ReviewSelection {
+    /// Suggestion ids as shown by `categorize review` (any unique prefix)
+    #[arg(value_name = "ID")]
+    ids: Vec<String>,
+
+    /// Every pending suggestion
+    #[arg(long)]
+    all: bool,
+
+    /// Only suggestions at or above this confidence (0-1)
+    #[arg(long, value_name = "CONFIDENCE")]
+    min_confidence: Option<f64>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ExtractCommand {
+    /// Read already-extracted text (`-` for standard input) into candidates for review
+    #[cfg(feature = "ai-analysis")]
+    Text {
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+
+        /// Account the transactions belong to
+        #[arg(long)]
+        account: String,
+    },
+
+    /// List proposed transactions waiting for review
+    Review,
+
+    /// Add proposed transactions to the books
+    Accept {
+        #[command(flatten)]
+        selection: CandidateSelection,
+    },
+
+    /// Dismiss proposed transactions
+    Reject {
+        #[command(flatten)]
+        selection: CandidateSelection,
+    },
+}
+
+/// Which proposed transactions a review decision applies to
+#[derive(Debug, clap::Args)]
+pub struct CandidateSelection {
+    /// Candidate ids as shown by `extract review` (any unique prefix)
+    #[arg(value_name = "ID")]
+    ids: Vec<String>,
+
+    /// Every pending candidate
+    #[arg(long)]
+    all: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum DbCommand {
+    /// Re-encrypt the database under a new passphrase (encrypts a plaintext store)
+    RotateKey {
+        /// Environment variable holding the new passphrase
+        #[arg(long, value_name = "VAR", default_value = "QSPEC_NEW_PASSPHRASE")]
+        new_passphrase_env: String,
+    },
+
+    /// Decrypt the database in place
+    Decrypt,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum BackupCommand {
+    /// Backups in the backup directory, newest first
+    List,
+
+    /// Verify a backup and replace the database with it (the current one is backed up first)
+    Restore {
+        /// Backup file, as a path or a name from `backup list`
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum JobsCommand {
+    /// Status, last run and next run of every job (the default)
+    List,
+
+    /// Past runs, newest first
+    History {
+        /// Only show runs of this job
+        job: Option<String>,
+
+        /// Only show failed runs
+        #[arg(long)]
+        failures: bool,
+
+        /// Maximum number of runs to show
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+
+    /// Run a job immediately, regardless of its schedule
+    Run { name: String },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommand {
+    /// Print the effective configuration as TOML
+    Show {
+        /// List each setting with the file, variable or flag it came from
+        #[arg(long)]
+        sources: bool,
+    },
+
+    /// Set a value by dotted key, e.g. `quicken.auto_import false`
+    Set { key: String, value: String },
+
+    /// Print the configuration file that `config set` writes to
+    Path,
+
+    /// Check every layer for unknown keys and invalid values
+    Validate,
+
+    /// Upgrade the configuration file to the current format, keeping a backup
+    Migrate,
+}
+
+/// Error from a CLI command, classified for its exit code
+#[derive(Debug, Error)]
+pub enum CliError {
+    #[error("{0:#}")]
+    Usage(anyhow::Error),
+
+    #[error("{0:#}")]
+    Config(anyhow::Error),
+
+    #[error("{0:#}")]
+    Data(anyhow::Error),
+
+    #[error("{0:#}")]
+    Failure(anyhow::Error),
+}
+
+impl CliError {
+    /// Process exit code for this error
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            CliError::Usage(_) => exit_code::USAGE,
+            CliError::Config(_) => exit_code::CONFIG,
+            CliError::Data(_) => exit_code::DATA,
+            CliError::Failure(_) => exit_code::FAILURE,
+        }
+    }
+}
+
+impl Cli {
+    /// Run the selected command, writing its output to standard output
+    pub async fn run(self) -> Result<(), CliError> {
+        // Not locked for the whole run: `mcp` writes to stdout from another thread
+        self.execute(&mut std::io::stdout()).await
+    }
+
+    /// Run the selected command, writing its output to `out`
+    pub async fn execute<W: Write>(self, out: &mut W) -> Result<(), CliError> {
+        let result = self.execute_command(out).await;
+
+        // A dry run stops at the first prompt, which has already been printed
+        #[cfg(feature = "ai-analysis")]
+        if let Err(CliError::Failure(e)) = &result {
+            if crate::ai::is_dry_run(e) {
+                return writeln!(out, "AI dry run: nothing was sent").map_err(output_error);
+            }
+        }
+        result
+    }
+
+    async fn execute_command<W: Write>(self, out: &mut W) -> Result<(), CliError> {
+        let loader = self.loader().map_err(CliError::Config)?;
+        let command = self.command.unwrap_or(Command::Daemon);
+        if let Command::Config { action } = command {
+            return Self::execute_config(loader, self.config, action, self.output, out).await;
+        }
+
+        let config = loader.load().map_err(CliError::Config)?.config;
+        let agent = FinancialAgent::with_config(config).with_plugins(self.plugins);
+
+        match command {
+            Command::Import { files } => {
+                for file in files {
+                    let count = agent.import_file(&file).await.map_err(CliError::Data)?;
+                    writeln!(
+                        out,
+                        "Imported {} transactions from {}",
+                        count,
+                        file.display()
+                    )
+                    .map_err(output_error)?;
+                }
+                Ok(())
+            }
+            Command::Export { format, path } => {
+                let data = agent.load_data().await.map_err(CliError::Data)?;
+                let encryption = &agent.config().database.encryption;
+                let passphrase = if encryption.encrypt_exports {
+                    let passphrase = encryption.resolve_passphrase().map_err(CliError::Config)?;
+                    Some(passphrase.ok_or_else(|| {
+                        CliError::Config(
+                            CryptoError::MissingPassphrase(encryption.passphrase_env.clone())
+                                .into(),
+                        )
+                    })?)
+                } else {
+                    None
+                };
+
+                let bytes = format
+                    .export_to_bytes(&data, passphrase.as_deref(), encryption.kdf)
+                    .map_err(CliError::Data)?;
+                match path {
+                    Some(path) => tokio::fs::write(&path, bytes)
+                        .await
+                        .with_context(|| format!("Failed to write {}", path.display()))
+                        .map_err(CliError::Data),
+                    None => out.write_all(&bytes).map_err(output_error),
+                }
+            }
+            Command::Report { report } => {
+                let data = agent.load_data().await.map_err(CliError::Data)?;
+                let currency = display_currency(&data);
+                match report {
+                    #[cfg(feature = "ai-analysis")]
+                    ReportCommand::Monthly {
+                        year,
+                        month,
+                        narrative: true,
+                    } => {
+                        let today = Utc::now();
+                        let year = year.unwrap_or(today.year());
+                        let month = month.unwrap_or(today.month());
+                        Self::narrative(&agent, &data, year, month)
+                            .await
+                            .and_then(|narrative| {
+                                output::render(&narrative, self.output, currency, out)
+                            })
+                    }
+                    ReportCommand::Monthly { year, month, .. } => {
+                        let today = Utc::now();
+                        let year = year.unwrap_or(today.year());
+                        let month = month.unwrap_or(today.month());
+                        AnalysisEngine::generate_monthly_report(&data, year, month)
+                            .and_then(|report| output::render(&report, self.output, currency, out))
+                    }
+                }
+                .map_err(CliError::Failure)
+            }
+            Command::Analyze { analysis } => {
+                let data = agent.load_data().await.map_err(CliError::Data)?;
+                let currency = display_currency(&data);
+                match analysis {
+                    AnalyzeCommand::Categories => AnalysisEngine::analyze_categories(&data)
+                        .and_then(|report| output::render(&report, self.output, currency, out)),
+                    AnalyzeCommand::Trends { months } => AnalysisEngine::analyze_spending_trends(
+                        &data, months,
+                    )
+                    .and_then(|mut trends| {
+                        trends.sort_by(|a, b| a.category.cmp(&b.category));
+                        output::render(&trends, self.output, currency, out)
+                    }),
+                    AnalyzeCommand::Anomalies => {
+                        AnalysisEngine::detect_anomalies(&data).and_then(|transactions| {
+                            let anomalies = Anomaly::from_transactions(&data, &transactions);
+                            output::render(&anomalies, self.output, currency, out)
+                        })
+                    }
+                    AnalyzeCommand::Findings => {
+                        let findings = agent.plugins().analyze(&data);
+                        output::render(&findings, self.output, currency, out)
+                    }
+                }
+                .map_err(CliError::Failure)
+            }
+            Command::Accounts => {
+                let data = agent.load_data().await.map_err(CliError::Data)?;
+                let balances = AccountBalance::from_data(&data);
+                output::render(&balances, self.output, display_currency(&data), out)
+                    .map_err(CliError::Failure)
+            }
+            Command::Jobs { action } => {
+                Self::execute_jobs(
+                    &agent,
+                    action.unwrap_or(JobsCommand::List),
+                    self.output,
+                    out,
+                )
+                .await
+            }
+            Command::Categorize { action } => {
+                Self::execute_categorize(
+                    &agent,
+                    action.unwrap_or(CategorizeCommand::Run),
+                    self.output,
+                    out,
+                )
+                .await
+            }
+            Command::Extract { action } => {
+                Self::execute_extract(&agent, action, self.output, out).await
+            }
+            #[cfg(feature = "ai-analysis")]
+            Command::Ask { question } => {
+                let ai = &agent.config().ai;
+                let provider = crate::ai::provider_from_config(agent.config())
+                    .map_err(CliError::Config)?
+                    .ok_or_else(|| {
+                        CliError::Config(anyhow::anyhow!(
+                            "Questions need an AI endpoint; set ai.enabled and ai.api_endpoint"
+                        ))
+                    })?;
+                let data = agent.load_data().await.map_err(CliError::Data)?;
+                let today = agent
+                    .clock()
+                    .now()
+                    .with_timezone(&chrono::Local)
+                    .date_naive();
+                let answer = crate::ai::QuestionAnswerer::new(provider, ai.share_data)
+                    .answer(&question.join(" "), &data, today)
+                    .await
+                    .map_err(CliError::Failure)?;
+                output::render(&answer, self.output, display_currency(&data), out)
+                    .map_err(CliError::Failure)
+            }
+            #[cfg(feature = "ai-analysis")]
+            Command::Agent {
+                request,
+                yes,
+                max_steps,
+            } => {
+                let ai = &agent.config().ai;
+                let provider = crate::ai::provider_from_config(agent.config())
+                    .map_err(CliError::Config)?
+                    .ok_or_else(|| {
+                        CliError::Config(anyhow::anyhow!(
+                            "The agent needs an AI endpoint; set ai.enabled and ai.api_endpoint"
+                        ))
+                    })?;
+                let confirm: std::sync::Arc<dyn crate::ai::Confirm> = if yes {
+                    std::sync::Arc::new(crate::ai::AutoConfirm(true))
+                } else {
+                    std::sync::Arc::new(TerminalConfirm)
+                };
+                let today = agent
+                    .clock()
+                    .now()
+                    .with_timezone(&chrono::Local)
+                    .date_naive();
+                let run = crate::ai::Assistant::new(
+                    provider,
+                    confirm,
+                    max_steps.unwrap_or(ai.max_steps).max(1),
+                    ai.share_data,
+                )
+                .run(&agent, &request.join(" "), today)
+                .await
+                .map_err(CliError::Failure)?;
+                let currency = agent_currency(&agent).await?;
+                output::render(&run, self.output, &currency, out).map_err(CliError::Failure)
+            }
+            #[cfg(feature = "ai-analysis")]
+            Command::Usage { days } => {
+                let ai = &agent.config().ai;
+                let path = ai.usage_db.as_ref().ok_or_else(|| {
+                    CliError::Config(anyhow::anyhow!("Usage history needs ai.usage_db"))
+                })?;
+                let ledger = crate::ai::UsageLedger::open(path).map_err(CliError::Data)?;
+                let today = agent
+                    .clock()
+                    .now()
+                    .with_timezone(&chrono::Local)
+                    .date_naive();
+                let report = ledger
+                    .report(today, days, &ai.budget)
+                    .await
+                    .map_err(CliError::Data)?;
+                let currency = agent_currency(&agent).await?;
+                output::render(&report, self.output, &currency, out).map_err(CliError::Failure)
+            }
+            Command::Mcp { allow_writes } => {
+                // stdout carries the protocol, so nothing else may be written to it
+                let stdin = tokio::io::BufReader::new(tokio::io::stdin());
+                crate::mcp::McpServer::new(&agent, allow_writes)
+                    .serve(stdin, tokio::io::stdout())
+                    .await
+                    .map_err(CliError::Failure)
+            }
+            Command::Db { action } => match action {
+                DbCommand::RotateKey { new_passphrase_env } => {
+                    let passphrase = std::env::var(&new_passphrase_env)
+                        .ok()
+                        .filter(|p| !p.is_empty())
+                        .ok_or_else(|| {
+                            CliError::Config(anyhow::anyhow!(
+                                "Set {} to the new passphrase",
+                                new_passphrase_env
+                            ))
+                        })?;
+                    agent
+                        .rotate_key(&passphrase)
+                        .await
+                        .map_err(CliError::Data)?;
+                    writeln!(
+                        out,
+                        "Database re-encrypted; point database.encryption at the new passphrase \
+                         and set database.encryption.enabled = true"
+                    )
+                    .map_err(output_error)
+                }
+                DbCommand::Decrypt => {
+                    agent.remove_encryption().await.map_err(CliError::Data)?;
+                    writeln!(
+                        out,
+                        "Database decrypted; set database.encryption.enabled = false"
+                    )
+                    .map_err(output_error)
+                }
+            },
+            Command::Backup { action } => {
+                let database = &agent.config().database;
+                let manager = BackupManager::new(database);
+                match action {
+                    BackupCommand::List => {
+                        let backups = manager.list_backups().await.map_err(CliError::Data)?;
+                        // Backups matter most when the live database cannot be opened
+                        let currency = match agent_currency(&agent).await {
+                            Ok(currency) => currency,
+                            Err(_) => display_currency(&FinancialData::default()).to_string(),
+                        };
+                        output::render(&backups, self.output, &currency, out)
+                            .map_err(CliError::Failure)
+                    }
+                    BackupCommand::Restore { file } => {
+                        let file = if file.exists() {
+                            file
+                        } else {
+                            manager.directory().join(file)
+                        };
+                        let passphrase = database
+                            .encryption
+                            .resolve_passphrase()
+                            .map_err(CliError::Config)?;
+                        manager
+                            .restore(database, &file, passphrase.as_deref(), agent.clock().now())
+                            .await
+                            .map_err(CliError::Data)?;
+                        writeln!(out, "Restored database from {}", file.display())
+                            .map_err(output_error)
+                    }
+                }
+            }
+            Command::Daemon => agent.run().await.map_err(CliError::Failure),
+            #[cfg(feature = "tui")]
+            Command::Tui => {
+                let database = agent.open_database().await.map_err(CliError::Data)?;
+                let result = crate::tui::run(&database).await.map_err(CliError::Failure);
+                database.close().await;
+                result
+            }
+            Command::Config { .. } => unreachable!("handled above"),
+        }
+    }
+
+    /// Monthly narrative, written by the model only when `ai.share_data` lets figures leave
+    #[cfg(feature = "ai-analysis")]
+    async fn narrative(
+        agent: &FinancialAgent,
+        data: &FinancialData,
+        year: i32,
+        month: u32,
+    ) -> Result<crate::ai::Narrative> {
+        use crate::ai::{MonthFacts, Narrative, NarrativeWriter, TRAILING_MONTHS};
+
+        let ai = &agent.config().ai;
+        let facts = MonthFacts::build(data, year, month, TRAILING_MONTHS)?;
+        match crate::ai::provider_from_config(agent.config())? {
+            Some(provider) if ai.share_data => NarrativeWriter::new(provider).write(facts).await,
+            _ => Ok(Narrative::template(facts)),
+        }
+    }
+
+    async fn execute_jobs<W: Write>(
+        agent: &FinancialAgent,
+        action: JobsCommand,
+        format: OutputFormat,
+        out: &mut W,
+    ) -> Result<(), CliError> {
+        let scheduler =
+            Scheduler::new(&agent.config().scheduler, agent.clock()).map_err(CliError::Config)?;
+        let database = agent.open_database().await.map_err(CliError::Data)?;
+
+        let result = match action {
+            JobsCommand::List => match scheduler.status(&database).await {
+                Ok(status) => match books_currency(&database).await {
+                    Ok(currency) => {
+                        output::render(&status, format, &currency, out).map_err(CliError::Failure)
+                    }
+                    Err(e) => Err(e),
+                },
+                Err(e) => Err(CliError::Data(e)),
+            },
+            JobsCommand::History {
+                job,
+                failures,
+                limit,
+            } => match database.job_runs(job.as_deref(), failures, limit).await {
+                Ok(runs) => match books_currency(&database).await {
+                    Ok(currency) => {
+                        output::render(&runs, format, &currency, out).map_err(CliError::Failure)
+                    }
+                    Err(e) => Err(e),
+                },
+                Err(e) => Err(CliError::Data(e)),
+            },
+            JobsCommand::Run { name } => {
+                Self::run_job_now(agent, &scheduler, &database, &name, out).await
+            }
+        };
+        database.close().await;
+        result
+    }
+
+    async fn execute_categorize<W: Write>(
+        agent: &FinancialAgent,
+        action: CategorizeCommand,
+        format: OutputFormat,
+        out: &mut W,
+    ) -> Result<(), CliError> {
+        let (selection, accept) = match action {
+            CategorizeCommand::Run => {
+                if agent.categorizer().is_none() {
+                    return Err(CliError::Config(anyhow::anyhow!(
+                        "No categorizer available; the llm engine needs ai.enabled and ai.api_endpoint"
+                    )));
+                }
+                let report = agent.categorize().await.map_err(CliError::Failure)?;
+                return output::render(&report, format, &agent_currency(agent).await?, out)
+                    .map_err(CliError::Failure);
+            }
+            CategorizeCommand::Review => {
+                let pending = agent.pending_reviews().await.map_err(CliError::Data)?;
+                return output::render(&pending, format, &agent_currency(agent).await?, out)
+                    .map_err(CliError::Failure);
+            }
+            CategorizeCommand::Train => {
+                let report = agent.train_categorizer().await.map_err(CliError::Data)?;
+                return output::render(&report, format, &agent_currency(agent).await?, out)
+                    .map_err(CliError::Failure);
+            }
+            CategorizeCommand::Accept { selection } => (selection, true),
+            CategorizeCommand::Reject { selection } => (selection, false),
+        };
+
+        let pending = agent.pending_reviews().await.map_err(CliError::Data)?;
+        let chosen = categorize::select(
+            pending,
+            &selection.ids,
+            selection.all,
+            selection.min_confidence,
+        )
+        .map_err(CliError::Usage)?;
+        let decided = agent
+            .decide_reviews(chosen, accept)
+            .await
+            .map_err(CliError::Data)?;
+        output::render(&decided, format, &agent_currency(agent).await?, out)
+            .map_err(CliError::Failure)
+    }
+
+    async fn execute_extract<W: Write>(
+        agent: &FinancialAgent,
+        action: ExtractCommand,
+        format: OutputFormat,
+        out: &mut W,
+    ) -> Result<(), CliError> {
+        let (selection, accept) = match action {
+            #[cfg(feature = "ai-analysis")]
+            ExtractCommand::Text { file, account } => {
+                let report = Self::extract_text(agent, &file, &account).await?;
+                return output::render(&report, format, &agent_currency(agent).await?, out)
+                    .map_err(CliError::Failure);
+            }
+            ExtractCommand::Review => {
+                let pending = agent.pending_candidates().await.map_err(CliError::Data)?;
+                return output::render(&pending, format, &agent_currency(agent).await?, out)
+                    .map_err(CliError::Failure);
+            }
+            ExtractCommand::Accept { selection } => (selection, true),
+            ExtractCommand::Reject { selection } => (selection, false),
+        };
+
+        let pending = agent.pending_candidates().await.map_err(CliError::Data)?;
+        let chosen =
+            candidates::select(pending, &selection.ids, selection.all).map_err(CliError::Usage)?;
+        let decided = agent
+            .decide_candidates(chosen, accept)
+            .await
+            .map_err(CliError::Data)?;
+        output::render(&decided, format, &agent_currency(agent).await?, out)
+            .map_err(CliError::Failure)
+    }
+
+    /// Send the text to the model and queue what checks out for review
+    #[cfg(feature = "ai-analysis")]
+    async fn extract_text(
+        agent: &FinancialAgent,
+        file: &Path,
+        account: &str,
+    ) -> Result<crate::ai::ExtractionReport, CliError> {
+        let ai = &agent.config().ai;
+        let provider = crate::ai::provider_from_config(agent.config())
+            .map_err(CliError::Config)?
+            .ok_or_else(|| {
+                CliError::Config(anyhow::anyhow!(
+                    "Extraction needs an AI endpoint; set ai.enabled and ai.api_endpoint"
+                ))
+            })?;
+        if !ai.share_data {
+            return Err(CliError::Config(anyhow::anyhow!(
+                "Extraction sends the receipt or statement text to the model; set ai.share_data to allow it"
+            )));
+        }
+
+        let (text, source) = if file == Path::new("-") {
+            let mut text = String::new();
+            std::io::Read::read_to_string(&mut std::io::stdin(), &mut text)
+                .context("Failed to read standard input")
+                .map_err(CliError::Data)?;
+            (text, "stdin".to_string())
+        } else {
+            let text = std::fs::read_to_string(file)
+                .with_context(|| format!("Failed to read {}", file.display()))
+                .map_err(CliError::Data)?;
+            (text, file.display().to_string())
+        };
+
+        let data = agent.load_data().await.map_err(CliError::Data)?;
+        let Some(account) = data
+            .accounts
+            .iter()
+            .find(|a| a.name.eq_ignore_ascii_case(account.trim()))
+        else {
+            let names: Vec<&str> = data.accounts.iter().map(|a| a.name.as_str()).collect();
+            return Err(CliError::Usage(anyhow::anyhow!(
+                "No account named '{}'; accounts are: {}",
+                account,
+                names.join(", ")
+            )));
+        };
+        let today = agent
+            .clock()
+            .now()
+            .with_timezone(&chrono::Local)
+            .date_naive();
+
+        let extractor = crate::ai::TextExtractor::new(provider);
+        let extraction = extractor
+            .extract(&text, account, &categorize::known_categories(&data), today)
+            .await
+            .map_err(CliError::Failure)?;
+        let queued = agent
+            .queue_candidates(extraction.transactions, &source, extractor.name())
+            .await
+            .map_err(CliError::Data)?;
+        Ok(crate::ai::ExtractionReport {
+            source,
+            queued,
+            rejected: extraction.rejected,
+        })
+    }
+
+    async fn run_job_now<W: Write>(
+        agent: &FinancialAgent,
+        scheduler: &Scheduler,
+        database: &Database,
+        name: &str,
+        out: &mut W,
+    ) -> Result<(), CliError> {
+        let job = scheduler
+            .job(name)
+            .ok_or_else(|| CliError::Usage(anyhow::anyhow!("Unknown job '{}'", name)))?;
+        let previous = database
+            .job_runs(Some(name), false, 1)
+            .await
+            .map_err(CliError::Data)?;
+        let run = scheduler
+            .run_job(job, agent, database, previous.first())
+            .await
+            .map_err(CliError::Data)?;
+
+        if !run.success {
+            return Err(CliError::Failure(anyhow::anyhow!(
+                "{} failed: {}",
+                run.job,
+                run.message
+            )));
+        }
+        writeln!(out, "{}: {}", run.job, run.message).map_err(output_error)
+    }
+
+    /// Resolve the configuration this invocation will use
+    pub fn load_config(&self) -> Result<Config> {
+        Ok(self.loader()?.load()?.config)
+    }
+
+    /// Configuration layers for this invocation
+    fn loader(&self) -> Result<ConfigLoader> {
+        let loader = match self.config {
+            Some(ref path) => ConfigLoader::new().file(path),
+            None => ConfigLoader::new().standard_files()?,
+        };
+        let loader = self
+            .overrides
+            .iter()
+            .fold(loader.environment(), |loader, (key, value)| {
+                loader.set(key, value)
+            });
+        Ok(if self.ai_dry_run {
+            loader.set("ai.dry_run", "true")
+        } else {
+            loader
+        })
+    }
+
+    async fn execute_config<W: Write>(
+        loader: ConfigLoader,
+        path: Option<PathBuf>,
+        action: ConfigCommand,
+        format: OutputFormat,
+        out: &mut W,
+    ) -> Result<(), CliError> {
+        let path = match path {
+            Some(path) => path,
+            None => Config::default_path().map_err(CliError::Config)?,
+        };
+
+        match action {
+            ConfigCommand::Show { sources } => {
+                let loaded = loader.load().map_err(CliError::Config)?;
+                if sources {
+                    let entries = loaded.entries().map_err(CliError::Failure)?;
+                    return output::render(&entries, format, "USD", out).map_err(CliError::Failure);
+                }
+                let rendered = toml::to_string_pretty(&loaded.config)
+                    .context("Failed to serialize config")
+                    .map_err(CliError::Failure)?;
+                write!(out, "{}", rendered).map_err(output_error)
+            }
+            ConfigCommand::Set { key, value } => {
+                Config::default()
+                    .set_value(&key, &value)
+                    .map_err(CliError::Usage)?;
+                Config::set_in_file(&path, &key, &value)
+                    .await
+                    .map_err(CliError::Config)?;
+                writeln!(out, "Set {} = {} in {}", key, value, path.display()).map_err(output_error)
+            }
+            ConfigCommand::Path => writeln!(out, "{}", path.display()).map_err(output_error),
+            ConfigCommand::Validate => {
+                let loaded = loader.load().map_err(CliError::Config)?;
+                loaded
+                    .config
+                    .check_paths()
+                    .map_err(|e| CliError::Config(e.into()))?;
+                for file in &loaded.migrated {
+                    writeln!(
+                        out,
+                        "{} uses an older format; run `qspec config migrate`",
+                        file.display()
+                    )
+                    .map_err(output_error)?;
+                }
+                writeln!(out, "Configuration is valid").map_err(output_error)
+            }
+            ConfigCommand::Migrate => Self::migrate_config_file(&path, out).await,
+        }
+    }
+
+    async fn migrate_config_file<W: Write>(path: &Path, out: &mut W) -> Result<(), CliError> {
+        let text = tokio::fs::read_to_string(path)
+            .await
+            .with_context(|| format!("Failed to read config file {}", path.display()))
+            .map_err(CliError::Config)?;
+        let mut file: toml::Table = toml::from_str(&text)
+            .with_context(|| format!("Failed to parse config file {}", path.display()))
+            .map_err(CliError::Config)?;
+
+        let applied = config::migrate(&mut file).map_err(CliError::Config)?;
+        if applied.is_empty() {
+            return writeln!(
+                out,
+                "{} is already at version {}",
+                path.display(),
+                config::CONFIG_VERSION
+            )
+            .map_err(output_error);
+        }
+
+        let backup = file_utils::generate_backup_filename(path, Utc::now());
+        tokio::fs::copy(path, &backup)
+            .await
+            .with_context(|| format!("Failed to back up {}", path.display()))
+            .map_err(CliError::Failure)?;
+        let migrated = toml::to_string_pretty(&file)
+            .context("Failed to serialize config")
+            .map_err(CliError::Failure)?;
+        tokio::fs::write(path, migrated)
+            .await
+            .context("Failed to write config file")
+            .map_err(CliError::Failure)?;
+
+        for step in applied {
+            writeln!(out, "Applied: {}", step).map_err(output_error)?;
+        }
+        writeln!(
+            out,
+            "Migrated {} to version {} (previous file saved as {})",
+            path.display(),
+            config::CONFIG_VERSION,
+            backup.display()
+        )
+        .map_err(output_error)
+    }
+}
+
+/// Asks on the terminal before the agent changes the books; declines when not interactive
+#[cfg(feature = "ai-analysis")]
+struct TerminalConfirm;
+
+#[cfg(feature = "ai-analysis")]
+#[async_trait::async_trait]
+impl crate::ai::Confirm for TerminalConfirm {
+    async fn confirm(&self, action: &str) -> bool {
+        use std::io::{BufRead, IsTerminal};
+
+        if !std::io::stdin().is_terminal() {
+            eprintln!(
+                "{}: declined (not interactive; pass --yes to allow changes)",
+                action
+            );
+            return false;
+        }
+        let action = action.to_string();
+        tokio::task::spawn_blocking(move || {
+            eprint!("{}? [y/N] ", action);
+            let mut answer = String::new();
+            std::io::stdin().lock().read_line(&mut answer).is_ok()
+                && matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+        })
+        .await
+        .unwrap_or(false)
+    }
+}
+
+/// Parse a `KEY=VALUE` override
+fn parse_override(arg: &str) -> Result<(String, String), String> {
+    match arg.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => {
+            Ok((key.trim().to_string(), value.to_string()))
+        }
+        _ => Err(format!("expected KEY=VALUE, got '{}'", arg)),
+    }
+}
+
+fn output_error(e: std::io::Error) -> CliError {
+    CliError::Failure(anyhow::Error::new(e).context("Failed to write output"))
+}
+
+/// Currency of the books, for commands whose output does not otherwise load them
+async fn books_currency(database: &Database) -> Result<String, CliError> {
+    let data = FinancialData {
+        accounts: database.load_accounts().await.map_err(CliError::Data)?,
+        ..FinancialData::default()
+    };
+    Ok(display_currency(&data).to_string())
+}
+
+/// Open the books just long enough to read their currency
+async fn agent_currency(agent: &FinancialAgent) -> Result<String, CliError> {
+    let database = agent.open_database().await.map_err(CliError::Data)?;
+    let currency = books_currency(&database).await;
+    database.close().await;
+    currency
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_database_config;
+    use std::path::Path;
+
+    const SAMPLE_QIF: &str = "!Account\nNChecking\nTBank\n^\n!Type:Bank\nD9/5/2026\nT-50.00\nPGrocer\nLGroceries\n^\nD9/20/2026\nT1000.00\nPEmployer\nLSalary\n^\n";
+
+    async fn write_config(dir: &Path) -> PathBuf {
+        let config = Config {
+            database: test_database_config(dir, false),
+            ..Config::default()
+        };
+        let path = dir.join("config.toml");
+        config.save_to(&path).await.unwrap();
+        path
+    }
+
+    async fn run(args: &[&str]) -> (Result<(), CliError>, String) {
+        let cli = Cli::try_parse_from(std::iter::once("qspec").chain(args.iter().copied()))
+            .expect("arguments should parse");
+        let mut out = Vec::new();
+        let result = cli.execute(&mut out).await;
+        (result, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn test_argument_parsing() {
+        assert!(Cli::try_parse_from(["qspec"]).unwrap().command.is_none());
+        assert!(Cli::try_parse_from(["qspec", "export", "--format", "ofx"]).is_ok());
+        assert!(Cli::try_parse_from(["qspec", "export", "--format", "pdf"]).is_err());
+        assert!(Cli::try_parse_from(["qspec", "report", "monthly", "--month", "13"]).is_err());
+        assert!(Cli::try_parse_from(["qspec", "import"]).is_err());
+
+        let cli = Cli::try_parse_from(["qspec", "accounts", "--config", "/tmp/q.toml"]).unwrap();
+        assert_eq!(cli.config, Some(PathBuf::from("/tmp/q.toml")));
+    }
+
+    #[tokio::test]
+    async fn test_import_report_and_export() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = write_config(dir.path()).await;
+        let config = config.to_str().unwrap();
+        let qif = dir.path().join("bank.qif");
+        std::fs::write(&qif, SAMPLE_QIF).unwrap();
+
+        let (result, out) = run(&["--config", config, "import", qif.to_str().unwrap()]).await;
+        result.unwrap();
+        assert!(out.starts_with("Imported 2 transactions"));
+
+        let (result, out) = run(&[
+            "--config", config, "report", "monthly", "--year", "2026", "--month", "9",
+        ])
+        .await;
+        result.unwrap();
+        assert!(out.contains("Monthly report 2026-09"));
+        assert!(out.contains("$1000.00"));
+        assert!(out.contains("Groceries"));
+
+        // Without AI the narrative is built locally from the same figures
+        #[cfg(feature = "ai-analysis")]
+        {
+            let (result, out) = run(&[
+                "--config",
+                config,
+                "-o",
+                "json",
+                "report",
+                "monthly",
+                "--year",
+                "2026",
+                "--month",
+                "9",
+                "--narrative",
+            ])
+            .await;
+            result.unwrap();
+            let json: serde_json::Value = serde_json::from_str(&out).unwrap();
+            assert_eq!(json["schema"], "qspec.report.narrative");
+            assert_eq!(json["data"]["source"], "template");
+            assert_eq!(json["data"]["facts"]["categories"][0]["spent"], "50.00");
+            assert!(json["data"]["summary"]
+                .as_str()
+                .unwrap()
+                .starts_with("In September 2026 income was $1000.00 and spending $50.00"));
+        }
+
+        let (result, out) = run(&["--config", config, "accounts", "--output", "json"]).await;
+        result.unwrap();
+        let json: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(json["schema"], "qspec.accounts");
+        assert_eq!(json["data"][0]["name"], "Checking");
+
+        let (result, out) = run(&["--config", config, "accounts"]).await;
+        result.unwrap();
+        assert!(out.contains("Checking"));
+
+        let csv = dir.path().join("out.csv");
+        let (result, _) = run(&[
+            "--config",
+            config,
+            "export",
+            "--format",
+            "csv",
+            csv.to_str().unwrap(),
+        ])
+        .await;
+        result.unwrap();
+        assert_eq!(std::fs::read_to_string(&csv).unwrap().lines().count(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_exit_codes() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = write_config(dir.path()).await;
+        let config = config.to_str().unwrap();
+
+        let missing = dir.path().join("missing.toml");
+        let (result, _) = run(&["--config", missing.to_str().unwrap(), "accounts"]).await;
+        assert_eq!(result.unwrap_err().exit_code(), exit_code::CONFIG);
+
+        let (result, _) = run(&["--config", config, "import", "/no/such/file.qif"]).await;
+        assert_eq!(result.unwrap_err().exit_code(), exit_code::DATA);
+
+        let (result, _) = run(&["--config", config, "config", "set", "bogus.key", "1"]).await;
+        assert_eq!(result.unwrap_err().exit_code(), exit_code::USAGE);
+
+        let (result, _) = run(&[
+            "--config",
+            config,
+            "config",
+            "set",
+            "logging.level",
+            "debug",
+        ])
+        .await;
+        result.unwrap();
+        let (result, out) = run(&["--config", config, "config", "show"]).await;
+        result.unwrap();
+        assert!(out.contains("level = \"debug\""));
+    }
+
+    #[tokio::test]
+    async fn test_jobs_commands() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = write_config(dir.path()).await;
+        let config = config.to_str().unwrap();
+
+        let (result, out) = run(&["--config", config, "jobs"]).await;
+        result.unwrap();
+        assert!(out.contains("nightly-anomaly-scan"));
+        assert!(out.contains("0 3 * * Sun"));
+
+        let (result, out) = run(&["--config", config, "jobs", "run", "nightly-anomaly-scan"]).await;
+        result.unwrap();
+        assert!(out.starts_with("nightly-anomaly-scan: No anomalies"));
+
+        let (result, out) = run(&["--config", config, "-o", "json", "jobs", "history"]).await;
+        result.unwrap();
+        let json: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(json["schema"], "qspec.jobs.history");
+        assert_eq!(json["data"][0]["job"], "nightly-anomaly-scan");
+        assert_eq!(json["data"][0]["success"], true);
+
+        let (result, out) = run(&["--config", config, "jobs", "history", "--failures"]).await;
+        result.unwrap();
+        assert!(!out.contains("nightly-anomaly-scan"));
+
+        let (result, _) = run(&["--config", config, "jobs", "run", "no-such-job"]).await;
+        assert_eq!(result.unwrap_err().exit_code(), exit_code::USAGE);
+    }
+
+    #[tokio::test]
+    async fn test_review_output_uses_the_books_currency() {
+        use crate::data::{Account, AccountType, Transaction, TransactionType};
+        use rust_decimal_macros::dec;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_config(dir.path()).await;
+        let config = path.to_str().unwrap();
+        let account = Account::new(
+            "Girokonto".to_string(),
+            AccountType::Checking,
+            dec!(0),
+            "EUR".to_string(),
+        );
+        let receipt = Transaction::new(
+            account.id,
+            Utc::now(),
+            dec!(12.40),
+            "Bäckerei".to_string(),
+            TransactionType::Debit,
+        );
+        let mut books = FinancialData::new();
+        books.add_account(account);
+        let agent = FinancialAgent::with_config(Config::load_from(&path).await.unwrap());
+        let database = agent.open_database().await.unwrap();
+        database.save_data(&books).await.unwrap();
+        database.close().await;
+        agent
+            .queue_candidates(vec![receipt], "receipt.txt", "llm:mock")
+            .await
+            .unwrap();
+
+        let (result, out) = run(&["--config", config, "extract", "review"]).await;
+        result.unwrap();
+        assert!(out.contains("€12.40"), "{}", out);
+
+        let (result, out) = run(&["--config", config, "-o", "json", "jobs", "history"]).await;
+        result.unwrap();
+        let json: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(json["currency"], "EUR");
+    }
+
+    #[tokio::test]
+    async fn test_backup_list_and_restore() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = write_config(dir.path()).await;
+        let config = config.to_str().unwrap();
+        let qif = dir.path().join("bank.qif");
+        std::fs::write(&qif, SAMPLE_QIF).unwrap();
+        run(&["--config", config, "import", qif.to_str().unwrap()])
+            .await
+            .0
+            .unwrap();
+
+        let (result, out) = run(&["--config", config, "-o", "json", "backup", "list"]).await;
+        result.unwrap();
+        let json: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(json["schema"], "qspec.backup.list");
+        let path = PathBuf::from(json["data"][0]["path"].as_str().unwrap());
+        let name = path.file_name().unwrap().to_str().unwrap();
+
+        let (result, out) = run(&["--config", config, "backup", "restore", name]).await;
+        result.unwrap();
+        assert!(out.starts_with("Restored database from"));
+
+        let corrupt = dir.path().join("corrupt.db");
+        std::fs::write(&corrupt, "not a database").unwrap();
+        let corrupt = corrupt.to_str().unwrap();
+        let (result, _) = run(&["--config", config, "backup", "restore", corrupt]).await;
+        assert_eq!(result.unwrap_err().exit_code(), exit_code::DATA);
+        let (result, _) = run(&["--config", config, "backup", "restore", "missing.db"]).await;
+        assert_eq!(result.unwrap_err().exit_code(), exit_code::DATA);
+    }
+
+    #[tokio::test]
+    async fn test_db_rotate_key_and_decrypt() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = write_config(dir.path()).await;
+        let config = config.to_str().unwrap();
+        let qif = dir.path().join("bank.qif");
+        std::fs::write(&qif, SAMPLE_QIF).unwrap();
+        run(&["--config", config, "import", qif.to_str().unwrap()])
+            .await
+            .0
+            .unwrap();
+
+        let (result, _) = run(&[
+            "--config",
+            config,
+            "db",
+            "rotate-key",
+            "--new-passphrase-env",
+            "QSPEC_TEST_UNSET_PASSPHRASE",
+        ])
+        .await;
+        assert_eq!(result.unwrap_err().exit_code(), exit_code::CONFIG);
+
+        std::env::set_var("QSPEC_TEST_ROTATED_PASSPHRASE", "rotated");
+        let (result, out) = run(&[
+            "--config",
+            config,
+            "db",
+            "rotate-key",
+            "--new-passphrase-env",
+            "QSPEC_TEST_ROTATED_PASSPHRASE",
+        ])
+        .await;
+        result.unwrap();
+        assert!(out.starts_with("Database re-encrypted"));
+
+        let (result, _) = run(&["--config", config, "accounts"]).await;
+        assert_eq!(result.unwrap_err().exit_code(), exit_code::DATA);
+
+        let passphrase_file = dir.path().join("passphrase");
+        std::fs::write(&passphrase_file, "rotated\n").unwrap();
+        let passphrase_file = format!(
+            "database.encryption.passphrase_file={}",
+            passphrase_file.display()
+        );
+        let encrypted = [
+            "--config",
+            config,
+            "--set",
+            "database.encryption.enabled=true",
+            "--set",
+            &passphrase_file,
+        ];
+        let (result, out) = run(&[&encrypted[..], &["accounts"]].concat()).await;
+        result.unwrap();
+        assert!(out.contains("Checking"));
+
+        let (result, out) = run(&[&encrypted[..], &["db", "decrypt"]].concat()).await;
+        result.unwrap();
+        assert!(out.starts_with("Database decrypted"));
+        let (result, out) = run(&["--config", config, "accounts"]).await;
+        result.unwrap();
+        assert!(out.contains("Checking"));
+    }
+
+    #[tokio::test]
+    async fn test_config_layers_on_command_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = write_config(dir.path()).await;
+        let config = config.to_str().unwrap();
+
+        let (result, out) = run(&[
+            "--config",
+            config,
+            "--set",
+            "logging.level=trace",
+            "-o",
+            "json",
+            "config",
+            "show",
+            "--sources",
+        ])
+        .await;
+        result.unwrap();
+        let json: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(json["schema"], "qspec.config");
+        let level = json["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|e| e["key"] == "logging.level")
+            .unwrap();
+        assert_eq!(level["value"], "\"trace\"");
+        assert_eq!(level["source"]["kind"], "command_line");
+
+        let (result, _) = run(&["--config", config, "--set", "logging.nope=1", "accounts"]).await;
+        assert_eq!(result.unwrap_err().exit_code(), exit_code::CONFIG);
+        assert!(Cli::try_parse_from(["qspec", "--set", "novalue", "accounts"]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_config_validate_and_migrate() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::create_dir(dir.path().join("inbox")).unwrap();
+        std::fs::write(
+            &path,
+            format!(
+                "[quicken]\nwatch_directory = {:?}\n[logging]\nlevel = \"warn\"\n",
+                dir.path().join("inbox")
+            ),
+        )
+        .unwrap();
+        let config = path.to_str().unwrap();
+
+        let (result, out) = run(&["--config", config, "config", "validate"]).await;
+        result.unwrap();
+        assert!(out.contains("older format"));
+
+        let (result, out) = run(&["--config", config, "config", "migrate"]).await;
+        result.unwrap();
+        assert!(out.contains("Migrated"));
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.contains("version = 1"));
+        assert!(text.contains("level = \"warn\""));
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 3);
+
+        let (result, out) = run(&["--config", config, "config", "validate"]).await;
+        result.unwrap();
+        assert_eq!(out, "Configuration is valid\n");
+
+        let (result, out) = run(&["--config", config, "config", "migrate"]).await;
+        result.unwrap();
+        assert!(out.contains("already at version"));
+
+        let (result, _) = run(&[
+            "--config",
+            config,
+            "--set",
+            "logging.level=loud",
+            "config",
+            "validate",
+        ])
+        .await;
+        let err = result.unwrap_err();
+        assert_eq!(err.exit_code(), exit_code::CONFIG);
+        assert!(err.to_string().contains("logging.level"));
+    }
+
+    #[tokio::test]
+    async fn test_categorize_review_commands() {
+        use crate::categorize::{CategorySuggestion, ReviewItem, ReviewStatus};
+
+        let dir = tempfile::tempdir().unwrap();
+        let config = write_config(dir.path()).await;
+        let config = config.to_str().unwrap();
+
+        // Without [ai] the local model is used
+        let (result, out) = run(&["--config", config, "categorize"]).await;
+        result.unwrap();
+        assert!(out.contains("local:naive-bayes: 0 considered"));
+
+        let database = Database::open(&test_database_config(dir.path(), false))
+            .await
+            .unwrap();
+        let mut ids = Vec::new();
+        for (payee, confidence) in [("Cafe", 0.8), ("Hardware", 0.3)] {
+            let transaction = crate::data::Transaction::new(
+                uuid::Uuid::new_v4(),
+                Utc::now(),
+                rust_decimal_macros::dec!(-5),
+                payee.to_string(),
+                crate::data::TransactionType::Debit,
+            );
+            database.upsert_transaction(&transaction).await.unwrap();
+            database
+                .save_review(&ReviewItem {
+                    suggestion: CategorySuggestion {
+                        transaction_id: transaction.id,
+                        category: "Dining".to_string(),
+                        confidence,
+                        rationale: "test".to_string(),
+                        source: "test".to_string(),
+                    },
+                    status: ReviewStatus::Pending,
+                    date: transaction.date.date_naive(),
+                    payee: payee.to_string(),
+                    amount: transaction.amount,
+                    created_at: Utc::now(),
+                    decided_at: None,
+                })
+                .await
+                .unwrap();
+            ids.push(transaction.id.to_string()[..8].to_string());
+        }
+        database.close().await;
+
+        let (result, out) = run(&["--config", config, "categorize", "review"]).await;
+        result.unwrap();
+        assert!(out.contains("Hardware") && out.contains("80.0%"));
+
+        let (result, _) = run(&["--config", config, "categorize", "accept"]).await;
+        assert_eq!(result.unwrap_err().exit_code(), exit_code::USAGE);
+
+        let (result, out) = run(&[
+            "--config",
+            config,
+            "-o",
+            "json",
+            "categorize",
+            "accept",
+            "--all",
+            "--min-confidence",
+            "0.5",
+        ])
+        .await;
+        result.unwrap();
+        let json: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(json["data"][0]["status"], "accepted");
+        assert_eq!(json["data"].as_array().unwrap().len(), 1);
+
+        let (result, _) = run(&["--config", config, "categorize", "reject", &ids[1]]).await;
+        result.unwrap();
+        let (_, out) = run(&["--config", config, "-o", "json", "categorize", "review"]).await;
+        let json: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert!(json["data"].as_array().unwrap().is_empty());
+
+        let (result, out) = run(&["--config", config, "-o", "json", "categorize", "train"]).await;
+        result.unwrap();
+        let json: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(json["schema"], "qspec.categorize.train");
+        assert_eq!(json["data"]["examples"], 1);
+    }
+
+    #[cfg(feature = "ai-analysis")]
+    #[tokio::test]
+    async fn test_ask_shows_generated_queries() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_config(dir.path()).await;
+        let (result, _) = run(&["--config", path.to_str().unwrap(), "ask", "dining?"]).await;
+        assert_eq!(result.unwrap_err().exit_code(), exit_code::CONFIG);
+
+        let server = MockServer::start().await;
+        let plan = r#"{"queries": [{"label": "2026", "from": "2026-01-01", "to": "2026-12-31", "categories": ["Dining"]}]}"#;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{"message": {"role": "assistant", "content": plan}, "finish_reason": "stop"}]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let mut config = Config {
+            database: test_database_config(dir.path(), false),
+            ..Config::default()
+        };
+        config.ai.enabled = true;
+        config.ai.api_endpoint = Some(server.uri());
+        config.ai.audit_log = Some(dir.path().join("ai_audit.jsonl"));
+        config.ai.usage_db = Some(dir.path().join("ai_usage.db"));
+        config.ai.budget.completion_price = rust_decimal_macros::dec!(0.60);
+        config.save_to(&path).await.unwrap();
+
+        let (result, out) = run(&[
+            "--config",
+            path.to_str().unwrap(),
+            "-o",
+            "json",
+            "ask",
+            "how",
+            "much",
+            "on",
+            "dining?",
+        ])
+        .await;
+        result.unwrap();
+        let json: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(json["schema"], "qspec.ask");
+        assert_eq!(json["data"]["question"], "how much on dining?");
+        assert_eq!(json["data"]["plan"]["queries"][0]["label"], "2026");
+        assert_eq!(json["data"]["results"][0]["rows"][0]["count"], 0);
+        assert!(json["data"]["summary"].is_null());
+
+        // A dry run audits the prompt but never reaches the endpoint
+        let (result, out) = run(&[
+            "--config",
+            path.to_str().unwrap(),
+            "--ai-dry-run",
+            "ask",
+            "dining?",
+        ])
+        .await;
+        result.unwrap();
+        assert_eq!(out.trim(), "AI dry run: nothing was sent");
+        let records = crate::ai::read_audit_log(&dir.path().join("ai_audit.jsonl")).unwrap();
+        let outcomes: Vec<_> = records.iter().map(|r| r.outcome).collect();
+        assert_eq!(
+            outcomes,
+            [
+                crate::ai::AuditOutcome::Sent,
+                crate::ai::AuditOutcome::DryRun
+            ]
+        );
+    }
+
+    #[cfg(feature = "ai-analysis")]
+    #[tokio::test]
+    #[cfg(feature = "ai-analysis")]
+    async fn test_extract_queues_candidates_for_review() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let reply = serde_json::json!({"transactions": [
+            {"date": "2026-10-02", "payee": "Green Market", "total": "8.00", "items": [
+                {"description": "Apples", "amount": "4.75", "category": "Groceries"},
+                {"description": "Soap", "amount": "3.25"}
+            ]},
+            {"date": "2026-09-05", "payee": "Grocer", "total": "50.00", "type": "debit"}
+        ]});
+        let dir = tempfile::tempdir().unwrap();
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{"message": {"role": "assistant", "content": reply.to_string()}, "finish_reason": "stop"}]
+            })))
+            .mount(&server)
+            .await;
+        let mut config = Config {
+            database: test_database_config(dir.path(), false),
+            ..Config::default()
+        };
+        config.ai.enabled = true;
+        config.ai.share_data = true;
+        config.ai.api_endpoint = Some(server.uri());
+        config.ai.audit_log = None;
+        config.ai.usage_db = None;
+        let path = dir.path().join("config.toml");
+        config.save_to(&path).await.unwrap();
+        let config = path.to_str().unwrap();
+        let qif = dir.path().join("bank.qif");
+        std::fs::write(&qif, SAMPLE_QIF).unwrap();
+        run(&["--config", config, "import", qif.to_str().unwrap()])
+            .await
+            .0
+            .unwrap();
+        let receipt = dir.path().join("receipt.txt");
+        std::fs::write(&receipt, "GREEN MARKET\nAPPLES 4.75\nSOAP 3.25\nTOTAL 8.00").unwrap();
+        let receipt = receipt.to_str().unwrap();
+
+        let (result, _) = run(&[
+            "--config",
+            config,
+            "extract",
+            "text",
+            receipt,
+            "--account",
+            "Savings",
+        ])
+        .await;
+        assert_eq!(result.unwrap_err().exit_code(), exit_code::USAGE);
+
+        let (result, out) = run(&[
+            "--config",
+            config,
+            "-o",
+            "json",
+            "extract",
+            "text",
+            receipt,
+            "--account",
+            "checking",
+        ])
+        .await;
+        result.unwrap();
+        let json: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(json["schema"], "qspec.extract.text");
+        let queued = json["data"]["queued"].as_array().unwrap();
+        assert_eq!(queued.len(), 2);
+        assert_eq!(queued[0]["transaction"]["splits"][1]["amount"], "3.25");
+        assert!(queued[0]["possible_duplicate"].is_null());
+        assert!(queued[1]["possible_duplicate"].is_string());
+
+        // Nothing reaches the books until it is accepted
+        let count = |data: &FinancialData| data.transactions.len();
+        let agent = FinancialAgent::with_config(Config::load_from(&path).await.unwrap());
+        assert_eq!(count(&agent.load_data().await.unwrap()), 2);
+
+        let (_, out) = run(&["--config", config, "extract", "review"]).await;
+        assert!(out.contains("Apples 4.75 (Groceries); Soap 3.25"));
+        assert!(out.contains("possible duplicate of"));
+        let id = queued[0]["transaction"]["id"].as_str().unwrap();
+        run(&["--config", config, "extract", "accept", &id[..8]])
+            .await
+            .0
+            .unwrap();
+        run(&["--config", config, "extract", "reject", "--all"])
+            .await
+            .0
+            .unwrap();
+
+        let data = agent.load_data().await.unwrap();
+        assert_eq!(count(&data), 3);
+        let receipt = data
+            .transactions
+            .iter()
+            .find(|t| t.splits.len() == 2)
+            .unwrap();
+        assert_eq!(receipt.category.as_deref(), Some("Groceries"));
+        let (_, out) = run(&["--config", config, "-o", "json", "extract", "review"]).await;
+        let json: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(json["data"], serde_json::json!([]));
+    }
+
+    #[tokio::test]
+    async fn test_agent_runs_tools_and_confirmed_writes() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+        /// Searches, then recategorizes the first hit, then answers
+        struct Script;
+
+        impl Respond for Script {
+            fn respond(&self, request: &Request) -> ResponseTemplate {
+                let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+                let messages = body["messages"].as_array().unwrap();
+                let reply = match messages.len() {
+                    2 => {
+                        serde_json::json!({"tool": "search_transactions", "arguments": {"query": "grocer"}})
+                    }
+                    4 => {
+                        let last = messages[3]["content"].as_str().unwrap();
+                        let result: serde_json::Value = serde_json::from_str(last).unwrap();
+                        serde_json::json!({
+                            "tool": "set_category",
+                            "arguments": {"transaction_id": result["result"][0]["id"], "category": "Food"}
+                        })
+                    }
+                    _ => serde_json::json!({"answer": "Moved Grocer to Food."}),
+                };
+                ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "choices": [{"message": {"role": "assistant", "content": reply.to_string()}, "finish_reason": "stop"}]
+                }))
+            }
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(Script)
+            .mount(&server)
+            .await;
+        let mut config = Config {
+            database: test_database_config(dir.path(), false),
+            ..Config::default()
+        };
+        config.ai.enabled = true;
+        config.ai.api_endpoint = Some(server.uri());
+        config.ai.audit_log = None;
+        config.ai.usage_db = None;
+        let path = dir.path().join("config.toml");
+        config.save_to(&path).await.unwrap();
+        let config = path.to_str().unwrap();
+        let qif = dir.path().join("bank.qif");
+        std::fs::write(&qif, SAMPLE_QIF).unwrap();
+        run(&["--config", config, "import", qif.to_str().unwrap()])
+            .await
+            .0
+            .unwrap();
+
+        // Without sharing, row-level tools are refused and nothing changes
+        let (result, out) = run(&[
+            "--config",
+            config,
+            "-o",
+            "json",
+            "agent",
+            "--yes",
+            "tidy",
+            "groceries",
+        ])
+        .await;
+        result.unwrap();
+        let json: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(json["data"]["steps"][0]["outcome"], "error");
+        assert_eq!(json["data"]["steps"][1]["outcome"], "error");
+
+        let (result, out) = run(&[
+            "--config",
+            config,
+            "--set",
+            "ai.share_data=true",
+            "-o",
+            "json",
+            "agent",
+            "--yes",
+            "tidy",
+            "groceries",
+        ])
+        .await;
+        result.unwrap();
+        let json: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(json["schema"], "qspec.agent");
+        assert_eq!(json["data"]["answer"], "Moved Grocer to Food.");
+        assert_eq!(json["data"]["steps"][1]["tool"], "set_category");
+        assert_eq!(json["data"]["steps"][1]["outcome"], "ok");
+
+        let (result, out) = run(&["--config", config, "-o", "json", "analyze", "categories"]).await;
+        result.unwrap();
+        assert!(out.contains("\"Food\""));
+    }
+}
@@ -1,11 +1,30 @@
-use anyhow::{Context, Result};
+use crate::crypto::KdfParams;
+use crate::events::AgentEvent;
+use crate::scheduler::ScheduledJob;
+use anyhow::{bail, Context, Result};
 use directories::ProjectDirs;
+use lettre::message::Mailbox;
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tracing::warn;
+
+/// Current configuration file format version
+pub const CONFIG_VERSION: u32 = 1;
+
+/// Log levels accepted by `logging.level`
+const LOG_LEVELS: [&str; 5] = ["trace", "debug", "info", "warn", "error"];
 
 /// Application configuration structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
+    /// Format version of the file; files without one are version 0
+    #[serde(default)]
+    pub version: u32,
+
     /// Database connection configuration
     pub database: DatabaseConfig,
 
@@ -17,6 +36,70 @@ pub struct Config {
 
     /// Logging configuration
     pub logging: LoggingConfig,
+
+    /// Recurring jobs run by the agent
+    #[serde(default)]
+    pub scheduler: SchedulerConfig,
+
+    /// Where agent alerts are delivered
+    #[serde(default)]
+    pub notifications: NotificationConfig,
+
+    /// Monthly spending limit per category, e.g. `Groceries = "600.00"`
+    #[serde(default)]
+    pub budgets: BTreeMap<String, Decimal>,
+
+    /// Automatic categorization of new transactions
+    #[serde(default)]
+    pub categorization: CategorizationConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CategorizationConfig {
+    /// Which categorizer makes suggestions
+    pub engine: CategorizerEngine,
+
+    /// Suggest categories for uncategorized transactions after each import
+    pub on_import: bool,
+
+    /// Suggestions at or above this confidence (0-1) are applied without review
+    pub auto_apply_threshold: f64,
+
+    /// Transactions sent to the categorizer per request
+    pub batch_size: usize,
+
+    /// Past payee/category pairs shown to the categorizer as examples
+    pub history_examples: usize,
+
+    /// Share of categorized transactions the local model holds out to measure accuracy
+    pub holdout_fraction: f64,
+}
+
+/// Source of category suggestions
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CategorizerEngine {
+    /// The AI endpoint when `[ai]` is enabled, otherwise the local model
+    #[default]
+    Auto,
+    /// Only the AI endpoint
+    Llm,
+    /// Only the classifier trained on this household's history
+    Local,
+}
+
+impl Default for CategorizationConfig {
+    fn default() -> Self {
+        Self {
+            engine: CategorizerEngine::Auto,
+            on_import: true,
+            auto_apply_threshold: 0.9,
+            batch_size: 20,
+            history_examples: 30,
+            holdout_fraction: 0.2,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,6 +109,53 @@ pub struct DatabaseConfig {
 
     /// Maximum number of database connections
     pub max_connections: u32,
+
+    /// Encryption at rest for the database and exports
+    #[serde(default)]
+    pub encryption: EncryptionConfig,
+
+    /// Automatic backups of the database
+    #[serde(default)]
+    pub backup: BackupConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupConfig {
+    /// Take automatic backups before imports, rule runs, migrations and on a schedule
+    pub enabled: bool,
+
+    /// Directory for backups (defaults to `backups/` next to the database)
+    pub directory: Option<PathBuf>,
+
+    /// Hours between scheduled backups
+    pub interval_hours: u32,
+
+    /// Number of most recent backups always kept
+    pub keep_last: usize,
+
+    /// Keep the newest backup of each day for this many days
+    pub keep_daily_days: u32,
+
+    /// Keep the newest backup of each month for this many months
+    pub keep_monthly_months: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptionConfig {
+    /// Encrypt the database contents with a passphrase-derived key
+    pub enabled: bool,
+
+    /// Also encrypt exported files
+    pub encrypt_exports: bool,
+
+    /// Environment variable holding the passphrase
+    pub passphrase_env: String,
+
+    /// File holding the passphrase (takes precedence over the environment variable)
+    pub passphrase_file: Option<PathBuf>,
+
+    /// Argon2id key derivation parameters used for new keys
+    pub kdf: KdfParams,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,6 +168,22 @@ pub struct QuickenConfig {
 
     /// Auto-import new files
     pub auto_import: bool,
+
+    /// How long a file must stay unchanged before it is imported (milliseconds)
+    #[serde(default = "default_settle_ms")]
+    pub settle_ms: u64,
+
+    /// Interval between directory scans, also used when file events are unavailable (milliseconds)
+    #[serde(default = "default_poll_interval_ms")]
+    pub poll_interval_ms: u64,
+}
+
+fn default_settle_ms() -> u64 {
+    2000
+}
+
+fn default_poll_interval_ms() -> u64 {
+    5000
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -50,6 +196,181 @@ pub struct AiConfig {
 
     /// API key for AI services
     pub api_key: Option<String>,
+
+    /// Environment variable holding the API key, used when `api_key` is not set
+    #[serde(default)]
+    pub api_key_env: Option<String>,
+
+    /// Model name sent with each request, e.g. `gpt-4o-mini` or `llama3.1:8b`
+    #[serde(default = "default_ai_model")]
+    pub model: String,
+
+    /// Per-request timeout (seconds)
+    #[serde(default = "default_ai_timeout")]
+    pub timeout_secs: u64,
+
+    /// Retries after a timeout, rate limit or server error
+    #[serde(default = "default_ai_retries")]
+    pub max_retries: u32,
+
+    /// Delay before the first retry, doubled for each further attempt (milliseconds)
+    #[serde(default = "default_ai_backoff_ms")]
+    pub retry_backoff_ms: u64,
+
+    /// Let `qspec ask` send payees and query results, monthly narratives the month's
+    /// figures, and `qspec agent` its tool results to the model
+    #[serde(default)]
+    pub share_data: bool,
+
+    /// What is hidden from prompts before they leave the machine
+    #[serde(default)]
+    pub redaction: RedactionConfig,
+
+    /// JSON-lines record of every prompt as sent; unset disables the audit trail
+    #[serde(default)]
+    pub audit_log: Option<PathBuf>,
+
+    /// Print and audit prompts without sending them
+    #[serde(default)]
+    pub dry_run: bool,
+
+    /// SQLite file holding cached responses and the usage that budgets are checked against
+    #[serde(default)]
+    pub usage_db: Option<PathBuf>,
+
+    #[serde(default)]
+    pub cache: AiCacheConfig,
+
+    #[serde(default)]
+    pub budget: AiBudgetConfig,
+
+    /// Most tool calls `qspec agent` makes before giving up
+    #[serde(default = "default_ai_max_steps")]
+    pub max_steps: usize,
+}
+
+/// Reuse of earlier answers to identical prompts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AiCacheConfig {
+    pub enabled: bool,
+    /// Days a cached answer stays valid; 0 keeps answers forever
+    pub ttl_days: u32,
+}
+
+/// Token and cost limits for AI calls; calls stop once a limit is reached
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AiBudgetConfig {
+    pub daily_tokens: Option<u64>,
+    pub monthly_tokens: Option<u64>,
+    pub daily_cost: Option<Decimal>,
+    pub monthly_cost: Option<Decimal>,
+    /// Price per million prompt tokens, used to compute cost
+    pub prompt_price: Decimal,
+    /// Price per million completion tokens
+    pub completion_price: Decimal,
+}
+
+/// Per-kind handling of personal details in prompts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RedactionConfig {
+    pub account_numbers: RedactionMode,
+    pub emails: RedactionMode,
+    pub phone_numbers: RedactionMode,
+    pub addresses: RedactionMode,
+    /// Applies to the people listed in `names`
+    pub people: RedactionMode,
+    /// Amounts labelled as balances
+    pub balances: RedactionMode,
+    /// Household members and other people to keep out of prompts
+    pub names: Vec<String>,
+}
+
+impl Default for AiCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            ttl_days: 30,
+        }
+    }
+}
+
+impl AiBudgetConfig {
+    /// Whether any limit is set
+    pub fn is_limited(&self) -> bool {
+        self.daily_tokens.is_some()
+            || self.monthly_tokens.is_some()
+            || self.daily_cost.is_some()
+            || self.monthly_cost.is_some()
+    }
+
+    /// Cost of `prompt_tokens` and `completion_tokens` at the configured prices
+    pub fn cost(&self, prompt_tokens: u64, completion_tokens: u64) -> Decimal {
+        (Decimal::from(prompt_tokens) * self.prompt_price
+            + Decimal::from(completion_tokens) * self.completion_price)
+            / Decimal::from(1_000_000)
+    }
+}
+
+/// How one kind of personal detail is treated
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RedactionMode {
+    /// Send unchanged
+    Keep,
+    /// Replace with a fixed marker; the model's answer keeps the marker
+    Mask,
+    /// Replace with a numbered placeholder that is swapped back in the answer
+    #[default]
+    Tokenize,
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            account_numbers: RedactionMode::Tokenize,
+            emails: RedactionMode::Tokenize,
+            phone_numbers: RedactionMode::Tokenize,
+            addresses: RedactionMode::Tokenize,
+            people: RedactionMode::Tokenize,
+            balances: RedactionMode::Tokenize,
+            names: Vec::new(),
+        }
+    }
+}
+
+fn default_ai_max_steps() -> usize {
+    8
+}
+
+fn default_ai_model() -> String {
+    "gpt-4o-mini".to_string()
+}
+
+fn default_ai_timeout() -> u64 {
+    60
+}
+
+fn default_ai_retries() -> u32 {
+    3
+}
+
+fn default_ai_backoff_ms() -> u64 {
+    500
+}
+
+impl AiConfig {
+    /// API key from the config file or from `api_key_env`
+    pub fn resolve_api_key(&self) -> Option<String> {
+        self.api_key.clone().or_else(|| {
+            self.api_key_env
+                .as_ref()
+                .and_then(|name| std::env::var(name).ok())
+                .filter(|key| !key.is_empty())
+        })
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -57,11 +378,312 @@ pub struct LoggingConfig {
     /// Log level (trace, debug, info, warn, error)
     pub level: String,
 
+    /// Per-module levels, e.g. `"qspec_fin_agent::watcher" = "debug"`
+    #[serde(default)]
+    pub modules: BTreeMap<String, String>,
+
+    /// Line format for both the console and the log file
+    #[serde(default)]
+    pub format: LogFormat,
+
+    /// Log to standard error
+    #[serde(default = "default_true")]
+    pub console: bool,
+
     /// Log to file
     pub file_logging: bool,
 
-    /// Log file path
+    /// Log file path; rotated files insert the period before the extension
     pub log_file: Option<PathBuf>,
+
+    /// How often to start a new log file
+    #[serde(default)]
+    pub rotation: LogRotation,
+
+    /// Number of rotated log files to keep
+    #[serde(default = "default_keep_log_files")]
+    pub keep_files: usize,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human-readable lines
+    #[default]
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRotation {
+    Hourly,
+    #[default]
+    Daily,
+    Never,
+}
+
+fn default_keep_log_files() -> usize {
+    14
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchedulerConfig {
+    /// Run scheduled jobs while the agent is running
+    pub enabled: bool,
+
+    /// Time zone used to evaluate cron expressions
+    pub timezone: ScheduleTimezone,
+
+    /// Directory for generated monthly reports (defaults to `reports/` next to the database)
+    pub report_directory: Option<PathBuf>,
+
+    /// Jobs and their cron schedules
+    pub jobs: Vec<JobConfig>,
+
+    /// Recurring bills covered by `bill_reminders` jobs
+    pub bills: Vec<BillConfig>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScheduleTimezone {
+    Local,
+    Utc,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobConfig {
+    /// Unique job name shown in `qspec jobs`
+    pub name: String,
+
+    /// What the job does
+    pub kind: JobKind,
+
+    /// Cron expression: `min hour day month weekday`, optionally with leading seconds
+    pub schedule: String,
+
+    /// Disabled jobs are listed but never run
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+/// Built-in job types
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    AnomalyScan,
+    MonthlyReport,
+    Backup,
+    BillReminders,
+}
+
+impl JobKind {
+    /// Name as written in the configuration file
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobKind::AnomalyScan => "anomaly_scan",
+            JobKind::MonthlyReport => "monthly_report",
+            JobKind::Backup => "backup",
+            JobKind::BillReminders => "bill_reminders",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BillConfig {
+    /// Bill name, e.g. "Electricity"
+    pub name: String,
+
+    /// Expected amount, if known
+    pub amount: Option<Decimal>,
+
+    /// Currency of the amount; defaults to the first account's currency
+    #[serde(default)]
+    pub currency: Option<String>,
+
+    /// Day of the month the bill is due (clamped to the month's last day)
+    pub due_day: u32,
+
+    /// Start reminding this many days before the due date
+    #[serde(default = "default_remind_days")]
+    pub remind_days_before: u32,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_remind_days() -> u32 {
+    3
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        let job = |name: &str, kind, schedule: &str| JobConfig {
+            name: name.to_string(),
+            kind,
+            schedule: schedule.to_string(),
+            enabled: true,
+        };
+
+        Self {
+            enabled: true,
+            timezone: ScheduleTimezone::Local,
+            report_directory: None,
+            jobs: vec![
+                job("nightly-anomaly-scan", JobKind::AnomalyScan, "0 2 * * *"),
+                job("monthly-report", JobKind::MonthlyReport, "0 6 1 * *"),
+                job("weekly-backup", JobKind::Backup, "0 3 * * Sun"),
+                job("bill-reminders", JobKind::BillReminders, "0 8 * * *"),
+            ],
+            bills: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NotificationConfig {
+    /// Delivery channels; every matching channel receives each alert
+    #[serde(default)]
+    pub channels: Vec<ChannelConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChannelConfig {
+    /// Channel name used in logs
+    pub name: String,
+
+    /// Transport and its settings
+    #[serde(flatten)]
+    pub kind: ChannelKind,
+
+    /// Event names routed to this channel, e.g. `anomaly_detected`; empty means all
+    #[serde(default)]
+    pub events: Vec<String>,
+
+    /// Send at most this many notifications per rolling hour
+    #[serde(default)]
+    pub max_per_hour: Option<u32>,
+
+    /// Batch alerts into a single digest sent at most every this many minutes
+    #[serde(default)]
+    pub digest_minutes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChannelKind {
+    Email(EmailChannelConfig),
+    Webhook(WebhookChannelConfig),
+    Command(CommandChannelConfig),
+    File(FileChannelConfig),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmailChannelConfig {
+    pub smtp_host: String,
+
+    #[serde(default = "default_smtp_port")]
+    pub smtp_port: u16,
+
+    /// `none` for a local relay, `starttls` or `tls`
+    #[serde(default)]
+    pub security: SmtpSecurity,
+
+    pub username: Option<String>,
+
+    /// Environment variable holding the SMTP password
+    pub password_env: Option<String>,
+
+    pub from: String,
+    pub to: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    None,
+    #[default]
+    StartTls,
+    Tls,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookChannelConfig {
+    pub url: String,
+
+    /// Extra request headers, e.g. an authorization token
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+
+    #[serde(default = "default_webhook_timeout")]
+    pub timeout_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandChannelConfig {
+    /// Program to run, e.g. `notify-send`
+    pub program: String,
+
+    /// Arguments; `{title}`, `{body}` and `{event}` are substituted
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileChannelConfig {
+    /// File that receives one JSON notification per line
+    pub path: PathBuf,
+}
+
+fn default_smtp_port() -> u16 {
+    587
+}
+
+fn default_webhook_timeout() -> u64 {
+    10
+}
+
+impl Default for EncryptionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            encrypt_exports: false,
+            passphrase_env: "QSPEC_PASSPHRASE".to_string(),
+            passphrase_file: None,
+            kdf: KdfParams::default(),
+        }
+    }
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            directory: None,
+            interval_hours: 24,
+            keep_last: 10,
+            keep_daily_days: 7,
+            keep_monthly_months: 12,
+        }
+    }
+}
+
+impl EncryptionConfig {
+    /// Resolve the passphrase from the configured file or environment variable
+    pub fn resolve_passphrase(&self) -> Result<Option<String>> {
+        if let Some(ref path) = self.passphrase_file {
+            let passphrase = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read passphrase file {}", path.display()))?;
+            return Ok(Some(passphrase.trim_end_matches(['\r', '\n']).to_string()));
+        }
+
+        Ok(std::env::var(&self.passphrase_env)
+            .ok()
+            .filter(|p| !p.is_empty()))
+    }
 }
 
 impl Default for Config {
@@ -73,9 +695,12 @@ impl Default for Config {
         let _config_dir = project_dirs.config_dir().to_path_buf();
 
         Self {
+            version: CONFIG_VERSION,
             database: DatabaseConfig {
                 path: data_dir.join("qspec_fin_agent.db"),
                 max_connections: 5,
+                encryption: EncryptionConfig::default(),
+                backup: BackupConfig::default(),
             },
             quicken: QuickenConfig {
                 watch_directory: dirs::home_dir()
@@ -84,64 +709,979 @@ impl Default for Config {
                     .join("Quicken"),
                 file_patterns: vec!["*.qif".to_string(), "*.QIF".to_string()],
                 auto_import: true,
+                settle_ms: default_settle_ms(),
+                poll_interval_ms: default_poll_interval_ms(),
             },
             ai: AiConfig {
                 enabled: false,
                 api_endpoint: None,
                 api_key: None,
+                api_key_env: None,
+                model: default_ai_model(),
+                timeout_secs: default_ai_timeout(),
+                max_retries: default_ai_retries(),
+                retry_backoff_ms: default_ai_backoff_ms(),
+                share_data: false,
+                redaction: RedactionConfig::default(),
+                audit_log: Some(data_dir.join("ai_audit.jsonl")),
+                dry_run: false,
+                usage_db: Some(data_dir.join("ai_usage.db")),
+                cache: AiCacheConfig::default(),
+                budget: AiBudgetConfig::default(),
+                max_steps: default_ai_max_steps(),
             },
             logging: LoggingConfig {
                 level: "info".to_string(),
+                modules: BTreeMap::new(),
+                format: LogFormat::Text,
+                console: true,
                 file_logging: true,
                 log_file: Some(data_dir.join("qspec_fin_agent.log")),
+                rotation: LogRotation::Daily,
+                keep_files: default_keep_log_files(),
             },
+            scheduler: SchedulerConfig::default(),
+            notifications: NotificationConfig::default(),
+            budgets: BTreeMap::new(),
+            categorization: CategorizationConfig::default(),
         }
     }
 }
 
 impl Config {
-    /// Load configuration from file or create default
-    pub async fn load() -> Result<Self> {
+    /// Default location of the configuration file
+    pub fn default_path() -> Result<PathBuf> {
         let project_dirs = ProjectDirs::from("com", "qspec", "fin-agent")
             .context("Failed to get project directories")?;
 
-        let config_path = project_dirs.config_dir().join("config.toml");
-
-        if config_path.exists() {
-            let config_str = tokio::fs::read_to_string(&config_path)
-                .await
-                .context("Failed to read config file")?;
-
-            let config: Config =
-                toml::from_str(&config_str).context("Failed to parse config file")?;
+        Ok(project_dirs.config_dir().join("config.toml"))
+    }
 
-            Ok(config)
+    /// System-wide configuration file, read before the user's
+    pub fn system_path() -> Option<PathBuf> {
+        if cfg!(windows) {
+            std::env::var_os("PROGRAMDATA").map(|dir| {
+                PathBuf::from(dir)
+                    .join("qspec")
+                    .join("fin-agent")
+                    .join("config.toml")
+            })
         } else {
-            let config = Config::default();
-            config.save().await?;
-            Ok(config)
+            Some(PathBuf::from("/etc/qspec-fin-agent/config.toml"))
         }
     }
 
+    /// Load the layered configuration from the standard locations
+    ///
+    /// Missing files are skipped and nothing is written.
+    pub async fn load() -> Result<Self> {
+        Ok(ConfigLoader::new()
+            .standard_files()?
+            .environment()
+            .load()?
+            .config)
+    }
+
+    /// Load built-in defaults overlaid with one explicit file
+    pub async fn load_from<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(ConfigLoader::new().file(path.as_ref()).load()?.config)
+    }
+
     /// Save configuration to file
     pub async fn save(&self) -> Result<()> {
-        let project_dirs = ProjectDirs::from("com", "qspec", "fin-agent")
-            .context("Failed to get project directories")?;
+        self.save_to(Self::default_path()?).await
+    }
 
-        let config_dir = project_dirs.config_dir();
-        tokio::fs::create_dir_all(config_dir)
-            .await
-            .context("Failed to create config directory")?;
+    /// Save configuration to an explicit file
+    pub async fn save_to<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .context("Failed to create config directory")?;
+        }
 
-        let config_path = config_dir.join("config.toml");
         let config_str = toml::to_string_pretty(self).context("Failed to serialize config")?;
 
-        tokio::fs::write(&config_path, config_str)
+        tokio::fs::write(path, config_str)
             .await
             .context("Failed to write config file")?;
 
         Ok(())
     }
+
+    /// Set one value in a configuration file, leaving the rest of the file alone
+    ///
+    /// The key and value are checked against the full configuration first, so
+    /// a typo never reaches the file.
+    pub async fn set_in_file<P: AsRef<Path>>(path: P, key: &str, value: &str) -> Result<()> {
+        let path = path.as_ref();
+        let (mut file, mut config) = if path.exists() {
+            let text = tokio::fs::read_to_string(path)
+                .await
+                .with_context(|| format!("Failed to read config file {}", path.display()))?;
+            let mut file: toml::Table = toml::from_str(&text)
+                .with_context(|| format!("Failed to parse config file {}", path.display()))?;
+            migrate(&mut file)?;
+            (file, Self::load_from(path).await?)
+        } else {
+            let mut file = toml::Table::new();
+            file.insert(
+                "version".to_string(),
+                toml::Value::Integer(CONFIG_VERSION.into()),
+            );
+            (file, Config::default())
+        };
+
+        config.set_value(key, value)?;
+        let updated = toml::Value::try_from(&config).context("Failed to serialize config")?;
+        let typed = key
+            .split('.')
+            .try_fold(&updated, |v, part| v.get(part))
+            .with_context(|| format!("Unknown configuration key '{}'", key))?;
+        insert_dotted(&mut file, key, typed.clone());
+
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .context("Failed to create config directory")?;
+        }
+        let text = toml::to_string_pretty(&file).context("Failed to serialize config")?;
+        tokio::fs::write(path, text)
+            .await
+            .context("Failed to write config file")
+    }
+
+    /// Set a value by dotted key (e.g. `quicken.auto_import`), parsing it as TOML
+    ///
+    /// Values for string settings are taken verbatim; anything else must parse
+    /// as a TOML value of the setting's type.
+    pub fn set_value(&mut self, key: &str, value: &str) -> Result<()> {
+        let mut root = toml::Value::try_from(&*self).context("Failed to serialize config")?;
+        let (parents, leaf) = match key.rsplit_once('.') {
+            Some((parents, leaf)) => (parents.split('.').collect::<Vec<_>>(), leaf),
+            None => (Vec::new(), key),
+        };
+
+        let mut table = root
+            .as_table_mut()
+            .context("Configuration is not a table")?;
+        for part in &parents {
+            table = table
+                .get_mut(*part)
+                .and_then(toml::Value::as_table_mut)
+                .with_context(|| format!("Unknown configuration key '{}'", key))?;
+        }
+
+        let parsed = match table.get(leaf) {
+            Some(toml::Value::String(_)) => toml::Value::String(value.to_string()),
+            _ => parse_toml_value(value),
+        };
+        table.insert(leaf.to_string(), parsed);
+
+        let updated: Config = root
+            .try_into()
+            .with_context(|| format!("Invalid value '{}' for '{}'", value, key))?;
+
+        // Unknown keys are ignored by deserialization, so make sure the value stuck
+        let check = toml::Value::try_from(&updated).context("Failed to serialize config")?;
+        let mut current = Some(&check);
+        for part in parents.iter().chain(std::iter::once(&leaf)) {
+            current = current.and_then(|v| v.get(*part));
+        }
+        if current.is_none() {
+            bail!("Unknown configuration key '{}'", key);
+        }
+
+        *self = updated;
+        Ok(())
+    }
+
+    /// Check values that parse but make no sense, reporting every problem
+    pub fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = Vec::new();
+        let mut fail = |path: String, message: String| {
+            errors.push(ValidationError { path, message });
+        };
+
+        if self.version > CONFIG_VERSION {
+            fail(
+                "version".to_string(),
+                format!("{} is newer than this build supports", self.version),
+            );
+        }
+        if self.database.max_connections == 0 {
+            fail(
+                "database.max_connections".to_string(),
+                "must be greater than 0".to_string(),
+            );
+        }
+        if let Err(message) = self.database.encryption.kdf.check() {
+            fail("database.encryption.kdf".to_string(), message);
+        }
+        if self.database.backup.enabled && self.database.backup.interval_hours == 0 {
+            fail(
+                "database.backup.interval_hours".to_string(),
+                "must be greater than 0".to_string(),
+            );
+        }
+
+        if self.quicken.file_patterns.is_empty() {
+            fail(
+                "quicken.file_patterns".to_string(),
+                "needs at least one pattern".to_string(),
+            );
+        }
+        for (i, pattern) in self.quicken.file_patterns.iter().enumerate() {
+            if let Err(e) = glob::Pattern::new(pattern) {
+                fail(format!("quicken.file_patterns[{}]", i), e.to_string());
+            }
+        }
+        if self.quicken.poll_interval_ms == 0 {
+            fail(
+                "quicken.poll_interval_ms".to_string(),
+                "must be greater than 0".to_string(),
+            );
+        }
+
+        match &self.ai.api_endpoint {
+            Some(endpoint) => {
+                if let Err(message) = check_http_url(endpoint) {
+                    fail("ai.api_endpoint".to_string(), message);
+                }
+            }
+            None if self.ai.enabled => fail(
+                "ai.api_endpoint".to_string(),
+                "is required when ai.enabled is true".to_string(),
+            ),
+            None => {}
+        }
+        if self.ai.enabled && self.ai.model.trim().is_empty() {
+            fail(
+                "ai.model".to_string(),
+                "is required when ai.enabled is true".to_string(),
+            );
+        }
+        if self.ai.timeout_secs == 0 {
+            fail(
+                "ai.timeout_secs".to_string(),
+                "must be greater than 0".to_string(),
+            );
+        }
+        if self.ai.max_steps == 0 {
+            fail(
+                "ai.max_steps".to_string(),
+                "must be greater than 0".to_string(),
+            );
+        }
+        if self.ai.budget.is_limited() && self.ai.usage_db.is_none() {
+            fail(
+                "ai.usage_db".to_string(),
+                "is required when an ai.budget limit is set".to_string(),
+            );
+        }
+        let budget = &self.ai.budget;
+        for (key, value) in [
+            ("daily_cost", budget.daily_cost),
+            ("monthly_cost", budget.monthly_cost),
+            ("prompt_price", Some(budget.prompt_price)),
+            ("completion_price", Some(budget.completion_price)),
+        ] {
+            if value.is_some_and(|v| v.is_sign_negative()) {
+                fail(
+                    format!("ai.budget.{}", key),
+                    "must not be negative".to_string(),
+                );
+            }
+        }
+
+        if !LOG_LEVELS.contains(&self.logging.level.to_lowercase().as_str()) {
+            fail(
+                "logging.level".to_string(),
+                format!(
+                    "'{}' is not one of {}",
+                    self.logging.level,
+                    LOG_LEVELS.join(", ")
+                ),
+            );
+        }
+
+        for (module, level) in &self.logging.modules {
+            if !LOG_LEVELS.contains(&level.to_lowercase().as_str()) {
+                fail(
+                    format!("logging.modules.{}", module),
+                    format!("'{}' is not one of {}", level, LOG_LEVELS.join(", ")),
+                );
+            }
+        }
+        if self.logging.file_logging {
+            if self.logging.log_file.is_none() {
+                fail(
+                    "logging.log_file".to_string(),
+                    "is required when file_logging is true".to_string(),
+                );
+            }
+            if self.logging.keep_files == 0 {
+                fail(
+                    "logging.keep_files".to_string(),
+                    "must be greater than 0".to_string(),
+                );
+            }
+        }
+
+        let mut job_names = std::collections::HashSet::new();
+        for (i, job) in self.scheduler.jobs.iter().enumerate() {
+            if !job_names.insert(job.name.as_str()) {
+                fail(
+                    format!("scheduler.jobs[{}].name", i),
+                    format!("duplicate job name '{}'", job.name),
+                );
+            }
+            if let Err(e) = ScheduledJob::parse(job) {
+                fail(
+                    format!("scheduler.jobs[{}].schedule", i),
+                    format!("{:#}", e),
+                );
+            }
+        }
+        for (i, bill) in self.scheduler.bills.iter().enumerate() {
+            if !(1..=31).contains(&bill.due_day) {
+                fail(
+                    format!("scheduler.bills[{}].due_day", i),
+                    "must be between 1 and 31".to_string(),
+                );
+            }
+        }
+
+        for (i, channel) in self.notifications.channels.iter().enumerate() {
+            let path = format!("notifications.channels[{}]", i);
+            for (j, event) in channel.events.iter().enumerate() {
+                if !AgentEvent::NAMES.contains(&event.as_str()) {
+                    fail(
+                        format!("{}.events[{}]", path, j),
+                        format!(
+                            "unknown event '{}' (expected one of {})",
+                            event,
+                            AgentEvent::NAMES.join(", ")
+                        ),
+                    );
+                }
+            }
+            match &channel.kind {
+                ChannelKind::Email(email) => {
+                    if email.from.parse::<Mailbox>().is_err() {
+                        fail(
+                            format!("{}.from", path),
+                            format!("'{}' is not an email address", email.from),
+                        );
+                    }
+                    if email.to.is_empty() {
+                        fail(
+                            format!("{}.to", path),
+                            "needs at least one recipient".to_string(),
+                        );
+                    }
+                    for (j, to) in email.to.iter().enumerate() {
+                        if to.parse::<Mailbox>().is_err() {
+                            fail(
+                                format!("{}.to[{}]", path, j),
+                                format!("'{}' is not an email address", to),
+                            );
+                        }
+                    }
+                }
+                ChannelKind::Webhook(webhook) => {
+                    if let Err(message) = check_http_url(&webhook.url) {
+                        fail(format!("{}.url", path), message);
+                    }
+                }
+                ChannelKind::Command(_) | ChannelKind::File(_) => {}
+            }
+        }
+
+        let categorization = &self.categorization;
+        if !(0.0..=1.0).contains(&categorization.auto_apply_threshold) {
+            fail(
+                "categorization.auto_apply_threshold".to_string(),
+                "must be between 0 and 1".to_string(),
+            );
+        }
+        if categorization.batch_size == 0 {
+            fail(
+                "categorization.batch_size".to_string(),
+                "must be greater than 0".to_string(),
+            );
+        }
+        if categorization.engine == CategorizerEngine::Llm && !self.ai.enabled {
+            fail(
+                "categorization.engine".to_string(),
+                "\"llm\" requires ai.enabled".to_string(),
+            );
+        }
+        if !(0.0..1.0).contains(&categorization.holdout_fraction) {
+            fail(
+                "categorization.holdout_fraction".to_string(),
+                "must be at least 0 and less than 1".to_string(),
+            );
+        }
+
+        for (category, budget) in &self.budgets {
+            if budget.is_sign_negative() {
+                fail(
+                    format!("budgets.{}", category),
+                    "must not be negative".to_string(),
+                );
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationErrors(errors))
+        }
+    }
+
+    /// Check paths the agent needs before it starts watching for imports
+    pub fn check_paths(&self) -> Result<(), ValidationErrors> {
+        let dir = &self.quicken.watch_directory;
+        if self.quicken.auto_import && !dir.is_dir() {
+            let message = if dir.exists() {
+                format!("{} is not a directory", dir.display())
+            } else {
+                format!("{} does not exist", dir.display())
+            };
+            return Err(ValidationErrors(vec![ValidationError {
+                path: "quicken.watch_directory".to_string(),
+                message,
+            }]));
+        }
+        Ok(())
+    }
+}
+
+fn check_http_url(value: &str) -> std::result::Result<(), String> {
+    match url::Url::parse(value) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") => Ok(()),
+        Ok(url) => Err(format!(
+            "'{}' must use http or https, not {}",
+            value,
+            url.scheme()
+        )),
+        Err(e) => Err(format!("'{}' is not a valid URL: {}", value, e)),
+    }
+}
+
+/// Prefix of environment variables that override configuration values
+pub const ENV_PREFIX: &str = "QSPEC_";
+
+/// Separator between key segments in environment variable names,
+/// e.g. `QSPEC_DATABASE__BACKUP__KEEP_LAST`
+pub const ENV_SEPARATOR: &str = "__";
+
+const DEFAULT_ORIGIN: &str = "default";
+const CLI_ORIGIN: &str = "command line";
+const ENV_ORIGIN: &str = "the environment";
+
+/// Where an effective configuration value came from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", content = "name", rename_all = "snake_case")]
+pub enum ConfigSource {
+    Default,
+    File(PathBuf),
+    Environment(String),
+    CommandLine,
+}
+
+/// One setting in the effective configuration
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConfigEntry {
+    pub key: String,
+    /// Value rendered as TOML
+    pub value: String,
+    pub source: ConfigSource,
+}
+
+/// Resolves configuration from layered sources
+///
+/// Layers apply in order, later ones winning: built-in defaults, files in the
+/// order added, `QSPEC_*` environment variables, then `set` overrides.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigLoader {
+    files: Vec<(PathBuf, bool)>,
+    env: Vec<(String, String)>,
+    overrides: Vec<(String, String)>,
+}
+
+/// Configuration together with the origin of each value
+#[derive(Debug, Clone)]
+pub struct LoadedConfig {
+    pub config: Config,
+    /// Files that existed and were read
+    pub files: Vec<PathBuf>,
+    /// Files older than `CONFIG_VERSION`, migrated in memory only
+    pub migrated: Vec<PathBuf>,
+    origins: BTreeMap<String, String>,
+}
+
+/// A fixed set of values that all report the same origin
+#[derive(Debug, Clone)]
+struct Layer {
+    origin: String,
+    values: toml::Table,
+}
+
+impl ConfigLoader {
+    /// Built-in defaults only
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the system and user files, skipping whichever do not exist
+    pub fn standard_files(mut self) -> Result<Self> {
+        if let Some(system) = Config::system_path() {
+            self.files.push((system, false));
+        }
+        self.files.push((Config::default_path()?, false));
+        Ok(self)
+    }
+
+    /// Add a file that must exist
+    pub fn file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.files.push((path.into(), true));
+        self
+    }
+
+    /// Read `QSPEC_*` overrides from the process environment
+    pub fn environment(self) -> Self {
+        self.env_vars(std::env::vars())
+    }
+
+    /// Read `QSPEC_*` overrides from the given variables
+    ///
+    /// Only names with a `__` key separator are used, so unrelated variables
+    /// such as `QSPEC_PASSPHRASE` are left alone.
+    pub fn env_vars(mut self, vars: impl IntoIterator<Item = (String, String)>) -> Self {
+        self.env = vars
+            .into_iter()
+            .filter(|(name, _)| {
+                name.strip_prefix(ENV_PREFIX)
+                    .is_some_and(|key| key.contains(ENV_SEPARATOR))
+            })
+            .collect();
+        self
+    }
+
+    /// Override a value by dotted key, as given on the command line
+    pub fn set(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.overrides.push((key.into(), value.into()));
+        self
+    }
+
+    /// Resolve every layer into a validated configuration
+    ///
+    /// Older files are migrated in memory; unknown keys and invalid values are
+    /// all reported together, each with its path.
+    pub fn load(&self) -> Result<LoadedConfig> {
+        let defaults = toml::Value::try_from(Config::default())
+            .context("Failed to serialize default config")?;
+        let mut builder = config::Config::builder().add_source(Layer {
+            origin: DEFAULT_ORIGIN.to_string(),
+            values: defaults.as_table().cloned().unwrap_or_default(),
+        });
+        let mut errors = Vec::new();
+
+        let mut tables = Vec::new();
+        for (path, required) in &self.files {
+            if !path.exists() {
+                if *required {
+                    bail!("Config file {} does not exist", path.display());
+                }
+                continue;
+            }
+            let text = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read config file {}", path.display()))?;
+            let values: toml::Table = toml::from_str(&text)
+                .with_context(|| format!("Failed to parse config file {}", path.display()))?;
+            tables.push((path, values));
+        }
+        // One layer's [scheduler] is the user's choice for all of them; an older
+        // file layered alongside must not switch the scheduler off
+        let scheduler_configured = tables.iter().any(|(_, v)| v.contains_key("scheduler"));
+
+        let mut files = Vec::new();
+        let mut migrated = Vec::new();
+        for (path, mut values) in tables {
+            let had_scheduler = values.contains_key("scheduler");
+            let steps = migrate(&mut values)
+                .with_context(|| format!("Cannot migrate config file {}", path.display()))?;
+            if scheduler_configured && !had_scheduler {
+                values.remove("scheduler");
+            }
+            if !steps.is_empty() {
+                warn!(
+                    "Config file {} is from an older version; run `qspec config migrate` to update it",
+                    path.display()
+                );
+                migrated.push(path.clone());
+            }
+
+            match unknown_keys(&values) {
+                Ok(keys) => errors.extend(keys.into_iter().map(|key| ValidationError {
+                    path: format!("{}: {}", path.display(), key),
+                    message: "unknown setting".to_string(),
+                })),
+                Err(e) => {
+                    // Merging would only fail later with a less precise message
+                    errors.push(ValidationError {
+                        path: format!("{}: {}", path.display(), e.path),
+                        message: e.message,
+                    });
+                    return Err(ValidationErrors(errors).into());
+                }
+            }
+            builder = builder.add_source(Layer {
+                origin: path.display().to_string(),
+                values,
+            });
+            files.push(path.clone());
+        }
+
+        if !self.env.is_empty() {
+            builder = builder.add_source(
+                config::Environment::with_prefix(ENV_PREFIX.trim_end_matches('_'))
+                    .prefix_separator("_")
+                    .separator(ENV_SEPARATOR)
+                    .try_parsing(true)
+                    .source(Some(self.env.iter().cloned().collect())),
+            );
+        }
+
+        if !self.overrides.is_empty() {
+            let mut values = toml::Table::new();
+            for (key, value) in &self.overrides {
+                insert_dotted(&mut values, key, parse_toml_value(value));
+            }
+            builder = builder.add_source(Layer {
+                origin: CLI_ORIGIN.to_string(),
+                values,
+            });
+        }
+
+        let merged = builder.build().context("Failed to merge configuration")?;
+        let mut origins = BTreeMap::new();
+        collect_origins(&config::Source::collect(&merged)?, "", &mut origins);
+        let config: Config = merged
+            .try_deserialize()
+            .map_err(|e| anyhow::anyhow!("Invalid configuration: {}", e))?;
+
+        let known = toml::Value::try_from(&config).context("Failed to serialize config")?;
+        for (name, _) in &self.env {
+            let key = env_key(name);
+            if lookup(&known, &key).is_none() {
+                errors.push(ValidationError {
+                    path: name.clone(),
+                    message: format!("unknown setting '{}'", key),
+                });
+            }
+        }
+        for (key, _) in &self.overrides {
+            if lookup(&known, key).is_none() {
+                errors.push(ValidationError {
+                    path: format!("--set {}", key),
+                    message: "unknown setting".to_string(),
+                });
+            }
+        }
+        if let Err(invalid) = config.validate() {
+            errors.extend(invalid.0);
+        }
+        if !errors.is_empty() {
+            return Err(ValidationErrors(errors).into());
+        }
+
+        Ok(LoadedConfig {
+            config,
+            files,
+            migrated,
+            origins,
+        })
+    }
+}
+
+impl LoadedConfig {
+    /// Where a dotted key's value came from
+    pub fn source(&self, key: &str) -> ConfigSource {
+        match self.origins.get(key).map(String::as_str) {
+            None | Some(DEFAULT_ORIGIN) => ConfigSource::Default,
+            Some(CLI_ORIGIN) => ConfigSource::CommandLine,
+            Some(ENV_ORIGIN) => ConfigSource::Environment(format!(
+                "{}{}",
+                ENV_PREFIX,
+                key.to_uppercase().replace('.', ENV_SEPARATOR)
+            )),
+            Some(path) => ConfigSource::File(PathBuf::from(path)),
+        }
+    }
+
+    /// Every effective setting with its source, in key order
+    pub fn entries(&self) -> Result<Vec<ConfigEntry>> {
+        let root = toml::Value::try_from(&self.config).context("Failed to serialize config")?;
+        let mut leaves = Vec::new();
+        flatten(&root, "", &mut leaves);
+        Ok(leaves
+            .into_iter()
+            .map(|(key, value)| ConfigEntry {
+                source: self.source(&key),
+                value: value.to_string(),
+                key,
+            })
+            .collect())
+    }
+}
+
+/// One problem found in the configuration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    /// Dotted key, prefixed by the file or variable it came from when known
+    pub path: String,
+    pub message: String,
+}
+
+/// Every problem found while loading the configuration, reported together
+#[derive(Debug, Error)]
+#[error("Invalid configuration:{}", .0.iter().map(|e| format!("\n  {}", e)).collect::<String>())]
+pub struct ValidationErrors(pub Vec<ValidationError>);
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// An upgrade from one file format version to the next
+struct Migration {
+    from: u32,
+    description: &'static str,
+    apply: fn(&mut toml::Table),
+}
+
+/// Upgrades applied in order to files older than `CONFIG_VERSION`
+const MIGRATIONS: &[Migration] = &[Migration {
+    from: 0,
+    description: "keep scheduled jobs off for installs that predate [scheduler]",
+    apply: |file| {
+        if file.contains_key("scheduler") {
+            return;
+        }
+        let mut scheduler = toml::Table::new();
+        scheduler.insert("enabled".to_string(), toml::Value::Boolean(false));
+        file.insert("scheduler".to_string(), toml::Value::Table(scheduler));
+    },
+}];
+
+/// Bring a parsed config file up to `CONFIG_VERSION`
+///
+/// Returns the description of each migration applied; an up-to-date file is
+/// left untouched.
+pub fn migrate(file: &mut toml::Table) -> Result<Vec<&'static str>> {
+    let current = match file.get("version") {
+        None => 0,
+        Some(toml::Value::Integer(v)) => u32::try_from(*v)
+            .map_err(|_| anyhow::anyhow!("version: {} is not a valid version", v))?,
+        Some(other) => bail!("version: expected an integer, found {}", other.type_str()),
+    };
+    if current > CONFIG_VERSION {
+        bail!(
+            "version: {} is newer than this build supports ({})",
+            current,
+            CONFIG_VERSION
+        );
+    }
+
+    let mut applied = Vec::new();
+    let mut version = current;
+    for migration in MIGRATIONS.iter().filter(|m| m.from >= current) {
+        (migration.apply)(file);
+        applied.push(migration.description);
+        version = migration.from + 1;
+    }
+    if !applied.is_empty() {
+        file.insert("version".to_string(), toml::Value::Integer(version.into()));
+    }
+    Ok(applied)
+}
+
+/// Keys in a config file that the configuration does not know about
+///
+/// Serde silently drops unknown fields, so the file is overlaid on the
+/// defaults, round-tripped through [`Config`] and compared. A file that does
+/// not deserialize yields the path of its first type error instead.
+fn unknown_keys(file: &toml::Table) -> std::result::Result<Vec<String>, ValidationError> {
+    let defaults = match toml::Value::try_from(Config::default()) {
+        Ok(toml::Value::Table(table)) => table,
+        _ => toml::Table::new(),
+    };
+    let merged = toml::Value::Table(merge_tables(defaults, file.clone()));
+    let config: Config = serde_path_to_error::deserialize(merged).map_err(|e| ValidationError {
+        path: e.path().to_string(),
+        message: e.into_inner().message().to_string(),
+    })?;
+
+    let mut unknown = Vec::new();
+    if let Ok(known) = toml::Value::try_from(&config) {
+        unknown_paths(&toml::Value::Table(file.clone()), &known, "", &mut unknown);
+    }
+    Ok(unknown)
+}
+
+fn unknown_paths(value: &toml::Value, known: &toml::Value, prefix: &str, out: &mut Vec<String>) {
+    match (value, known) {
+        (toml::Value::Table(table), toml::Value::Table(known)) => {
+            for (key, child) in table {
+                let path = join_key(prefix, key);
+                match known.get(key) {
+                    Some(known_child) => unknown_paths(child, known_child, &path, out),
+                    None => out.push(path),
+                }
+            }
+        }
+        (toml::Value::Array(items), toml::Value::Array(known)) => {
+            for (i, (item, known_item)) in items.iter().zip(known).enumerate() {
+                unknown_paths(item, known_item, &format!("{}[{}]", prefix, i), out);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn merge_tables(mut base: toml::Table, overlay: toml::Table) -> toml::Table {
+    for (key, value) in overlay {
+        let merged = match (base.remove(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(overlay)) => {
+                toml::Value::Table(merge_tables(base, overlay))
+            }
+            (_, value) => value,
+        };
+        base.insert(key, merged);
+    }
+    base
+}
+
+/// Dotted key for a `QSPEC_SECTION__KEY` variable
+fn env_key(name: &str) -> String {
+    name.trim_start_matches(ENV_PREFIX)
+        .to_lowercase()
+        .replace(ENV_SEPARATOR, ".")
+}
+
+fn lookup<'a>(root: &'a toml::Value, key: &str) -> Option<&'a toml::Value> {
+    key.split('.').try_fold(root, |v, part| v.get(part))
+}
+
+impl config::Source for Layer {
+    fn clone_into_box(&self) -> Box<dyn config::Source + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn collect(&self) -> Result<config::Map<String, config::Value>, config::ConfigError> {
+        Ok(self
+            .values
+            .iter()
+            .map(|(key, value)| (key.clone(), to_config_value(value, &self.origin)))
+            .collect())
+    }
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigSource::Default => write!(f, "default"),
+            ConfigSource::File(path) => write!(f, "{}", path.display()),
+            ConfigSource::Environment(name) => write!(f, "${}", name),
+            ConfigSource::CommandLine => write!(f, "--set"),
+        }
+    }
+}
+
+fn to_config_value(value: &toml::Value, origin: &String) -> config::Value {
+    let kind = match value {
+        toml::Value::String(s) => config::ValueKind::String(s.clone()),
+        toml::Value::Integer(i) => config::ValueKind::I64(*i),
+        toml::Value::Float(f) => config::ValueKind::Float(*f),
+        toml::Value::Boolean(b) => config::ValueKind::Boolean(*b),
+        toml::Value::Datetime(d) => config::ValueKind::String(d.to_string()),
+        toml::Value::Array(items) => {
+            config::ValueKind::Array(items.iter().map(|v| to_config_value(v, origin)).collect())
+        }
+        toml::Value::Table(table) => config::ValueKind::Table(
+            table
+                .iter()
+                .map(|(k, v)| (k.clone(), to_config_value(v, origin)))
+                .collect(),
+        ),
+    };
+    config::Value::new(Some(origin), kind)
+}
+
+/// Record the origin of every leaf; arrays count as a single value
+fn collect_origins(
+    table: &config::Map<String, config::Value>,
+    prefix: &str,
+    origins: &mut BTreeMap<String, String>,
+) {
+    for (key, value) in table {
+        let path = join_key(prefix, key);
+        match value.kind {
+            config::ValueKind::Table(ref child) => collect_origins(child, &path, origins),
+            _ => {
+                if let Some(origin) = value.origin() {
+                    origins.insert(path, origin.to_string());
+                }
+            }
+        }
+    }
+}
+
+fn flatten(value: &toml::Value, prefix: &str, leaves: &mut Vec<(String, toml::Value)>) {
+    match value {
+        toml::Value::Table(table) => {
+            for (key, child) in table {
+                flatten(child, &join_key(prefix, key), leaves);
+            }
+        }
+        _ => leaves.push((prefix.to_string(), value.clone())),
+    }
+}
+
+fn join_key(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", prefix, key)
+    }
+}
+
+fn insert_dotted(table: &mut toml::Table, key: &str, value: toml::Value) {
+    match key.split_once('.') {
+        Some((head, rest)) => {
+            let child = table
+                .entry(head.to_string())
+                .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+            if let toml::Value::Table(child) = child {
+                insert_dotted(child, rest, value);
+            }
+        }
+        None => {
+            table.insert(key.to_string(), value);
+        }
+    }
+}
+
+fn parse_toml_value(value: &str) -> toml::Value {
+    toml::from_str::<toml::Table>(&format!("value = {}", value))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(value.to_string()))
 }
 
 #[cfg(test)]
@@ -159,14 +1699,94 @@ mod tests {
 
     #[tokio::test]
     async fn test_config_load_and_save() {
-        let _temp_dir = tempdir().expect("Failed to create temp dir");
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let path = temp_dir.path().join("nested").join("config.toml");
+
+        // Partial files are filled in from the defaults, and set only touches its key
+        tokio::fs::create_dir_all(path.parent().unwrap())
+            .await
+            .unwrap();
+        tokio::fs::write(&path, "# household settings\n[logging]\nlevel = \"warn\"\n")
+            .await
+            .unwrap();
+        Config::set_in_file(&path, "database.max_connections", "2")
+            .await
+            .unwrap();
+        assert!(Config::set_in_file(&path, "database.nope", "2")
+            .await
+            .is_err());
+
+        let text = tokio::fs::read_to_string(&path).await.unwrap();
+        assert!(!text.contains("[quicken]"));
+        let loaded = Config::load_from(&path).await.unwrap();
+        assert_eq!(loaded.logging.level, "warn");
+        assert_eq!(loaded.database.max_connections, 2);
+        assert!(loaded.quicken.auto_import);
+
+        let missing = temp_dir.path().join("missing.toml");
+        assert!(Config::load_from(&missing).await.is_err());
+        assert!(!missing.exists());
+    }
+
+    #[test]
+    fn test_layered_config_sources() {
+        let dir = tempdir().unwrap();
+        let system = dir.path().join("system.toml");
+        let user = dir.path().join("user.toml");
+        std::fs::write(
+            &system,
+            "[logging]\nlevel = \"warn\"\n[database]\nmax_connections = 3\n",
+        )
+        .unwrap();
+        std::fs::write(&user, "[logging]\nlevel = \"debug\"\n").unwrap();
+
+        let loaded = ConfigLoader::new()
+            .file(&system)
+            .file(&user)
+            .env_vars([
+                (
+                    "QSPEC_QUICKEN__AUTO_IMPORT".to_string(),
+                    "false".to_string(),
+                ),
+                (
+                    "QSPEC_DATABASE__MAX_CONNECTIONS".to_string(),
+                    "7".to_string(),
+                ),
+                ("QSPEC_PASSPHRASE".to_string(), "secret".to_string()),
+                ("HOME".to_string(), "/tmp".to_string()),
+            ])
+            .set("database.max_connections", "9")
+            .load()
+            .unwrap();
+
+        assert_eq!(loaded.config.logging.level, "debug");
+        assert!(!loaded.config.quicken.auto_import);
+        assert_eq!(loaded.config.database.max_connections, 9);
+        assert_eq!(loaded.files, vec![system.clone(), user.clone()]);
+
+        assert_eq!(loaded.source("logging.level"), ConfigSource::File(user));
+        assert_eq!(
+            loaded.source("quicken.auto_import"),
+            ConfigSource::Environment("QSPEC_QUICKEN__AUTO_IMPORT".to_string())
+        );
+        assert_eq!(
+            loaded.source("database.max_connections"),
+            ConfigSource::CommandLine
+        );
+        assert_eq!(loaded.source("ai.enabled"), ConfigSource::Default);
 
-        // Create a default config
-        let _config = Config::default();
+        let entries = loaded.entries().unwrap();
+        let level = entries.iter().find(|e| e.key == "logging.level").unwrap();
+        assert_eq!(level.value, "\"debug\"");
 
-        // Test that we can create a config (load will create default if none exists)
-        let loaded_config = Config::load().await;
-        assert!(loaded_config.is_ok());
+        assert!(ConfigLoader::new()
+            .set("database.nope", "1")
+            .load()
+            .is_err());
+        assert!(ConfigLoader::new()
+            .file(dir.path().join("missing.toml"))
+            .load()
+            .is_err());
     }
 
     #[test]
@@ -178,4 +1798,199 @@ mod tests {
         let deserialized: Result<Config, _> = toml::from_str(&serialized.unwrap());
         assert!(deserialized.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_config_load_from_explicit_path() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("nested").join("config.toml");
+
+        let mut config = Config::default();
+        config.logging.level = "debug".to_string();
+        config.save_to(&path).await.unwrap();
+
+        let loaded = Config::load_from(&path).await.unwrap();
+        assert_eq!(loaded.logging.level, "debug");
+        assert!(Config::load_from(dir.path().join("missing.toml"))
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_config_set_value() {
+        let mut config = Config::default();
+
+        config.set_value("quicken.auto_import", "false").unwrap();
+        config.set_value("database.max_connections", "8").unwrap();
+        config.set_value("logging.level", "debug").unwrap();
+        config
+            .set_value("ai.api_endpoint", "http://localhost:8080")
+            .unwrap();
+        config
+            .set_value("quicken.file_patterns", r#"["*.qif", "*.ofx"]"#)
+            .unwrap();
+
+        assert!(!config.quicken.auto_import);
+        assert_eq!(config.database.max_connections, 8);
+        assert_eq!(config.logging.level, "debug");
+        assert_eq!(
+            config.ai.api_endpoint.as_deref(),
+            Some("http://localhost:8080")
+        );
+        assert_eq!(config.quicken.file_patterns, vec!["*.qif", "*.ofx"]);
+
+        assert!(config
+            .set_value("database.max_connections", "many")
+            .is_err());
+        assert!(config.set_value("quicken.no_such_key", "1").is_err());
+        assert!(config.set_value("nope.level", "1").is_err());
+        assert_eq!(config.database.max_connections, 8);
+    }
+
+    #[test]
+    fn test_validation_reports_every_path() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            r#"
+            version = 1
+            [database]
+            max_connections = 0
+            [database.encryption.kdf]
+            memory_kib = 4194304
+            iterations = 2
+            parallelism = 1
+            [quicken]
+            auto_imprt = false
+            file_patterns = ["*.qif", "[bad"]
+            [ai]
+            api_endpoint = "localhost:8080"
+            [ai.budget]
+            monthly_cost = "-5"
+            [logging]
+            level = "loud"
+            [[notifications.channels]]
+            name = "phone"
+            type = "webhook"
+            url = "ftp://example.com"
+            events = ["anomaly_detected", "anomaly"]
+            [categorization]
+            engine = "llm"
+            holdout_fraction = 1.0
+            "#,
+        )
+        .unwrap();
+
+        let err = ConfigLoader::new().file(&path).load().unwrap_err();
+        let errors = err.downcast::<ValidationErrors>().unwrap().0;
+        let paths: Vec<&str> = errors.iter().map(|e| e.path.as_str()).collect();
+        let unknown = format!("{}: quicken.auto_imprt", path.display());
+        assert_eq!(
+            paths,
+            vec![
+                unknown.as_str(),
+                "database.max_connections",
+                "database.encryption.kdf",
+                "quicken.file_patterns[1]",
+                "ai.api_endpoint",
+                "ai.budget.monthly_cost",
+                "logging.level",
+                "notifications.channels[0].events[1]",
+                "notifications.channels[0].url",
+                "categorization.engine",
+                "categorization.holdout_fraction",
+            ]
+        );
+
+        std::fs::write(&path, "[database]\nmax_connections = \"five\"\n").unwrap();
+        let err = ConfigLoader::new().file(&path).load().unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("database.max_connections: invalid type"));
+
+        let mut config = Config::default();
+        config.quicken.watch_directory = dir.path().join("inbox");
+        assert!(config.validate().is_ok());
+        assert_eq!(
+            config.check_paths().unwrap_err().0[0].path,
+            "quicken.watch_directory"
+        );
+        std::fs::create_dir(dir.path().join("inbox")).unwrap();
+        assert!(config.check_paths().is_ok());
+    }
+
+    #[test]
+    fn test_migrate_older_files() {
+        let mut file: toml::Table = toml::from_str("[logging]\nlevel = \"warn\"\n").unwrap();
+        assert_eq!(migrate(&mut file).unwrap().len(), 1);
+        assert_eq!(file["version"].as_integer(), Some(CONFIG_VERSION.into()));
+        assert_eq!(file["scheduler"]["enabled"].as_bool(), Some(false));
+        assert!(migrate(&mut file).unwrap().is_empty());
+
+        // An existing [scheduler] section is the user's choice and is kept
+        let mut file: toml::Table = toml::from_str("[scheduler]\nenabled = true\n").unwrap();
+        migrate(&mut file).unwrap();
+        assert_eq!(file["scheduler"]["enabled"].as_bool(), Some(true));
+
+        let mut future: toml::Table = toml::from_str("version = 99\n").unwrap();
+        assert!(migrate(&mut future).is_err());
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "[logging]\nlevel = \"warn\"\n").unwrap();
+        let loaded = ConfigLoader::new().file(&path).load().unwrap();
+        assert_eq!(loaded.migrated, vec![path.clone()]);
+        assert!(!loaded.config.scheduler.enabled);
+        assert_eq!(loaded.config.version, CONFIG_VERSION);
+
+        // An older file layered over one that turns the scheduler on leaves it on
+        let base = dir.path().join("base.toml");
+        std::fs::write(&base, "version = 1\n[scheduler]\nenabled = true\n").unwrap();
+        let loaded = ConfigLoader::new().file(&base).file(&path).load().unwrap();
+        assert_eq!(loaded.migrated, vec![path.clone()]);
+        assert!(loaded.config.scheduler.enabled);
+    }
+
+    #[test]
+    fn test_notification_channels_parse() {
+        let mut value = toml::Value::try_from(Config::default()).unwrap();
+        let channels: toml::Value = toml::from_str(
+            r#"
+            [[channels]]
+            name = "phone"
+            type = "webhook"
+            url = "http://localhost:9000/hook"
+            events = ["anomaly_detected", "budget_exceeded"]
+            max_per_hour = 5
+
+            [[channels]]
+            name = "family"
+            type = "email"
+            smtp_host = "localhost"
+            security = "none"
+            from = "qspec@example.com"
+            to = ["us@example.com"]
+            digest_minutes = 60
+            "#,
+        )
+        .unwrap();
+        value
+            .as_table_mut()
+            .unwrap()
+            .insert("notifications".to_string(), channels);
+
+        let config: Config = value.try_into().unwrap();
+        let channels = &config.notifications.channels;
+        assert_eq!(channels.len(), 2);
+        assert!(matches!(channels[0].kind, ChannelKind::Webhook(ref w) if w.timeout_secs == 10));
+        assert_eq!(channels[0].max_per_hour, Some(5));
+        match channels[1].kind {
+            ChannelKind::Email(ref email) => {
+                assert_eq!(email.smtp_port, 587);
+                assert_eq!(email.security, SmtpSecurity::None);
+            }
+            ref other => panic!("unexpected channel {:?}", other),
+        }
+        assert_eq!(channels[1].digest_minutes, Some(60));
+    }
 }
@@ -0,0 +1,311 @@
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, KeyInit, OsRng};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use zeroize::Zeroizing;
+
+/// Magic bytes identifying a passphrase-encrypted envelope
+const MAGIC: &[u8; 6] = b"QSPEC\x01";
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+const KEY_LEN: usize = 32;
+
+/// Length of the envelope header: magic, KDF parameters, salt
+const HEADER_LEN: usize = MAGIC.len() + 12 + SALT_LEN;
+
+/// Largest Argon2id memory cost accepted, 1 GiB
+pub const MAX_KDF_MEMORY_KIB: u32 = 1024 * 1024;
+
+/// Most Argon2id passes accepted
+pub const MAX_KDF_ITERATIONS: u32 = 10;
+
+/// Most Argon2id lanes accepted
+pub const MAX_KDF_PARALLELISM: u32 = 16;
+
+/// Errors raised while encrypting or decrypting data
+#[derive(Debug, Error)]
+pub enum CryptoError {
+    #[error("wrong passphrase (or the data was tampered with)")]
+    WrongPassphrase,
+
+    #[error("data is encrypted but no passphrase was provided (set {0})")]
+    MissingPassphrase(String),
+
+    #[error("data is not in the qspec encrypted format")]
+    NotEncrypted,
+
+    #[error("malformed encrypted data: {0}")]
+    Malformed(&'static str),
+
+    #[error("key derivation failed: {0}")]
+    Kdf(String),
+}
+
+/// Argon2id key derivation parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    /// Memory cost in KiB
+    pub memory_kib: u32,
+
+    /// Number of passes over memory
+    pub iterations: u32,
+
+    /// Degree of parallelism
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        // OWASP recommended minimum for Argon2id
+        Self {
+            memory_kib: 19 * 1024,
+            iterations: 2,
+            parallelism: 1,
+        }
+    }
+}
+
+impl KdfParams {
+    /// Check the parameters are within the limits a header may ask for
+    pub fn check(&self) -> Result<(), String> {
+        if self.memory_kib > MAX_KDF_MEMORY_KIB {
+            return Err(format!(
+                "memory_kib {} exceeds the limit of {}",
+                self.memory_kib, MAX_KDF_MEMORY_KIB
+            ));
+        }
+        if !(1..=MAX_KDF_ITERATIONS).contains(&self.iterations) {
+            return Err(format!(
+                "iterations must be between 1 and {}, got {}",
+                MAX_KDF_ITERATIONS, self.iterations
+            ));
+        }
+        if !(1..=MAX_KDF_PARALLELISM).contains(&self.parallelism) {
+            return Err(format!(
+                "parallelism must be between 1 and {}, got {}",
+                MAX_KDF_PARALLELISM, self.parallelism
+            ));
+        }
+        Ok(())
+    }
+
+    fn to_bytes(self) -> [u8; 12] {
+        let mut out = [0u8; 12];
+        out[..4].copy_from_slice(&self.memory_kib.to_le_bytes());
+        out[4..8].copy_from_slice(&self.iterations.to_le_bytes());
+        out[8..].copy_from_slice(&self.parallelism.to_le_bytes());
+        out
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Self {
+        let word = |i: usize| u32::from_le_bytes(bytes[i..i + 4].try_into().unwrap());
+        Self {
+            memory_kib: word(0),
+            iterations: word(4),
+            parallelism: word(8),
+        }
+    }
+}
+
+/// Symmetric cipher keyed from a passphrase with Argon2id and using XChaCha20-Poly1305
+pub struct Cipher {
+    key: Zeroizing<[u8; KEY_LEN]>,
+    salt: [u8; SALT_LEN],
+    params: KdfParams,
+}
+
+impl Cipher {
+    /// Derive a cipher from a passphrase using a fresh random salt
+    pub fn generate(passphrase: &str, params: KdfParams) -> Result<Self, CryptoError> {
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        Self::derive(passphrase, salt, params)
+    }
+
+    /// Derive a cipher from a passphrase and an existing salt
+    pub fn derive(
+        passphrase: &str,
+        salt: [u8; SALT_LEN],
+        params: KdfParams,
+    ) -> Result<Self, CryptoError> {
+        params.check().map_err(CryptoError::Kdf)?;
+        let argon_params = Params::new(
+            params.memory_kib,
+            params.iterations,
+            params.parallelism,
+            Some(KEY_LEN),
+        )
+        .map_err(|e| CryptoError::Kdf(e.to_string()))?;
+
+        let mut key = Zeroizing::new([0u8; KEY_LEN]);
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, argon_params)
+            .hash_password_into(passphrase.as_bytes(), &salt, key.as_mut())
+            .map_err(|e| CryptoError::Kdf(e.to_string()))?;
+
+        Ok(Self { key, salt, params })
+    }
+
+    /// Re-derive a cipher from a header previously produced by [`Cipher::header`]
+    pub fn from_header(passphrase: &str, header: &[u8]) -> Result<Self, CryptoError> {
+        if header.len() < HEADER_LEN || &header[..MAGIC.len()] != MAGIC {
+            return Err(CryptoError::NotEncrypted);
+        }
+
+        // A tampered header must not make opening the store allocate or spin without bound
+        let params = KdfParams::from_bytes(&header[MAGIC.len()..MAGIC.len() + 12]);
+        if params.check().is_err() {
+            return Err(CryptoError::Malformed(
+                "key derivation parameters out of range",
+            ));
+        }
+        let mut salt = [0u8; SALT_LEN];
+        salt.copy_from_slice(&header[MAGIC.len() + 12..HEADER_LEN]);
+
+        Self::derive(passphrase, salt, params)
+    }
+
+    /// Header describing how the key was derived (magic, KDF parameters and salt)
+    pub fn header(&self) -> Vec<u8> {
+        let mut header = Vec::with_capacity(HEADER_LEN);
+        header.extend_from_slice(MAGIC);
+        header.extend_from_slice(&self.params.to_bytes());
+        header.extend_from_slice(&self.salt);
+        header
+    }
+
+    /// Encrypt a payload, returning `nonce || ciphertext`
+    pub fn seal(&self, plaintext: &[u8]) -> Vec<u8> {
+        let mut nonce = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut nonce);
+
+        let ciphertext = self
+            .aead()
+            .encrypt(&XNonce::from(nonce), plaintext)
+            .expect("XChaCha20-Poly1305 encryption cannot fail for in-memory buffers");
+
+        let mut out = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+        out.extend_from_slice(&nonce);
+        out.extend_from_slice(&ciphertext);
+        out
+    }
+
+    /// Decrypt a payload produced by [`Cipher::seal`]
+    pub fn open(&self, sealed: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        if sealed.len() < NONCE_LEN {
+            return Err(CryptoError::Malformed("payload shorter than nonce"));
+        }
+
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let nonce: [u8; NONCE_LEN] = nonce.try_into().expect("split at nonce length");
+        self.aead()
+            .decrypt(&XNonce::from(nonce), ciphertext)
+            .map_err(|_| CryptoError::WrongPassphrase)
+    }
+
+    fn aead(&self) -> XChaCha20Poly1305 {
+        XChaCha20Poly1305::new(&Key::from(*self.key))
+    }
+}
+
+/// Check whether a byte buffer is a passphrase-encrypted envelope
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.len() >= HEADER_LEN && &data[..MAGIC.len()] == MAGIC
+}
+
+/// Encrypt a self-contained buffer (header followed by sealed payload)
+pub fn encrypt_with_passphrase(
+    passphrase: &str,
+    params: KdfParams,
+    plaintext: &[u8],
+) -> Result<Vec<u8>, CryptoError> {
+    let cipher = Cipher::generate(passphrase, params)?;
+    let mut out = cipher.header();
+    out.extend_from_slice(&cipher.seal(plaintext));
+    Ok(out)
+}
+
+/// Decrypt a buffer produced by [`encrypt_with_passphrase`]
+pub fn decrypt_with_passphrase(passphrase: &str, data: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    if !is_encrypted(data) {
+        return Err(CryptoError::NotEncrypted);
+    }
+
+    let cipher = Cipher::from_header(passphrase, &data[..HEADER_LEN])?;
+    cipher.open(&data[HEADER_LEN..])
+}
+
+#[cfg(test)]
+pub(crate) const TEST_KDF_PARAMS: KdfParams = KdfParams {
+    memory_kib: 64,
+    iterations: 1,
+    parallelism: 1,
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_and_open() {
+        let cipher = Cipher::generate("correct horse", TEST_KDF_PARAMS).unwrap();
+        let sealed = cipher.seal(b"account 12345678");
+
+        assert_ne!(&sealed[NONCE_LEN..], b"account 12345678");
+        assert_eq!(cipher.open(&sealed).unwrap(), b"account 12345678");
+    }
+
+    #[test]
+    fn test_wrong_passphrase() {
+        let encrypted =
+            encrypt_with_passphrase("correct horse", TEST_KDF_PARAMS, b"secret").unwrap();
+        assert!(is_encrypted(&encrypted));
+
+        let result = decrypt_with_passphrase("battery staple", &encrypted);
+        assert!(matches!(result, Err(CryptoError::WrongPassphrase)));
+
+        let decrypted = decrypt_with_passphrase("correct horse", &encrypted).unwrap();
+        assert_eq!(decrypted, b"secret");
+    }
+
+    #[test]
+    fn test_header_round_trip() {
+        let cipher = Cipher::generate("pass", TEST_KDF_PARAMS).unwrap();
+        let sealed = cipher.seal(b"data");
+
+        let restored = Cipher::from_header("pass", &cipher.header()).unwrap();
+        assert_eq!(restored.open(&sealed).unwrap(), b"data");
+        assert!(!is_encrypted(b"!Account\nNChecking\n^\n"));
+    }
+
+    #[test]
+    fn test_oversized_kdf_parameters_are_rejected() {
+        let header = Cipher::generate("pass", TEST_KDF_PARAMS).unwrap().header();
+        for (offset, value) in [
+            (0, MAX_KDF_MEMORY_KIB + 1),
+            (4, MAX_KDF_ITERATIONS + 1),
+            (4, 0),
+            (8, MAX_KDF_PARALLELISM + 1),
+        ] {
+            let mut tampered = header.clone();
+            let at = MAGIC.len() + offset;
+            tampered[at..at + 4].copy_from_slice(&value.to_le_bytes());
+            assert!(matches!(
+                Cipher::from_header("pass", &tampered),
+                Err(CryptoError::Malformed(_))
+            ));
+        }
+
+        let greedy = KdfParams {
+            memory_kib: 4 * MAX_KDF_MEMORY_KIB,
+            ..KdfParams::default()
+        };
+        assert!(matches!(
+            Cipher::generate("pass", greedy),
+            Err(CryptoError::Kdf(_))
+        ));
+        assert!(KdfParams::default().check().is_ok());
+    }
+}
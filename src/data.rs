@@ -44,10 +44,21 @@ pub struct Transaction {
     pub cleared: bool,
     pub reconciled: bool,
     pub transaction_type: TransactionType,
+    /// Line items, e.g. from a receipt; when present they add up to `amount`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub splits: Vec<Split>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+/// One line of a split transaction, in the same direction as its transaction
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Split {
+    pub category: Option<String>,
+    pub memo: Option<String>,
+    pub amount: Decimal,
+}
+
 /// Types of transactions
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum TransactionType {
@@ -120,11 +131,23 @@ impl Transaction {
             cleared: false,
             reconciled: false,
             transaction_type,
+            splits: Vec::new(),
             created_at: now,
             updated_at: now,
         }
     }
 
+    /// Amount per category: one entry per split, or the whole amount when unsplit
+    pub fn category_amounts(&self) -> Vec<(Option<&str>, Decimal)> {
+        if self.splits.is_empty() {
+            return vec![(self.category.as_deref(), self.amount)];
+        }
+        self.splits
+            .iter()
+            .map(|split| (split.category.as_deref(), split.amount))
+            .collect()
+    }
+
     /// Mark transaction as cleared
     pub fn mark_cleared(&mut self) {
         self.cleared = true;
@@ -157,8 +180,12 @@ impl FinancialData {
 
     /// Add a transaction
     pub fn add_transaction(&mut self, transaction: Transaction) {
-        // Add category if not exists
-        if let Some(ref category) = transaction.category {
+        // Add categories, including those of split lines, if not exists
+        let split_categories = transaction
+            .splits
+            .iter()
+            .filter_map(|s| s.category.as_ref());
+        for category in transaction.category.iter().chain(split_categories) {
             if !self.categories.contains(category) {
                 self.categories.push(category.clone());
             }
@@ -239,6 +266,43 @@ mod tests {
         assert_eq!(transaction.transaction_type, TransactionType::Debit);
         assert!(!transaction.cleared);
         assert!(!transaction.reconciled);
+        assert_eq!(transaction.category_amounts(), vec![(None, dec!(50.00))]);
+    }
+
+    #[test]
+    fn test_split_transaction() {
+        let mut transaction = Transaction::new(
+            Uuid::new_v4(),
+            Utc::now(),
+            dec!(30.00),
+            "Market".to_string(),
+            TransactionType::Debit,
+        );
+        let json = serde_json::to_value(&transaction).unwrap();
+        assert!(json.get("splits").is_none());
+
+        transaction.splits = vec![
+            Split {
+                category: Some("Groceries".to_string()),
+                memo: Some("Produce".to_string()),
+                amount: dec!(18.00),
+            },
+            Split {
+                category: Some("Household".to_string()),
+                memo: None,
+                amount: dec!(12.00),
+            },
+        ];
+        assert_eq!(
+            transaction.category_amounts(),
+            vec![
+                (Some("Groceries"), dec!(18.00)),
+                (Some("Household"), dec!(12.00))
+            ]
+        );
+        let back: Transaction =
+            serde_json::from_str(&serde_json::to_string(&transaction).unwrap()).unwrap();
+        assert_eq!(back, transaction);
     }
 
     #[test]
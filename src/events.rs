@@ -0,0 +1,148 @@
+use crate::data::Transaction;
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tokio::sync::broadcast;
+
+/// Number of events a slow subscriber may fall behind before it starts missing them
+pub const DEFAULT_EVENT_CAPACITY: usize = 1024;
+
+/// Something that happened inside the agent
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum AgentEvent {
+    /// A transaction was added to the books by an import
+    TransactionImported {
+        transaction: Transaction,
+        source: PathBuf,
+    },
+
+    /// A file could not be imported
+    ImportFailed { path: PathBuf, error: String },
+
+    /// A transaction was flagged as unusual for its category
+    AnomalyDetected { transaction: Transaction },
+
+    /// Spending in a category went over its monthly budget
+    BudgetExceeded {
+        category: String,
+        year: i32,
+        month: u32,
+        budget: Decimal,
+        spent: Decimal,
+    },
+
+    /// A scheduled report was written
+    ReportGenerated {
+        year: i32,
+        month: u32,
+        path: PathBuf,
+        generated_at: DateTime<Utc>,
+    },
+
+    /// A configured bill falls due within its reminder window
+    BillDue {
+        name: String,
+        amount: Option<Decimal>,
+        currency: String,
+        due: NaiveDate,
+        days_until: i64,
+    },
+}
+
+impl AgentEvent {
+    /// Every event name, as used for routing in the configuration
+    pub const NAMES: [&'static str; 6] = [
+        "transaction_imported",
+        "import_failed",
+        "anomaly_detected",
+        "budget_exceeded",
+        "report_generated",
+        "bill_due",
+    ];
+
+    /// Short event name, matching the serialized `event` tag
+    pub fn name(&self) -> &'static str {
+        match self {
+            AgentEvent::TransactionImported { .. } => "transaction_imported",
+            AgentEvent::ImportFailed { .. } => "import_failed",
+            AgentEvent::AnomalyDetected { .. } => "anomaly_detected",
+            AgentEvent::BudgetExceeded { .. } => "budget_exceeded",
+            AgentEvent::ReportGenerated { .. } => "report_generated",
+            AgentEvent::BillDue { .. } => "bill_due",
+        }
+    }
+}
+
+/// Broadcast channel carrying [`AgentEvent`]s to any number of subscribers
+///
+/// Publishing never blocks; subscribers that lag by more than the channel
+/// capacity receive `RecvError::Lagged` and skip ahead.
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<AgentEvent>,
+}
+
+impl EventBus {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self { sender }
+    }
+
+    /// Receive every event published from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<AgentEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Send an event to current subscribers, returning how many received it
+    pub fn publish(&self, event: AgentEvent) -> usize {
+        self.sender.send(event).unwrap_or(0)
+    }
+
+    pub fn subscriber_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new(DEFAULT_EVENT_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[tokio::test]
+    async fn test_publish_reaches_every_subscriber() {
+        let bus = EventBus::default();
+        assert_eq!(
+            bus.publish(AgentEvent::ImportFailed {
+                path: PathBuf::from("lost.qif"),
+                error: "nobody listening".to_string(),
+            }),
+            0
+        );
+
+        let mut first = bus.subscribe();
+        let mut second = bus.subscribe();
+        let event = AgentEvent::BudgetExceeded {
+            category: "Dining".to_string(),
+            year: 2026,
+            month: 9,
+            budget: dec!(200),
+            spent: dec!(245.50),
+        };
+        assert_eq!(bus.publish(event.clone()), 2);
+
+        assert_eq!(first.recv().await.unwrap(), event);
+        assert_eq!(second.recv().await.unwrap(), event);
+
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["event"], event.name());
+        assert_eq!(json["spent"], "245.50");
+    }
+}
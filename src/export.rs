@@ -0,0 +1,408 @@
+use crate::crypto::{self, KdfParams};
+use crate::data::{Account, AccountType, FinancialData, Transaction, TransactionType};
+use crate::quicken::QifExporter;
+use anyhow::{Context, Result};
+use chrono::Utc;
+use quick_xml::escape::escape;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::Path;
+
+/// File formats the books can be exported to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Qif,
+    Ofx,
+    Csv,
+}
+
+/// OFX (Open Financial Exchange) exporter producing OFX 2.x XML statements
+pub struct OfxExporter;
+
+/// CSV exporter with one row per transaction
+pub struct CsvExporter;
+
+impl ExportFormat {
+    /// Conventional file extension for the format
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Qif => "qif",
+            ExportFormat::Ofx => "ofx",
+            ExportFormat::Csv => "csv",
+        }
+    }
+
+    /// Render the books in this format
+    pub fn export_to_string(self, data: &FinancialData) -> Result<String> {
+        match self {
+            ExportFormat::Qif => QifExporter::export_to_string(data),
+            ExportFormat::Ofx => OfxExporter::export_to_string(data),
+            ExportFormat::Csv => CsvExporter::export_to_string(data),
+        }
+    }
+
+    /// Render the books, sealing the output when a passphrase is given
+    pub fn export_to_bytes(
+        self,
+        data: &FinancialData,
+        passphrase: Option<&str>,
+        params: KdfParams,
+    ) -> Result<Vec<u8>> {
+        let content = self.export_to_string(data)?;
+        match passphrase {
+            Some(passphrase) => Ok(crypto::encrypt_with_passphrase(
+                passphrase,
+                params,
+                content.as_bytes(),
+            )?),
+            None => Ok(content.into_bytes()),
+        }
+    }
+
+    /// Write the books to a file, sealing the output when a passphrase is given
+    pub async fn export_file<P: AsRef<Path>>(
+        self,
+        data: &FinancialData,
+        path: P,
+        passphrase: Option<&str>,
+        params: KdfParams,
+    ) -> Result<()> {
+        let bytes = self.export_to_bytes(data, passphrase, params)?;
+        tokio::fs::write(path.as_ref(), bytes)
+            .await
+            .with_context(|| format!("Failed to write {}", path.as_ref().display()))?;
+        Ok(())
+    }
+}
+
+impl OfxExporter {
+    /// Export financial data as an OFX document with one statement per account
+    pub fn export_to_string(data: &FinancialData) -> Result<String> {
+        let now = Utc::now().format("%Y%m%d%H%M%S");
+        let mut bank = String::new();
+        let mut card = String::new();
+
+        for account in &data.accounts {
+            let transactions = data.get_account_transactions(&account.id);
+            if matches!(account.account_type, AccountType::CreditCard) {
+                Self::write_card_statement(&mut card, account, &transactions)?;
+            } else {
+                Self::write_bank_statement(&mut bank, account, &transactions)?;
+            }
+        }
+
+        let mut output = String::new();
+        writeln!(
+            output,
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\"?>"
+        )?;
+        writeln!(
+            output,
+            "<?OFX OFXHEADER=\"200\" VERSION=\"220\" SECURITY=\"NONE\" OLDFILEUID=\"NONE\" NEWFILEUID=\"NONE\"?>"
+        )?;
+        writeln!(output, "<OFX>")?;
+        writeln!(output, "<SIGNONMSGSRSV1><SONRS>")?;
+        writeln!(
+            output,
+            "<STATUS><CODE>0</CODE><SEVERITY>INFO</SEVERITY></STATUS>"
+        )?;
+        writeln!(
+            output,
+            "<DTSERVER>{}</DTSERVER><LANGUAGE>ENG</LANGUAGE>",
+            now
+        )?;
+        writeln!(output, "</SONRS></SIGNONMSGSRSV1>")?;
+        if !bank.is_empty() {
+            writeln!(output, "<BANKMSGSRSV1>")?;
+            output.push_str(&bank);
+            writeln!(output, "</BANKMSGSRSV1>")?;
+        }
+        if !card.is_empty() {
+            writeln!(output, "<CREDITCARDMSGSRSV1>")?;
+            output.push_str(&card);
+            writeln!(output, "</CREDITCARDMSGSRSV1>")?;
+        }
+        writeln!(output, "</OFX>")?;
+
+        Ok(output)
+    }
+
+    fn write_bank_statement(
+        output: &mut String,
+        account: &Account,
+        transactions: &[&Transaction],
+    ) -> Result<()> {
+        let account_type = match account.account_type {
+            AccountType::Savings => "SAVINGS",
+            _ => "CHECKING",
+        };
+
+        writeln!(output, "<STMTTRNRS><TRNUID>{}</TRNUID>", account.id)?;
+        writeln!(
+            output,
+            "<STATUS><CODE>0</CODE><SEVERITY>INFO</SEVERITY></STATUS>"
+        )?;
+        writeln!(
+            output,
+            "<STMTRS><CURDEF>{}</CURDEF>",
+            escape(&account.currency)
+        )?;
+        writeln!(
+            output,
+            "<BANKACCTFROM><BANKID>{}</BANKID><ACCTID>{}</ACCTID><ACCTTYPE>{}</ACCTTYPE></BANKACCTFROM>",
+            escape(account.institution.as_deref().unwrap_or("")),
+            escape(&Self::account_id(account)),
+            account_type
+        )?;
+        Self::write_transactions(output, transactions)?;
+        Self::write_balance(output, account)?;
+        writeln!(output, "</STMTRS></STMTTRNRS>")?;
+        Ok(())
+    }
+
+    fn write_card_statement(
+        output: &mut String,
+        account: &Account,
+        transactions: &[&Transaction],
+    ) -> Result<()> {
+        writeln!(output, "<CCSTMTTRNRS><TRNUID>{}</TRNUID>", account.id)?;
+        writeln!(
+            output,
+            "<STATUS><CODE>0</CODE><SEVERITY>INFO</SEVERITY></STATUS>"
+        )?;
+        writeln!(
+            output,
+            "<CCSTMTRS><CURDEF>{}</CURDEF>",
+            escape(&account.currency)
+        )?;
+        writeln!(
+            output,
+            "<CCACCTFROM><ACCTID>{}</ACCTID></CCACCTFROM>",
+            escape(&Self::account_id(account))
+        )?;
+        Self::write_transactions(output, transactions)?;
+        Self::write_balance(output, account)?;
+        writeln!(output, "</CCSTMTRS></CCSTMTTRNRS>")?;
+        Ok(())
+    }
+
+    fn write_transactions(output: &mut String, transactions: &[&Transaction]) -> Result<()> {
+        let start = transactions.iter().map(|t| t.date).min();
+        let end = transactions.iter().map(|t| t.date).max();
+
+        write!(output, "<BANKTRANLIST>")?;
+        if let (Some(start), Some(end)) = (start, end) {
+            write!(
+                output,
+                "<DTSTART>{}</DTSTART><DTEND>{}</DTEND>",
+                start.format("%Y%m%d"),
+                end.format("%Y%m%d")
+            )?;
+        }
+        writeln!(output)?;
+
+        for transaction in transactions {
+            writeln!(output, "<STMTTRN>")?;
+            writeln!(
+                output,
+                "<TRNTYPE>{}</TRNTYPE>",
+                Self::transaction_type_to_ofx(&transaction.transaction_type)
+            )?;
+            writeln!(
+                output,
+                "<DTPOSTED>{}</DTPOSTED>",
+                transaction.date.format("%Y%m%d")
+            )?;
+            writeln!(output, "<TRNAMT>{}</TRNAMT>", signed_amount(transaction))?;
+            writeln!(output, "<FITID>{}</FITID>", transaction.id)?;
+
+            let name = transaction
+                .payee
+                .as_deref()
+                .unwrap_or(&transaction.description);
+            // OFX limits NAME to 32 characters
+            let name: String = name.chars().take(32).collect();
+            writeln!(output, "<NAME>{}</NAME>", escape(&name))?;
+
+            if let Some(ref memo) = transaction.memo {
+                writeln!(output, "<MEMO>{}</MEMO>", escape(memo))?;
+            }
+            writeln!(output, "</STMTTRN>")?;
+        }
+
+        writeln!(output, "</BANKTRANLIST>")?;
+        Ok(())
+    }
+
+    fn write_balance(output: &mut String, account: &Account) -> Result<()> {
+        writeln!(
+            output,
+            "<LEDGERBAL><BALAMT>{}</BALAMT><DTASOF>{}</DTASOF></LEDGERBAL>",
+            account.balance,
+            account.updated_at.format("%Y%m%d")
+        )?;
+        Ok(())
+    }
+
+    fn account_id(account: &Account) -> String {
+        account
+            .account_number
+            .clone()
+            .unwrap_or_else(|| account.id.to_string())
+    }
+
+    fn transaction_type_to_ofx(transaction_type: &TransactionType) -> &str {
+        match transaction_type {
+            TransactionType::Debit => "DEBIT",
+            TransactionType::Credit => "CREDIT",
+            TransactionType::Transfer => "XFER",
+            TransactionType::Fee => "FEE",
+            TransactionType::Interest => "INT",
+            TransactionType::Dividend => "DIV",
+            TransactionType::Other(_) => "OTHER",
+        }
+    }
+}
+
+impl CsvExporter {
+    /// Column headers of the exported file
+    pub const HEADERS: [&'static str; 11] = [
+        "date",
+        "account",
+        "payee",
+        "description",
+        "category",
+        "memo",
+        "amount",
+        "type",
+        "cleared",
+        "reconciled",
+        "id",
+    ];
+
+    /// Export transactions as CSV, oldest first, with signed amounts
+    pub fn export_to_string(data: &FinancialData) -> Result<String> {
+        let accounts: HashMap<_, _> = data
+            .accounts
+            .iter()
+            .map(|a| (a.id, a.name.as_str()))
+            .collect();
+
+        let mut transactions: Vec<&Transaction> = data.transactions.iter().collect();
+        transactions.sort_by_key(|t| t.date);
+
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        writer.write_record(Self::HEADERS)?;
+
+        for transaction in transactions {
+            writer.write_record([
+                transaction.date.format("%Y-%m-%d").to_string(),
+                accounts
+                    .get(&transaction.account_id)
+                    .copied()
+                    .unwrap_or_default()
+                    .to_string(),
+                transaction.payee.clone().unwrap_or_default(),
+                transaction.description.clone(),
+                transaction.category.clone().unwrap_or_default(),
+                transaction.memo.clone().unwrap_or_default(),
+                signed_amount(transaction).to_string(),
+                transaction_type_name(&transaction.transaction_type),
+                transaction.cleared.to_string(),
+                transaction.reconciled.to_string(),
+                transaction.id.to_string(),
+            ])?;
+        }
+
+        let bytes = writer.into_inner().context("Failed to flush CSV output")?;
+        String::from_utf8(bytes).context("CSV output is not valid UTF-8")
+    }
+}
+
+/// Amount with the same sign convention as QIF exports (debits negative)
+fn signed_amount(transaction: &Transaction) -> Decimal {
+    match transaction.transaction_type {
+        TransactionType::Debit => -transaction.amount,
+        _ => transaction.amount,
+    }
+}
+
+fn transaction_type_name(transaction_type: &TransactionType) -> String {
+    match transaction_type {
+        TransactionType::Other(name) => name.clone(),
+        other => format!("{:?}", other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use rust_decimal_macros::dec;
+
+    fn books() -> FinancialData {
+        let mut data = FinancialData::new();
+        let mut account = Account::new(
+            "Checking".to_string(),
+            AccountType::Checking,
+            dec!(950.00),
+            "USD".to_string(),
+        );
+        account.institution = Some("First Bank".to_string());
+        let account_id = account.id;
+        data.add_account(account);
+
+        let mut transaction = Transaction::new(
+            account_id,
+            Utc.with_ymd_and_hms(2026, 9, 3, 0, 0, 0).unwrap(),
+            dec!(50.00),
+            "Groceries".to_string(),
+            TransactionType::Debit,
+        );
+        transaction.payee = Some("Smith & Sons, \"Fresh\"".to_string());
+        data.add_transaction(transaction);
+        data
+    }
+
+    #[test]
+    fn test_ofx_export() {
+        let ofx = OfxExporter::export_to_string(&books()).unwrap();
+
+        assert!(ofx.contains("<ACCTTYPE>CHECKING</ACCTTYPE>"));
+        assert!(ofx.contains("<TRNTYPE>DEBIT</TRNTYPE>"));
+        assert!(ofx.contains("<DTPOSTED>20260903</DTPOSTED>"));
+        assert!(ofx.contains("<TRNAMT>-50.00</TRNAMT>"));
+        assert!(ofx.contains("<NAME>Smith &amp; Sons, &quot;Fresh&quot;</NAME>"));
+        assert!(!ofx.contains("<CREDITCARDMSGSRSV1>"));
+    }
+
+    #[test]
+    fn test_csv_export_quotes_fields() {
+        let csv = CsvExporter::export_to_string(&books()).unwrap();
+        let mut lines = csv.lines();
+
+        assert_eq!(lines.next().unwrap(), CsvExporter::HEADERS.join(","));
+        let row = lines.next().unwrap();
+        assert!(row.starts_with("2026-09-03,Checking,\"Smith & Sons, \"\"Fresh\"\"\",Groceries,"));
+        assert!(row.contains(",-50.00,Debit,false,false,"));
+        assert!(lines.next().is_none());
+    }
+
+    #[test]
+    fn test_encrypted_export() {
+        let data = books();
+        let bytes = ExportFormat::Csv
+            .export_to_bytes(&data, Some("hunter2"), crypto::TEST_KDF_PARAMS)
+            .unwrap();
+
+        assert!(crypto::is_encrypted(&bytes));
+        let plain = crypto::decrypt_with_passphrase("hunter2", &bytes).unwrap();
+        assert_eq!(
+            String::from_utf8(plain).unwrap(),
+            CsvExporter::export_to_string(&data).unwrap()
+        );
+    }
+}
@@ -9,14 +9,39 @@
 //! For commercial licensing options, contact daveboyd777@gmail.com
 
 pub mod agent;
+#[cfg(feature = "ai-analysis")]
+pub mod ai;
 pub mod analysis;
+pub mod backup;
+pub mod candidates;
+pub mod categorize;
+pub mod classifier;
+pub mod cli;
 pub mod config;
+pub mod crypto;
 pub mod data;
+pub mod events;
+pub mod export;
+pub mod logging;
+pub mod mcp;
+pub mod merge;
+pub mod notifications;
+pub mod output;
+pub mod plugin;
+pub mod query;
 pub mod quicken;
+pub mod scheduler;
+pub mod search;
+pub mod snapshot;
+pub mod storage;
+pub mod tools;
+pub mod tui;
 pub mod utils;
+pub mod watcher;
 
 pub use agent::FinancialAgent;
 pub use config::Config;
+pub use storage::Database;
 
 // Re-export commonly used types
 pub use data::{Account, FinancialData, Transaction};
@@ -24,11 +49,12 @@ pub use quicken::{QifExporter, QifImporter};
 
 #[cfg(test)]
 mod tests {
+    use super::*;
 
     #[tokio::test]
     async fn test_financial_agent_creation() {
-        // Test that we can create a financial agent
-        // This is a basic smoke test
-        assert!(true);
+        // Basic smoke test of the re-exported types
+        let data = FinancialData::new();
+        assert!(data.accounts.is_empty());
     }
 }
@@ -0,0 +1,323 @@
+use crate::config::{ChannelKind, Config, LogFormat, LogRotation, LoggingConfig};
+use anyhow::{Context, Result};
+use regex::Regex;
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+use tracing::Subscriber;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::fmt::{self, MakeWriter};
+use tracing_subscriber::layer::{Layered, SubscriberExt};
+use tracing_subscriber::{EnvFilter, Layer, Registry};
+
+/// Replacement text for secrets found in log lines
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Shorter values are not treated as secrets, so a one-character key can't blank every line
+const MIN_SECRET_LEN: usize = 4;
+
+type BoxedLayer = Box<dyn Layer<Layered<EnvFilter, Registry>> + Send + Sync>;
+
+/// Keeps the background log file writer alive; dropping it flushes pending lines
+#[must_use = "log lines are lost if the guard is dropped early"]
+pub struct LogGuard {
+    _file: Option<WorkerGuard>,
+}
+
+/// Masks secrets and account numbers in formatted log lines
+#[derive(Debug, Clone, Default)]
+pub struct Redactor {
+    secrets: Vec<String>,
+}
+
+/// Install the global subscriber described by `config.logging`
+///
+/// `RUST_LOG`, when set, replaces the configured levels.
+pub fn init(config: &Config) -> Result<LogGuard> {
+    let (subscriber, guard) = subscriber(config)?;
+    tracing::subscriber::set_global_default(subscriber).context("A logger is already installed")?;
+    Ok(guard)
+}
+
+/// Build the subscriber without installing it
+pub fn subscriber(config: &Config) -> Result<(impl Subscriber + Send + Sync, LogGuard)> {
+    let logging = &config.logging;
+    let redactor = Arc::new(Redactor::new(secrets(config)));
+
+    let mut layers: Vec<BoxedLayer> = Vec::new();
+    if logging.console {
+        let console = Redacting::new(io::stderr as fn() -> io::Stderr, redactor.clone());
+        layers.push(layer(logging.format, console, io::stderr().is_terminal()));
+    }
+
+    let mut file_guard = None;
+    if logging.file_logging {
+        let path = logging
+            .log_file
+            .as_deref()
+            .context("logging.log_file is required when file_logging is true")?;
+        let (writer, guard) = tracing_appender::non_blocking(rolling_appender(path, logging)?);
+        file_guard = Some(guard);
+        layers.push(layer(
+            logging.format,
+            Redacting::new(writer, redactor),
+            false,
+        ));
+    }
+
+    let subscriber = tracing_subscriber::registry()
+        .with(filter(logging)?)
+        .with(layers);
+    Ok((subscriber, LogGuard { _file: file_guard }))
+}
+
+/// Level filter from `RUST_LOG`, or from the configured level and module overrides
+pub fn filter(logging: &LoggingConfig) -> Result<EnvFilter> {
+    match std::env::var(EnvFilter::DEFAULT_ENV) {
+        Ok(directives) if !directives.trim().is_empty() => EnvFilter::try_new(&directives)
+            .with_context(|| format!("Invalid RUST_LOG '{}'", directives)),
+        _ => EnvFilter::try_new(directives(logging)).context("Invalid logging levels"),
+    }
+}
+
+/// `EnvFilter` directives for the configured levels, e.g. `info,qspec_fin_agent::watcher=debug`
+pub fn directives(logging: &LoggingConfig) -> String {
+    std::iter::once(logging.level.to_lowercase())
+        .chain(
+            logging
+                .modules
+                .iter()
+                .map(|(module, level)| format!("{}={}", module, level.to_lowercase())),
+        )
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Values from the configuration that must never reach a log
+///
+/// Covers the AI API key, webhook header values, and the database and SMTP
+/// passwords that the configuration points at.
+pub fn secrets(config: &Config) -> Vec<String> {
+    let mut secrets: Vec<String> = config.ai.resolve_api_key().into_iter().collect();
+    if let Ok(Some(passphrase)) = config.database.encryption.resolve_passphrase() {
+        secrets.push(passphrase);
+    }
+    for channel in &config.notifications.channels {
+        match &channel.kind {
+            ChannelKind::Email(email) => secrets.extend(
+                email
+                    .password_env
+                    .iter()
+                    .filter_map(|name| std::env::var(name).ok()),
+            ),
+            ChannelKind::Webhook(webhook) => secrets.extend(webhook.headers.values().cloned()),
+            ChannelKind::Command(_) | ChannelKind::File(_) => {}
+        }
+    }
+    secrets.retain(|s| s.len() >= MIN_SECRET_LEN);
+    secrets
+}
+
+impl Redactor {
+    pub fn new(mut secrets: Vec<String>) -> Self {
+        // Longest first, so a secret containing another is masked whole
+        secrets.sort_by_key(|s| std::cmp::Reverse(s.len()));
+        secrets.dedup();
+        Self { secrets }
+    }
+
+    /// Mask known secrets, bearer tokens and anything shaped like an account or card number
+    pub fn redact(&self, line: &str) -> String {
+        let mut line = line.to_string();
+        for secret in &self.secrets {
+            if line.contains(secret.as_str()) {
+                line = line.replace(secret.as_str(), REDACTED);
+            }
+        }
+
+        static BEARER: OnceLock<Regex> = OnceLock::new();
+        static NUMBER: OnceLock<Regex> = OnceLock::new();
+        let bearer = BEARER.get_or_init(|| Regex::new(r"(?i)\b(bearer\s+)[\w.~+/-]+=*").unwrap());
+        let number =
+            NUMBER.get_or_init(|| Regex::new(r"\b(?:\d{4}[ -]){2,3}\d{4}\b|\b\d{8,19}\b").unwrap());
+
+        let line = bearer.replace_all(&line, format!("${{1}}{}", REDACTED));
+        number
+            .replace_all(&line, |caps: &regex::Captures| {
+                let digits: String = caps[0].chars().filter(char::is_ascii_digit).collect();
+                format!("****{}", &digits[digits.len() - 4..])
+            })
+            .into_owned()
+    }
+}
+
+fn layer<M>(format: LogFormat, writer: M, ansi: bool) -> BoxedLayer
+where
+    M: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    match format {
+        LogFormat::Text => fmt::layer().with_writer(writer).with_ansi(ansi).boxed(),
+        LogFormat::Json => fmt::layer().json().with_writer(writer).boxed(),
+    }
+}
+
+fn rolling_appender(path: &Path, logging: &LoggingConfig) -> Result<RollingFileAppender> {
+    let dir = path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create log directory {}", dir.display()))?;
+
+    let stem = path
+        .file_stem()
+        .with_context(|| format!("Log file {} has no file name", path.display()))?;
+    let mut builder = RollingFileAppender::builder()
+        .rotation(match logging.rotation {
+            LogRotation::Hourly => Rotation::HOURLY,
+            LogRotation::Daily => Rotation::DAILY,
+            LogRotation::Never => Rotation::NEVER,
+        })
+        .filename_prefix(stem.to_string_lossy())
+        .max_log_files(logging.keep_files);
+    if let Some(extension) = path.extension() {
+        builder = builder.filename_suffix(extension.to_string_lossy());
+    }
+    builder
+        .build(dir)
+        .with_context(|| format!("Failed to open log file {}", path.display()))
+}
+
+/// Wraps a writer so each formatted event is redacted before it is written
+struct Redacting<M> {
+    inner: M,
+    redactor: Arc<Redactor>,
+}
+
+impl<M> Redacting<M> {
+    fn new(inner: M, redactor: Arc<Redactor>) -> Self {
+        Self { inner, redactor }
+    }
+}
+
+impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for Redacting<M> {
+    type Writer = RedactedEvent<'a, M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactedEvent {
+            inner: self.inner.make_writer(),
+            redactor: &self.redactor,
+            buffer: Vec::new(),
+        }
+    }
+}
+
+/// Buffers one event, so secrets split across `write` calls are still caught
+struct RedactedEvent<'a, W: Write> {
+    inner: W,
+    redactor: &'a Redactor,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> Write for RedactedEvent<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.buffer.is_empty() {
+            let line = self.redactor.redact(&String::from_utf8_lossy(&self.buffer));
+            self.buffer.clear();
+            self.inner.write_all(line.as_bytes())?;
+        }
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Drop for RedactedEvent<'_, W> {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use tracing::{info, warn};
+
+    #[test]
+    fn test_redactor_masks_secrets_and_numbers() {
+        let redactor = Redactor::new(vec!["sk-live-abc123".to_string()]);
+        assert_eq!(
+            redactor.redact("calling api with key sk-live-abc123"),
+            format!("calling api with key {}", REDACTED)
+        );
+        assert_eq!(
+            redactor.redact("Authorization: Bearer eyJhbGciOi.payload"),
+            format!("Authorization: Bearer {}", REDACTED)
+        );
+        assert_eq!(
+            redactor.redact("account 123456789012 card 4111 1111 1111 1234"),
+            "account ****9012 card ****1234"
+        );
+        // Dates inside backup file names and short numbers are left alone
+        assert_eq!(
+            redactor.redact("wrote backup_20261018_101500.db with 42 rows"),
+            "wrote backup_20261018_101500.db with 42 rows"
+        );
+    }
+
+    #[test]
+    fn test_directives_include_module_levels() {
+        let mut logging = Config::default().logging;
+        logging.level = "WARN".to_string();
+        logging.modules = BTreeMap::from([
+            ("qspec_fin_agent::watcher".to_string(), "debug".to_string()),
+            ("sqlx".to_string(), "error".to_string()),
+        ]);
+        assert_eq!(
+            directives(&logging),
+            "warn,qspec_fin_agent::watcher=debug,sqlx=error"
+        );
+    }
+
+    #[test]
+    fn test_json_file_logging_is_redacted() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.ai.api_key = Some("sk-test-secret".to_string());
+        config.logging.console = false;
+        config.logging.format = LogFormat::Json;
+        config.logging.rotation = LogRotation::Never;
+        config.logging.log_file = Some(dir.path().join("logs").join("agent.log"));
+        config.logging.modules =
+            BTreeMap::from([("qspec_fin_agent::logging".to_string(), "warn".to_string())]);
+
+        let (subscriber, guard) = subscriber(&config).unwrap();
+        tracing::subscriber::with_default(subscriber, || {
+            info!("hidden by the module level");
+            warn!(
+                account = "000123456789",
+                "request with key sk-test-secret failed"
+            );
+        });
+        drop(guard);
+
+        let text = std::fs::read_to_string(dir.path().join("logs").join("agent.log")).unwrap();
+        let lines: Vec<serde_json::Value> = text
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0]["level"], "WARN");
+        assert_eq!(
+            lines[0]["fields"]["message"],
+            format!("request with key {} failed", REDACTED)
+        );
+        assert_eq!(lines[0]["fields"]["account"], "****6789");
+        assert!(!text.contains("sk-test-secret"));
+    }
+}
@@ -1,15 +1,33 @@
-use qspec_fin_agent::FinancialAgent;
+use clap::Parser;
+use qspec_fin_agent::cli::{exit_code, Cli};
+use qspec_fin_agent::{logging, Config};
+use std::process::ExitCode;
 
 #[tokio::main]
-async fn main() -> anyhow::Result<()> {
-    // Initialize logging
-    tracing_subscriber::fmt()
-        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
-        .init();
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
 
-    // Create and start the financial agent
-    let agent = FinancialAgent::new().await?;
-    agent.run().await?;
+    // Initialize logging; an unusable configuration is reported by the command
+    // itself, so log to the console only until then
+    let config = cli.load_config().unwrap_or_else(|_| {
+        let mut config = Config::default();
+        config.logging.file_logging = false;
+        config
+    });
+    let _log_guard = match logging::init(&config) {
+        Ok(guard) => guard,
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            return ExitCode::from(exit_code::CONFIG);
+        }
+    };
 
-    Ok(())
+    // Run the requested command
+    match cli.run().await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::from(e.exit_code())
+        }
+    }
 }
@@ -0,0 +1,454 @@
+//! Model Context Protocol server over stdio
+//!
+//! Speaks newline-delimited JSON-RPC 2.0 so desktop AI assistants can read
+//! the books through the same typed tools the built-in assistant uses, plus
+//! monthly reports as resources. The categorization tool is only offered when
+//! writes are allowed.
+
+use crate::analysis::AnalysisEngine;
+use crate::tools::{Books, ToolCall, TOOLS};
+use chrono::Datelike;
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt};
+use tracing::{debug, info};
+
+/// Protocol revision the server implements
+pub const PROTOCOL_VERSION: &str = "2024-11-05";
+
+const REPORT_URI_PREFIX: &str = "qspec://reports/monthly/";
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+const RESOURCE_NOT_FOUND: i64 = -32002;
+
+/// A JSON-RPC error response
+#[derive(Debug, Clone, PartialEq)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// MCP server over a set of books
+pub struct McpServer<'a> {
+    books: &'a dyn Books,
+    allow_writes: bool,
+}
+
+impl<'a> McpServer<'a> {
+    pub fn new(books: &'a dyn Books, allow_writes: bool) -> Self {
+        Self {
+            books,
+            allow_writes,
+        }
+    }
+
+    /// Answer messages from `reader` on `writer` until the client closes the input
+    pub async fn serve<R, W>(&self, reader: R, mut writer: W) -> anyhow::Result<()>
+    where
+        R: AsyncBufRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        let mut lines = reader.lines();
+        while let Some(line) = lines.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let response = match serde_json::from_str::<Value>(&line) {
+                Ok(message) => self.handle(message).await,
+                Err(e) => Some(error_response(
+                    Value::Null,
+                    RpcError::new(PARSE_ERROR, format!("parse error: {}", e)),
+                )),
+            };
+            if let Some(response) = response {
+                writer.write_all(response.to_string().as_bytes()).await?;
+                writer.write_all(b"\n").await?;
+                writer.flush().await?;
+            }
+        }
+        info!("MCP client closed the connection");
+        Ok(())
+    }
+
+    /// Response to one message, or `None` for notifications
+    pub async fn handle(&self, message: Value) -> Option<Value> {
+        let id = message.get("id").cloned();
+        let Some(method) = message.get("method").and_then(Value::as_str) else {
+            return Some(error_response(
+                id.unwrap_or(Value::Null),
+                RpcError::new(INVALID_REQUEST, "expected a JSON-RPC request"),
+            ));
+        };
+        let params = message.get("params").cloned().unwrap_or_else(|| json!({}));
+        debug!("MCP {}", method);
+
+        // Notifications get no reply, even when they fail
+        let id = id?;
+        Some(match self.dispatch(method, params).await {
+            Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+            Err(e) => error_response(id, e),
+        })
+    }
+
+    async fn dispatch(&self, method: &str, params: Value) -> Result<Value, RpcError> {
+        match method {
+            "initialize" => Ok(json!({
+                "protocolVersion": PROTOCOL_VERSION,
+                "capabilities": {"tools": {}, "resources": {}},
+                "serverInfo": {"name": "qspec", "version": env!("CARGO_PKG_VERSION")},
+            })),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({"tools": self.list_tools()})),
+            "tools/call" => self.call_tool(params).await,
+            "resources/list" => self.list_resources().await,
+            "resources/read" => self.read_resource(params).await,
+            other => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("unknown method {}", other),
+            )),
+        }
+    }
+
+    fn list_tools(&self) -> Vec<Value> {
+        TOOLS
+            .iter()
+            .filter(|tool| self.allow_writes || !tool.write)
+            .map(|tool| {
+                json!({
+                    "name": tool.name,
+                    "description": tool.description,
+                    "inputSchema": tool.input_schema(),
+                })
+            })
+            .collect()
+    }
+
+    async fn call_tool(&self, params: Value) -> Result<Value, RpcError> {
+        let Some(name) = params.get("name").and_then(Value::as_str) else {
+            return Err(RpcError::new(INVALID_PARAMS, "missing tool name"));
+        };
+        let offered = TOOLS
+            .iter()
+            .any(|tool| tool.name == name && (self.allow_writes || !tool.write));
+        if !offered {
+            return Err(RpcError::new(
+                INVALID_PARAMS,
+                format!("unknown tool {}", name),
+            ));
+        }
+        let arguments = params
+            .get("arguments")
+            .cloned()
+            .unwrap_or_else(|| json!({}));
+
+        // Tool failures are results the client's model can read and act on
+        let outcome = match ToolCall::parse(name, arguments) {
+            Ok(call) => self.run_tool(&call).await,
+            Err(e) => Err(anyhow::anyhow!("invalid arguments: {}", e)),
+        };
+        Ok(match outcome {
+            Ok(result) => tool_result(&result, false),
+            Err(e) => tool_result(&json!({"error": format!("{:#}", e)}), true),
+        })
+    }
+
+    async fn run_tool(&self, call: &ToolCall) -> anyhow::Result<Value> {
+        let data = self.books.load().await?;
+        if !call.is_write() {
+            return call.read(&data);
+        }
+        let action = call.describe(&data)?;
+        let ToolCall::SetCategory {
+            transaction_id,
+            category,
+        } = call
+        else {
+            unreachable!("set_category is the only write");
+        };
+        self.books.set_category(*transaction_id, category).await?;
+        info!("MCP client: {}", action);
+        Ok(json!({"done": action}))
+    }
+
+    async fn list_resources(&self) -> Result<Value, RpcError> {
+        let data = self.books.load().await.map_err(internal)?;
+        let months: BTreeSet<(i32, u32)> = data
+            .transactions
+            .iter()
+            .map(|t| (t.date.year(), t.date.month()))
+            .collect();
+        let resources: Vec<Value> = months
+            .into_iter()
+            .rev()
+            .map(|(year, month)| {
+                json!({
+                    "uri": format!("{}{:04}-{:02}", REPORT_URI_PREFIX, year, month),
+                    "name": format!("Monthly report {:04}-{:02}", year, month),
+                    "description": "Income, expenses and spending per category",
+                    "mimeType": "application/json",
+                })
+            })
+            .collect();
+        Ok(json!({"resources": resources}))
+    }
+
+    async fn read_resource(&self, params: Value) -> Result<Value, RpcError> {
+        let Some(uri) = params.get("uri").and_then(Value::as_str) else {
+            return Err(RpcError::new(INVALID_PARAMS, "missing resource uri"));
+        };
+        let Some((year, month)) = parse_report_uri(uri) else {
+            return Err(RpcError::new(
+                RESOURCE_NOT_FOUND,
+                format!("unknown resource {}", uri),
+            ));
+        };
+        let data = self.books.load().await.map_err(internal)?;
+        let report =
+            AnalysisEngine::generate_monthly_report(&data, year, month).map_err(internal)?;
+        let text = serde_json::to_string_pretty(&report).map_err(|e| internal(e.into()))?;
+        Ok(json!({
+            "contents": [{"uri": uri, "mimeType": "application/json", "text": text}]
+        }))
+    }
+}
+
+/// Year and month named by a `qspec://reports/monthly/YYYY-MM` uri
+fn parse_report_uri(uri: &str) -> Option<(i32, u32)> {
+    let (year, month) = uri.strip_prefix(REPORT_URI_PREFIX)?.split_once('-')?;
+    if year.len() != 4 || month.len() != 2 {
+        return None;
+    }
+    let month: u32 = month.parse().ok()?;
+    (1..=12)
+        .contains(&month)
+        .then_some((year.parse().ok()?, month))
+}
+
+fn tool_result(result: &Value, is_error: bool) -> Value {
+    let text = serde_json::to_string_pretty(result).unwrap_or_else(|_| result.to_string());
+    json!({"content": [{"type": "text", "text": text}], "isError": is_error})
+}
+
+fn internal(e: anyhow::Error) -> RpcError {
+    RpcError::new(INTERNAL_ERROR, format!("{:#}", e))
+}
+
+fn error_response(id: Value, error: RpcError) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": {"code": error.code, "message": error.message},
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Account, AccountType, FinancialData, Transaction, TransactionType};
+    use anyhow::{Context, Result};
+    use async_trait::async_trait;
+    use chrono::{TimeZone, Utc};
+    use rust_decimal_macros::dec;
+    use std::sync::Mutex;
+    use tokio::io::BufReader;
+    use uuid::Uuid;
+
+    struct MemoryBooks(Mutex<FinancialData>);
+
+    #[async_trait]
+    impl Books for MemoryBooks {
+        async fn load(&self) -> Result<FinancialData> {
+            Ok(self.0.lock().unwrap().clone())
+        }
+
+        async fn set_category(&self, transaction_id: Uuid, category: &str) -> Result<()> {
+            let mut data = self.0.lock().unwrap();
+            let transaction = data
+                .transactions
+                .iter_mut()
+                .find(|t| t.id == transaction_id)
+                .context("missing")?;
+            transaction.category = Some(category.to_string());
+            Ok(())
+        }
+    }
+
+    fn books() -> (MemoryBooks, Uuid) {
+        let mut data = FinancialData::new();
+        let account = Account::new(
+            "Checking".to_string(),
+            AccountType::Checking,
+            dec!(900),
+            "USD".to_string(),
+        );
+        let t = Transaction::new(
+            account.id,
+            Utc.with_ymd_and_hms(2026, 9, 5, 12, 0, 0).unwrap(),
+            dec!(50.00),
+            "Grocer".to_string(),
+            TransactionType::Debit,
+        );
+        let id = t.id;
+        data.add_transaction(t);
+        data.add_account(account);
+        (MemoryBooks(Mutex::new(data)), id)
+    }
+
+    /// Send `requests` as a client would and collect the server's replies
+    async fn session(server: &McpServer<'_>, requests: &[Value]) -> Vec<Value> {
+        let input: String = requests.iter().map(|r| format!("{}\n", r)).collect();
+        let mut output = Vec::new();
+        server
+            .serve(BufReader::new(input.as_bytes()), &mut output)
+            .await
+            .unwrap();
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    fn request(id: u64, method: &str, params: Value) -> Value {
+        json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params})
+    }
+
+    fn tool_json(response: &Value) -> Value {
+        let text = response["result"]["content"][0]["text"].as_str().unwrap();
+        serde_json::from_str(text).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_read_only_session() {
+        let (books, id) = books();
+        let server = McpServer::new(&books, false);
+        let replies = session(
+            &server,
+            &[
+                request(1, "initialize", json!({"protocolVersion": PROTOCOL_VERSION})),
+                json!({"jsonrpc": "2.0", "method": "notifications/initialized"}),
+                request(2, "tools/list", json!({})),
+                request(
+                    3,
+                    "tools/call",
+                    json!({"name": "search_transactions", "arguments": {"query": "grocer"}}),
+                ),
+                request(4, "resources/list", json!({})),
+                request(
+                    5,
+                    "resources/read",
+                    json!({"uri": "qspec://reports/monthly/2026-09"}),
+                ),
+                request(
+                    6,
+                    "tools/call",
+                    json!({"name": "set_category", "arguments": {"transaction_id": id, "category": "Food"}}),
+                ),
+                request(7, "bogus/method", json!({})),
+            ],
+        )
+        .await;
+
+        // The notification got no reply
+        assert_eq!(replies.len(), 7);
+        assert_eq!(replies[0]["result"]["protocolVersion"], PROTOCOL_VERSION);
+        let tools: Vec<_> = replies[1]["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["name"].as_str().unwrap())
+            .collect();
+        assert!(tools.contains(&"account_balances") && !tools.contains(&"set_category"));
+        assert_eq!(tool_json(&replies[2])[0]["payee"], "Grocer");
+        assert_eq!(
+            replies[3]["result"]["resources"][0]["uri"],
+            "qspec://reports/monthly/2026-09"
+        );
+        let report: Value = serde_json::from_str(
+            replies[4]["result"]["contents"][0]["text"]
+                .as_str()
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(report["total_expenses"], "50.00");
+        assert_eq!(replies[5]["error"]["code"], INVALID_PARAMS);
+        assert_eq!(replies[6]["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(books.load().await.unwrap().transactions[0].category, None);
+    }
+
+    #[tokio::test]
+    async fn test_writes_and_tool_errors() {
+        let (books, id) = books();
+        let server = McpServer::new(&books, true);
+        let replies = session(
+            &server,
+            &[
+                request(
+                    1,
+                    "tools/call",
+                    json!({"name": "set_category", "arguments": {"transaction_id": id, "category": "Groceries"}}),
+                ),
+                request(
+                    2,
+                    "tools/call",
+                    json!({"name": "generate_monthly_report", "arguments": {"year": 2026, "month": 13}}),
+                ),
+                request(
+                    3,
+                    "resources/read",
+                    json!({"uri": "qspec://reports/monthly/2026-13"}),
+                ),
+            ],
+        )
+        .await;
+
+        assert_eq!(replies[0]["result"]["isError"], false);
+        assert_eq!(
+            tool_json(&replies[0])["done"],
+            "Set the category of 2026-09-05 Grocer 50.00 (uncategorized) to Groceries"
+        );
+        assert_eq!(
+            books.load().await.unwrap().transactions[0]
+                .category
+                .as_deref(),
+            Some("Groceries")
+        );
+        assert_eq!(replies[1]["result"]["isError"], true);
+        assert_eq!(replies[2]["error"]["code"], RESOURCE_NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_malformed_input() {
+        let (books, _) = books();
+        let server = McpServer::new(&books, false);
+        let mut output = Vec::new();
+        server
+            .serve(
+                BufReader::new(&b"not json\n\n{\"id\": 9}\n"[..]),
+                &mut output,
+            )
+            .await
+            .unwrap();
+        let replies: Vec<Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(replies[0]["error"]["code"], PARSE_ERROR);
+        assert_eq!(replies[1]["id"], 9);
+        assert_eq!(replies[1]["error"]["code"], INVALID_REQUEST);
+    }
+}
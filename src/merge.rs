@@ -0,0 +1,619 @@
+use crate::data::{Account, FinancialData, Transaction};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Merges financial data sets from separate Quicken files
+pub struct MergeEngine;
+
+/// User decision for an incoming account
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AccountMapping {
+    /// Merge into the base account with this name or id
+    MergeInto(String),
+    /// Never merge; add as a separate account
+    KeepSeparate,
+}
+
+/// Options controlling a merge
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MergeOptions {
+    /// Explicit decisions keyed by incoming account name or id
+    pub account_mapping: HashMap<String, AccountMapping>,
+    /// Take matched accounts' balances from the incoming data instead of reporting a mismatch
+    #[serde(default)]
+    pub take_incoming_balances: bool,
+}
+
+/// How an incoming account was matched to a base account
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccountMatch {
+    pub incoming: String,
+    pub target: String,
+    /// Attributes that matched (`name`, `institution`, `account_number`, `mapping`)
+    pub matched_on: Vec<String>,
+}
+
+/// Kinds of merge conflict
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictKind {
+    /// Several base accounts match equally well
+    AmbiguousMatch,
+    /// Names match but institution or account number differ
+    IdentityMismatch,
+    /// Matched accounts use different currencies
+    CurrencyMismatch,
+    /// Matched accounts report different balances
+    BalanceMismatch,
+    /// A mapping refers to an account that does not exist
+    UnknownMappingTarget,
+}
+
+/// A conflict that needs a user decision
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MergeConflict {
+    pub account: String,
+    pub kind: ConflictKind,
+    pub detail: String,
+}
+
+/// Summary of what a merge did
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MergeReport {
+    pub matched_accounts: Vec<AccountMatch>,
+    pub added_accounts: Vec<String>,
+    pub conflicts: Vec<MergeConflict>,
+    pub transactions_added: usize,
+    pub duplicate_transactions: usize,
+    /// Incoming spellings unified with an existing category (`from`, `to`)
+    pub unified_categories: Vec<(String, String)>,
+    /// Incoming spellings unified with an existing payee (`from`, `to`)
+    pub unified_payees: Vec<(String, String)>,
+}
+
+/// Merged data and its report
+#[derive(Debug, Clone)]
+pub struct MergeResult {
+    pub data: FinancialData,
+    pub report: MergeReport,
+}
+
+/// Last four digits of an account number, as printed on statements
+pub fn masked_account_number(account_number: &str) -> Option<String> {
+    let digits: Vec<char> = account_number
+        .chars()
+        .filter(|c| c.is_ascii_digit())
+        .collect();
+    (digits.len() >= 4).then(|| digits[digits.len() - 4..].iter().collect())
+}
+
+impl MergeEngine {
+    /// Merge `incoming` into a copy of `base`
+    ///
+    /// Accounts are matched by name, institution and masked account number.
+    /// Anything that cannot be decided safely is added as a separate account and
+    /// reported as a conflict; supply an [`AccountMapping`] to resolve it.
+    pub fn merge(
+        base: &FinancialData,
+        incoming: &FinancialData,
+        options: &MergeOptions,
+    ) -> Result<MergeResult> {
+        let mut data = base.clone();
+        let mut report = MergeReport::default();
+        let mut account_ids: HashMap<Uuid, Uuid> = HashMap::new();
+
+        for account in &incoming.accounts {
+            let target = Self::resolve_account(&base.accounts, account, options, &mut report);
+
+            match target {
+                Some(target_id) => {
+                    account_ids.insert(account.id, target_id);
+                    if options.take_incoming_balances {
+                        if let Some(target) = data.accounts.iter_mut().find(|a| a.id == target_id) {
+                            if target.balance != account.balance {
+                                target.update_balance(account.balance);
+                            }
+                        }
+                    }
+                }
+                None => {
+                    data.add_account(account.clone());
+                    report.added_accounts.push(account.name.clone());
+                    account_ids.insert(account.id, account.id);
+                }
+            }
+        }
+
+        let mut categories = Canonicalizer::new(&data.categories);
+        let mut payees = Canonicalizer::new(&data.payees);
+        // Each base transaction absorbs at most one incoming copy, so identical
+        // purchases within the incoming file all survive
+        let mut absorbed = vec![false; base.transactions.len()];
+        let mut by_id: HashMap<Uuid, usize> = HashMap::new();
+        let mut by_key: HashMap<DuplicateKey, Vec<usize>> = HashMap::new();
+        for (i, existing) in base.transactions.iter().enumerate() {
+            by_id.entry(existing.id).or_insert(i);
+            by_key.entry(duplicate_key(existing)).or_default().push(i);
+        }
+
+        for transaction in &incoming.transactions {
+            let mut transaction = transaction.clone();
+            transaction.account_id = account_ids
+                .get(&transaction.account_id)
+                .copied()
+                .unwrap_or(transaction.account_id);
+            transaction.category = transaction
+                .category
+                .map(|c| categories.canonical(&c, &mut report.unified_categories));
+            for split in &mut transaction.splits {
+                split.category = split
+                    .category
+                    .take()
+                    .map(|c| categories.canonical(&c, &mut report.unified_categories));
+            }
+            transaction.payee = transaction
+                .payee
+                .map(|p| payees.canonical(&p, &mut report.unified_payees));
+
+            let duplicate = by_id
+                .get(&transaction.id)
+                .into_iter()
+                .chain(
+                    by_key
+                        .get(&duplicate_key(&transaction))
+                        .into_iter()
+                        .flatten(),
+                )
+                .copied()
+                .filter(|&i| !absorbed[i] && is_duplicate(&base.transactions[i], &transaction))
+                .min();
+            if let Some(i) = duplicate {
+                absorbed[i] = true;
+                report.duplicate_transactions += 1;
+                continue;
+            }
+
+            data.add_transaction(transaction);
+            report.transactions_added += 1;
+        }
+
+        for name in &incoming.categories {
+            let name = categories.canonical(name, &mut report.unified_categories);
+            if !data.categories.contains(&name) {
+                data.categories.push(name);
+            }
+        }
+        for name in &incoming.payees {
+            let name = payees.canonical(name, &mut report.unified_payees);
+            if !data.payees.contains(&name) {
+                data.payees.push(name);
+            }
+        }
+
+        Ok(MergeResult { data, report })
+    }
+
+    /// Find the base account an incoming account should merge into, if any
+    fn resolve_account(
+        accounts: &[Account],
+        incoming: &Account,
+        options: &MergeOptions,
+        report: &mut MergeReport,
+    ) -> Option<Uuid> {
+        let mapping = options
+            .account_mapping
+            .get(&incoming.id.to_string())
+            .or_else(|| options.account_mapping.get(&incoming.name));
+
+        let (target, matched_on) = match mapping {
+            Some(AccountMapping::KeepSeparate) => return None,
+            Some(AccountMapping::MergeInto(wanted)) => {
+                let found = accounts
+                    .iter()
+                    .find(|a| a.id.to_string() == *wanted)
+                    .or_else(|| accounts.iter().find(|a| a.name == *wanted));
+                match found {
+                    Some(target) => (target, vec!["mapping".to_string()]),
+                    None => {
+                        report.conflicts.push(MergeConflict {
+                            account: incoming.name.clone(),
+                            kind: ConflictKind::UnknownMappingTarget,
+                            detail: format!("no account named '{}' to merge into", wanted),
+                        });
+                        return None;
+                    }
+                }
+            }
+            None => Self::match_account(accounts, incoming, report)?,
+        };
+
+        if target.currency != incoming.currency {
+            report.conflicts.push(MergeConflict {
+                account: incoming.name.clone(),
+                kind: ConflictKind::CurrencyMismatch,
+                detail: format!(
+                    "'{}' is in {} but '{}' is in {}; kept separate",
+                    incoming.name, incoming.currency, target.name, target.currency
+                ),
+            });
+            return None;
+        }
+
+        if target.balance != incoming.balance && !options.take_incoming_balances {
+            report.conflicts.push(MergeConflict {
+                account: incoming.name.clone(),
+                kind: ConflictKind::BalanceMismatch,
+                detail: format!(
+                    "balance {} differs from '{}' balance {}; kept {}",
+                    incoming.balance, target.name, target.balance, target.balance
+                ),
+            });
+        }
+
+        report.matched_accounts.push(AccountMatch {
+            incoming: incoming.name.clone(),
+            target: target.name.clone(),
+            matched_on,
+        });
+        Some(target.id)
+    }
+
+    fn match_account<'a>(
+        accounts: &'a [Account],
+        incoming: &Account,
+        report: &mut MergeReport,
+    ) -> Option<(&'a Account, Vec<String>)> {
+        let mut best: Vec<(&Account, Vec<String>)> = Vec::new();
+        let mut best_score = 0;
+        let mut mismatched: Vec<&Account> = Vec::new();
+
+        for candidate in accounts {
+            let Some((score, matched_on)) = identity_match(candidate, incoming) else {
+                if same_name(candidate, incoming) {
+                    mismatched.push(candidate);
+                }
+                continue;
+            };
+
+            if score > best_score {
+                best_score = score;
+                best.clear();
+            }
+            if score == best_score {
+                best.push((candidate, matched_on));
+            }
+        }
+
+        match best.len() {
+            1 => best.pop(),
+            0 => {
+                if let Some(candidate) = mismatched.first() {
+                    report.conflicts.push(MergeConflict {
+                        account: incoming.name.clone(),
+                        kind: ConflictKind::IdentityMismatch,
+                        detail: format!(
+                            "same name as '{}' but institution or account number differs; kept separate",
+                            candidate.name
+                        ),
+                    });
+                }
+                None
+            }
+            _ => {
+                let names: Vec<&str> = best.iter().map(|(a, _)| a.name.as_str()).collect();
+                report.conflicts.push(MergeConflict {
+                    account: incoming.name.clone(),
+                    kind: ConflictKind::AmbiguousMatch,
+                    detail: format!("matches {}; kept separate", names.join(", ")),
+                });
+                None
+            }
+        }
+    }
+}
+
+/// Score how well two accounts identify the same real account
+///
+/// Returns `None` when any known attribute contradicts, or nothing beyond the
+/// account type matches.
+fn identity_match(a: &Account, b: &Account) -> Option<(u32, Vec<String>)> {
+    let mut score = 0;
+    let mut matched_on = Vec::new();
+
+    let institution = compare(
+        a.institution.as_deref().map(normalize),
+        b.institution.as_deref().map(normalize),
+    )?;
+    let number = compare(
+        a.account_number.as_deref().and_then(masked_account_number),
+        b.account_number.as_deref().and_then(masked_account_number),
+    )?;
+
+    if same_name(a, b) {
+        score += 2;
+        matched_on.push("name".to_string());
+    }
+    if institution {
+        score += 1;
+        matched_on.push("institution".to_string());
+    }
+    if number {
+        score += 3;
+        matched_on.push("account_number".to_string());
+    }
+
+    // A shared institution alone is not enough to call two accounts the same
+    (score >= 2 && a.account_type == b.account_type).then_some((score, matched_on))
+}
+
+/// `Some(true)` when both known and equal, `Some(false)` when either is unknown,
+/// `None` when both known and different
+fn compare(a: Option<String>, b: Option<String>) -> Option<bool> {
+    match (a, b) {
+        (Some(a), Some(b)) if a == b => Some(true),
+        (Some(_), Some(_)) => None,
+        _ => Some(false),
+    }
+}
+
+fn same_name(a: &Account, b: &Account) -> bool {
+    normalize(&a.name) == normalize(&b.name)
+}
+
+fn normalize(value: &str) -> String {
+    value
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Fields every duplicate of a transaction shares, other than its id
+type DuplicateKey = (Uuid, DateTime<Utc>, Decimal);
+
+fn duplicate_key(transaction: &Transaction) -> DuplicateKey {
+    (transaction.account_id, transaction.date, transaction.amount)
+}
+
+fn is_duplicate(a: &Transaction, b: &Transaction) -> bool {
+    a.id == b.id
+        || (a.account_id == b.account_id
+            && a.date == b.date
+            && a.amount == b.amount
+            && a.transaction_type == b.transaction_type
+            && a.payee == b.payee)
+}
+
+/// Maps differently spelled names onto the first spelling seen
+struct Canonicalizer {
+    names: HashMap<String, String>,
+}
+
+impl Canonicalizer {
+    fn new(existing: &[String]) -> Self {
+        let mut names = HashMap::new();
+        for name in existing {
+            names.entry(normalize(name)).or_insert_with(|| name.clone());
+        }
+        Self { names }
+    }
+
+    fn canonical(&mut self, name: &str, unified: &mut Vec<(String, String)>) -> String {
+        let canonical = self
+            .names
+            .entry(normalize(name))
+            .or_insert_with(|| name.to_string())
+            .clone();
+
+        if canonical != name && !unified.iter().any(|(from, _)| from == name) {
+            unified.push((name.to_string(), canonical.clone()));
+        }
+        canonical
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{AccountType, TransactionType};
+    use chrono::{TimeZone, Utc};
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+
+    fn account(name: &str, institution: Option<&str>, number: Option<&str>) -> Account {
+        let mut account = Account::new(
+            name.to_string(),
+            AccountType::Checking,
+            dec!(100.00),
+            "USD".to_string(),
+        );
+        account.institution = institution.map(str::to_string);
+        account.account_number = number.map(str::to_string);
+        account
+    }
+
+    fn spend(
+        account: &Account,
+        day: u32,
+        amount: Decimal,
+        payee: &str,
+        category: &str,
+    ) -> Transaction {
+        let mut t = Transaction::new(
+            account.id,
+            Utc.with_ymd_and_hms(2026, 9, day, 0, 0, 0).unwrap(),
+            amount,
+            payee.to_string(),
+            TransactionType::Debit,
+        );
+        t.payee = Some(payee.to_string());
+        t.category = Some(category.to_string());
+        t
+    }
+
+    #[test]
+    fn test_masked_account_number() {
+        assert_eq!(
+            masked_account_number("1234-5678-9012"),
+            Some("9012".to_string())
+        );
+        assert_eq!(masked_account_number("xxxx9012"), Some("9012".to_string()));
+        assert_eq!(masked_account_number("12"), None);
+    }
+
+    #[test]
+    fn test_merge_matches_accounts_and_dedupes() {
+        let joint = account("Joint Checking", Some("First Bank"), Some("000111222333"));
+        let mut mine = FinancialData::new();
+        mine.add_account(joint.clone());
+        mine.add_transaction(spend(&joint, 1, dec!(60.00), "Kroger", "Groceries"));
+
+        // Partner's file: same joint account under another name and random id
+        let theirs_joint = account("Joint Chk", Some("first bank"), Some("****2333"));
+        let savings = account("Savings", Some("Credit Union"), None);
+        let mut theirs = FinancialData::new();
+        theirs.add_account(theirs_joint.clone());
+        theirs.add_account(savings.clone());
+        theirs.add_transaction(spend(&theirs_joint, 1, dec!(60.00), "Kroger", "groceries"));
+        theirs.add_transaction(spend(&theirs_joint, 2, dec!(15.00), "kroger", "Groceries"));
+        theirs.add_transaction(spend(&savings, 3, dec!(5.00), "Bank", "Fees"));
+
+        let result = MergeEngine::merge(&mine, &theirs, &MergeOptions::default()).unwrap();
+        let report = &result.report;
+
+        assert_eq!(result.data.accounts.len(), 2);
+        assert_eq!(report.matched_accounts.len(), 1);
+        assert_eq!(
+            report.matched_accounts[0].matched_on,
+            vec!["institution".to_string(), "account_number".to_string()]
+        );
+        assert_eq!(report.added_accounts, vec!["Savings".to_string()]);
+        assert_eq!(report.duplicate_transactions, 1);
+        assert_eq!(report.transactions_added, 2);
+        assert!(report.conflicts.is_empty());
+
+        assert_eq!(result.data.categories, vec!["Groceries", "Fees"]);
+        assert_eq!(result.data.payees, vec!["Kroger", "Bank"]);
+        assert_eq!(
+            report.unified_categories,
+            vec![("groceries".to_string(), "Groceries".to_string())]
+        );
+        assert_eq!(result.data.get_account_transactions(&joint.id).len(), 2);
+    }
+
+    #[test]
+    fn test_identical_purchases_in_one_file_survive() {
+        let checking = account("Checking", Some("First Bank"), None);
+        let mut base = FinancialData::new();
+        base.add_account(checking.clone());
+
+        let mut incoming = FinancialData::new();
+        incoming.add_account(checking.clone());
+        incoming.add_transaction(spend(&checking, 3, dec!(4.50), "Cafe", "Dining"));
+        incoming.add_transaction(spend(&checking, 3, dec!(4.50), "Cafe", "Dining"));
+
+        let merged = MergeEngine::merge(&base, &incoming, &MergeOptions::default()).unwrap();
+        assert_eq!(merged.report.transactions_added, 2);
+        assert_eq!(merged.report.duplicate_transactions, 0);
+        assert_eq!(merged.data.transactions.len(), 2);
+
+        // Merging the same file again adds nothing
+        let again = MergeEngine::merge(&merged.data, &incoming, &MergeOptions::default()).unwrap();
+        assert_eq!(again.report.transactions_added, 0);
+        assert_eq!(again.report.duplicate_transactions, 2);
+        assert_eq!(again.data.transactions.len(), 2);
+    }
+
+    #[test]
+    fn test_duplicates_found_by_id_or_equal_amounts() {
+        let checking = account("Checking", Some("First Bank"), None);
+        let mut base = FinancialData::new();
+        base.add_account(checking.clone());
+        base.add_transaction(spend(&checking, 4, dec!(12.5), "Deli", "Dining"));
+        base.add_transaction(spend(&checking, 5, dec!(8.00), "Deli", "Dining"));
+
+        let mut incoming = FinancialData::new();
+        incoming.add_account(checking.clone());
+        // Same amount at another scale, and the same record since recategorized
+        incoming.add_transaction(spend(&checking, 4, dec!(12.50), "Deli", "Dining"));
+        let mut edited = base.transactions[1].clone();
+        edited.amount = dec!(9.00);
+        incoming.add_transaction(edited);
+
+        let result = MergeEngine::merge(&base, &incoming, &MergeOptions::default()).unwrap();
+        assert_eq!(result.report.duplicate_transactions, 2);
+        assert_eq!(result.data.transactions.len(), 2);
+    }
+
+    #[test]
+    fn test_conflicts_are_reported_not_resolved() {
+        let mut base = FinancialData::new();
+        base.add_account(account("Checking", Some("First Bank"), Some("1111")));
+        base.add_account(account("Visa", None, None));
+        base.add_account(account("visa", None, None));
+
+        let mut incoming = FinancialData::new();
+        incoming.add_account(account("Checking", Some("Other Bank"), Some("2222")));
+        incoming.add_account(account("Visa", None, None));
+
+        let result = MergeEngine::merge(&base, &incoming, &MergeOptions::default()).unwrap();
+        let kinds: Vec<ConflictKind> = result.report.conflicts.iter().map(|c| c.kind).collect();
+
+        assert_eq!(
+            kinds,
+            vec![ConflictKind::IdentityMismatch, ConflictKind::AmbiguousMatch]
+        );
+        assert_eq!(result.data.accounts.len(), 5);
+    }
+
+    #[test]
+    fn test_user_mapping_resolves_conflicts() {
+        let mut base = FinancialData::new();
+        let first = account("Visa", None, None);
+        base.add_account(first.clone());
+        base.add_account(account("visa", None, None));
+
+        let mut other = account("Visa", None, None);
+        other.balance = dec!(250.00);
+        let mut incoming = FinancialData::new();
+        incoming.add_account(other);
+        incoming.add_account(account("Cash", None, None));
+
+        let mut options = MergeOptions::default();
+        options.account_mapping.insert(
+            "Visa".to_string(),
+            AccountMapping::MergeInto(first.id.to_string()),
+        );
+        options.account_mapping.insert(
+            "Cash".to_string(),
+            AccountMapping::MergeInto("Wallet".to_string()),
+        );
+
+        let result = MergeEngine::merge(&base, &incoming, &options).unwrap();
+        let report = &result.report;
+
+        assert_eq!(
+            report.matched_accounts[0].matched_on,
+            vec!["mapping".to_string()]
+        );
+        assert_eq!(report.conflicts.len(), 2);
+        assert_eq!(report.conflicts[0].kind, ConflictKind::BalanceMismatch);
+        assert_eq!(report.conflicts[1].kind, ConflictKind::UnknownMappingTarget);
+        assert_eq!(report.added_accounts, vec!["Cash".to_string()]);
+
+        // A newer statement's balance replaces the stale one
+        options.take_incoming_balances = true;
+        let result = MergeEngine::merge(&base, &incoming, &options).unwrap();
+        assert_eq!(result.report.conflicts.len(), 1);
+        assert_eq!(
+            result.report.conflicts[0].kind,
+            ConflictKind::UnknownMappingTarget
+        );
+        let visa = result.data.accounts.iter().find(|a| a.id == first.id);
+        assert_eq!(visa.unwrap().balance, dec!(250.00));
+    }
+}
@@ -0,0 +1,787 @@
+use crate::config::{
+    ChannelConfig, ChannelKind, CommandChannelConfig, EmailChannelConfig, FileChannelConfig,
+    NotificationConfig, SmtpSecurity, WebhookChannelConfig,
+};
+use crate::events::AgentEvent;
+use crate::utils::format_currency;
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use lettre::message::header::ContentType;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::Mutex;
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, warn};
+
+/// Schema name carried by webhook and file payloads
+pub const NOTIFICATION_SCHEMA: &str = "qspec.notification";
+
+/// An alert ready for delivery
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Notification {
+    pub schema: String,
+    /// Event name, or `digest` for a batch
+    pub event: String,
+    pub title: String,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+    /// The event that caused this notification
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<AgentEvent>,
+    /// Notifications batched into a digest
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub items: Vec<Notification>,
+}
+
+/// Delivers notifications over one transport
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    async fn send(&self, notification: &Notification) -> Result<()>;
+}
+
+/// Sends plain-text email through an SMTP server
+pub struct EmailNotifier {
+    config: EmailChannelConfig,
+}
+
+/// POSTs the notification as JSON
+pub struct WebhookNotifier {
+    config: WebhookChannelConfig,
+    client: reqwest::Client,
+}
+
+/// Runs a command such as `notify-send` for each notification
+pub struct CommandNotifier {
+    config: CommandChannelConfig,
+}
+
+/// Appends each notification to a file as a JSON line
+pub struct FileNotifier {
+    config: FileChannelConfig,
+}
+
+/// Routing, rate limiting and digest settings for one channel
+#[derive(Debug, Clone, Default)]
+pub struct ChannelOptions {
+    pub events: Vec<String>,
+    pub max_per_hour: Option<u32>,
+    pub digest_minutes: Option<u64>,
+}
+
+struct Channel {
+    name: String,
+    options: ChannelOptions,
+    notifier: Box<dyn Notifier>,
+    state: Mutex<ChannelState>,
+}
+
+#[derive(Default)]
+struct ChannelState {
+    sent: VecDeque<DateTime<Utc>>,
+    pending: Vec<Notification>,
+    window_start: Option<DateTime<Utc>>,
+    suppressed: usize,
+}
+
+/// Routes agent events to the configured notification channels
+///
+/// Delivery failures are logged rather than returned so a broken channel
+/// never interrupts imports or scheduled jobs.
+#[derive(Default)]
+pub struct NotificationCenter {
+    channels: Vec<Channel>,
+}
+
+impl Notification {
+    pub fn new(event: &str, title: impl Into<String>, body: impl Into<String>) -> Self {
+        Self {
+            schema: NOTIFICATION_SCHEMA.to_string(),
+            event: event.to_string(),
+            title: title.into(),
+            body: body.into(),
+            created_at: Utc::now(),
+            details: None,
+            items: Vec::new(),
+        }
+    }
+
+    /// Describe an agent event for a person
+    pub fn from_event(event: &AgentEvent, now: DateTime<Utc>) -> Self {
+        let (title, body) = match event {
+            AgentEvent::TransactionImported {
+                transaction,
+                source,
+            } => (
+                "Transaction imported".to_string(),
+                format!(
+                    "{} {} {} from {}",
+                    transaction.date.format("%Y-%m-%d"),
+                    transaction
+                        .payee
+                        .as_deref()
+                        .unwrap_or(&transaction.description),
+                    transaction.amount.round_dp(2),
+                    file_name(source)
+                ),
+            ),
+            AgentEvent::ImportFailed { path, error } => {
+                (format!("Import failed: {}", file_name(path)), error.clone())
+            }
+            AgentEvent::AnomalyDetected { transaction } => (
+                format!(
+                    "Unusual transaction: {}",
+                    transaction
+                        .payee
+                        .as_deref()
+                        .unwrap_or(&transaction.description)
+                ),
+                format!(
+                    "{} {} in {}",
+                    transaction.date.format("%Y-%m-%d"),
+                    transaction.amount.round_dp(2),
+                    transaction.category.as_deref().unwrap_or("Uncategorized")
+                ),
+            ),
+            AgentEvent::BudgetExceeded {
+                category,
+                year,
+                month,
+                budget,
+                spent,
+            } => (
+                format!("{} over budget", category),
+                format!(
+                    "Spent {} of {} in {}-{:02}",
+                    spent.round_dp(2),
+                    budget.round_dp(2),
+                    year,
+                    month
+                ),
+            ),
+            AgentEvent::ReportGenerated {
+                year, month, path, ..
+            } => (
+                format!("Monthly report {}-{:02}", year, month),
+                format!("Written to {}", path.display()),
+            ),
+            AgentEvent::BillDue {
+                name,
+                amount,
+                currency,
+                due,
+                days_until,
+            } => {
+                let amount = amount
+                    .map(|a| format!("{} ", format_currency(a, currency)))
+                    .unwrap_or_default();
+                let when = match days_until {
+                    0 => "today".to_string(),
+                    1 => "tomorrow".to_string(),
+                    n => format!("in {} days", n),
+                };
+                (
+                    format!("{} due {}", name, when),
+                    format!("{}due {}", amount, due),
+                )
+            }
+        };
+
+        Self {
+            created_at: now,
+            details: Some(event.clone()),
+            ..Self::new(event.name(), title, body)
+        }
+    }
+
+    /// Combine several notifications into one
+    pub fn digest(items: Vec<Notification>, now: DateTime<Utc>) -> Self {
+        let title = match items.len() {
+            1 => "1 QSpec alert".to_string(),
+            n => format!("{} QSpec alerts", n),
+        };
+        let body = items
+            .iter()
+            .map(|n| format!("- {}: {}", n.title, n.body))
+            .collect::<Vec<_>>()
+            .join("\n");
+        Self {
+            created_at: now,
+            items,
+            ..Self::new("digest", title, body)
+        }
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+impl NotificationCenter {
+    /// Build the channels described in the configuration
+    pub fn from_config(config: &NotificationConfig) -> Self {
+        let mut center = Self::default();
+        for channel in &config.channels {
+            center.add_channel(
+                &channel.name,
+                channel_options(channel),
+                notifier_for(channel),
+            );
+        }
+        center
+    }
+
+    /// Add a channel with its own transport
+    pub fn add_channel(
+        &mut self,
+        name: &str,
+        options: ChannelOptions,
+        notifier: Box<dyn Notifier>,
+    ) {
+        self.channels.push(Channel {
+            name: name.to_string(),
+            options,
+            notifier,
+            state: Mutex::new(ChannelState::default()),
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.channels.is_empty()
+    }
+
+    /// Deliver an event to every channel that routes it
+    pub async fn notify(&self, event: &AgentEvent, now: DateTime<Utc>) {
+        let notification = Notification::from_event(event, now);
+        for channel in &self.channels {
+            if channel.routes(event.name()) {
+                channel.submit(notification.clone(), now).await;
+            }
+        }
+    }
+
+    /// Send digests whose batching window has elapsed
+    pub async fn flush_due(&self, now: DateTime<Utc>) {
+        for channel in &self.channels {
+            channel.flush(now, false).await;
+        }
+    }
+
+    /// Send every pending digest, e.g. at shutdown
+    pub async fn flush_all(&self, now: DateTime<Utc>) {
+        for channel in &self.channels {
+            channel.flush(now, true).await;
+        }
+    }
+}
+
+fn channel_options(config: &ChannelConfig) -> ChannelOptions {
+    ChannelOptions {
+        events: config.events.clone(),
+        max_per_hour: config.max_per_hour,
+        digest_minutes: config.digest_minutes,
+    }
+}
+
+fn notifier_for(config: &ChannelConfig) -> Box<dyn Notifier> {
+    match config.kind {
+        ChannelKind::Email(ref email) => Box::new(EmailNotifier::new(email.clone())),
+        ChannelKind::Webhook(ref webhook) => Box::new(WebhookNotifier::new(webhook.clone())),
+        ChannelKind::Command(ref command) => Box::new(CommandNotifier::new(command.clone())),
+        ChannelKind::File(ref file) => Box::new(FileNotifier::new(file.clone())),
+    }
+}
+
+impl Channel {
+    fn routes(&self, event: &str) -> bool {
+        self.options.events.is_empty() || self.options.events.iter().any(|e| e == event)
+    }
+
+    async fn submit(&self, notification: Notification, now: DateTime<Utc>) {
+        if self.options.digest_minutes.is_some() {
+            {
+                let mut state = self.state.lock().expect("channel lock poisoned");
+                state.window_start.get_or_insert(now);
+                state.pending.push(notification);
+            }
+            self.flush(now, false).await;
+        } else {
+            self.deliver(notification, now).await;
+        }
+    }
+
+    async fn flush(&self, now: DateTime<Utc>, force: bool) {
+        let Some(minutes) = self.options.digest_minutes else {
+            return;
+        };
+        let pending = {
+            let mut state = self.state.lock().expect("channel lock poisoned");
+            let due = state
+                .window_start
+                .is_some_and(|start| now - start >= Duration::minutes(minutes as i64));
+            if state.pending.is_empty() || !(due || force) {
+                return;
+            }
+            state.window_start = None;
+            std::mem::take(&mut state.pending)
+        };
+        self.deliver(Notification::digest(pending, now), now).await;
+    }
+
+    async fn deliver(&self, mut notification: Notification, now: DateTime<Utc>) {
+        {
+            let mut state = self.state.lock().expect("channel lock poisoned");
+            while state
+                .sent
+                .front()
+                .is_some_and(|sent| now - *sent >= Duration::hours(1))
+            {
+                state.sent.pop_front();
+            }
+
+            if let Some(limit) = self.options.max_per_hour {
+                if state.sent.len() >= limit as usize {
+                    state.suppressed += 1;
+                    debug!("Channel {} rate limited {}", self.name, notification.title);
+                    return;
+                }
+            }
+
+            if state.suppressed > 0 {
+                notification.body.push_str(&format!(
+                    "\n({} earlier notifications suppressed by rate limit)",
+                    state.suppressed
+                ));
+                state.suppressed = 0;
+            }
+            state.sent.push_back(now);
+        }
+
+        if let Err(e) = self.notifier.send(&notification).await {
+            warn!("Notification channel {} failed: {:#}", self.name, e);
+        }
+    }
+}
+
+impl EmailNotifier {
+    pub fn new(config: EmailChannelConfig) -> Self {
+        Self { config }
+    }
+
+    fn transport(&self) -> Result<AsyncSmtpTransport<Tokio1Executor>> {
+        let host = &self.config.smtp_host;
+        let builder = match self.config.security {
+            SmtpSecurity::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host),
+            SmtpSecurity::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)?,
+            SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(host)?,
+        }
+        .port(self.config.smtp_port);
+
+        let builder = match self.config.username {
+            Some(ref username) => {
+                let password = match self.config.password_env {
+                    Some(ref var) => std::env::var(var)
+                        .with_context(|| format!("SMTP password variable {} is not set", var))?,
+                    None => String::new(),
+                };
+                builder.credentials(Credentials::new(username.clone(), password))
+            }
+            None => builder,
+        };
+        Ok(builder.build())
+    }
+}
+
+#[async_trait]
+impl Notifier for EmailNotifier {
+    async fn send(&self, notification: &Notification) -> Result<()> {
+        if self.config.to.is_empty() {
+            bail!("Email channel has no recipients");
+        }
+
+        let mut message = Message::builder()
+            .from(self.config.from.parse().context("Invalid sender address")?)
+            .subject(notification.title.clone())
+            .header(ContentType::TEXT_PLAIN);
+        for to in &self.config.to {
+            message = message.to(to
+                .parse()
+                .with_context(|| format!("Invalid recipient address {}", to))?);
+        }
+        let message = message.body(notification.body.clone())?;
+
+        self.transport()?
+            .send(message)
+            .await
+            .context("SMTP delivery failed")?;
+        Ok(())
+    }
+}
+
+impl WebhookNotifier {
+    pub fn new(config: WebhookChannelConfig) -> Self {
+        Self {
+            config,
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    async fn send(&self, notification: &Notification) -> Result<()> {
+        let mut request = self
+            .client
+            .post(&self.config.url)
+            .timeout(std::time::Duration::from_secs(self.config.timeout_secs))
+            .json(notification);
+        for (name, value) in &self.config.headers {
+            request = request.header(name, value);
+        }
+
+        request
+            .send()
+            .await
+            .with_context(|| format!("Webhook request to {} failed", self.config.url))?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+impl CommandNotifier {
+    pub fn new(config: CommandChannelConfig) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl Notifier for CommandNotifier {
+    async fn send(&self, notification: &Notification) -> Result<()> {
+        let args = self.config.args.iter().map(|arg| {
+            arg.replace("{title}", &notification.title)
+                .replace("{body}", &notification.body)
+                .replace("{event}", &notification.event)
+        });
+
+        let status = tokio::process::Command::new(&self.config.program)
+            .args(args)
+            .env("QSPEC_EVENT", &notification.event)
+            .env("QSPEC_TITLE", &notification.title)
+            .env("QSPEC_BODY", &notification.body)
+            .status()
+            .await
+            .with_context(|| format!("Failed to run {}", self.config.program))?;
+        if !status.success() {
+            bail!("{} exited with {}", self.config.program, status);
+        }
+        Ok(())
+    }
+}
+
+impl FileNotifier {
+    pub fn new(config: FileChannelConfig) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl Notifier for FileNotifier {
+    async fn send(&self, notification: &Notification) -> Result<()> {
+        let path = &self.config.path;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        let mut line = serde_json::to_string(notification)?;
+        line.push('\n');
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        file.write_all(line.as_bytes()).await?;
+        file.flush().await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Transaction, TransactionType};
+    use chrono::TimeZone;
+    use rust_decimal_macros::dec;
+    use std::path::PathBuf;
+    use tokio::io::{AsyncBufReadExt, BufReader};
+    use tokio::net::TcpListener;
+    use uuid::Uuid;
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn anomaly() -> AgentEvent {
+        let mut transaction = Transaction::new(
+            Uuid::new_v4(),
+            Utc.with_ymd_and_hms(2026, 9, 14, 0, 0, 0).unwrap(),
+            dec!(940.00),
+            "Electronics".to_string(),
+            TransactionType::Debit,
+        );
+        transaction.payee = Some("Gadget Hut".to_string());
+        AgentEvent::AnomalyDetected { transaction }
+    }
+
+    fn failure(name: &str) -> AgentEvent {
+        AgentEvent::ImportFailed {
+            path: PathBuf::from(name),
+            error: "bad date".to_string(),
+        }
+    }
+
+    fn read_lines(path: &Path) -> Vec<Notification> {
+        std::fs::read_to_string(path)
+            .unwrap_or_default()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    fn file_channel(path: &Path, options: ChannelOptions) -> NotificationCenter {
+        let mut center = NotificationCenter::default();
+        center.add_channel(
+            "file",
+            options,
+            Box::new(FileNotifier::new(FileChannelConfig {
+                path: path.to_path_buf(),
+            })),
+        );
+        center
+    }
+
+    #[test]
+    fn test_bill_due_notification() {
+        let event = AgentEvent::BillDue {
+            name: "Electricity".to_string(),
+            amount: Some(dec!(120)),
+            currency: "EUR".to_string(),
+            due: chrono::NaiveDate::from_ymd_opt(2026, 10, 5).unwrap(),
+            days_until: 2,
+        };
+        let notification = Notification::from_event(&event, Utc::now());
+        assert_eq!(notification.event, "bill_due");
+        assert_eq!(notification.title, "Electricity due in 2 days");
+        assert_eq!(notification.body, "€120.00 due 2026-10-05");
+    }
+
+    #[tokio::test]
+    async fn test_routing_and_rate_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("alerts.jsonl");
+        let center = file_channel(
+            &log,
+            ChannelOptions {
+                events: vec!["import_failed".to_string()],
+                max_per_hour: Some(2),
+                digest_minutes: None,
+            },
+        );
+        let start = Utc.with_ymd_and_hms(2026, 10, 1, 9, 0, 0).unwrap();
+
+        center.notify(&anomaly(), start).await;
+        for name in ["a.qif", "b.qif", "c.qif", "d.qif"] {
+            center.notify(&failure(name), start).await;
+        }
+        let sent = read_lines(&log);
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent[0].title, "Import failed: a.qif");
+
+        // An hour later the window has room again and mentions what was dropped
+        center
+            .notify(&failure("e.qif"), start + Duration::minutes(61))
+            .await;
+        let sent = read_lines(&log);
+        assert_eq!(sent.len(), 3);
+        assert!(sent[2]
+            .body
+            .ends_with("(2 earlier notifications suppressed by rate limit)"));
+    }
+
+    #[tokio::test]
+    async fn test_digest_batching() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("digest.jsonl");
+        let center = file_channel(
+            &log,
+            ChannelOptions {
+                digest_minutes: Some(30),
+                ..ChannelOptions::default()
+            },
+        );
+        let start = Utc.with_ymd_and_hms(2026, 10, 1, 9, 0, 0).unwrap();
+
+        center.notify(&anomaly(), start).await;
+        center
+            .notify(&failure("a.qif"), start + Duration::minutes(10))
+            .await;
+        center.flush_due(start + Duration::minutes(20)).await;
+        assert!(read_lines(&log).is_empty());
+
+        center.flush_due(start + Duration::minutes(30)).await;
+        let sent = read_lines(&log);
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].event, "digest");
+        assert_eq!(sent[0].title, "2 QSpec alerts");
+        assert_eq!(sent[0].items[0].title, "Unusual transaction: Gadget Hut");
+        assert!(sent[0].body.contains("- Import failed: a.qif: bad date"));
+
+        center
+            .notify(&failure("b.qif"), start + Duration::minutes(40))
+            .await;
+        center.flush_all(start + Duration::minutes(41)).await;
+        assert_eq!(read_lines(&log).len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_webhook_posts_json() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/hooks/qspec"))
+            .and(header("x-token", "secret"))
+            .and(body_partial_json(serde_json::json!({
+                "schema": "qspec.notification",
+                "event": "anomaly_detected",
+                "title": "Unusual transaction: Gadget Hut",
+                "details": { "event": "anomaly_detected" }
+            })))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let notifier = WebhookNotifier::new(WebhookChannelConfig {
+            url: format!("{}/hooks/qspec", server.uri()),
+            headers: [("x-token".to_string(), "secret".to_string())].into(),
+            timeout_secs: 5,
+        });
+        notifier
+            .send(&Notification::from_event(&anomaly(), Utc::now()))
+            .await
+            .unwrap();
+
+        let failing = WebhookNotifier::new(WebhookChannelConfig {
+            url: format!("{}/missing", server.uri()),
+            headers: Default::default(),
+            timeout_secs: 5,
+        });
+        assert!(failing
+            .send(&Notification::new("test", "t", "b"))
+            .await
+            .is_err());
+    }
+
+    /// Minimal SMTP server that accepts one message and returns its DATA section
+    async fn fake_smtp(listener: TcpListener) -> String {
+        let (socket, _) = listener.accept().await.unwrap();
+        let (read, mut write) = socket.into_split();
+        let mut lines = BufReader::new(read).lines();
+        write.write_all(b"220 localhost ESMTP\r\n").await.unwrap();
+
+        let mut data = String::new();
+        let mut in_data = false;
+        while let Some(line) = lines.next_line().await.unwrap() {
+            if in_data {
+                if line == "." {
+                    in_data = false;
+                    write.write_all(b"250 OK queued\r\n").await.unwrap();
+                } else {
+                    data.push_str(&line);
+                    data.push('\n');
+                }
+                continue;
+            }
+            let command = line.to_ascii_uppercase();
+            let reply: &[u8] = if command.starts_with("EHLO") {
+                b"250-localhost\r\n250 8BITMIME\r\n"
+            } else if command.starts_with("DATA") {
+                in_data = true;
+                b"354 End data with <CR><LF>.<CR><LF>\r\n"
+            } else if command.starts_with("QUIT") {
+                write.write_all(b"221 Bye\r\n").await.unwrap();
+                break;
+            } else {
+                b"250 OK\r\n"
+            };
+            write.write_all(reply).await.unwrap();
+        }
+        data
+    }
+
+    #[tokio::test]
+    async fn test_email_via_local_smtp() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(fake_smtp(listener));
+
+        let notifier = EmailNotifier::new(EmailChannelConfig {
+            smtp_host: "127.0.0.1".to_string(),
+            smtp_port: port,
+            security: SmtpSecurity::None,
+            username: None,
+            password_env: None,
+            from: "QSpec <qspec@example.com>".to_string(),
+            to: vec!["family@example.com".to_string()],
+        });
+        notifier
+            .send(&Notification::from_event(&anomaly(), Utc::now()))
+            .await
+            .unwrap();
+
+        let data = server.await.unwrap();
+        assert!(data.contains("Subject: Unusual transaction: Gadget Hut"));
+        assert!(data.contains("To: family@example.com"));
+        assert!(data.contains("2026-09-14 940.00 in Uncategorized"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_command_hook() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("out.txt");
+        let notifier = CommandNotifier::new(CommandChannelConfig {
+            program: "sh".to_string(),
+            args: vec![
+                "-c".to_string(),
+                format!(
+                    "printf '%s|%s' \"$QSPEC_EVENT\" '{{title}}' > {}",
+                    out.display()
+                ),
+            ],
+        });
+        notifier
+            .send(&Notification::new(
+                "budget_exceeded",
+                "Dining over budget",
+                "",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&out).unwrap(),
+            "budget_exceeded|Dining over budget"
+        );
+
+        let failing = CommandNotifier::new(CommandChannelConfig {
+            program: "false".to_string(),
+            args: Vec::new(),
+        });
+        assert!(failing
+            .send(&Notification::new("test", "t", "b"))
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,1029 @@
+use crate::analysis::{CategoryAnalysis, MonthlyReport, SpendingTrend};
+use crate::backup::BackupEntry;
+use crate::candidates::Candidate;
+use crate::categorize::{CategorizationReport, ReviewItem};
+use crate::classifier::TrainingReport;
+use crate::config::ConfigEntry;
+use crate::data::{AccountType, FinancialData, Transaction};
+use crate::plugin::Finding;
+use crate::scheduler::{JobRun, JobStatus};
+use crate::utils::format_currency;
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
+use uuid::Uuid;
+
+/// Version of the JSON envelope and report payloads; bumped on breaking changes
+pub const OUTPUT_SCHEMA_VERSION: u32 = 1;
+
+/// How report commands print their results
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Aligned table for people
+    #[default]
+    Table,
+    /// Versioned JSON envelope
+    Json,
+    /// Comma-separated values with raw amounts
+    Csv,
+    /// GitHub-flavored Markdown table
+    Markdown,
+}
+
+/// JSON envelope wrapped around every report
+#[derive(Debug, Serialize)]
+pub struct Envelope<'a, T: Serialize + ?Sized> {
+    pub schema: &'static str,
+    pub version: u32,
+    pub generated_at: DateTime<Utc>,
+    pub currency: &'a str,
+    pub data: &'a T,
+}
+
+/// A value in a report table
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cell {
+    Text(String),
+    Money(Decimal),
+    Count(usize),
+    Percent(Decimal),
+}
+
+/// A titled table of cells, the common shape behind table, CSV and Markdown output
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table {
+    /// Machine-readable name, used as the `section` column when CSV holds several tables
+    pub key: &'static str,
+    pub title: String,
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<Cell>>,
+}
+
+/// A report that can be printed in every output format
+pub trait Renderable: Serialize {
+    /// Schema name in the JSON envelope
+    const SCHEMA: &'static str;
+
+    /// Tables shown for table, CSV and Markdown output
+    fn tables(&self) -> Vec<Table>;
+}
+
+/// Account balance row of `qspec accounts`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccountBalance {
+    pub id: Uuid,
+    pub name: String,
+    pub account_type: String,
+    pub institution: Option<String>,
+    pub currency: String,
+    pub balance: Decimal,
+}
+
+/// Flagged transaction row of `qspec analyze anomalies`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Anomaly {
+    pub id: Uuid,
+    pub date: NaiveDate,
+    pub account: String,
+    pub payee: String,
+    pub category: Option<String>,
+    pub amount: Decimal,
+}
+
+impl Cell {
+    fn is_numeric(&self) -> bool {
+        !matches!(self, Cell::Text(_))
+    }
+
+    /// Human-readable form, with currency symbols and rounded percentages
+    fn display(&self, currency: &str) -> String {
+        match self {
+            Cell::Text(text) => text.clone(),
+            Cell::Money(amount) => format_currency(*amount, currency),
+            Cell::Count(count) => count.to_string(),
+            Cell::Percent(percent) => format!("{:.1}%", percent),
+        }
+    }
+
+    /// Machine-readable form without symbols
+    fn raw(&self) -> String {
+        match self {
+            Cell::Text(text) => text.clone(),
+            Cell::Money(amount) => amount.to_string(),
+            Cell::Count(count) => count.to_string(),
+            Cell::Percent(percent) => percent.round_dp(2).to_string(),
+        }
+    }
+}
+
+impl Table {
+    /// Create an empty table
+    pub fn new(key: &'static str, title: impl Into<String>, headers: &[&str]) -> Self {
+        Self {
+            key,
+            title: title.into(),
+            headers: headers.iter().map(|h| h.to_string()).collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    /// Append a row
+    pub fn row(mut self, cells: Vec<Cell>) -> Self {
+        self.rows.push(cells);
+        self
+    }
+
+    fn numeric_columns(&self) -> Vec<bool> {
+        (0..self.headers.len())
+            .map(|i| {
+                self.rows
+                    .first()
+                    .and_then(|row| row.get(i))
+                    .is_some_and(Cell::is_numeric)
+            })
+            .collect()
+    }
+}
+
+/// Print a report in the requested format
+pub fn render<R, W>(report: &R, format: OutputFormat, currency: &str, out: &mut W) -> Result<()>
+where
+    R: Renderable + ?Sized,
+    W: Write,
+{
+    match format {
+        OutputFormat::Json => {
+            let envelope = Envelope {
+                schema: R::SCHEMA,
+                version: OUTPUT_SCHEMA_VERSION,
+                generated_at: Utc::now(),
+                currency,
+                data: report,
+            };
+            serde_json::to_writer_pretty(&mut *out, &envelope)
+                .context("Failed to serialize report")?;
+            writeln!(out)?;
+        }
+        OutputFormat::Table => write_text_tables(&report.tables(), currency, out)?,
+        OutputFormat::Csv => write_csv(&report.tables(), out)?,
+        OutputFormat::Markdown => write_markdown(&report.tables(), currency, out)?,
+    }
+    Ok(())
+}
+
+/// Currency used to display totals that span accounts
+pub fn display_currency(data: &FinancialData) -> &str {
+    data.accounts
+        .first()
+        .map(|a| a.currency.as_str())
+        .unwrap_or("USD")
+}
+
+fn write_text_tables<W: Write>(tables: &[Table], currency: &str, out: &mut W) -> Result<()> {
+    for (index, table) in tables.iter().enumerate() {
+        if index > 0 {
+            writeln!(out)?;
+        }
+        writeln!(out, "{}", table.title)?;
+
+        let rows: Vec<Vec<String>> = table
+            .rows
+            .iter()
+            .map(|row| row.iter().map(|cell| cell.display(currency)).collect())
+            .collect();
+        let numeric = table.numeric_columns();
+        let widths: Vec<usize> = table
+            .headers
+            .iter()
+            .enumerate()
+            .map(|(i, header)| {
+                rows.iter()
+                    .filter_map(|row| row.get(i))
+                    .map(|cell| cell.chars().count())
+                    .chain(std::iter::once(header.chars().count()))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        let line = |cells: &[String]| -> String {
+            cells
+                .iter()
+                .enumerate()
+                .map(|(i, cell)| {
+                    if numeric[i] {
+                        format!("{:>width$}", cell, width = widths[i])
+                    } else {
+                        format!("{:<width$}", cell, width = widths[i])
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        };
+
+        writeln!(out, "{}", line(&table.headers))?;
+        let rule: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+        writeln!(out, "{}", rule.join("  "))?;
+        for row in &rows {
+            writeln!(out, "{}", line(row))?;
+        }
+    }
+    Ok(())
+}
+
+fn write_csv<W: Write>(tables: &[Table], out: &mut W) -> Result<()> {
+    let sectioned = tables.len() > 1;
+    let mut writer = csv::Writer::from_writer(&mut *out);
+
+    if let Some(first) = tables.first() {
+        let mut headers: Vec<String> = first
+            .headers
+            .iter()
+            .map(|h| h.to_lowercase().replace(' ', "_"))
+            .collect();
+        if sectioned {
+            headers.insert(0, "section".to_string());
+        }
+        writer.write_record(&headers)?;
+    }
+
+    for table in tables {
+        for row in &table.rows {
+            let mut record: Vec<String> = row.iter().map(Cell::raw).collect();
+            if sectioned {
+                record.insert(0, table.key.to_string());
+            }
+            writer.write_record(&record)?;
+        }
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+fn write_markdown<W: Write>(tables: &[Table], currency: &str, out: &mut W) -> Result<()> {
+    for (index, table) in tables.iter().enumerate() {
+        if index > 0 {
+            writeln!(out)?;
+        }
+        writeln!(out, "### {}", table.title)?;
+        writeln!(out)?;
+
+        let escape = |text: &str| text.replace('|', "\\|");
+        let headers: Vec<String> = table.headers.iter().map(|h| escape(h)).collect();
+        writeln!(out, "| {} |", headers.join(" | "))?;
+
+        let alignments: Vec<&str> = table
+            .numeric_columns()
+            .into_iter()
+            .map(|numeric| if numeric { "---:" } else { "---" })
+            .collect();
+        writeln!(out, "| {} |", alignments.join(" | "))?;
+
+        for row in &table.rows {
+            let cells: Vec<String> = row
+                .iter()
+                .map(|cell| escape(&cell.display(currency)))
+                .collect();
+            writeln!(out, "| {} |", cells.join(" | "))?;
+        }
+    }
+    Ok(())
+}
+
+impl Renderable for MonthlyReport {
+    const SCHEMA: &'static str = "qspec.report.monthly";
+
+    fn tables(&self) -> Vec<Table> {
+        let summary = Table::new(
+            "summary",
+            format!("Monthly report {}-{:02}", self.year, self.month),
+            &["Item", "Value"],
+        )
+        .row(vec![
+            Cell::Text("Income".to_string()),
+            Cell::Money(self.total_income),
+        ])
+        .row(vec![
+            Cell::Text("Expenses".to_string()),
+            Cell::Money(self.total_expenses),
+        ])
+        .row(vec![
+            Cell::Text("Net".to_string()),
+            Cell::Money(self.net_income),
+        ])
+        .row(vec![
+            Cell::Text("Transactions".to_string()),
+            Cell::Count(self.transaction_count),
+        ]);
+
+        let mut breakdown: Vec<_> = self.category_breakdown.iter().collect();
+        breakdown.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+
+        let mut categories = Table::new("categories", "Categories", &["Item", "Value"]);
+        for (category, amount) in breakdown {
+            categories = categories.row(vec![Cell::Text(category.clone()), Cell::Money(*amount)]);
+        }
+
+        vec![summary, categories]
+    }
+}
+
+impl Renderable for Vec<CategoryAnalysis> {
+    const SCHEMA: &'static str = "qspec.analysis.categories";
+
+    fn tables(&self) -> Vec<Table> {
+        let mut table = Table::new(
+            "categories",
+            "Spending by category",
+            &["Category", "Total", "Count", "Average", "Share"],
+        );
+        for category in self {
+            table = table.row(vec![
+                Cell::Text(category.category.clone()),
+                Cell::Money(category.total_amount),
+                Cell::Count(category.transaction_count),
+                Cell::Money(category.average_amount),
+                Cell::Percent(category.percentage_of_total),
+            ]);
+        }
+        vec![table]
+    }
+}
+
+impl Renderable for Vec<SpendingTrend> {
+    const SCHEMA: &'static str = "qspec.analysis.trends";
+
+    fn tables(&self) -> Vec<Table> {
+        let months: Vec<&str> = self
+            .first()
+            .map(|t| t.monthly_amounts.iter().map(|(m, _)| m.as_str()).collect())
+            .unwrap_or_default();
+
+        let mut headers = vec!["Category", "Trend"];
+        headers.extend(months.iter().copied());
+        headers.push("Monthly avg");
+
+        let mut table = Table::new("trends", "Spending trends", &headers);
+        for trend in self {
+            let mut row = vec![
+                Cell::Text(trend.category.clone()),
+                Cell::Text(format!("{:?}", trend.trend_direction)),
+            ];
+            row.extend(trend.monthly_amounts.iter().map(|(_, a)| Cell::Money(*a)));
+            row.push(Cell::Money(trend.average_monthly));
+            table = table.row(row);
+        }
+        vec![table]
+    }
+}
+
+impl Renderable for Vec<Anomaly> {
+    const SCHEMA: &'static str = "qspec.analysis.anomalies";
+
+    fn tables(&self) -> Vec<Table> {
+        let mut table = Table::new(
+            "anomalies",
+            "Anomalies",
+            &["Date", "Account", "Payee", "Category", "Amount"],
+        );
+        for anomaly in self {
+            table = table.row(vec![
+                Cell::Text(anomaly.date.to_string()),
+                Cell::Text(anomaly.account.clone()),
+                Cell::Text(anomaly.payee.clone()),
+                Cell::Text(
+                    anomaly
+                        .category
+                        .clone()
+                        .unwrap_or_else(|| "Uncategorized".to_string()),
+                ),
+                Cell::Money(anomaly.amount),
+            ]);
+        }
+        vec![table]
+    }
+}
+
+impl Renderable for Vec<AccountBalance> {
+    const SCHEMA: &'static str = "qspec.accounts";
+
+    fn tables(&self) -> Vec<Table> {
+        let mut table = Table::new(
+            "accounts",
+            "Accounts",
+            &["Account", "Type", "Institution", "Currency", "Balance"],
+        );
+        for account in self {
+            table = table.row(vec![
+                Cell::Text(account.name.clone()),
+                Cell::Text(account.account_type.clone()),
+                Cell::Text(account.institution.clone().unwrap_or_default()),
+                Cell::Text(account.currency.clone()),
+                Cell::Money(account.balance),
+            ]);
+        }
+        vec![table]
+    }
+}
+
+impl AccountBalance {
+    /// Balance rows for every account in the books
+    pub fn from_data(data: &FinancialData) -> Vec<Self> {
+        data.accounts
+            .iter()
+            .map(|account| Self {
+                id: account.id,
+                name: account.name.clone(),
+                account_type: match &account.account_type {
+                    AccountType::Other(name) => name.clone(),
+                    other => format!("{:?}", other),
+                },
+                institution: account.institution.clone(),
+                currency: account.currency.clone(),
+                balance: account.balance,
+            })
+            .collect()
+    }
+}
+
+impl Anomaly {
+    /// Rows for flagged transactions, resolving account names
+    pub fn from_transactions(data: &FinancialData, transactions: &[&Transaction]) -> Vec<Self> {
+        let accounts: HashMap<Uuid, &str> = data
+            .accounts
+            .iter()
+            .map(|a| (a.id, a.name.as_str()))
+            .collect();
+
+        transactions
+            .iter()
+            .map(|t| Self {
+                id: t.id,
+                date: t.date.date_naive(),
+                account: accounts
+                    .get(&t.account_id)
+                    .copied()
+                    .unwrap_or_default()
+                    .to_string(),
+                payee: t.payee.clone().unwrap_or_else(|| t.description.clone()),
+                category: t.category.clone(),
+                amount: t.amount,
+            })
+            .collect()
+    }
+}
+
+impl Renderable for Vec<Finding> {
+    const SCHEMA: &'static str = "qspec.analysis.findings";
+
+    fn tables(&self) -> Vec<Table> {
+        let mut table = Table::new(
+            "findings",
+            "Plugin findings",
+            &["Plugin", "Finding", "Detail", "Transactions"],
+        );
+        for finding in self {
+            table = table.row(vec![
+                Cell::Text(finding.plugin.clone()),
+                Cell::Text(finding.title.clone()),
+                Cell::Text(finding.detail.clone()),
+                Cell::Count(finding.transaction_ids.len()),
+            ]);
+        }
+        vec![table]
+    }
+}
+
+impl Renderable for Vec<ConfigEntry> {
+    const SCHEMA: &'static str = "qspec.config";
+
+    fn tables(&self) -> Vec<Table> {
+        let mut table = Table::new(
+            "settings",
+            "Effective configuration",
+            &["Key", "Value", "Source"],
+        );
+        for entry in self {
+            table = table.row(vec![
+                Cell::Text(entry.key.clone()),
+                Cell::Text(entry.value.clone()),
+                Cell::Text(entry.source.to_string()),
+            ]);
+        }
+        vec![table]
+    }
+}
+
+/// Timestamps in job tables, always shown in UTC
+fn job_time(time: Option<DateTime<Utc>>) -> String {
+    time.map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+impl Renderable for Vec<JobStatus> {
+    const SCHEMA: &'static str = "qspec.jobs";
+
+    fn tables(&self) -> Vec<Table> {
+        let mut table = Table::new(
+            "jobs",
+            "Scheduled jobs (UTC)",
+            &[
+                "Job", "Kind", "Schedule", "Enabled", "Last run", "Result", "Next run", "Failures",
+            ],
+        );
+        for job in self {
+            let result = match job.last_success {
+                Some(true) => "ok",
+                Some(false) => "failed",
+                None => "-",
+            };
+            table = table.row(vec![
+                Cell::Text(job.name.clone()),
+                Cell::Text(job.kind.as_str().to_string()),
+                Cell::Text(job.schedule.clone()),
+                Cell::Text(if job.enabled { "yes" } else { "no" }.to_string()),
+                Cell::Text(job_time(job.last_run)),
+                Cell::Text(result.to_string()),
+                Cell::Text(job_time(job.next_run)),
+                Cell::Count(job.recent_failures),
+            ]);
+        }
+        vec![table]
+    }
+}
+
+impl Renderable for Vec<BackupEntry> {
+    const SCHEMA: &'static str = "qspec.backup.list";
+
+    fn tables(&self) -> Vec<Table> {
+        let mut table = Table::new(
+            "backups",
+            "Backups, newest first (UTC)",
+            &["Created", "File"],
+        );
+        for backup in self {
+            table = table.row(vec![
+                Cell::Text(job_time(Some(backup.created_at))),
+                Cell::Text(backup.path.display().to_string()),
+            ]);
+        }
+        vec![table]
+    }
+}
+
+impl Renderable for Vec<JobRun> {
+    const SCHEMA: &'static str = "qspec.jobs.history";
+
+    fn tables(&self) -> Vec<Table> {
+        let mut table = Table::new(
+            "runs",
+            "Job history (UTC)",
+            &["Started", "Job", "Result", "Seconds", "Message"],
+        );
+        for run in self {
+            let seconds = (run.finished_at - run.started_at).num_seconds().max(0);
+            table = table.row(vec![
+                Cell::Text(job_time(Some(run.started_at))),
+                Cell::Text(run.job.clone()),
+                Cell::Text(if run.success { "ok" } else { "failed" }.to_string()),
+                Cell::Count(seconds as usize),
+                Cell::Text(run.message.clone()),
+            ]);
+        }
+        vec![table]
+    }
+}
+
+impl Renderable for Vec<ReviewItem> {
+    const SCHEMA: &'static str = "qspec.categorize.review";
+
+    fn tables(&self) -> Vec<Table> {
+        vec![review_table("suggestions", "Category suggestions", self)]
+    }
+}
+
+impl Renderable for Vec<Candidate> {
+    const SCHEMA: &'static str = "qspec.extract.review";
+
+    fn tables(&self) -> Vec<Table> {
+        vec![candidate_table("candidates", "Proposed transactions", self)]
+    }
+}
+
+#[cfg(feature = "ai-analysis")]
+impl Renderable for crate::ai::ExtractionReport {
+    const SCHEMA: &'static str = "qspec.extract.text";
+
+    fn tables(&self) -> Vec<Table> {
+        let title = format!(
+            "{}: {} queued for review, {} rejected",
+            self.source,
+            self.queued.len(),
+            self.rejected.len()
+        );
+        let mut tables = vec![candidate_table("candidates", title, &self.queued)];
+        if !self.rejected.is_empty() {
+            let mut rejected = Table::new(
+                "rejected",
+                "Rejected after validation",
+                &["Date", "Payee", "Total", "Reason"],
+            );
+            for candidate in &self.rejected {
+                rejected = rejected.row(vec![
+                    Cell::Text(candidate.date.to_string()),
+                    Cell::Text(candidate.payee.clone()),
+                    Cell::Money(candidate.total),
+                    Cell::Text(candidate.reason.clone()),
+                ]);
+            }
+            tables.push(rejected);
+        }
+        tables
+    }
+}
+
+impl Renderable for CategorizationReport {
+    const SCHEMA: &'static str = "qspec.categorize.run";
+
+    fn tables(&self) -> Vec<Table> {
+        let title = format!(
+            "{}: {} considered, {} applied, {} queued for review",
+            self.categorizer, self.considered, self.auto_applied, self.queued
+        );
+        vec![review_table("suggestions", title, &self.items)]
+    }
+}
+
+#[cfg(feature = "ai-analysis")]
+impl Renderable for crate::ai::Answer {
+    const SCHEMA: &'static str = "qspec.ask";
+
+    fn tables(&self) -> Vec<Table> {
+        use crate::query::Aggregate;
+
+        let mut tables = Vec::new();
+        if let Some(summary) = &self.summary {
+            tables.push(
+                Table::new("answer", self.question.clone(), &["Answer"])
+                    .row(vec![Cell::Text(summary.clone())]),
+            );
+        }
+
+        let mut queries = Table::new("queries", "Generated queries", &["Label", "Query"]);
+        for query in &self.plan.queries {
+            let json = serde_json::to_string(query).unwrap_or_default();
+            queries = queries.row(vec![Cell::Text(query.label.clone()), Cell::Text(json)]);
+        }
+        tables.push(queries);
+
+        for result in &self.results {
+            let query = &result.query;
+            let mut headers: Vec<String> =
+                query.group_by.iter().map(|g| format!("{:?}", g)).collect();
+            let aggregates = query.aggregates();
+            headers.extend(aggregates.iter().map(|a| format!("{:?}", a)));
+            let headers: Vec<&str> = headers.iter().map(String::as_str).collect();
+
+            let mut title = if query.label.is_empty() {
+                "Result".to_string()
+            } else {
+                query.label.clone()
+            };
+            for warning in &result.warnings {
+                title.push_str(&format!(" ({})", warning));
+            }
+            let mut table = Table::new("result", title, &headers);
+            for row in &result.rows {
+                let mut cells: Vec<Cell> = row.group.iter().cloned().map(Cell::Text).collect();
+                for aggregate in &aggregates {
+                    cells.push(match aggregate {
+                        Aggregate::Count => Cell::Count(row.count.unwrap_or_default()),
+                        Aggregate::Sum => Cell::Money(row.sum.unwrap_or_default()),
+                        Aggregate::Average => Cell::Money(row.average.unwrap_or_default()),
+                        Aggregate::Min => Cell::Money(row.min.unwrap_or_default()),
+                        Aggregate::Max => Cell::Money(row.max.unwrap_or_default()),
+                    });
+                }
+                table = table.row(cells);
+            }
+            tables.push(table);
+        }
+        tables
+    }
+}
+
+#[cfg(feature = "ai-analysis")]
+impl Renderable for crate::ai::Narrative {
+    const SCHEMA: &'static str = "qspec.report.narrative";
+
+    fn tables(&self) -> Vec<Table> {
+        let facts = &self.facts;
+        let summary = Table::new(
+            "summary",
+            format!("Monthly summary {}-{:02}", facts.year, facts.month),
+            &["Summary"],
+        )
+        .row(vec![Cell::Text(self.summary.clone())]);
+
+        let mut categories = Table::new(
+            "categories",
+            format!("Against the {}-month average", facts.trailing_months),
+            &["Category", "Spent", "Average", "Change", "Anomalies"],
+        );
+        for category in &facts.categories {
+            categories = categories.row(vec![
+                Cell::Text(category.category.clone()),
+                Cell::Money(category.spent),
+                Cell::Money(category.trailing_average),
+                category
+                    .change_percent
+                    .map(Cell::Percent)
+                    .unwrap_or_else(|| Cell::Text("new".to_string())),
+                Cell::Count(category.anomalies),
+            ]);
+        }
+
+        let mut anomalies = Table::new(
+            "anomalies",
+            "Unusual charges",
+            &["Date", "Payee", "Category", "Amount"],
+        );
+        for anomaly in &facts.anomalies {
+            anomalies = anomalies.row(vec![
+                Cell::Text(anomaly.date.to_string()),
+                Cell::Text(anomaly.payee.clone()),
+                Cell::Text(anomaly.category.clone().unwrap_or_default()),
+                Cell::Money(anomaly.amount),
+            ]);
+        }
+
+        vec![summary, categories, anomalies]
+    }
+}
+
+#[cfg(feature = "ai-analysis")]
+impl Renderable for crate::ai::UsageReport {
+    const SCHEMA: &'static str = "qspec.ai.usage";
+
+    fn tables(&self) -> Vec<Table> {
+        let headers = ["Period", "Requests", "Cached", "Tokens", "Cost"];
+        let row = |label: String, spend: &crate::ai::Spend| {
+            vec![
+                Cell::Text(label),
+                Cell::Count(spend.requests as usize),
+                Cell::Count(spend.cached as usize),
+                Cell::Count(spend.total_tokens() as usize),
+                Cell::Money(spend.cost),
+            ]
+        };
+
+        let budget = &self.budget;
+        let limit = |tokens: Option<u64>, cost: Option<Decimal>| {
+            let tokens = tokens.map(|t| format!("{} tokens", t));
+            let cost = cost.map(|c| format!("cost {}", c));
+            match (tokens, cost) {
+                (None, None) => String::new(),
+                (tokens, cost) => format!(
+                    " (limit {})",
+                    tokens
+                        .into_iter()
+                        .chain(cost)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            }
+        };
+        let totals = Table::new("totals", "AI usage", &headers)
+            .row(row(
+                format!("Today{}", limit(budget.daily_tokens, budget.daily_cost)),
+                &self.today,
+            ))
+            .row(row(
+                format!(
+                    "This month{}",
+                    limit(budget.monthly_tokens, budget.monthly_cost)
+                ),
+                &self.month,
+            ));
+
+        let mut days = Table::new("days", "By day", &headers);
+        for day in &self.days {
+            days = days.row(row(day.day.to_string(), &day.spend));
+        }
+        vec![totals, days]
+    }
+}
+
+#[cfg(feature = "ai-analysis")]
+impl Renderable for crate::ai::AgentRun {
+    const SCHEMA: &'static str = "qspec.agent";
+
+    fn tables(&self) -> Vec<Table> {
+        let answer = self
+            .answer
+            .clone()
+            .or_else(|| self.stopped.as_ref().map(|s| format!("No answer: {}", s)))
+            .unwrap_or_default();
+        let summary =
+            Table::new("answer", self.request.clone(), &["Answer"]).row(vec![Cell::Text(answer)]);
+
+        let mut steps = Table::new(
+            "steps",
+            "Trace",
+            &["Step", "Tool", "Arguments", "Outcome", "Reason"],
+        );
+        for step in &self.steps {
+            steps = steps.row(vec![
+                Cell::Count(step.step),
+                Cell::Text(step.tool.clone().unwrap_or_else(|| "-".to_string())),
+                Cell::Text(step.arguments.to_string()),
+                Cell::Text(
+                    serde_json::to_value(step.outcome)
+                        .ok()
+                        .and_then(|v| v.as_str().map(str::to_string))
+                        .unwrap_or_default(),
+                ),
+                Cell::Text(step.reason.clone().unwrap_or_default()),
+            ]);
+        }
+        vec![summary, steps]
+    }
+}
+
+impl Renderable for TrainingReport {
+    const SCHEMA: &'static str = "qspec.categorize.train";
+
+    fn tables(&self) -> Vec<Table> {
+        let mut table = Table::new("model", "Local categorizer", &["Measure", "Value"])
+            .row(vec![
+                Cell::Text("Training transactions".to_string()),
+                Cell::Count(self.examples),
+            ])
+            .row(vec![
+                Cell::Text("Categories".to_string()),
+                Cell::Count(self.categories),
+            ])
+            .row(vec![
+                Cell::Text("Vocabulary".to_string()),
+                Cell::Count(self.vocabulary),
+            ]);
+        if let Some(evaluation) = &self.evaluation {
+            table = table
+                .row(vec![
+                    Cell::Text("Held out".to_string()),
+                    Cell::Count(evaluation.test_examples),
+                ])
+                .row(vec![
+                    Cell::Text("Held out, no prediction".to_string()),
+                    Cell::Count(evaluation.abstained),
+                ]);
+            if let Some(accuracy) = evaluation.accuracy {
+                table = table.row(vec![
+                    Cell::Text("Held-out accuracy".to_string()),
+                    Cell::Percent(Decimal::from_f64_retain(accuracy * 100.0).unwrap_or_default()),
+                ]);
+            }
+        }
+        vec![table]
+    }
+}
+
+fn candidate_table(key: &'static str, title: impl Into<String>, items: &[Candidate]) -> Table {
+    let mut table = Table::new(
+        key,
+        title,
+        &[
+            "Id", "Date", "Payee", "Amount", "Category", "Items", "Status", "Note",
+        ],
+    );
+    for item in items {
+        let transaction = &item.transaction;
+        let lines: Vec<String> = transaction
+            .splits
+            .iter()
+            .map(|split| {
+                let memo = split.memo.as_deref().unwrap_or("item");
+                match &split.category {
+                    Some(category) => format!("{} {} ({})", memo, split.amount, category),
+                    None => format!("{} {}", memo, split.amount),
+                }
+            })
+            .collect();
+        let note = item
+            .possible_duplicate
+            .map(|id| format!("possible duplicate of {}", &id.to_string()[..8]))
+            .unwrap_or_default();
+        table = table.row(vec![
+            Cell::Text(item.short_id()),
+            Cell::Text(transaction.date.date_naive().to_string()),
+            Cell::Text(item.payee().to_string()),
+            Cell::Money(transaction.amount),
+            Cell::Text(transaction.category.clone().unwrap_or_default()),
+            Cell::Text(lines.join("; ")),
+            Cell::Text(item.status.as_str().to_string()),
+            Cell::Text(note),
+        ]);
+    }
+    table
+}
+
+fn review_table(key: &'static str, title: impl Into<String>, items: &[ReviewItem]) -> Table {
+    let mut table = Table::new(
+        key,
+        title,
+        &[
+            "Id",
+            "Date",
+            "Payee",
+            "Amount",
+            "Category",
+            "Confidence",
+            "Status",
+            "Rationale",
+        ],
+    );
+    for item in items {
+        let confidence =
+            Decimal::from_f64_retain(item.suggestion.confidence * 100.0).unwrap_or_default();
+        table = table.row(vec![
+            Cell::Text(item.short_id()),
+            Cell::Text(item.date.to_string()),
+            Cell::Text(item.payee.clone()),
+            Cell::Money(item.amount),
+            Cell::Text(item.suggestion.category.clone()),
+            Cell::Percent(confidence),
+            Cell::Text(item.status.as_str().to_string()),
+            Cell::Text(item.suggestion.rationale.clone()),
+        ]);
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn report() -> MonthlyReport {
+        MonthlyReport {
+            year: 2026,
+            month: 9,
+            total_income: dec!(1000.00),
+            total_expenses: dec!(62.50),
+            net_income: dec!(937.50),
+            category_breakdown: HashMap::from([
+                ("Groceries".to_string(), dec!(50.00)),
+                ("Coffee | Tea".to_string(), dec!(12.50)),
+            ]),
+            transaction_count: 3,
+        }
+    }
+
+    fn rendered(format: OutputFormat) -> String {
+        let mut out = Vec::new();
+        render(&report(), format, "USD", &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_table_output_is_aligned() {
+        let text = rendered(OutputFormat::Table);
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert_eq!(lines[0], "Monthly report 2026-09");
+        assert_eq!(lines[1], "Item             Value");
+        assert_eq!(lines[3], "Income        $1000.00");
+        assert_eq!(lines[6], "Transactions         3");
+        assert!(text.contains("Groceries     $50.00"));
+    }
+
+    #[test]
+    fn test_json_envelope() {
+        let json: serde_json::Value = serde_json::from_str(&rendered(OutputFormat::Json)).unwrap();
+
+        assert_eq!(json["schema"], "qspec.report.monthly");
+        assert_eq!(json["version"], OUTPUT_SCHEMA_VERSION);
+        assert_eq!(json["currency"], "USD");
+        assert_eq!(json["data"]["total_income"], "1000.00");
+        assert_eq!(json["data"]["category_breakdown"]["Groceries"], "50.00");
+    }
+
+    #[test]
+    fn test_csv_and_markdown_output() {
+        let csv = rendered(OutputFormat::Csv);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "section,item,value");
+        assert_eq!(lines[1], "summary,Income,1000.00");
+        assert_eq!(lines[5], "categories,Groceries,50.00");
+
+        let markdown = rendered(OutputFormat::Markdown);
+        assert!(markdown.contains("| Item | Value |\n| --- | ---: |"));
+        assert!(markdown.contains("| Coffee \\| Tea | $12.50 |"));
+    }
+}
@@ -0,0 +1,209 @@
+use crate::data::FinancialData;
+use crate::events::AgentEvent;
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+use tracing::warn;
+use uuid::Uuid;
+
+/// Extension point for house-specific behaviour
+///
+/// Plugins are registered on the agent (or the CLI) before it starts. Each one
+/// sees every [`AgentEvent`] in order and may contribute findings to
+/// `qspec analyze findings`. A failing plugin is logged and never stops the agent.
+#[async_trait]
+pub trait Plugin: Send + Sync {
+    /// Unique, human-readable name used in logs and findings
+    fn name(&self) -> &str;
+
+    /// React to an agent event
+    async fn on_event(&self, _event: &AgentEvent) -> Result<()> {
+        Ok(())
+    }
+
+    /// Run the plugin's own checks over the books
+    fn analyze(&self, _data: &FinancialData) -> Result<Vec<Finding>> {
+        Ok(Vec::new())
+    }
+}
+
+/// Result of a plugin analysis
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Finding {
+    pub plugin: String,
+    pub title: String,
+    pub detail: String,
+    #[serde(default)]
+    pub transaction_ids: Vec<Uuid>,
+}
+
+/// Plugins loaded into an agent, in registration order
+#[derive(Clone, Default)]
+pub struct PluginRegistry {
+    plugins: Vec<Arc<dyn Plugin>>,
+}
+
+impl Finding {
+    pub fn new(plugin: &str, title: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            plugin: plugin.to_string(),
+            title: title.into(),
+            detail: detail.into(),
+            transaction_ids: Vec::new(),
+        }
+    }
+
+    /// Attach the transactions this finding refers to
+    pub fn with_transactions(mut self, ids: impl IntoIterator<Item = Uuid>) -> Self {
+        self.transaction_ids.extend(ids);
+        self
+    }
+}
+
+impl PluginRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a plugin; later plugins see events after earlier ones
+    pub fn register(&mut self, plugin: impl Plugin + 'static) {
+        self.plugins.push(Arc::new(plugin));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+
+    /// Names of the registered plugins
+    pub fn names(&self) -> Vec<&str> {
+        self.plugins.iter().map(|p| p.name()).collect()
+    }
+
+    /// Deliver an event to every plugin
+    pub async fn dispatch(&self, event: &AgentEvent) {
+        for plugin in &self.plugins {
+            if let Err(e) = plugin.on_event(event).await {
+                warn!(
+                    "Plugin {} failed to handle {}: {:#}",
+                    plugin.name(),
+                    event.name(),
+                    e
+                );
+            }
+        }
+    }
+
+    /// Collect findings from every plugin, skipping plugins whose analysis fails
+    pub fn analyze(&self, data: &FinancialData) -> Vec<Finding> {
+        self.plugins
+            .iter()
+            .flat_map(|plugin| match plugin.analyze(data) {
+                Ok(findings) => findings,
+                Err(e) => {
+                    warn!("Plugin {} analysis failed: {:#}", plugin.name(), e);
+                    Vec::new()
+                }
+            })
+            .collect()
+    }
+}
+
+impl fmt::Debug for PluginRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Transaction, TransactionType};
+    use anyhow::bail;
+    use chrono::Utc;
+    use rust_decimal_macros::dec;
+    use std::path::PathBuf;
+    use std::sync::Mutex;
+
+    /// Flags round-number transactions and remembers which events it saw
+    #[derive(Default)]
+    struct RoundNumbers {
+        seen: Arc<Mutex<Vec<&'static str>>>,
+    }
+
+    #[async_trait]
+    impl Plugin for RoundNumbers {
+        fn name(&self) -> &str {
+            "round-numbers"
+        }
+
+        async fn on_event(&self, event: &AgentEvent) -> Result<()> {
+            self.seen.lock().unwrap().push(event.name());
+            Ok(())
+        }
+
+        fn analyze(&self, data: &FinancialData) -> Result<Vec<Finding>> {
+            let round: Vec<Uuid> = data
+                .transactions
+                .iter()
+                .filter(|t| t.amount.fract().is_zero() && t.amount >= dec!(100))
+                .map(|t| t.id)
+                .collect();
+            Ok(vec![Finding::new(
+                self.name(),
+                "Round-number payments",
+                format!("{} payments of whole hundreds", round.len()),
+            )
+            .with_transactions(round)])
+        }
+    }
+
+    struct Broken;
+
+    #[async_trait]
+    impl Plugin for Broken {
+        fn name(&self) -> &str {
+            "broken"
+        }
+
+        async fn on_event(&self, _event: &AgentEvent) -> Result<()> {
+            bail!("always fails")
+        }
+
+        fn analyze(&self, _data: &FinancialData) -> Result<Vec<Finding>> {
+            bail!("always fails")
+        }
+    }
+
+    #[tokio::test]
+    async fn test_registry_dispatches_and_collects_findings() {
+        let plugin = RoundNumbers::default();
+        let seen = plugin.seen.clone();
+        let mut registry = PluginRegistry::new();
+        registry.register(Broken);
+        registry.register(plugin);
+        assert_eq!(registry.names(), vec!["broken", "round-numbers"]);
+
+        registry
+            .dispatch(&AgentEvent::ImportFailed {
+                path: PathBuf::from("x.qif"),
+                error: "bad date".to_string(),
+            })
+            .await;
+        assert_eq!(*seen.lock().unwrap(), vec!["import_failed"]);
+
+        let mut data = FinancialData::new();
+        data.add_transaction(Transaction::new(
+            Uuid::new_v4(),
+            Utc::now(),
+            dec!(500),
+            "Cash".to_string(),
+            TransactionType::Debit,
+        ));
+        let findings = registry.analyze(&data);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].plugin, "round-numbers");
+        assert_eq!(findings[0].transaction_ids.len(), 1);
+    }
+}
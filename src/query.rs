@@ -0,0 +1,538 @@
+use crate::data::{FinancialData, Transaction, TransactionType};
+use crate::search::{SearchEngine, SearchQuery};
+use anyhow::{bail, Context, Result};
+use chrono::{Datelike, NaiveDate};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+/// Most queries one question may expand into
+pub const MAX_QUERIES: usize = 6;
+
+/// Most result rows one query may return
+pub const MAX_ROWS: usize = 200;
+
+/// A read-only aggregate query over transactions
+///
+/// This is the whole surface a model can reach when answering questions: it
+/// filters, groups and aggregates, and cannot change or export anything.
+/// Unknown fields are rejected so a malformed plan fails instead of being
+/// half-applied.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Query {
+    /// Short name for the result, e.g. `Q2 2026`
+    pub label: String,
+    /// First day included
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<NaiveDate>,
+    /// Last day included
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<NaiveDate>,
+    pub direction: Direction,
+    /// Category names; a parent also matches its `Parent:Child` subcategories
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub categories: Vec<String>,
+    /// Case-insensitive substrings of the payee or description
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub payees: Vec<String>,
+    /// Account names
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub accounts: Vec<String>,
+    /// Words in the style of `qspec search`, matched against description, payee, memo and category
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_amount: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_amount: Option<Decimal>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub group_by: Vec<GroupBy>,
+    /// Defaults to sum and count
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub aggregates: Vec<Aggregate>,
+    /// Keep only the first rows; grouped results are ordered by total, largest first
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+}
+
+/// Which way the money moved
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    /// Debits and fees
+    Spending,
+    /// Credits, interest and dividends
+    Income,
+    #[default]
+    Any,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GroupBy {
+    Category,
+    Payee,
+    Account,
+    Month,
+    Quarter,
+    Year,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Aggregate {
+    Sum,
+    Count,
+    Average,
+    Min,
+    Max,
+}
+
+/// The queries answering one question, e.g. this year and last year
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct QueryPlan {
+    pub queries: Vec<Query>,
+}
+
+/// One row of a query result; aggregates not asked for are left out
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ResultRow {
+    /// Values of the `group_by` fields, in order; empty for ungrouped queries
+    pub group: Vec<String>,
+    pub count: Option<usize>,
+    pub sum: Option<Decimal>,
+    pub average: Option<Decimal>,
+    pub min: Option<Decimal>,
+    pub max: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QueryResult {
+    pub query: Query,
+    pub rows: Vec<ResultRow>,
+    /// Filters naming categories or accounts that don't exist
+    pub warnings: Vec<String>,
+}
+
+/// Runs queries against in-memory financial data
+pub struct QueryEngine;
+
+impl Query {
+    /// Aggregates to compute, with the default applied
+    pub fn aggregates(&self) -> Vec<Aggregate> {
+        if self.aggregates.is_empty() {
+            vec![Aggregate::Sum, Aggregate::Count]
+        } else {
+            self.aggregates.clone()
+        }
+    }
+
+    /// Check limits and ranges before running
+    pub fn validate(&self) -> Result<()> {
+        if let (Some(from), Some(to)) = (self.from, self.to) {
+            if from > to {
+                bail!("'{}': from {} is after to {}", self.label, from, to);
+            }
+        }
+        if let (Some(min), Some(max)) = (self.min_amount, self.max_amount) {
+            if min > max {
+                bail!("'{}': min_amount is above max_amount", self.label);
+            }
+        }
+        if self
+            .limit
+            .is_some_and(|limit| limit == 0 || limit > MAX_ROWS)
+        {
+            bail!("'{}': limit must be between 1 and {}", self.label, MAX_ROWS);
+        }
+        let mut seen = HashSet::new();
+        if !self.group_by.iter().all(|g| seen.insert(*g)) {
+            bail!("'{}': group_by lists a field twice", self.label);
+        }
+        if let Some(text) = &self.text {
+            SearchQuery::parse(text).with_context(|| format!("'{}': invalid text", self.label))?;
+        }
+        Ok(())
+    }
+
+    fn matches(&self, transaction: &Transaction) -> bool {
+        let date = transaction.date.date_naive();
+        let amount = transaction.amount.abs();
+        self.from.is_none_or(|from| date >= from)
+            && self.to.is_none_or(|to| date <= to)
+            && self.min_amount.is_none_or(|min| amount >= min)
+            && self.max_amount.is_none_or(|max| amount <= max)
+            && self.direction.matches(&transaction.transaction_type)
+            && (self.categories.is_empty()
+                || transaction
+                    .category
+                    .as_deref()
+                    .is_some_and(|c| self.categories.iter().any(|wanted| in_category(c, wanted))))
+            && (self.payees.is_empty()
+                || self.payees.iter().any(|wanted| {
+                    let wanted = wanted.to_lowercase();
+                    transaction
+                        .payee
+                        .as_deref()
+                        .is_some_and(|p| p.to_lowercase().contains(&wanted))
+                        || transaction.description.to_lowercase().contains(&wanted)
+                }))
+    }
+}
+
+impl Direction {
+    fn matches(&self, kind: &TransactionType) -> bool {
+        match self {
+            Direction::Spending => matches!(kind, TransactionType::Debit | TransactionType::Fee),
+            Direction::Income => matches!(
+                kind,
+                TransactionType::Credit | TransactionType::Interest | TransactionType::Dividend
+            ),
+            Direction::Any => true,
+        }
+    }
+}
+
+impl QueryPlan {
+    pub fn validate(&self) -> Result<()> {
+        if self.queries.is_empty() {
+            bail!("The plan has no queries");
+        }
+        if self.queries.len() > MAX_QUERIES {
+            bail!(
+                "The plan has {} queries; at most {} are allowed",
+                self.queries.len(),
+                MAX_QUERIES
+            );
+        }
+        self.queries.iter().try_for_each(Query::validate)
+    }
+}
+
+impl QueryEngine {
+    /// Run one query; amounts are absolute, so spending and income both sum positive
+    pub fn run(data: &FinancialData, query: &Query) -> Result<QueryResult> {
+        query.validate()?;
+
+        let mut matching: Vec<&Transaction> = data
+            .transactions
+            .iter()
+            .filter(|t| query.matches(t))
+            .collect();
+        if let Some(text) = &query.text {
+            let search = SearchQuery::parse(text)?;
+            let hits: HashSet<_> = SearchEngine::search_transactions(
+                &data.accounts,
+                matching.iter().copied(),
+                &search,
+            )
+            .into_iter()
+            .map(|hit| hit.transaction.id)
+            .collect();
+            matching.retain(|t| hits.contains(&t.id));
+        }
+        let account_names: BTreeMap<_, _> = data
+            .accounts
+            .iter()
+            .map(|a| (a.id, a.name.as_str()))
+            .collect();
+        if !query.accounts.is_empty() {
+            matching.retain(|t| {
+                account_names.get(&t.account_id).is_some_and(|name| {
+                    query
+                        .accounts
+                        .iter()
+                        .any(|wanted| wanted.eq_ignore_ascii_case(name))
+                })
+            });
+        }
+
+        let mut groups: BTreeMap<Vec<String>, Vec<Decimal>> = BTreeMap::new();
+        for transaction in &matching {
+            let key = query
+                .group_by
+                .iter()
+                .map(|field| group_value(transaction, *field, &account_names))
+                .collect();
+            groups
+                .entry(key)
+                .or_default()
+                .push(transaction.amount.abs());
+        }
+        if groups.is_empty() && query.group_by.is_empty() {
+            groups.insert(Vec::new(), Vec::new());
+        }
+
+        let aggregates = query.aggregates();
+        let mut rows: Vec<ResultRow> = groups
+            .into_iter()
+            .map(|(group, amounts)| aggregate(group, &amounts, &aggregates))
+            .collect();
+        let by_time = query
+            .group_by
+            .first()
+            .is_some_and(|g| matches!(g, GroupBy::Month | GroupBy::Quarter | GroupBy::Year));
+        if !by_time {
+            rows.sort_by(|a, b| b.sum.cmp(&a.sum).then_with(|| b.count.cmp(&a.count)));
+        }
+        rows.truncate(query.limit.unwrap_or(MAX_ROWS));
+
+        Ok(QueryResult {
+            query: query.clone(),
+            rows,
+            warnings: warnings(data, query),
+        })
+    }
+
+    /// Run every query of a plan
+    pub fn run_plan(data: &FinancialData, plan: &QueryPlan) -> Result<Vec<QueryResult>> {
+        plan.validate()?;
+        plan.queries.iter().map(|q| Self::run(data, q)).collect()
+    }
+}
+
+fn aggregate(group: Vec<String>, amounts: &[Decimal], aggregates: &[Aggregate]) -> ResultRow {
+    let sum: Decimal = amounts.iter().sum();
+    let mut row = ResultRow {
+        group,
+        ..ResultRow::default()
+    };
+    for aggregate in aggregates {
+        match aggregate {
+            Aggregate::Sum => row.sum = Some(sum),
+            Aggregate::Count => row.count = Some(amounts.len()),
+            Aggregate::Average => {
+                row.average =
+                    (!amounts.is_empty()).then(|| (sum / Decimal::from(amounts.len())).round_dp(2))
+            }
+            Aggregate::Min => row.min = amounts.iter().min().copied(),
+            Aggregate::Max => row.max = amounts.iter().max().copied(),
+        }
+    }
+    row
+}
+
+fn group_value(
+    transaction: &Transaction,
+    field: GroupBy,
+    account_names: &BTreeMap<uuid::Uuid, &str>,
+) -> String {
+    let date = transaction.date.date_naive();
+    match field {
+        GroupBy::Category => transaction
+            .category
+            .clone()
+            .unwrap_or_else(|| "Uncategorized".to_string()),
+        GroupBy::Payee => crate::categorize::payee_of(transaction).to_string(),
+        GroupBy::Account => account_names
+            .get(&transaction.account_id)
+            .map(|n| n.to_string())
+            .unwrap_or_else(|| transaction.account_id.to_string()),
+        GroupBy::Month => format!("{}-{:02}", date.year(), date.month()),
+        GroupBy::Quarter => format!("{}-Q{}", date.year(), (date.month() - 1) / 3 + 1),
+        GroupBy::Year => date.year().to_string(),
+    }
+}
+
+/// `Dining` matches `Dining` and `Dining:Restaurants`, ignoring case
+fn in_category(category: &str, wanted: &str) -> bool {
+    let category = category.to_lowercase();
+    let wanted = wanted.trim().to_lowercase();
+    category == wanted
+        || category
+            .strip_prefix(&wanted)
+            .is_some_and(|rest| rest.starts_with(':'))
+}
+
+fn warnings(data: &FinancialData, query: &Query) -> Vec<String> {
+    let categories = crate::categorize::known_categories(data);
+    let mut warnings: Vec<String> = query
+        .categories
+        .iter()
+        .filter(|wanted| !categories.iter().any(|c| in_category(c, wanted)))
+        .map(|wanted| format!("No category named '{}'", wanted))
+        .collect();
+    warnings.extend(
+        query
+            .accounts
+            .iter()
+            .filter(|wanted| {
+                !data
+                    .accounts
+                    .iter()
+                    .any(|a| a.name.eq_ignore_ascii_case(wanted))
+            })
+            .map(|wanted| format!("No account named '{}'", wanted)),
+    );
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Account, AccountType};
+    use chrono::{TimeZone, Utc};
+    use rust_decimal_macros::dec;
+
+    fn books() -> FinancialData {
+        let mut data = FinancialData::new();
+        let account = Account::new(
+            "Checking".to_string(),
+            AccountType::Checking,
+            dec!(0),
+            "USD".to_string(),
+        );
+        let account_id = account.id;
+        data.add_account(account);
+        let mut add = |y, m, d, amount, payee: &str, category: &str, kind| {
+            let mut t = Transaction::new(
+                account_id,
+                Utc.with_ymd_and_hms(y, m, d, 12, 0, 0).unwrap(),
+                amount,
+                payee.to_string(),
+                kind,
+            );
+            t.payee = Some(payee.to_string());
+            t.category = Some(category.to_string());
+            data.add_transaction(t);
+        };
+        add(
+            2025,
+            4,
+            10,
+            dec!(40),
+            "Cafe Luna",
+            "Dining",
+            TransactionType::Debit,
+        );
+        add(
+            2025,
+            5,
+            2,
+            dec!(60),
+            "Noodle Bar",
+            "Dining:Takeout",
+            TransactionType::Debit,
+        );
+        add(
+            2026,
+            4,
+            3,
+            dec!(55),
+            "Cafe Luna",
+            "Dining",
+            TransactionType::Debit,
+        );
+        add(
+            2026,
+            6,
+            20,
+            dec!(95),
+            "Bistro Nord",
+            "Dining",
+            TransactionType::Debit,
+        );
+        add(
+            2026,
+            6,
+            21,
+            dec!(120),
+            "Fresh Market",
+            "Groceries",
+            TransactionType::Debit,
+        );
+        add(
+            2026,
+            6,
+            30,
+            dec!(3000),
+            "Employer",
+            "Salary",
+            TransactionType::Credit,
+        );
+        data
+    }
+
+    fn q2(year: i32) -> Query {
+        Query {
+            label: format!("Q2 {}", year),
+            from: NaiveDate::from_ymd_opt(year, 4, 1),
+            to: NaiveDate::from_ymd_opt(year, 6, 30),
+            direction: Direction::Spending,
+            categories: vec!["dining".to_string()],
+            ..Query::default()
+        }
+    }
+
+    #[test]
+    fn test_filters_and_aggregates() {
+        let data = books();
+        let plan = QueryPlan {
+            queries: vec![q2(2026), q2(2025)],
+        };
+        let results = QueryEngine::run_plan(&data, &plan).unwrap();
+        assert_eq!(results[0].rows[0].sum, Some(dec!(150)));
+        assert_eq!(results[0].rows[0].count, Some(2));
+        // Subcategories count towards their parent
+        assert_eq!(results[1].rows[0].sum, Some(dec!(100)));
+
+        let by_month = Query {
+            group_by: vec![GroupBy::Month],
+            aggregates: vec![Aggregate::Average, Aggregate::Max],
+            ..q2(2026)
+        };
+        let result = QueryEngine::run(&data, &by_month).unwrap();
+        let groups: Vec<_> = result.rows.iter().map(|r| r.group[0].as_str()).collect();
+        assert_eq!(groups, vec!["2026-04", "2026-06"]);
+        assert_eq!(result.rows[1].max, Some(dec!(95)));
+        assert_eq!(result.rows[1].sum, None);
+
+        let top_payees = Query {
+            direction: Direction::Spending,
+            group_by: vec![GroupBy::Payee],
+            text: Some("~cafe".to_string()),
+            limit: Some(1),
+            ..Query::default()
+        };
+        let result = QueryEngine::run(&data, &top_payees).unwrap();
+        assert_eq!(result.rows.len(), 1);
+        assert_eq!(result.rows[0].group, vec!["Cafe Luna"]);
+
+        let unknown = Query {
+            categories: vec!["Restaurants".to_string()],
+            accounts: vec!["Savings".to_string()],
+            ..Query::default()
+        };
+        let result = QueryEngine::run(&data, &unknown).unwrap();
+        assert_eq!(result.rows[0].count, Some(0));
+        assert_eq!(result.warnings.len(), 2);
+    }
+
+    #[test]
+    fn test_plans_are_parsed_strictly_and_bounded() {
+        let plan: QueryPlan = serde_json::from_str(
+            r#"{"queries": [{"label": "Q2", "from": "2026-04-01", "to": "2026-06-30",
+                "direction": "spending", "group_by": ["category"]}]}"#,
+        )
+        .unwrap();
+        assert_eq!(plan.queries[0].group_by, vec![GroupBy::Category]);
+        assert!(plan.validate().is_ok());
+
+        assert!(
+            serde_json::from_str::<QueryPlan>(r#"{"queries": [{"sql": "DROP TABLE"}]}"#).is_err()
+        );
+        assert!(QueryPlan::default().validate().is_err());
+        let backwards = Query {
+            from: NaiveDate::from_ymd_opt(2026, 6, 1),
+            to: NaiveDate::from_ymd_opt(2026, 1, 1),
+            ..Query::default()
+        };
+        assert!(backwards.validate().is_err());
+        let too_many = QueryPlan {
+            queries: vec![Query::default(); MAX_QUERIES + 1],
+        };
+        assert!(too_many.validate().is_err());
+    }
+}
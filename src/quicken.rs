@@ -1,25 +1,55 @@
-use crate::data::{Account, AccountType, FinancialData, Transaction, TransactionType};
+use crate::crypto::{self, CryptoError, KdfParams};
+use crate::data::{Account, AccountType, FinancialData, Split, Transaction, TransactionType};
 use anyhow::{bail, Context, Result};
 use chrono::{DateTime, NaiveDate, Utc};
 use rust_decimal::Decimal;
 use std::collections::HashMap;
 use std::path::Path;
+use thiserror::Error;
+use tracing::warn;
 use uuid::Uuid;
 
 /// QIF (Quicken Interchange Format) importer
 pub struct QifImporter;
 
+/// A file whose contents are not QIF, so importing it again cannot succeed
+#[derive(Debug, Error)]
+#[error("not a valid QIF file")]
+pub struct InvalidQif;
+
 /// QIF exporter for creating Quicken-compatible files
 pub struct QifExporter;
 
 impl QifImporter {
     /// Import QIF file and return financial data
     pub async fn import_file<P: AsRef<Path>>(path: P) -> Result<FinancialData> {
-        let content = tokio::fs::read_to_string(path.as_ref())
+        Self::import_file_with_passphrase(path, None).await
+    }
+
+    /// Import a QIF file that may have been exported with encryption
+    pub async fn import_file_with_passphrase<P: AsRef<Path>>(
+        path: P,
+        passphrase: Option<&str>,
+    ) -> Result<FinancialData> {
+        let bytes = tokio::fs::read(path.as_ref())
             .await
             .context("Failed to read QIF file")?;
 
-        Self::parse_qif_content(&content)
+        let bytes = if crypto::is_encrypted(&bytes) {
+            let passphrase =
+                passphrase.context("QIF file is encrypted; a passphrase is required")?;
+            crypto::decrypt_with_passphrase(passphrase, &bytes).map_err(|e| match e {
+                CryptoError::Malformed(_) => anyhow::Error::new(e).context(InvalidQif),
+                e => e.into(),
+            })?
+        } else {
+            bytes
+        };
+
+        let content = String::from_utf8(bytes)
+            .context("QIF file is not valid UTF-8")
+            .context(InvalidQif)?;
+        Self::parse_qif_content(&content).context(InvalidQif)
     }
 
     /// Parse QIF content from string
@@ -66,7 +96,7 @@ impl QifImporter {
         let mut name = "Unknown Account".to_string();
         let mut account_type = AccountType::Other("Unknown".to_string());
         let mut description = None;
-        let balance = Decimal::ZERO;
+        let mut balance = Decimal::ZERO;
 
         while *index < lines.len() {
             let line = lines[*index].trim();
@@ -81,6 +111,11 @@ impl QifImporter {
                 account_type = Self::parse_account_type(content);
             } else if let Some(content) = line.strip_prefix('D') {
                 description = Some(content.to_string());
+            } else if let Some(content) = line.strip_prefix('$') {
+                balance = content
+                    .trim()
+                    .parse::<Decimal>()
+                    .context("Failed to parse account balance")?;
             } else if line == "^" {
                 *index += 1;
                 break;
@@ -112,10 +147,17 @@ impl QifImporter {
                 break;
             }
 
-            if let Ok(transaction) = Self::parse_single_transaction(lines, index, account_id) {
-                transactions.push(transaction);
-            } else {
-                *index += 1;
+            let start = *index;
+            match Self::parse_single_transaction(lines, index, account_id) {
+                Ok(transaction) => transactions.push(transaction),
+                Err(e) => {
+                    // Drop the whole record rather than reading its remaining lines as another
+                    warn!("Skipping transaction at line {}: {:#}", start + 1, e);
+                    *index = lines[start..]
+                        .iter()
+                        .position(|l| l.trim() == "^")
+                        .map_or(lines.len(), |end| start + end + 1);
+                }
             }
         }
 
@@ -134,6 +176,7 @@ impl QifImporter {
         let mut category = None;
         let mut memo = None;
         let mut cleared = false;
+        let mut splits: Vec<Split> = Vec::new();
 
         while *index < lines.len() {
             let line = lines[*index].trim();
@@ -160,12 +203,40 @@ impl QifImporter {
                 description = content.to_string(); // Use memo as description
             } else if let Some(content) = line.strip_prefix('C') {
                 cleared = content == "*" || content.to_lowercase() == "x";
+            } else if let Some(content) = line.strip_prefix('S') {
+                splits.push(Split {
+                    category: Some(content.to_string()).filter(|c| !c.is_empty()),
+                    memo: None,
+                    amount: Decimal::ZERO,
+                });
+            } else if let Some(content) = line.strip_prefix('E') {
+                if let Some(split) = splits.last_mut() {
+                    split.memo = Some(content.to_string());
+                }
+            } else if let Some(content) = line.strip_prefix('$') {
+                if let Some(split) = splits.last_mut() {
+                    split.amount = content
+                        .trim()
+                        .parse::<Decimal>()
+                        .context("Failed to parse split amount")?;
+                }
             }
 
             *index += 1;
         }
 
         let transaction_date = date.unwrap_or_else(Utc::now);
+        if !splits.is_empty() {
+            let sum: Decimal = splits.iter().map(|s| s.amount).sum();
+            if sum != amount {
+                bail!(
+                    "Split lines of the {} transaction add up to {} but the total is {}",
+                    transaction_date.format("%Y-%m-%d"),
+                    sum,
+                    amount
+                );
+            }
+        }
         let transaction_type = if amount >= Decimal::ZERO {
             TransactionType::Credit
         } else {
@@ -184,6 +255,13 @@ impl QifImporter {
         transaction.category = category;
         transaction.memo = memo;
         transaction.cleared = cleared;
+        // Split amounts are signed like the total; stored in the transaction's direction
+        if amount < Decimal::ZERO {
+            for split in &mut splits {
+                split.amount = -split.amount;
+            }
+        }
+        transaction.splits = splits;
 
         Ok(transaction)
     }
@@ -246,6 +324,21 @@ impl QifExporter {
         Ok(())
     }
 
+    /// Export to a QIF file encrypted with a passphrase-derived key
+    pub async fn export_file_encrypted<P: AsRef<Path>>(
+        data: &FinancialData,
+        path: P,
+        passphrase: &str,
+        params: KdfParams,
+    ) -> Result<()> {
+        let content = Self::export_to_string(data)?;
+        let encrypted = crypto::encrypt_with_passphrase(passphrase, params, content.as_bytes())?;
+        tokio::fs::write(path.as_ref(), encrypted)
+            .await
+            .context("Failed to write encrypted QIF file")?;
+        Ok(())
+    }
+
     fn export_account(account: &Account) -> Result<String> {
         let mut output = String::new();
 
@@ -259,6 +352,7 @@ impl QifExporter {
         if let Some(ref institution) = account.institution {
             output.push_str(&format!("D{}\n", institution));
         }
+        output.push_str(&format!("${}\n", account.balance));
 
         output.push_str("^\n");
 
@@ -308,6 +402,19 @@ impl QifExporter {
             output.push_str(&format!("M{}\n", memo));
         }
 
+        // Split lines, signed like the amount
+        for split in &transaction.splits {
+            output.push_str(&format!("S{}\n", split.category.as_deref().unwrap_or("")));
+            if let Some(ref memo) = split.memo {
+                output.push_str(&format!("E{}\n", memo));
+            }
+            let amount = match transaction.transaction_type {
+                TransactionType::Debit => -split.amount,
+                _ => split.amount,
+            };
+            output.push_str(&format!("${}\n", amount));
+        }
+
         // Cleared status
         if transaction.cleared {
             output.push_str("C*\n");
@@ -455,6 +562,29 @@ MMonthly salary
         }
     }
 
+    #[tokio::test]
+    async fn test_encrypted_export_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("export.qif");
+        let data = QifImporter::parse_qif_content(SAMPLE_QIF).unwrap();
+
+        QifExporter::export_file_encrypted(&data, &path, "hunter2", crypto::TEST_KDF_PARAMS)
+            .await
+            .unwrap();
+
+        assert!(QifImporter::import_file(&path).await.is_err());
+        assert!(
+            QifImporter::import_file_with_passphrase(&path, Some("wrong"))
+                .await
+                .is_err()
+        );
+
+        let imported = QifImporter::import_file_with_passphrase(&path, Some("hunter2"))
+            .await
+            .unwrap();
+        assert_eq!(imported.transactions.len(), 2);
+    }
+
     #[test]
     fn test_round_trip() {
         // Import QIF, then export, then import again
@@ -468,4 +598,37 @@ MMonthly salary
             reimported_data.transactions.len()
         );
     }
+
+    #[test]
+    fn test_split_round_trip() {
+        let qif = "!Account\nNChecking\nTBank\n^\n!Type:Bank\nD9/5/2026\nT-30.00\nPMarket\nSGroceries\nEProduce\n$-18.00\nSHousehold\n$-12.00\n^\n";
+        let data = QifImporter::parse_qif_content(qif).unwrap();
+        let splits = &data.transactions[0].splits;
+        assert_eq!(splits.len(), 2);
+        assert_eq!(splits[0].category.as_deref(), Some("Groceries"));
+        assert_eq!(splits[0].memo.as_deref(), Some("Produce"));
+        assert_eq!(splits[0].amount, dec!(18.00));
+
+        let exported = QifExporter::export_to_string(&data).unwrap();
+        assert!(exported.contains("SGroceries\nEProduce\n$-18.00\n"));
+        let reimported = QifImporter::parse_qif_content(&exported).unwrap();
+        assert_eq!(&reimported.transactions[0].splits, splits);
+    }
+
+    #[test]
+    fn test_split_mismatch_is_rejected() {
+        let qif = "!Account\nNChecking\nTBank\n^\n!Type:Bank\nD9/5/2026\nT-30.00\nPMarket\nSGroceries\n$-18.00\nSHousehold\n$-11.00\n^\n";
+        let lines: Vec<&str> = qif.lines().collect();
+        let err =
+            QifImporter::parse_single_transaction(&lines[5..], &mut 0, Uuid::new_v4()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("add up to -29.00 but the total is -30.00"));
+
+        // The record is dropped whole and the next one still imports
+        let both = format!("{}D9/6/2026\nT-5.00\nPBakery\n^\n", qif);
+        let data = QifImporter::parse_qif_content(&both).unwrap();
+        assert_eq!(data.transactions.len(), 1);
+        assert_eq!(data.transactions[0].payee.as_deref(), Some("Bakery"));
+    }
 }
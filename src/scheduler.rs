@@ -0,0 +1,789 @@
+use crate::agent::FinancialAgent;
+use crate::analysis::AnalysisEngine;
+use crate::backup::{BackupManager, BackupReason};
+use crate::config::{BillConfig, JobConfig, JobKind, ScheduleTimezone, SchedulerConfig};
+use crate::data::{FinancialData, Transaction};
+use crate::events::AgentEvent;
+use crate::output::{self, OutputFormat};
+use crate::storage::Database;
+use crate::utils::format_currency;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, Utc};
+use cron::Schedule;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use tracing::{info, warn};
+
+/// Source of the current time, injectable so scheduling is deterministic under test
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// Wall-clock time
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+/// Clock that only moves when told to
+#[derive(Debug)]
+pub struct ManualClock {
+    now: Mutex<DateTime<Utc>>,
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+impl ManualClock {
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            now: Mutex::new(now),
+        }
+    }
+
+    /// Jump to a point in time
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self.now.lock().expect("clock lock poisoned") = now;
+    }
+
+    /// Move the clock forward
+    pub fn advance(&self, by: Duration) {
+        *self.now.lock().expect("clock lock poisoned") += by;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().expect("clock lock poisoned")
+    }
+}
+
+/// Outcome of one job execution
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobRun {
+    pub job: String,
+    pub kind: JobKind,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub success: bool,
+    pub message: String,
+}
+
+/// Current state of a configured job, as shown by `qspec jobs`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobStatus {
+    pub name: String,
+    pub kind: JobKind,
+    pub schedule: String,
+    pub enabled: bool,
+    pub last_run: Option<DateTime<Utc>>,
+    pub last_success: Option<bool>,
+    pub last_message: Option<String>,
+    pub next_run: Option<DateTime<Utc>>,
+    pub recent_failures: usize,
+}
+
+/// A bill falling due within its reminder window
+#[derive(Debug, Clone, PartialEq)]
+pub struct BillReminder {
+    pub name: String,
+    pub amount: Option<rust_decimal::Decimal>,
+    pub currency: String,
+    pub due: NaiveDate,
+    pub days_until: i64,
+}
+
+/// A configured job with its parsed cron schedule
+#[derive(Debug, Clone)]
+pub struct ScheduledJob {
+    pub config: JobConfig,
+    schedule: Schedule,
+}
+
+/// Runs configured jobs when their cron schedules come due
+///
+/// Jobs that were missed while the agent was down run once on the next tick
+/// rather than once per missed occurrence.
+pub struct Scheduler {
+    jobs: Vec<ScheduledJob>,
+    timezone: ScheduleTimezone,
+    report_directory: Option<PathBuf>,
+    bills: Vec<BillConfig>,
+    clock: Arc<dyn Clock>,
+    started_at: DateTime<Utc>,
+    /// Last runs as of the previous `run_due`, so idle ticks skip the database
+    last_runs: Mutex<Option<HashMap<String, JobRun>>>,
+}
+
+/// Number of recent runs inspected when counting failures for `qspec jobs`
+const STATUS_HISTORY: usize = 10;
+
+impl ScheduledJob {
+    /// Parse a job's cron expression, accepting standard 5-field syntax
+    pub fn parse(config: &JobConfig) -> Result<Self> {
+        let expression = config.schedule.trim();
+        let expression = match expression.split_whitespace().count() {
+            5 => format!("0 {}", expression),
+            _ => expression.to_string(),
+        };
+        let schedule = Schedule::from_str(&expression).with_context(|| {
+            format!(
+                "Invalid schedule '{}' for job '{}'",
+                config.schedule, config.name
+            )
+        })?;
+
+        Ok(Self {
+            config: config.clone(),
+            schedule,
+        })
+    }
+
+    /// First occurrence strictly after `after`
+    pub fn next_after(
+        &self,
+        after: DateTime<Utc>,
+        timezone: ScheduleTimezone,
+    ) -> Option<DateTime<Utc>> {
+        match timezone {
+            ScheduleTimezone::Utc => self.schedule.after(&after).next(),
+            ScheduleTimezone::Local => self
+                .schedule
+                .after(&after.with_timezone(&Local))
+                .next()
+                .map(|t| t.with_timezone(&Utc)),
+        }
+    }
+}
+
+impl Scheduler {
+    /// Parse every job's schedule; names must be unique
+    pub fn new(config: &SchedulerConfig, clock: Arc<dyn Clock>) -> Result<Self> {
+        let mut names = HashSet::new();
+        for job in &config.jobs {
+            if !names.insert(job.name.as_str()) {
+                bail!("Duplicate job name '{}'", job.name);
+            }
+        }
+
+        let jobs = config
+            .jobs
+            .iter()
+            .map(ScheduledJob::parse)
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            jobs,
+            timezone: config.timezone,
+            report_directory: config.report_directory.clone(),
+            bills: config.bills.clone(),
+            started_at: clock.now(),
+            clock,
+            last_runs: Mutex::new(None),
+        })
+    }
+
+    pub fn jobs(&self) -> &[ScheduledJob] {
+        &self.jobs
+    }
+
+    /// Look up a job by name
+    pub fn job(&self, name: &str) -> Option<&ScheduledJob> {
+        self.jobs.iter().find(|j| j.config.name == name)
+    }
+
+    /// When a job should next run, counting from its last run or from scheduler start
+    pub fn next_run(&self, job: &ScheduledJob, last_run: Option<&JobRun>) -> Option<DateTime<Utc>> {
+        let after = last_run.map(|r| r.started_at).unwrap_or(self.started_at);
+        job.next_after(after, self.timezone)
+    }
+
+    /// Enabled jobs whose next run is at or before the current time
+    pub fn due_jobs(&self, last_runs: &HashMap<String, JobRun>) -> Vec<&ScheduledJob> {
+        let now = self.clock.now();
+        self.jobs
+            .iter()
+            .filter(|job| job.config.enabled)
+            .filter(|job| {
+                self.next_run(job, last_runs.get(&job.config.name))
+                    .is_some_and(|next| next <= now)
+            })
+            .collect()
+    }
+
+    /// Run every due job, recording each outcome
+    ///
+    /// The database is only opened when a job is due by the last runs seen here.
+    pub async fn run_due(&self, agent: &FinancialAgent) -> Result<Vec<JobRun>> {
+        if let Some(ref last_runs) = *self.last_runs.lock().expect("last runs lock poisoned") {
+            if self.due_jobs(last_runs).is_empty() {
+                return Ok(Vec::new());
+            }
+        }
+
+        let database = agent.open_database().await?;
+        let result = async {
+            let mut last_runs = database.last_job_runs().await?;
+            let mut runs = Vec::new();
+            for job in self.due_jobs(&last_runs) {
+                let previous = last_runs.get(&job.config.name);
+                runs.push(self.run_job(job, agent, &database, previous).await?);
+            }
+            for run in &runs {
+                last_runs.insert(run.job.clone(), run.clone());
+            }
+            *self.last_runs.lock().expect("last runs lock poisoned") = Some(last_runs);
+            Ok(runs)
+        }
+        .await;
+        database.close().await;
+        result
+    }
+
+    /// Run one job now, regardless of its schedule, and record the outcome
+    pub async fn run_job(
+        &self,
+        job: &ScheduledJob,
+        agent: &FinancialAgent,
+        database: &Database,
+        previous: Option<&JobRun>,
+    ) -> Result<JobRun> {
+        let started_at = self.clock.now();
+        info!("Running job {}", job.config.name);
+
+        let outcome = self
+            .execute(job.config.kind, agent, database, previous, started_at)
+            .await;
+        let run = JobRun {
+            job: job.config.name.clone(),
+            kind: job.config.kind,
+            started_at,
+            finished_at: self.clock.now(),
+            success: outcome.is_ok(),
+            message: match outcome {
+                Ok(message) => message,
+                Err(e) => format!("{:#}", e),
+            },
+        };
+
+        if run.success {
+            info!("Job {} finished: {}", run.job, run.message);
+        } else {
+            warn!("Job {} failed: {}", run.job, run.message);
+        }
+        database.record_job_run(&run).await?;
+        Ok(run)
+    }
+
+    /// Status of every configured job
+    pub async fn status(&self, database: &Database) -> Result<Vec<JobStatus>> {
+        let last_runs = database.last_job_runs().await?;
+        let mut statuses = Vec::new();
+
+        for job in &self.jobs {
+            let last = last_runs.get(&job.config.name);
+            let recent_failures = database
+                .job_runs(Some(&job.config.name), false, STATUS_HISTORY)
+                .await?
+                .iter()
+                .filter(|r| !r.success)
+                .count();
+
+            statuses.push(JobStatus {
+                name: job.config.name.clone(),
+                kind: job.config.kind,
+                schedule: job.config.schedule.clone(),
+                enabled: job.config.enabled,
+                last_run: last.map(|r| r.started_at),
+                last_success: last.map(|r| r.success),
+                last_message: last.map(|r| r.message.clone()),
+                next_run: job
+                    .config
+                    .enabled
+                    .then(|| self.next_run(job, last))
+                    .flatten(),
+                recent_failures,
+            });
+        }
+        Ok(statuses)
+    }
+
+    async fn execute(
+        &self,
+        kind: JobKind,
+        agent: &FinancialAgent,
+        database: &Database,
+        previous: Option<&JobRun>,
+        now: DateTime<Utc>,
+    ) -> Result<String> {
+        match kind {
+            JobKind::AnomalyScan => {
+                let data = database.load_data().await?;
+                let (mut message, anomalies) = anomaly_scan(&data, previous.map(|r| r.started_at));
+                for transaction in anomalies {
+                    agent
+                        .publish(AgentEvent::AnomalyDetected { transaction })
+                        .await;
+                }
+
+                if !agent.plugins().is_empty() {
+                    let findings = agent.plugins().analyze(&data);
+                    message.push_str(&format!("; {} plugin findings", findings.len()));
+                }
+                Ok(message)
+            }
+            JobKind::MonthlyReport => {
+                let data = database.load_data().await?;
+                self.monthly_report(agent, &data, now).await
+            }
+            JobKind::Backup => {
+                let config = &agent.config().database;
+                if !config.backup.enabled {
+                    return Ok("Backups are disabled; skipped".to_string());
+                }
+                let path = BackupManager::new(config)
+                    .create_backup(database, BackupReason::Scheduled, now)
+                    .await?;
+                Ok(format!("Backed up to {}", path.display()))
+            }
+            JobKind::BillReminders => {
+                let data = database.load_data().await?;
+                let today = self.local_date(now);
+                let reminders = upcoming_bills(&self.bills, today, books_currency(&data));
+                if reminders.is_empty() {
+                    return Ok("No bills due soon".to_string());
+                }
+                let lines: Vec<String> = reminders.iter().map(BillReminder::describe).collect();
+                for reminder in reminders {
+                    agent
+                        .publish(AgentEvent::BillDue {
+                            name: reminder.name,
+                            amount: reminder.amount,
+                            currency: reminder.currency,
+                            due: reminder.due,
+                            days_until: reminder.days_until,
+                        })
+                        .await;
+                }
+                Ok(lines.join("; "))
+            }
+        }
+    }
+
+    async fn monthly_report(
+        &self,
+        agent: &FinancialAgent,
+        data: &FinancialData,
+        now: DateTime<Utc>,
+    ) -> Result<String> {
+        let today = self.local_date(now);
+        let first_of_month = today.with_day(1).context("Invalid date")?;
+        let last_month = first_of_month - Duration::days(1);
+
+        let report =
+            AnalysisEngine::generate_monthly_report(data, last_month.year(), last_month.month())?;
+
+        let directory = match self.report_directory {
+            Some(ref directory) => directory.clone(),
+            None => agent
+                .config()
+                .database
+                .path
+                .parent()
+                .map(|p| p.join("reports"))
+                .unwrap_or_else(|| PathBuf::from("reports")),
+        };
+        tokio::fs::create_dir_all(&directory)
+            .await
+            .with_context(|| format!("Failed to create {}", directory.display()))?;
+
+        let path = directory.join(format!(
+            "monthly-{}-{:02}.json",
+            last_month.year(),
+            last_month.month()
+        ));
+        let currency = books_currency(data);
+        let mut out = Vec::new();
+        output::render(&report, OutputFormat::Json, currency, &mut out)?;
+        tokio::fs::write(&path, out)
+            .await
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        agent
+            .publish(AgentEvent::ReportGenerated {
+                year: report.year,
+                month: report.month,
+                path: path.clone(),
+                generated_at: now,
+            })
+            .await;
+
+        Ok(format!(
+            "Report for {}-{:02}: net {} over {} transactions, written to {}",
+            report.year,
+            report.month,
+            format_currency(report.net_income, currency),
+            report.transaction_count,
+            path.display()
+        ))
+    }
+
+    fn local_date(&self, now: DateTime<Utc>) -> NaiveDate {
+        match self.timezone {
+            ScheduleTimezone::Utc => now.date_naive(),
+            ScheduleTimezone::Local => now.with_timezone(&Local).date_naive(),
+        }
+    }
+}
+
+impl BillReminder {
+    fn describe(&self) -> String {
+        let amount = self
+            .amount
+            .map(|a| format!(" ({})", format_currency(a, &self.currency)))
+            .unwrap_or_default();
+        let when = match self.days_until {
+            0 => "today".to_string(),
+            1 => "tomorrow".to_string(),
+            n => format!("in {} days", n),
+        };
+        format!("{}{} due {} ({})", self.name, amount, self.due, when)
+    }
+}
+
+/// Currency of the first account, for amounts not tied to one
+fn books_currency(data: &FinancialData) -> &str {
+    data.accounts
+        .first()
+        .map(|a| a.currency.as_str())
+        .unwrap_or("USD")
+}
+
+/// Flag anomalies among transactions recorded since the previous scan
+fn anomaly_scan(data: &FinancialData, since: Option<DateTime<Utc>>) -> (String, Vec<Transaction>) {
+    let is_new = |created_at: DateTime<Utc>| since.is_none_or(|since| created_at > since);
+    let new_count = data
+        .transactions
+        .iter()
+        .filter(|t| is_new(t.created_at))
+        .count();
+
+    let anomalies: Vec<Transaction> = AnalysisEngine::detect_anomalies(data)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|t| is_new(t.created_at))
+        .cloned()
+        .collect();
+
+    if anomalies.is_empty() {
+        return (
+            format!("No anomalies in {} new transactions", new_count),
+            anomalies,
+        );
+    }
+
+    let examples: Vec<String> = anomalies
+        .iter()
+        .take(5)
+        .map(|t| {
+            format!(
+                "{} {} {}",
+                t.date.format("%Y-%m-%d"),
+                t.payee.as_deref().unwrap_or(&t.description),
+                t.amount
+            )
+        })
+        .collect();
+    let message = format!(
+        "{} anomalies in {} new transactions: {}",
+        anomalies.len(),
+        new_count,
+        examples.join(", ")
+    );
+    (message, anomalies)
+}
+
+/// Bills whose next due date falls within their reminder window
+///
+/// Bills without their own currency are reminded in `default_currency`.
+pub fn upcoming_bills(
+    bills: &[BillConfig],
+    today: NaiveDate,
+    default_currency: &str,
+) -> Vec<BillReminder> {
+    let mut reminders: Vec<BillReminder> = bills
+        .iter()
+        .filter_map(|bill| {
+            let this_month = due_date(today.year(), today.month(), bill.due_day)?;
+            let due = if this_month >= today {
+                this_month
+            } else {
+                let (year, month) = if today.month() == 12 {
+                    (today.year() + 1, 1)
+                } else {
+                    (today.year(), today.month() + 1)
+                };
+                due_date(year, month, bill.due_day)?
+            };
+
+            let days_until = (due - today).num_days();
+            (days_until <= i64::from(bill.remind_days_before)).then(|| BillReminder {
+                name: bill.name.clone(),
+                amount: bill.amount,
+                currency: bill
+                    .currency
+                    .clone()
+                    .unwrap_or_else(|| default_currency.to_string()),
+                due,
+                days_until,
+            })
+        })
+        .collect();
+    reminders.sort_by_key(|r| r.due);
+    reminders
+}
+
+/// Due date in a month, clamping e.g. day 31 to the month's last day
+fn due_date(year: i32, month: u32, day: u32) -> Option<NaiveDate> {
+    let day = day.max(1);
+    (0..4).find_map(|back| NaiveDate::from_ymd_opt(year, month, day.saturating_sub(back)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::storage::test_database_config;
+    use chrono::TimeZone;
+    use rust_decimal_macros::dec;
+
+    fn at(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    fn scheduler_config(dir: &std::path::Path) -> SchedulerConfig {
+        SchedulerConfig {
+            timezone: ScheduleTimezone::Utc,
+            report_directory: Some(dir.join("reports")),
+            bills: vec![BillConfig {
+                name: "Electricity".to_string(),
+                amount: Some(dec!(120.00)),
+                currency: None,
+                due_day: 5,
+                remind_days_before: 3,
+            }],
+            ..SchedulerConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_schedule_parsing_and_next_run() {
+        let config = SchedulerConfig::default();
+        let clock = Arc::new(ManualClock::new(at(2026, 10, 1, 12, 0)));
+        let scheduler = Scheduler::new(
+            &SchedulerConfig {
+                timezone: ScheduleTimezone::Utc,
+                ..config
+            },
+            clock,
+        )
+        .unwrap();
+
+        let next = |name: &str| {
+            scheduler
+                .next_run(scheduler.job(name).unwrap(), None)
+                .unwrap()
+        };
+        assert_eq!(next("nightly-anomaly-scan"), at(2026, 10, 2, 2, 0));
+        assert_eq!(next("monthly-report"), at(2026, 11, 1, 6, 0));
+        // 2026-10-01 is a Thursday
+        assert_eq!(next("weekly-backup"), at(2026, 10, 4, 3, 0));
+
+        let bad = JobConfig {
+            name: "broken".to_string(),
+            kind: JobKind::Backup,
+            schedule: "every tuesday".to_string(),
+            enabled: true,
+        };
+        assert!(ScheduledJob::parse(&bad).is_err());
+
+        let mut duplicate = SchedulerConfig::default();
+        duplicate.jobs.push(duplicate.jobs[0].clone());
+        assert!(Scheduler::new(&duplicate, Arc::new(SystemClock)).is_err());
+    }
+
+    #[test]
+    fn test_upcoming_bills() {
+        let bills = vec![
+            BillConfig {
+                name: "Rent".to_string(),
+                amount: None,
+                currency: None,
+                due_day: 31,
+                remind_days_before: 2,
+            },
+            BillConfig {
+                name: "Phone".to_string(),
+                amount: Some(dec!(45.00)),
+                currency: Some("EUR".to_string()),
+                due_day: 1,
+                remind_days_before: 3,
+            },
+        ];
+
+        // February has no 31st, so rent is due on the 28th
+        let reminders =
+            upcoming_bills(&bills, NaiveDate::from_ymd_opt(2026, 2, 27).unwrap(), "GBP");
+        assert_eq!(reminders.len(), 2);
+        assert_eq!(reminders[0].name, "Rent");
+        assert_eq!(
+            reminders[0].due,
+            NaiveDate::from_ymd_opt(2026, 2, 28).unwrap()
+        );
+        assert_eq!(
+            reminders[1].due,
+            NaiveDate::from_ymd_opt(2026, 3, 1).unwrap()
+        );
+        assert_eq!(reminders[1].days_until, 2);
+        assert_eq!(reminders[0].currency, "GBP");
+        assert_eq!(reminders[1].currency, "EUR");
+        assert_eq!(
+            reminders[1].describe(),
+            "Phone (€45.00) due 2026-03-01 (in 2 days)"
+        );
+
+        assert!(
+            upcoming_bills(&bills, NaiveDate::from_ymd_opt(2026, 2, 10).unwrap(), "GBP").is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_jobs_run_when_due_and_record_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let clock = Arc::new(ManualClock::new(at(2026, 10, 1, 12, 0)));
+        let config = Config {
+            database: test_database_config(dir.path(), false),
+            scheduler: scheduler_config(dir.path()),
+            ..Config::default()
+        };
+        let agent = FinancialAgent::with_config(config.clone()).with_clock(clock.clone());
+        let scheduler = Scheduler::new(&config.scheduler, clock.clone()).unwrap();
+
+        // Nothing is due at start-up
+        assert!(scheduler.run_due(&agent).await.unwrap().is_empty());
+
+        // 02:00 the next day: the nightly scan runs once, the reminder at 08:00 has not
+        clock.set(at(2026, 10, 2, 2, 30));
+        let runs = scheduler.run_due(&agent).await.unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].job, "nightly-anomaly-scan");
+        assert!(runs[0].success);
+        assert!(scheduler.run_due(&agent).await.unwrap().is_empty());
+
+        // Agent down until the 3rd: each missed job runs once
+        let mut events = agent.events().subscribe();
+        clock.set(at(2026, 10, 3, 9, 0));
+        let mut jobs: Vec<String> = scheduler
+            .run_due(&agent)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|r| r.job)
+            .collect();
+        jobs.sort();
+        assert_eq!(jobs, vec!["bill-reminders", "nightly-anomaly-scan"]);
+
+        let database = agent.open_database().await.unwrap();
+        let reminder = database
+            .job_runs(Some("bill-reminders"), false, 1)
+            .await
+            .unwrap();
+        assert!(reminder[0]
+            .message
+            .contains("Electricity ($120.00) due 2026-10-05"));
+        let bills: Vec<AgentEvent> = std::iter::from_fn(|| events.try_recv().ok())
+            .filter(|e| e.name() == "bill_due")
+            .collect();
+        assert_eq!(
+            bills,
+            vec![AgentEvent::BillDue {
+                name: "Electricity".to_string(),
+                amount: Some(dec!(120.00)),
+                currency: "USD".to_string(),
+                due: NaiveDate::from_ymd_opt(2026, 10, 5).unwrap(),
+                days_until: 2,
+            }]
+        );
+
+        let status = scheduler.status(&database).await.unwrap();
+        let nightly = status
+            .iter()
+            .find(|s| s.name == "nightly-anomaly-scan")
+            .unwrap();
+        assert_eq!(nightly.last_run, Some(at(2026, 10, 3, 9, 0)));
+        assert_eq!(nightly.next_run, Some(at(2026, 10, 4, 2, 0)));
+        assert_eq!(database.job_runs(None, false, 10).await.unwrap().len(), 3);
+        assert!(database.job_runs(None, true, 10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_idle_ticks_skip_the_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let clock = Arc::new(ManualClock::new(at(2026, 10, 1, 12, 0)));
+        let config = Config {
+            database: test_database_config(dir.path(), false),
+            scheduler: scheduler_config(dir.path()),
+            ..Config::default()
+        };
+        let agent = FinancialAgent::with_config(config.clone()).with_clock(clock.clone());
+        let scheduler = Scheduler::new(&config.scheduler, clock.clone()).unwrap();
+        assert!(scheduler.run_due(&agent).await.unwrap().is_empty());
+
+        // A store that cannot be opened goes unnoticed until a job comes due
+        std::fs::write(&config.database.path, "not a database").unwrap();
+        clock.advance(Duration::hours(1));
+        assert!(scheduler.run_due(&agent).await.unwrap().is_empty());
+        clock.set(at(2026, 10, 2, 2, 30));
+        assert!(scheduler.run_due(&agent).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_monthly_report_job_writes_previous_month() {
+        let dir = tempfile::tempdir().unwrap();
+        let clock = Arc::new(ManualClock::new(at(2026, 11, 1, 6, 5)));
+        let config = Config {
+            database: test_database_config(dir.path(), false),
+            scheduler: scheduler_config(dir.path()),
+            ..Config::default()
+        };
+        let agent = FinancialAgent::with_config(config.clone()).with_clock(clock.clone());
+        let scheduler = Scheduler::new(&config.scheduler, clock).unwrap();
+        let database = agent.open_database().await.unwrap();
+
+        let mut events = agent.events().subscribe();
+
+        let job = scheduler.job("monthly-report").unwrap();
+        let run = scheduler
+            .run_job(job, &agent, &database, None)
+            .await
+            .unwrap();
+
+        assert!(run.success, "{}", run.message);
+        let report = dir.path().join("reports").join("monthly-2026-10.json");
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(report).unwrap()).unwrap();
+        assert_eq!(json["schema"], "qspec.report.monthly");
+        assert_eq!(json["data"]["month"], 10);
+        assert!(matches!(
+            events.try_recv().unwrap(),
+            AgentEvent::ReportGenerated {
+                year: 2026,
+                month: 10,
+                ..
+            }
+        ));
+    }
+}
@@ -0,0 +1,514 @@
+use crate::data::{Account, FinancialData, Transaction};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Duration, Months, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use std::cmp::Reverse;
+
+/// A text term matched against description, payee, memo and category
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextTerm {
+    /// Whole word, e.g. `depot`
+    Word(String),
+    /// Word prefix, e.g. `dep*`
+    Prefix(String),
+    /// Consecutive words, e.g. `"home depot"`
+    Phrase(String),
+    /// Word within a small edit distance, e.g. `~deopt`
+    Fuzzy(String),
+}
+
+/// A combined full-text and structured transaction query
+///
+/// All terms and filters must match. Amount filters compare against the
+/// absolute transaction amount; date filters are inclusive.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchQuery {
+    pub terms: Vec<TextTerm>,
+    pub min_amount: Option<Decimal>,
+    pub max_amount: Option<Decimal>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    /// Account name (case-insensitive) or id
+    pub account: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// A matching transaction with its relevance score
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    pub transaction: Transaction,
+    pub score: u32,
+}
+
+/// In-memory search over financial data
+pub struct SearchEngine;
+
+impl SearchQuery {
+    /// Create an empty query that matches every transaction
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse a query string
+    ///
+    /// Supported syntax: `word`, `prefix*`, `"a phrase"`, `~fuzzy`,
+    /// `amount:50..200`, `amount:>50`, `amount:<20`, `date:2026-03..2026-05`,
+    /// `date:2026-04-01..2026-04-30`, `account:Checking` and `limit:10`.
+    pub fn parse(input: &str) -> Result<Self> {
+        let mut query = Self::new();
+
+        for token in split_tokens(input)? {
+            if let Some(phrase) = token.strip_prefix('"') {
+                let phrase = normalize(phrase);
+                if !phrase.is_empty() {
+                    query.terms.push(TextTerm::Phrase(phrase));
+                }
+            } else if let Some((key, value)) = token.split_once(':') {
+                query.apply_filter(key, value)?;
+            } else if let Some(word) = token.strip_prefix('~') {
+                // A bare `~` or `*` has nothing to match and is skipped
+                if !word.is_empty() {
+                    query.terms.push(TextTerm::Fuzzy(word.to_lowercase()));
+                }
+            } else if token.ends_with('*') {
+                let word = token.trim_end_matches('*');
+                if !word.is_empty() {
+                    query.terms.push(TextTerm::Prefix(word.to_lowercase()));
+                }
+            } else {
+                query.terms.push(TextTerm::Word(token.to_lowercase()));
+            }
+        }
+
+        Ok(query)
+    }
+
+    fn apply_filter(&mut self, key: &str, value: &str) -> Result<()> {
+        match key.to_lowercase().as_str() {
+            "amount" => {
+                if let Some(min) = value.strip_prefix('>') {
+                    self.min_amount = Some(parse_amount(min)?);
+                } else if let Some(max) = value.strip_prefix('<') {
+                    self.max_amount = Some(parse_amount(max)?);
+                } else if let Some((min, max)) = value.split_once("..") {
+                    self.min_amount = (!min.is_empty()).then(|| parse_amount(min)).transpose()?;
+                    self.max_amount = (!max.is_empty()).then(|| parse_amount(max)).transpose()?;
+                } else {
+                    let amount = parse_amount(value)?;
+                    self.min_amount = Some(amount);
+                    self.max_amount = Some(amount);
+                }
+            }
+            "date" => {
+                let (start, end) = value.split_once("..").unwrap_or((value, value));
+                if !start.is_empty() {
+                    self.from = Some(parse_date_bound(start, false)?);
+                }
+                if !end.is_empty() {
+                    self.to = Some(parse_date_bound(end, true)?);
+                }
+            }
+            "account" => self.account = Some(value.to_string()),
+            "limit" => self.limit = Some(value.parse().context("Invalid limit")?),
+            other => bail!("Unknown search filter '{}'", other),
+        }
+        Ok(())
+    }
+
+    /// Add a text term
+    pub fn term(mut self, term: TextTerm) -> Self {
+        self.terms.push(term);
+        self
+    }
+
+    /// Restrict to absolute amounts within the range
+    pub fn amount_between(mut self, min: Option<Decimal>, max: Option<Decimal>) -> Self {
+        self.min_amount = min;
+        self.max_amount = max;
+        self
+    }
+
+    /// Restrict to dates within the range
+    pub fn date_between(mut self, from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> Self {
+        self.from = from;
+        self.to = to;
+        self
+    }
+
+    /// Restrict to an account by name or id
+    pub fn account(mut self, account: impl Into<String>) -> Self {
+        self.account = Some(account.into());
+        self
+    }
+
+    /// Render the indexable terms as an SQLite FTS5 match expression
+    pub fn fts_expression(&self) -> Option<String> {
+        let parts: Vec<String> = self
+            .terms
+            .iter()
+            .filter_map(|term| match term {
+                TextTerm::Word(w) => Some(format!("\"{}\"", w.replace('"', "\"\""))),
+                TextTerm::Prefix(p) => Some(format!("\"{}\"*", p.replace('"', "\"\""))),
+                TextTerm::Phrase(p) => Some(format!("\"{}\"", p.replace('"', "\"\""))),
+                TextTerm::Fuzzy(_) => None,
+            })
+            .collect();
+
+        (!parts.is_empty()).then(|| parts.join(" AND "))
+    }
+}
+
+impl SearchEngine {
+    /// Search the transactions in `data`, best matches first
+    pub fn search(data: &FinancialData, query: &SearchQuery) -> Vec<SearchHit> {
+        Self::search_transactions(&data.accounts, data.transactions.iter(), query)
+    }
+
+    /// Search an arbitrary set of candidate transactions
+    pub fn search_transactions<'a>(
+        accounts: &[Account],
+        transactions: impl IntoIterator<Item = &'a Transaction>,
+        query: &SearchQuery,
+    ) -> Vec<SearchHit> {
+        let account_ids: Option<Vec<_>> = query.account.as_ref().map(|wanted| {
+            accounts
+                .iter()
+                .filter(|a| a.name.eq_ignore_ascii_case(wanted) || a.id.to_string() == *wanted)
+                .map(|a| a.id)
+                .collect()
+        });
+
+        let mut hits: Vec<SearchHit> = transactions
+            .into_iter()
+            .filter(|t| {
+                account_ids
+                    .as_ref()
+                    .is_none_or(|ids| ids.contains(&t.account_id))
+            })
+            .filter(|t| Self::matches_filters(t, query))
+            .filter_map(|t| {
+                Self::score(t, &query.terms).map(|score| SearchHit {
+                    transaction: t.clone(),
+                    score,
+                })
+            })
+            .collect();
+
+        hits.sort_by_key(|h| (Reverse(h.score), Reverse(h.transaction.date)));
+        if let Some(limit) = query.limit {
+            hits.truncate(limit);
+        }
+        hits
+    }
+
+    fn matches_filters(transaction: &Transaction, query: &SearchQuery) -> bool {
+        let amount = transaction.amount.abs();
+        query.min_amount.is_none_or(|min| amount >= min)
+            && query.max_amount.is_none_or(|max| amount <= max)
+            && query.from.is_none_or(|from| transaction.date >= from)
+            && query.to.is_none_or(|to| transaction.date <= to)
+    }
+
+    /// Score a transaction against the terms, or `None` if any term fails to match
+    fn score(transaction: &Transaction, terms: &[TextTerm]) -> Option<u32> {
+        let text = normalize(
+            &[
+                Some(transaction.description.as_str()),
+                transaction.payee.as_deref(),
+                transaction.memo.as_deref(),
+                transaction.category.as_deref(),
+            ]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" | "),
+        );
+        let words: Vec<&str> = text.split(' ').filter(|w| *w != "|").collect();
+
+        let mut score = 0;
+        for term in terms {
+            score += match term {
+                TextTerm::Word(w) => words.contains(&w.as_str()).then_some(3)?,
+                TextTerm::Prefix(p) => words
+                    .iter()
+                    .any(|w| w.starts_with(p.as_str()))
+                    .then_some(2)?,
+                TextTerm::Phrase(p) => contains_phrase(&text, p).then_some(4)?,
+                TextTerm::Fuzzy(f) => {
+                    let allowed = if f.chars().count() <= 4 { 1 } else { 2 };
+                    let best = words.iter().map(|w| levenshtein(w, f)).min()?;
+                    match best {
+                        0 => 3,
+                        d if d <= allowed => 1,
+                        _ => return None,
+                    }
+                }
+            };
+        }
+
+        Some(score)
+    }
+}
+
+/// Lowercase and collapse everything but letters and digits into single spaces
+fn normalize(text: &str) -> String {
+    text.split(|c: char| !c.is_alphanumeric() && c != '|')
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn contains_phrase(text: &str, phrase: &str) -> bool {
+    text.split(" | ").any(|field| {
+        field == phrase
+            || field.starts_with(&format!("{} ", phrase))
+            || field.ends_with(&format!(" {}", phrase))
+            || field.contains(&format!(" {} ", phrase))
+    })
+}
+
+/// Edit distance between two strings
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            current[j + 1] = (previous[j] + cost)
+                .min(previous[j + 1] + 1)
+                .min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+fn split_tokens(input: &str) -> Result<Vec<String>> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' {
+            chars.next();
+            let phrase: String = chars.by_ref().take_while(|c| *c != '"').collect();
+            tokens.push(format!("\"{}", phrase));
+        } else {
+            let mut token = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                token.push(c);
+                chars.next();
+            }
+            tokens.push(token);
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn parse_amount(value: &str) -> Result<Decimal> {
+    crate::utils::parse_currency(value)
+}
+
+/// Parse `YYYY-MM-DD` or `YYYY-MM`, taking the end of the period for upper bounds
+fn parse_date_bound(value: &str, end: bool) -> Result<DateTime<Utc>> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        let start = date.and_hms_opt(0, 0, 0).unwrap().and_utc();
+        return Ok(if end {
+            start + Duration::days(1) - Duration::seconds(1)
+        } else {
+            start
+        });
+    }
+
+    if let Ok(date) = NaiveDate::parse_from_str(&format!("{}-01", value), "%Y-%m-%d") {
+        let start = date.and_hms_opt(0, 0, 0).unwrap().and_utc();
+        return Ok(if end {
+            start + Months::new(1) - Duration::seconds(1)
+        } else {
+            start
+        });
+    }
+
+    bail!("Invalid date '{}': expected YYYY-MM-DD or YYYY-MM", value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{AccountType, TransactionType};
+    use chrono::TimeZone;
+    use rust_decimal_macros::dec;
+
+    fn sample_data() -> FinancialData {
+        let mut data = FinancialData::new();
+        let checking = Account::new(
+            "Checking".to_string(),
+            AccountType::Checking,
+            dec!(0),
+            "USD".to_string(),
+        );
+        let card = Account::new(
+            "Visa".to_string(),
+            AccountType::CreditCard,
+            dec!(0),
+            "USD".to_string(),
+        );
+        let (checking_id, card_id) = (checking.id, card.id);
+        data.add_account(checking);
+        data.add_account(card);
+
+        let rows = [
+            (
+                checking_id,
+                (2026, 4, 12),
+                dec!(86.40),
+                "Home Depot",
+                "Return of drill",
+                "Home Improvement",
+            ),
+            (
+                card_id,
+                (2026, 4, 20),
+                dec!(23.10),
+                "Home Depot",
+                "Paint",
+                "Home Improvement",
+            ),
+            (
+                card_id,
+                (2026, 5, 2),
+                dec!(54.00),
+                "Depot Cafe",
+                "Lunch",
+                "Dining",
+            ),
+            (
+                checking_id,
+                (2025, 11, 1),
+                dec!(1200.00),
+                "Landlord",
+                "Rent",
+                "Housing",
+            ),
+        ];
+        for (account_id, (y, m, d), amount, payee, memo, category) in rows {
+            let mut t = Transaction::new(
+                account_id,
+                Utc.with_ymd_and_hms(y, m, d, 0, 0, 0).unwrap(),
+                amount,
+                memo.to_string(),
+                TransactionType::Debit,
+            );
+            t.payee = Some(payee.to_string());
+            t.memo = Some(memo.to_string());
+            t.category = Some(category.to_string());
+            data.add_transaction(t);
+        }
+        data
+    }
+
+    fn payees(hits: &[SearchHit]) -> Vec<String> {
+        hits.iter()
+            .map(|h| {
+                format!(
+                    "{} {}",
+                    h.transaction.payee.as_deref().unwrap(),
+                    h.transaction.amount
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_query() {
+        let query = SearchQuery::parse(
+            r#"home dep* "home depot" ~deopt amount:20..100 date:2026-03..2026-05 account:Checking"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            query.terms,
+            vec![
+                TextTerm::Word("home".to_string()),
+                TextTerm::Prefix("dep".to_string()),
+                TextTerm::Phrase("home depot".to_string()),
+                TextTerm::Fuzzy("deopt".to_string()),
+            ]
+        );
+        assert_eq!(query.min_amount, Some(dec!(20)));
+        assert_eq!(query.max_amount, Some(dec!(100)));
+        assert_eq!(
+            query.from,
+            Some(Utc.with_ymd_and_hms(2026, 3, 1, 0, 0, 0).unwrap())
+        );
+        assert_eq!(
+            query.to,
+            Some(Utc.with_ymd_and_hms(2026, 5, 31, 23, 59, 59).unwrap())
+        );
+        assert_eq!(query.account.as_deref(), Some("Checking"));
+        assert!(SearchQuery::parse("colour:red").is_err());
+
+        // Bare operators add no empty terms, so nothing reaches FTS as `""*`
+        let query = SearchQuery::parse("* ~ ** amount:>5").unwrap();
+        assert!(query.terms.is_empty());
+        assert_eq!(query.fts_expression(), None);
+        assert_eq!(
+            SearchQuery::parse("dep** ~")
+                .unwrap()
+                .fts_expression()
+                .as_deref(),
+            Some("\"dep\"*")
+        );
+    }
+
+    #[test]
+    fn test_phrase_prefix_and_fuzzy() {
+        let data = sample_data();
+
+        let hits = SearchEngine::search(&data, &SearchQuery::parse(r#""home depot""#).unwrap());
+        assert_eq!(hits.len(), 2);
+
+        let hits = SearchEngine::search(&data, &SearchQuery::parse("depo*").unwrap());
+        assert_eq!(hits.len(), 3);
+
+        let hits = SearchEngine::search(&data, &SearchQuery::parse("~deopt ~retrun").unwrap());
+        assert_eq!(payees(&hits), vec!["Home Depot 86.40"]);
+
+        let hits = SearchEngine::search(&data, &SearchQuery::parse("~xyzzy").unwrap());
+        assert!(hits.is_empty());
+    }
+
+    #[test]
+    fn test_structured_filters() {
+        let data = sample_data();
+
+        let query = SearchQuery::parse("depot amount:>50 date:2026-04..2026-06").unwrap();
+        let hits = SearchEngine::search(&data, &query);
+        assert_eq!(payees(&hits), vec!["Depot Cafe 54.00", "Home Depot 86.40"]);
+
+        let query = SearchQuery::new()
+            .term(TextTerm::Word("depot".to_string()))
+            .account("visa");
+        let hits = SearchEngine::search(&data, &query);
+        assert_eq!(payees(&hits), vec!["Depot Cafe 54.00", "Home Depot 23.10"]);
+
+        let hits = SearchEngine::search(&data, &SearchQuery::parse("limit:1").unwrap());
+        assert_eq!(hits.len(), 1);
+    }
+
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(levenshtein("depot", "depot"), 0);
+        assert_eq!(levenshtein("depot", "deopt"), 2);
+        assert_eq!(levenshtein("", "abc"), 3);
+    }
+}
@@ -0,0 +1,369 @@
+use crate::data::{Account, FinancialData, Transaction};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write;
+use uuid::Uuid;
+
+/// Bookkeeping fields that change without the books changing
+const IGNORED_FIELDS: &[&str] = &["created_at", "updated_at"];
+
+/// A named, point-in-time copy of the books (e.g. "2026-09 close")
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub data: FinancialData,
+}
+
+/// Kind of change to a record between two states
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+/// A single field that differs between two versions of a record
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub before: Value,
+    pub after: Value,
+}
+
+/// An account or transaction that was added, removed or modified
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordChange {
+    pub id: Uuid,
+    pub label: String,
+    pub kind: ChangeKind,
+    pub fields: Vec<FieldChange>,
+}
+
+/// Differences between two states of the books
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DataDiff {
+    pub accounts: Vec<RecordChange>,
+    pub transactions: Vec<RecordChange>,
+}
+
+impl Snapshot {
+    /// Capture the current state of the books under a name
+    pub fn new(name: impl Into<String>, data: &FinancialData) -> Self {
+        Self {
+            name: name.into(),
+            created_at: Utc::now(),
+            data: data.clone(),
+        }
+    }
+
+    /// Diff this snapshot against a later state
+    pub fn diff(&self, later: &FinancialData) -> DataDiff {
+        DataDiff::between(&self.data, later)
+    }
+}
+
+impl DataDiff {
+    /// Compute the changes needed to go from `before` to `after`
+    pub fn between(before: &FinancialData, after: &FinancialData) -> Self {
+        Self {
+            accounts: diff_records(
+                &before.accounts,
+                &after.accounts,
+                |a: &Account| a.id,
+                account_label,
+            ),
+            transactions: diff_records(
+                &before.transactions,
+                &after.transactions,
+                |t: &Transaction| t.id,
+                transaction_label,
+            ),
+        }
+    }
+
+    /// Whether the two states are identical
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty() && self.transactions.is_empty()
+    }
+
+    /// Count changes of a given kind across accounts and transactions
+    pub fn count(&self, kind: ChangeKind) -> (usize, usize) {
+        (
+            self.accounts.iter().filter(|c| c.kind == kind).count(),
+            self.transactions.iter().filter(|c| c.kind == kind).count(),
+        )
+    }
+
+    /// Serialize the diff as pretty-printed JSON
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).context("Failed to serialize diff")
+    }
+
+    /// Render the diff as a human-readable report
+    pub fn to_text_report(&self) -> String {
+        let mut out = String::new();
+
+        if self.is_empty() {
+            out.push_str("No changes.\n");
+            return out;
+        }
+
+        for (title, changes) in [
+            ("Accounts", &self.accounts),
+            ("Transactions", &self.transactions),
+        ] {
+            if changes.is_empty() {
+                continue;
+            }
+
+            let _ = writeln!(out, "{} ({} changed)", title, changes.len());
+            for change in changes {
+                let marker = match change.kind {
+                    ChangeKind::Added => '+',
+                    ChangeKind::Removed => '-',
+                    ChangeKind::Modified => '~',
+                };
+                let _ = writeln!(out, "  {} {}", marker, change.label);
+
+                for field in &change.fields {
+                    let _ = writeln!(
+                        out,
+                        "      {}: {} -> {}",
+                        field.field,
+                        display_value(&field.before),
+                        display_value(&field.after)
+                    );
+                }
+            }
+        }
+
+        let (accounts_added, transactions_added) = self.count(ChangeKind::Added);
+        let (accounts_removed, transactions_removed) = self.count(ChangeKind::Removed);
+        let (accounts_modified, transactions_modified) = self.count(ChangeKind::Modified);
+        let _ = writeln!(
+            out,
+            "Summary: accounts +{} -{} ~{}, transactions +{} -{} ~{}",
+            accounts_added,
+            accounts_removed,
+            accounts_modified,
+            transactions_added,
+            transactions_removed,
+            transactions_modified
+        );
+
+        out
+    }
+}
+
+fn diff_records<T: Serialize>(
+    before: &[T],
+    after: &[T],
+    id_of: impl Fn(&T) -> Uuid,
+    label_of: impl Fn(&T) -> String,
+) -> Vec<RecordChange> {
+    let before_by_id: HashMap<Uuid, &T> = before.iter().map(|r| (id_of(r), r)).collect();
+    let after_ids: BTreeSet<Uuid> = after.iter().map(&id_of).collect();
+    let mut changes = Vec::new();
+
+    for record in after {
+        let id = id_of(record);
+        match before_by_id.get(&id) {
+            None => changes.push(RecordChange {
+                id,
+                label: label_of(record),
+                kind: ChangeKind::Added,
+                fields: Vec::new(),
+            }),
+            Some(old) => {
+                let fields = diff_fields(*old, record);
+                if !fields.is_empty() {
+                    changes.push(RecordChange {
+                        id,
+                        label: label_of(record),
+                        kind: ChangeKind::Modified,
+                        fields,
+                    });
+                }
+            }
+        }
+    }
+
+    for record in before {
+        let id = id_of(record);
+        if !after_ids.contains(&id) {
+            changes.push(RecordChange {
+                id,
+                label: label_of(record),
+                kind: ChangeKind::Removed,
+                fields: Vec::new(),
+            });
+        }
+    }
+
+    changes
+}
+
+fn diff_fields<T: Serialize>(before: &T, after: &T) -> Vec<FieldChange> {
+    let (Ok(Value::Object(before)), Ok(Value::Object(after))) =
+        (serde_json::to_value(before), serde_json::to_value(after))
+    else {
+        return Vec::new();
+    };
+
+    let keys: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    keys.into_iter()
+        .filter(|key| !IGNORED_FIELDS.contains(&key.as_str()))
+        .filter_map(|key| {
+            let old = before.get(key).cloned().unwrap_or(Value::Null);
+            let new = after.get(key).cloned().unwrap_or(Value::Null);
+            (old != new).then(|| FieldChange {
+                field: key.clone(),
+                before: old,
+                after: new,
+            })
+        })
+        .collect()
+}
+
+fn account_label(account: &Account) -> String {
+    account.name.clone()
+}
+
+fn transaction_label(transaction: &Transaction) -> String {
+    format!(
+        "{} {} {}",
+        transaction.date.format("%Y-%m-%d"),
+        transaction
+            .payee
+            .as_deref()
+            .unwrap_or(&transaction.description),
+        transaction.amount
+    )
+}
+
+fn display_value(value: &Value) -> String {
+    match value {
+        Value::Null => "(none)".to_string(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{AccountType, TransactionType};
+    use crate::storage::{test_database_config, Database};
+    use chrono::TimeZone;
+    use rust_decimal_macros::dec;
+
+    fn books() -> FinancialData {
+        let mut data = FinancialData::new();
+        let account = Account::new(
+            "Checking".to_string(),
+            AccountType::Checking,
+            dec!(500.00),
+            "USD".to_string(),
+        );
+        let account_id = account.id;
+        data.add_account(account);
+
+        for (day, amount, payee) in [(3, dec!(42.50), "Home Depot"), (9, dec!(12.00), "Cafe")] {
+            let mut transaction = Transaction::new(
+                account_id,
+                Utc.with_ymd_and_hms(2026, 9, day, 0, 0, 0).unwrap(),
+                amount,
+                payee.to_string(),
+                TransactionType::Debit,
+            );
+            transaction.payee = Some(payee.to_string());
+            data.add_transaction(transaction);
+        }
+        data
+    }
+
+    #[test]
+    fn test_diff_added_removed_modified() {
+        let before = books();
+        let mut after = before.clone();
+
+        after.transactions[0].category = Some("Home".to_string());
+        after.transactions[0].mark_cleared();
+        after.transactions.remove(1);
+        after.accounts[0].update_balance(dec!(457.50));
+
+        let mut new_tx = Transaction::new(
+            after.accounts[0].id,
+            Utc.with_ymd_and_hms(2026, 10, 1, 0, 0, 0).unwrap(),
+            dec!(80.00),
+            "Gas".to_string(),
+            TransactionType::Debit,
+        );
+        new_tx.payee = Some("Shell".to_string());
+        after.add_transaction(new_tx);
+
+        let diff = DataDiff::between(&before, &after);
+
+        assert_eq!(diff.count(ChangeKind::Added), (0, 1));
+        assert_eq!(diff.count(ChangeKind::Removed), (0, 1));
+        assert_eq!(diff.count(ChangeKind::Modified), (1, 1));
+
+        let modified = diff
+            .transactions
+            .iter()
+            .find(|c| c.kind == ChangeKind::Modified)
+            .unwrap();
+        let fields: Vec<&str> = modified.fields.iter().map(|f| f.field.as_str()).collect();
+        assert_eq!(fields, vec!["category", "cleared"]);
+
+        let report = diff.to_text_report();
+        assert!(report.contains("+ 2026-10-01 Shell 80.00"));
+        assert!(report.contains("- 2026-09-09 Cafe 12.00"));
+        assert!(report.contains("category: (none) -> Home"));
+
+        let json: Value = serde_json::from_str(&diff.to_json().unwrap()).unwrap();
+        assert_eq!(json["accounts"][0]["fields"][0]["field"], "balance");
+    }
+
+    #[test]
+    fn test_identical_states() {
+        let data = books();
+        let snapshot = Snapshot::new("2026-09 close", &data);
+        let diff = snapshot.diff(&data);
+        assert!(diff.is_empty());
+        assert_eq!(diff.to_text_report(), "No changes.\n");
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_persistence() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = test_database_config(dir.path(), false);
+        let database = Database::open_with_passphrase(&config, None).await.unwrap();
+
+        let data = books();
+        database
+            .save_snapshot(&Snapshot::new("2026-09 close", &data))
+            .await
+            .unwrap();
+
+        let listed = database.list_snapshots().await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].0, "2026-09 close");
+
+        let loaded = database
+            .load_snapshot("2026-09 close")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(loaded.data.transactions, data.transactions);
+        assert!(database.load_snapshot("missing").await.unwrap().is_none());
+
+        assert!(database.delete_snapshot("2026-09 close").await.unwrap());
+        assert!(database.list_snapshots().await.unwrap().is_empty());
+    }
+}
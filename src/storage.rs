@@ -0,0 +1,1090 @@
+use crate::backup::{BackupManager, BackupReason};
+use crate::candidates::Candidate;
+use crate::categorize::{ReviewItem, ReviewStatus};
+use crate::config::DatabaseConfig;
+use crate::crypto::{Cipher, CryptoError, KdfParams};
+use crate::data::{Account, FinancialData, Transaction};
+use crate::scheduler::JobRun;
+use crate::search::{SearchEngine, SearchHit, SearchQuery};
+use crate::snapshot::Snapshot;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteConnection, SqlitePool, SqlitePoolOptions};
+use sqlx::Row;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Current schema version of the SQLite store
+pub const SCHEMA_VERSION: i64 = 7;
+
+/// Known plaintext sealed into the store so a wrong passphrase is detected on open
+const KEY_CHECK: &[u8] = b"qspec-fin-agent key check";
+
+const SCHEMA: &[&str] = &[
+    "CREATE TABLE IF NOT EXISTS meta (key TEXT PRIMARY KEY, value BLOB NOT NULL)",
+    "CREATE TABLE IF NOT EXISTS accounts (id TEXT PRIMARY KEY, payload BLOB NOT NULL)",
+    "CREATE TABLE IF NOT EXISTS transactions (
+        id TEXT PRIMARY KEY,
+        account_id TEXT NOT NULL,
+        payload BLOB NOT NULL
+    )",
+    "CREATE INDEX IF NOT EXISTS idx_transactions_account ON transactions (account_id)",
+    "CREATE TABLE IF NOT EXISTS snapshots (
+        name TEXT PRIMARY KEY,
+        created_at TEXT NOT NULL,
+        payload BLOB NOT NULL
+    )",
+    "CREATE TABLE IF NOT EXISTS job_runs (
+        id TEXT PRIMARY KEY,
+        job TEXT NOT NULL,
+        started_at TEXT NOT NULL,
+        success INTEGER NOT NULL,
+        payload BLOB NOT NULL
+    )",
+    "CREATE INDEX IF NOT EXISTS idx_job_runs_job ON job_runs (job, started_at)",
+    "CREATE TABLE IF NOT EXISTS category_reviews (
+        transaction_id TEXT PRIMARY KEY,
+        status TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        payload BLOB NOT NULL
+    )",
+    "CREATE TABLE IF NOT EXISTS candidate_reviews (
+        id TEXT PRIMARY KEY,
+        status TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        payload BLOB NOT NULL
+    )",
+    "CREATE TABLE IF NOT EXISTS models (
+        name TEXT PRIMARY KEY,
+        updated_at TEXT NOT NULL,
+        payload BLOB NOT NULL
+    )",
+    "CREATE VIRTUAL TABLE IF NOT EXISTS transactions_fts USING fts5 (
+        id UNINDEXED,
+        description,
+        payee,
+        memo,
+        category
+    )",
+];
+
+/// First schema version with the full-text index
+const FTS_SCHEMA_VERSION: i64 = 3;
+
+/// Tables holding encoded payloads, with their key column
+const PAYLOAD_TABLES: &[(&str, &str)] = &[
+    ("accounts", "id"),
+    ("transactions", "id"),
+    ("snapshots", "name"),
+    ("job_runs", "id"),
+    ("category_reviews", "transaction_id"),
+    ("candidate_reviews", "id"),
+    ("models", "name"),
+];
+
+/// SQLite-backed persistence for financial data
+///
+/// Records are stored as JSON payloads. When encryption is enabled every payload
+/// is sealed with a key derived from the passphrase, so account numbers and
+/// transaction details never reach the disk in plaintext.
+pub struct Database {
+    pool: SqlitePool,
+    cipher: Option<Cipher>,
+}
+
+impl Database {
+    /// Open the database described by the configuration, resolving the passphrase if needed
+    pub async fn open(config: &DatabaseConfig) -> Result<Self> {
+        let passphrase = config.encryption.resolve_passphrase()?;
+
+        if config.encryption.enabled && passphrase.is_none() {
+            return Err(
+                CryptoError::MissingPassphrase(config.encryption.passphrase_env.clone()).into(),
+            );
+        }
+
+        Self::open_with_passphrase(config, passphrase.as_deref()).await
+    }
+
+    /// Open the database with an explicit passphrase
+    pub async fn open_with_passphrase(
+        config: &DatabaseConfig,
+        passphrase: Option<&str>,
+    ) -> Result<Self> {
+        if let Some(parent) = config.path.parent() {
+            if !parent.as_os_str().is_empty() {
+                tokio::fs::create_dir_all(parent)
+                    .await
+                    .context("Failed to create database directory")?;
+            }
+        }
+
+        let pool = Self::connect(&config.path, config.max_connections).await?;
+        let mut database = Self { pool, cipher: None };
+        let previous_version = database.migrate(config).await?;
+
+        match database.stored_cipher(config, passphrase).await? {
+            Some(cipher) => database.cipher = Some(cipher),
+            None => {
+                if let Some(passphrase) = passphrase.filter(|_| config.encryption.enabled) {
+                    database.refuse_plaintext(config).await?;
+                    let cipher = Cipher::generate(passphrase, config.encryption.kdf)?;
+                    database.install_cipher(cipher).await?;
+                }
+            }
+        }
+
+        if matches!(previous_version, Some(version) if version < FTS_SCHEMA_VERSION) {
+            database.rebuild_search_index().await?;
+        }
+
+        Ok(database)
+    }
+
+    /// Open an existing database without creating, migrating or writing to it
+    ///
+    /// The passphrase is checked the same way as by `open_with_passphrase`.
+    pub async fn open_read_only(config: &DatabaseConfig, passphrase: Option<&str>) -> Result<Self> {
+        let options = SqliteConnectOptions::new()
+            .filename(&config.path)
+            .read_only(true);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(options)
+            .await
+            .with_context(|| format!("Failed to open database {}", config.path.display()))?;
+
+        let mut database = Self { pool, cipher: None };
+        let checked = async {
+            database.cipher = database.stored_cipher(config, passphrase).await?;
+            if database.cipher.is_none() && passphrase.is_some() && config.encryption.enabled {
+                database.refuse_plaintext(config).await?;
+            }
+            Ok(())
+        }
+        .await;
+        match checked {
+            Ok(()) => Ok(database),
+            Err(e) => {
+                database.close().await;
+                Err(e)
+            }
+        }
+    }
+
+    /// Unlock an encrypted store with the passphrase, or `None` for a plaintext one
+    async fn stored_cipher(
+        &self,
+        config: &DatabaseConfig,
+        passphrase: Option<&str>,
+    ) -> Result<Option<Cipher>> {
+        let Some(check) = self.get_meta_raw("key_check").await? else {
+            return Ok(None);
+        };
+        let Some(passphrase) = passphrase else {
+            return Err(
+                CryptoError::MissingPassphrase(config.encryption.passphrase_env.clone()).into(),
+            );
+        };
+        let header = self
+            .get_meta_raw("key_header")
+            .await?
+            .context("Encrypted database is missing its key header")?;
+        let cipher = Cipher::from_header(passphrase, &header)?;
+        if cipher.open(&check)? != KEY_CHECK {
+            return Err(CryptoError::WrongPassphrase.into());
+        }
+        Ok(Some(cipher))
+    }
+
+    /// Refuse to treat a plaintext store holding records as encrypted
+    async fn refuse_plaintext(&self, config: &DatabaseConfig) -> Result<()> {
+        if self.has_records().await? {
+            bail!(
+                "Database {} holds unencrypted data; run `qspec db rotate-key` to encrypt it",
+                config.path.display()
+            );
+        }
+        Ok(())
+    }
+
+    /// Bring the schema up to date, backing up older stores first
+    ///
+    /// Returns the schema version found before migrating.
+    async fn migrate(&self, config: &DatabaseConfig) -> Result<Option<i64>> {
+        let current = self.schema_version().await?;
+
+        if matches!(current, Some(version) if version < SCHEMA_VERSION) && config.backup.enabled {
+            BackupManager::new(config)
+                .create_backup(self, BackupReason::Migration, Utc::now())
+                .await?;
+        }
+
+        for statement in SCHEMA {
+            sqlx::query(statement)
+                .execute(&self.pool)
+                .await
+                .context("Failed to create database schema")?;
+        }
+
+        if current != Some(SCHEMA_VERSION) {
+            self.set_meta_raw("schema_version", SCHEMA_VERSION.to_string().as_bytes())
+                .await?;
+        }
+
+        Ok(current)
+    }
+
+    /// Schema version recorded in the store, if it has been initialized
+    pub async fn schema_version(&self) -> Result<Option<i64>> {
+        let has_meta =
+            sqlx::query("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'meta'")
+                .fetch_optional(&self.pool)
+                .await?
+                .is_some();
+
+        if !has_meta {
+            return Ok(None);
+        }
+
+        match self.get_meta_raw("schema_version").await? {
+            Some(bytes) => {
+                let version = String::from_utf8_lossy(&bytes)
+                    .parse()
+                    .context("Invalid schema version in database")?;
+                Ok(Some(version))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Write a transactionally consistent copy of the database to `path`
+    pub async fn backup_to(&self, path: &Path) -> Result<()> {
+        sqlx::query("VACUUM INTO ?")
+            .bind(path.to_string_lossy().into_owned())
+            .execute(&self.pool)
+            .await
+            .with_context(|| format!("Failed to write database snapshot {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Run SQLite's integrity check, returning the reported problems (empty when healthy)
+    pub async fn integrity_check(&self) -> Result<Vec<String>> {
+        let rows = sqlx::query("PRAGMA integrity_check")
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to run integrity check")?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| row.get::<String, _>(0))
+            .filter(|message| message != "ok")
+            .collect())
+    }
+
+    async fn connect(path: &Path, max_connections: u32) -> Result<SqlitePool> {
+        let options = SqliteConnectOptions::new()
+            .filename(path)
+            .create_if_missing(true);
+
+        SqlitePoolOptions::new()
+            .max_connections(max_connections.max(1))
+            .connect_with(options)
+            .await
+            .with_context(|| format!("Failed to open database {}", path.display()))
+    }
+
+    /// Whether the store contents are encrypted
+    pub fn is_encrypted(&self) -> bool {
+        self.cipher.is_some()
+    }
+
+    /// Close all connections
+    pub async fn close(self) {
+        self.pool.close().await;
+    }
+
+    /// Replace the stored data with the given financial data
+    pub async fn save_data(&self, data: &FinancialData) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM transactions")
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM accounts")
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM transactions_fts")
+            .execute(&mut *tx)
+            .await?;
+
+        for account in &data.accounts {
+            sqlx::query("INSERT INTO accounts (id, payload) VALUES (?, ?)")
+                .bind(account.id.to_string())
+                .bind(self.encode(account)?)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        for transaction in &data.transactions {
+            sqlx::query("INSERT INTO transactions (id, account_id, payload) VALUES (?, ?, ?)")
+                .bind(transaction.id.to_string())
+                .bind(transaction.account_id.to_string())
+                .bind(self.encode(transaction)?)
+                .execute(&mut *tx)
+                .await?;
+
+            if self.cipher.is_none() {
+                index_transaction(&mut tx, transaction).await?;
+            }
+        }
+
+        for (key, values) in [("categories", &data.categories), ("payees", &data.payees)] {
+            sqlx::query("INSERT OR REPLACE INTO meta (key, value) VALUES (?, ?)")
+                .bind(key)
+                .bind(self.encode(values)?)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await.context("Failed to save financial data")?;
+        Ok(())
+    }
+
+    /// Load all stored financial data
+    pub async fn load_data(&self) -> Result<FinancialData> {
+        let mut data = FinancialData::new();
+        data.accounts = self.load_accounts().await?;
+
+        let rows = sqlx::query("SELECT payload FROM transactions ORDER BY rowid")
+            .fetch_all(&self.pool)
+            .await?;
+        for row in rows {
+            data.transactions.push(self.decode(row.get("payload"))?);
+        }
+
+        if let Some(categories) = self.get_meta("categories").await? {
+            data.categories = categories;
+        }
+        if let Some(payees) = self.get_meta("payees").await? {
+            data.payees = payees;
+        }
+
+        Ok(data)
+    }
+
+    /// Load all stored accounts
+    pub async fn load_accounts(&self) -> Result<Vec<Account>> {
+        let rows = sqlx::query("SELECT payload FROM accounts ORDER BY rowid")
+            .fetch_all(&self.pool)
+            .await?;
+
+        rows.into_iter()
+            .map(|row| self.decode(row.get("payload")))
+            .collect()
+    }
+
+    /// Insert or update a single account
+    pub async fn upsert_account(&self, account: &Account) -> Result<()> {
+        sqlx::query("INSERT OR REPLACE INTO accounts (id, payload) VALUES (?, ?)")
+            .bind(account.id.to_string())
+            .bind(self.encode(account)?)
+            .execute(&self.pool)
+            .await
+            .context("Failed to save account")?;
+        Ok(())
+    }
+
+    /// Insert or update a single transaction
+    pub async fn upsert_transaction(&self, transaction: &Transaction) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            "INSERT OR REPLACE INTO transactions (id, account_id, payload) VALUES (?, ?, ?)",
+        )
+        .bind(transaction.id.to_string())
+        .bind(transaction.account_id.to_string())
+        .bind(self.encode(transaction)?)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("DELETE FROM transactions_fts WHERE id = ?")
+            .bind(transaction.id.to_string())
+            .execute(&mut *tx)
+            .await?;
+        if self.cipher.is_none() {
+            index_transaction(&mut tx, transaction).await?;
+        }
+
+        tx.commit().await.context("Failed to save transaction")?;
+        Ok(())
+    }
+
+    /// Search transactions
+    ///
+    /// Plaintext stores pre-filter candidates through the SQLite FTS5 index;
+    /// encrypted stores keep no plaintext index, so the search runs in memory
+    /// over the decrypted data. Both paths apply the same matching rules.
+    pub async fn search(&self, query: &SearchQuery) -> Result<Vec<SearchHit>> {
+        let expression = match (&self.cipher, query.fts_expression()) {
+            (None, Some(expression)) => expression,
+            _ => return Ok(SearchEngine::search(&self.load_data().await?, query)),
+        };
+
+        let rows = sqlx::query(
+            "SELECT payload FROM transactions WHERE id IN
+                (SELECT id FROM transactions_fts WHERE transactions_fts MATCH ?)",
+        )
+        .bind(expression)
+        .fetch_all(&self.pool)
+        .await
+        .context("Full-text search failed")?;
+
+        let candidates: Vec<Transaction> = rows
+            .into_iter()
+            .map(|row| self.decode(row.get("payload")))
+            .collect::<Result<_>>()?;
+        let accounts = self.load_accounts().await?;
+
+        Ok(SearchEngine::search_transactions(
+            &accounts,
+            &candidates,
+            query,
+        ))
+    }
+
+    /// Rebuild the full-text index from the stored transactions
+    ///
+    /// Encrypted stores never keep a plaintext index, so this only clears it.
+    pub async fn rebuild_search_index(&self) -> Result<()> {
+        let transactions = if self.cipher.is_none() {
+            self.load_data().await?.transactions
+        } else {
+            Vec::new()
+        };
+
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM transactions_fts")
+            .execute(&mut *tx)
+            .await?;
+        for transaction in &transactions {
+            index_transaction(&mut tx, transaction).await?;
+        }
+        tx.commit()
+            .await
+            .context("Failed to rebuild search index")?;
+        Ok(())
+    }
+
+    /// Store a named snapshot, replacing any existing snapshot with the same name
+    pub async fn save_snapshot(&self, snapshot: &Snapshot) -> Result<()> {
+        sqlx::query(
+            "INSERT OR REPLACE INTO snapshots (name, created_at, payload) VALUES (?, ?, ?)",
+        )
+        .bind(&snapshot.name)
+        .bind(snapshot.created_at.to_rfc3339())
+        .bind(self.encode(snapshot)?)
+        .execute(&self.pool)
+        .await
+        .context("Failed to save snapshot")?;
+        Ok(())
+    }
+
+    /// Load a snapshot by name
+    pub async fn load_snapshot(&self, name: &str) -> Result<Option<Snapshot>> {
+        let row = sqlx::query("SELECT payload FROM snapshots WHERE name = ?")
+            .bind(name)
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to load snapshot")?;
+
+        row.map(|r| self.decode(r.get("payload"))).transpose()
+    }
+
+    /// List snapshot names and creation times, oldest first
+    pub async fn list_snapshots(&self) -> Result<Vec<(String, DateTime<Utc>)>> {
+        let rows = sqlx::query("SELECT name, created_at FROM snapshots ORDER BY created_at")
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to list snapshots")?;
+
+        rows.into_iter()
+            .map(|row| {
+                let created_at: String = row.get("created_at");
+                let created_at = DateTime::parse_from_rfc3339(&created_at)
+                    .context("Invalid snapshot timestamp")?
+                    .with_timezone(&Utc);
+                Ok((row.get("name"), created_at))
+            })
+            .collect()
+    }
+
+    /// Delete a snapshot, returning whether it existed
+    pub async fn delete_snapshot(&self, name: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM snapshots WHERE name = ?")
+            .bind(name)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete snapshot")?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Record the outcome of a scheduled job
+    pub async fn record_job_run(&self, run: &JobRun) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO job_runs (id, job, started_at, success, payload) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(uuid::Uuid::new_v4().to_string())
+        .bind(&run.job)
+        // Fixed-width timestamps so text ordering matches time ordering
+        .bind(run.started_at.to_rfc3339_opts(SecondsFormat::Micros, true))
+        .bind(run.success)
+        .bind(self.encode(run)?)
+        .execute(&self.pool)
+        .await
+        .context("Failed to record job run")?;
+        Ok(())
+    }
+
+    /// Job runs, newest first, optionally for one job and only failures
+    pub async fn job_runs(
+        &self,
+        job: Option<&str>,
+        failures_only: bool,
+        limit: usize,
+    ) -> Result<Vec<JobRun>> {
+        let rows = sqlx::query(
+            "SELECT payload FROM job_runs
+             WHERE (?1 IS NULL OR job = ?1) AND (?2 = 0 OR success = 0)
+             ORDER BY started_at DESC
+             LIMIT ?3",
+        )
+        .bind(job)
+        .bind(failures_only)
+        .bind(i64::try_from(limit).unwrap_or(i64::MAX))
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to load job runs")?;
+
+        rows.into_iter()
+            .map(|row| self.decode(row.get("payload")))
+            .collect()
+    }
+
+    /// Most recent run of every job that has run
+    pub async fn last_job_runs(&self) -> Result<HashMap<String, JobRun>> {
+        let rows = sqlx::query(
+            "SELECT payload FROM job_runs AS r
+             WHERE started_at = (SELECT MAX(started_at) FROM job_runs WHERE job = r.job)",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to load job runs")?;
+
+        rows.into_iter()
+            .map(|row| {
+                let run: JobRun = self.decode(row.get("payload"))?;
+                Ok((run.job.clone(), run))
+            })
+            .collect()
+    }
+
+    /// Add or update a categorization suggestion
+    pub async fn save_review(&self, item: &ReviewItem) -> Result<()> {
+        sqlx::query(
+            "INSERT OR REPLACE INTO category_reviews (transaction_id, status, created_at, payload)
+             VALUES (?, ?, ?, ?)",
+        )
+        .bind(item.suggestion.transaction_id.to_string())
+        .bind(item.status.as_str())
+        .bind(item.created_at.to_rfc3339_opts(SecondsFormat::Micros, true))
+        .bind(self.encode(item)?)
+        .execute(&self.pool)
+        .await
+        .context("Failed to save categorization review")?;
+        Ok(())
+    }
+
+    /// Categorization suggestions, oldest first, optionally with one status
+    pub async fn reviews(&self, status: Option<ReviewStatus>) -> Result<Vec<ReviewItem>> {
+        let rows = sqlx::query(
+            "SELECT payload FROM category_reviews
+             WHERE ?1 IS NULL OR status = ?1
+             ORDER BY created_at, transaction_id",
+        )
+        .bind(status.map(|s| s.as_str()))
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to load categorization reviews")?;
+
+        rows.into_iter()
+            .map(|row| self.decode(row.get("payload")))
+            .collect()
+    }
+
+    /// Add or update a proposed transaction in the review queue
+    pub async fn save_candidate(&self, candidate: &Candidate) -> Result<()> {
+        sqlx::query(
+            "INSERT OR REPLACE INTO candidate_reviews (id, status, created_at, payload)
+             VALUES (?, ?, ?, ?)",
+        )
+        .bind(candidate.transaction.id.to_string())
+        .bind(candidate.status.as_str())
+        .bind(
+            candidate
+                .created_at
+                .to_rfc3339_opts(SecondsFormat::Micros, true),
+        )
+        .bind(self.encode(candidate)?)
+        .execute(&self.pool)
+        .await
+        .context("Failed to save transaction candidate")?;
+        Ok(())
+    }
+
+    /// Proposed transactions, oldest first, optionally with one status
+    pub async fn candidates(&self, status: Option<ReviewStatus>) -> Result<Vec<Candidate>> {
+        let rows = sqlx::query(
+            "SELECT payload FROM candidate_reviews
+             WHERE ?1 IS NULL OR status = ?1
+             ORDER BY created_at, id",
+        )
+        .bind(status.map(|s| s.as_str()))
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to load transaction candidates")?;
+
+        rows.into_iter()
+            .map(|row| self.decode(row.get("payload")))
+            .collect()
+    }
+
+    /// Store learned state, such as the local categorizer, under `name`
+    pub async fn save_model<T: Serialize>(&self, name: &str, model: &T) -> Result<()> {
+        sqlx::query("INSERT OR REPLACE INTO models (name, updated_at, payload) VALUES (?, ?, ?)")
+            .bind(name)
+            .bind(Utc::now().to_rfc3339())
+            .bind(self.encode(model)?)
+            .execute(&self.pool)
+            .await
+            .with_context(|| format!("Failed to save model '{}'", name))?;
+        Ok(())
+    }
+
+    /// Load learned state saved with `save_model`
+    pub async fn load_model<T: DeserializeOwned>(&self, name: &str) -> Result<Option<T>> {
+        let row = sqlx::query("SELECT payload FROM models WHERE name = ?")
+            .bind(name)
+            .fetch_optional(&self.pool)
+            .await
+            .with_context(|| format!("Failed to load model '{}'", name))?;
+
+        row.map(|r| self.decode(r.get("payload"))).transpose()
+    }
+
+    /// Re-encrypt every record under a new passphrase
+    ///
+    /// Also used to encrypt a store that was previously plaintext.
+    pub async fn rotate_key(&mut self, new_passphrase: &str, params: KdfParams) -> Result<()> {
+        let new_cipher = Cipher::generate(new_passphrase, params)?;
+        self.reencrypt(Some(new_cipher)).await
+    }
+
+    /// Decrypt every record and store the data in plaintext
+    pub async fn remove_encryption(&mut self) -> Result<()> {
+        self.reencrypt(None).await
+    }
+
+    async fn reencrypt(&mut self, new_cipher: Option<Cipher>) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        for (table, key) in PAYLOAD_TABLES {
+            let rows = sqlx::query(&format!("SELECT {} AS id, payload FROM {}", key, table))
+                .fetch_all(&mut *tx)
+                .await?;
+
+            for row in rows {
+                let id: String = row.get("id");
+                let plaintext = self.unseal(row.get("payload"))?;
+                let payload = seal_with(new_cipher.as_ref(), plaintext);
+
+                sqlx::query(&format!(
+                    "UPDATE {} SET payload = ? WHERE {} = ?",
+                    table, key
+                ))
+                .bind(payload)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+
+        let rows = sqlx::query(
+            "SELECT key, value FROM meta WHERE key NOT IN ('schema_version', 'key_check', 'key_header')",
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        for row in rows {
+            let key: String = row.get("key");
+            let plaintext = self.unseal(row.get("value"))?;
+            sqlx::query("UPDATE meta SET value = ? WHERE key = ?")
+                .bind(seal_with(new_cipher.as_ref(), plaintext))
+                .bind(key)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        sqlx::query("DELETE FROM meta WHERE key IN ('key_check', 'key_header')")
+            .execute(&mut *tx)
+            .await?;
+        if let Some(ref cipher) = new_cipher {
+            for (key, value) in [
+                ("key_header", cipher.header()),
+                ("key_check", cipher.seal(KEY_CHECK)),
+            ] {
+                sqlx::query("INSERT INTO meta (key, value) VALUES (?, ?)")
+                    .bind(key)
+                    .bind(value)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+        }
+
+        tx.commit().await.context("Failed to re-encrypt database")?;
+        self.cipher = new_cipher;
+        self.rebuild_search_index().await?;
+
+        // Old payloads linger in free pages and the WAL until both are rewritten
+        sqlx::query("VACUUM")
+            .execute(&self.pool)
+            .await
+            .context("Failed to vacuum re-encrypted database")?;
+        sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+            .execute(&self.pool)
+            .await
+            .context("Failed to checkpoint re-encrypted database")?;
+        Ok(())
+    }
+
+    async fn install_cipher(&mut self, cipher: Cipher) -> Result<()> {
+        self.set_meta_raw("key_header", &cipher.header()).await?;
+        self.set_meta_raw("key_check", &cipher.seal(KEY_CHECK))
+            .await?;
+        self.cipher = Some(cipher);
+        Ok(())
+    }
+
+    async fn has_records(&self) -> Result<bool> {
+        let row = sqlx::query(
+            "SELECT (SELECT COUNT(*) FROM accounts) + (SELECT COUNT(*) FROM transactions) AS n",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(row.get::<i64, _>("n") > 0)
+    }
+
+    /// Read a serializable value from the metadata table
+    pub(crate) async fn get_meta<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        match self.get_meta_raw(key).await? {
+            Some(bytes) => Ok(Some(self.decode(bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn set_meta_raw(&self, key: &str, value: &[u8]) -> Result<()> {
+        sqlx::query("INSERT OR REPLACE INTO meta (key, value) VALUES (?, ?)")
+            .bind(key)
+            .bind(value)
+            .execute(&self.pool)
+            .await
+            .context("Failed to write database metadata")?;
+        Ok(())
+    }
+
+    async fn get_meta_raw(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let row = sqlx::query("SELECT value FROM meta WHERE key = ?")
+            .bind(key)
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to read database metadata")?;
+        Ok(row.map(|r| r.get("value")))
+    }
+
+    /// Serialize a value to a payload, sealing it when encryption is enabled
+    pub(crate) fn encode<T: Serialize + ?Sized>(&self, value: &T) -> Result<Vec<u8>> {
+        let json = serde_json::to_vec(value).context("Failed to serialize record")?;
+        Ok(seal_with(self.cipher.as_ref(), json))
+    }
+
+    /// Deserialize a payload produced by [`Database::encode`]
+    pub(crate) fn decode<T: DeserializeOwned>(&self, payload: Vec<u8>) -> Result<T> {
+        let json = self.unseal(payload)?;
+        serde_json::from_slice(&json).context("Failed to deserialize record")
+    }
+
+    fn unseal(&self, payload: Vec<u8>) -> Result<Vec<u8>> {
+        match self.cipher {
+            Some(ref cipher) => Ok(cipher.open(&payload)?),
+            None => Ok(payload),
+        }
+    }
+}
+
+async fn index_transaction(conn: &mut SqliteConnection, transaction: &Transaction) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO transactions_fts (id, description, payee, memo, category) VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(transaction.id.to_string())
+    .bind(&transaction.description)
+    .bind(&transaction.payee)
+    .bind(&transaction.memo)
+    .bind(&transaction.category)
+    .execute(conn)
+    .await
+    .context("Failed to index transaction")?;
+    Ok(())
+}
+
+fn seal_with(cipher: Option<&Cipher>, plaintext: Vec<u8>) -> Vec<u8> {
+    match cipher {
+        Some(cipher) => cipher.seal(&plaintext),
+        None => plaintext,
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn test_database_config(dir: &Path, encrypted: bool) -> DatabaseConfig {
+    use crate::config::{BackupConfig, EncryptionConfig};
+
+    DatabaseConfig {
+        path: dir.join("test.db"),
+        max_connections: 1,
+        encryption: EncryptionConfig {
+            enabled: encrypted,
+            kdf: crate::crypto::TEST_KDF_PARAMS,
+            ..EncryptionConfig::default()
+        },
+        backup: BackupConfig {
+            directory: Some(dir.join("backups")),
+            ..BackupConfig::default()
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::TEST_KDF_PARAMS;
+    use crate::data::{AccountType, TransactionType};
+    use chrono::Utc;
+    use rust_decimal_macros::dec;
+    use tempfile::tempdir;
+
+    fn sample_data() -> FinancialData {
+        let mut data = FinancialData::new();
+        let mut account = Account::new(
+            "Checking".to_string(),
+            AccountType::Checking,
+            dec!(1000.00),
+            "USD".to_string(),
+        );
+        account.account_number = Some("9876543210".to_string());
+        let account_id = account.id;
+        data.add_account(account);
+
+        let mut transaction = Transaction::new(
+            account_id,
+            Utc::now(),
+            dec!(42.50),
+            "Hardware".to_string(),
+            TransactionType::Debit,
+        );
+        transaction.payee = Some("Home Depot".to_string());
+        transaction.category = Some("Home".to_string());
+        data.add_transaction(transaction);
+        data
+    }
+
+    #[tokio::test]
+    async fn test_save_and_load_plaintext() {
+        let dir = tempdir().unwrap();
+        let config = test_database_config(dir.path(), false);
+
+        let db = Database::open_with_passphrase(&config, None).await.unwrap();
+        let data = sample_data();
+        db.save_data(&data).await.unwrap();
+
+        let loaded = db.load_data().await.unwrap();
+        assert!(!db.is_encrypted());
+        assert_eq!(loaded.accounts, data.accounts);
+        assert_eq!(loaded.transactions, data.transactions);
+        assert_eq!(loaded.categories, data.categories);
+    }
+
+    #[tokio::test]
+    async fn test_open_read_only_leaves_the_file_alone() {
+        let dir = tempdir().unwrap();
+        let config = test_database_config(dir.path(), true);
+
+        let db = Database::open_with_passphrase(&config, Some("s3cret"))
+            .await
+            .unwrap();
+        db.save_data(&sample_data()).await.unwrap();
+        db.set_meta_raw("schema_version", b"6").await.unwrap();
+        db.close().await;
+        let before = std::fs::read(&config.path).unwrap();
+
+        let err = Database::open_read_only(&config, Some("wrong"))
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err.downcast_ref::<CryptoError>(),
+            Some(CryptoError::WrongPassphrase)
+        ));
+
+        let db = Database::open_read_only(&config, Some("s3cret"))
+            .await
+            .unwrap();
+        assert!(db.is_encrypted());
+        assert!(db.integrity_check().await.unwrap().is_empty());
+        assert_eq!(db.schema_version().await.unwrap(), Some(6));
+        db.close().await;
+        assert_eq!(std::fs::read(&config.path).unwrap(), before);
+
+        assert!(Database::open_read_only(
+            &test_database_config(&dir.path().join("none"), false),
+            None
+        )
+        .await
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_store_and_wrong_passphrase() {
+        let dir = tempdir().unwrap();
+        let config = test_database_config(dir.path(), true);
+
+        let db = Database::open_with_passphrase(&config, Some("s3cret"))
+            .await
+            .unwrap();
+        db.save_data(&sample_data()).await.unwrap();
+        db.close().await;
+
+        let raw = std::fs::read(&config.path).unwrap();
+        let needle = b"9876543210";
+        assert!(!raw.windows(needle.len()).any(|w| w == needle));
+
+        let err = Database::open_with_passphrase(&config, Some("wrong"))
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err.downcast_ref::<CryptoError>(),
+            Some(CryptoError::WrongPassphrase)
+        ));
+
+        let err = Database::open_with_passphrase(&config, None)
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err.downcast_ref::<CryptoError>(),
+            Some(CryptoError::MissingPassphrase(_))
+        ));
+
+        let db = Database::open_with_passphrase(&config, Some("s3cret"))
+            .await
+            .unwrap();
+        let loaded = db.load_data().await.unwrap();
+        assert_eq!(
+            loaded.accounts[0].account_number.as_deref(),
+            Some("9876543210")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_migration_takes_backup() {
+        let dir = tempdir().unwrap();
+        let config = test_database_config(dir.path(), false);
+
+        let db = Database::open_with_passphrase(&config, None).await.unwrap();
+        db.set_meta_raw("schema_version", b"1").await.unwrap();
+        db.close().await;
+
+        let db = Database::open_with_passphrase(&config, None).await.unwrap();
+        assert_eq!(db.schema_version().await.unwrap(), Some(SCHEMA_VERSION));
+
+        let backups = BackupManager::new(&config).list_backups().await.unwrap();
+        assert_eq!(backups.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_search_uses_index_and_encrypted_fallback() {
+        let dir = tempdir().unwrap();
+        let config = test_database_config(dir.path(), false);
+        let query = SearchQuery::parse(r#""home depot" hard* amount:40..50"#).unwrap();
+
+        let mut db = Database::open_with_passphrase(&config, None).await.unwrap();
+        db.save_data(&sample_data()).await.unwrap();
+
+        let hits = db.search(&query).await.unwrap();
+        assert_eq!(hits.len(), 1);
+        assert!(db
+            .search(&SearchQuery::parse("lumber").unwrap())
+            .await
+            .unwrap()
+            .is_empty());
+
+        let mut moved = hits[0].transaction.clone();
+        moved.payee = Some("Lowes".to_string());
+        db.upsert_transaction(&moved).await.unwrap();
+        assert!(db.search(&query).await.unwrap().is_empty());
+        moved.payee = Some("Home Depot".to_string());
+        db.upsert_transaction(&moved).await.unwrap();
+
+        db.rotate_key("pw", TEST_KDF_PARAMS).await.unwrap();
+        let indexed: i64 = sqlx::query("SELECT COUNT(*) AS n FROM transactions_fts")
+            .fetch_one(&db.pool)
+            .await
+            .unwrap()
+            .get("n");
+        assert_eq!(indexed, 0);
+        assert_eq!(db.search(&query).await.unwrap().len(), 1);
+
+        db.remove_encryption().await.unwrap();
+        assert_eq!(db.search(&query).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_key_rotation() {
+        let dir = tempdir().unwrap();
+        let config = test_database_config(dir.path(), false);
+
+        let mut db = Database::open_with_passphrase(&config, None).await.unwrap();
+        db.save_data(&sample_data()).await.unwrap();
+
+        db.rotate_key("first", TEST_KDF_PARAMS).await.unwrap();
+        for path in [config.path.clone(), config.path.with_extension("db-wal")] {
+            let bytes = std::fs::read(&path).unwrap_or_default();
+            let text = String::from_utf8_lossy(&bytes);
+            assert!(!text.contains("Home Depot"), "plaintext left in {:?}", path);
+        }
+        db.rotate_key("second", TEST_KDF_PARAMS).await.unwrap();
+        db.close().await;
+
+        assert!(Database::open_with_passphrase(&config, Some("first"))
+            .await
+            .is_err());
+
+        let db = Database::open_with_passphrase(&config, Some("second"))
+            .await
+            .unwrap();
+        assert!(db.is_encrypted());
+        let loaded = db.load_data().await.unwrap();
+        assert_eq!(loaded.transactions.len(), 1);
+        assert_eq!(loaded.payees, vec!["Home Depot".to_string()]);
+    }
+}
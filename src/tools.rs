@@ -0,0 +1,396 @@
+//! Typed tools over the analysis API, shared by the assistant loop and the
+//! MCP server.
+
+use crate::agent::FinancialAgent;
+use crate::analysis::AnalysisEngine;
+use crate::data::{FinancialData, Transaction};
+use crate::output::AccountBalance;
+use crate::search::{SearchEngine, SearchQuery};
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+/// Longest list a tool returns
+pub const MAX_TOOL_ROWS: usize = 50;
+
+/// Name, purpose and example arguments of one tool
+#[derive(Debug, Clone, Copy)]
+pub struct ToolSpec {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub example: &'static str,
+    /// Changes the books rather than reading them
+    pub write: bool,
+    /// Works on individual transactions rather than aggregates
+    pub rows: bool,
+}
+
+/// Every tool, read-only ones first
+pub const TOOLS: &[ToolSpec] = &[
+    ToolSpec {
+        name: "generate_monthly_report",
+        description: "income, expenses and spending per category for one month",
+        example: r#"{"year": 2026, "month": 9}"#,
+        write: false,
+        rows: false,
+    },
+    ToolSpec {
+        name: "analyze_categories",
+        description: "total, count, average and share of spending for every category",
+        example: "{}",
+        write: false,
+        rows: false,
+    },
+    ToolSpec {
+        name: "analyze_spending_trends",
+        description: "spending per category for each of the last N months, with the trend direction",
+        example: r#"{"months": 6}"#,
+        write: false,
+        rows: false,
+    },
+    ToolSpec {
+        name: "detect_anomalies",
+        description: "charges far above their category's average",
+        example: "{}",
+        write: false,
+        rows: true,
+    },
+    ToolSpec {
+        name: "search_transactions",
+        description: "transactions matching words, prefix*, \"phrases\", ~fuzzy, amount:50..200, date:2026-03..2026-05, account:Checking",
+        example: r#"{"query": "...", "limit": 20}"#,
+        write: false,
+        rows: true,
+    },
+    ToolSpec {
+        name: "account_balances",
+        description: "every account with its balance",
+        example: "{}",
+        write: false,
+        rows: false,
+    },
+    ToolSpec {
+        name: "set_category",
+        description: "change a transaction's category",
+        example: r#"{"transaction_id": "...", "category": "..."}"#,
+        write: true,
+        rows: true,
+    },
+];
+
+impl ToolSpec {
+    /// JSON Schema of the tool's arguments
+    pub fn input_schema(&self) -> Value {
+        let (properties, required) = match self.name {
+            "generate_monthly_report" => (
+                json!({
+                    "year": {"type": "integer"},
+                    "month": {"type": "integer", "minimum": 1, "maximum": 12}
+                }),
+                vec!["year", "month"],
+            ),
+            "analyze_spending_trends" => (
+                json!({"months": {"type": "integer", "minimum": 1, "maximum": 36}}),
+                vec!["months"],
+            ),
+            "search_transactions" => (
+                json!({
+                    "query": {"type": "string"},
+                    "limit": {"type": "integer", "minimum": 1, "maximum": MAX_TOOL_ROWS}
+                }),
+                vec!["query"],
+            ),
+            "set_category" => (
+                json!({
+                    "transaction_id": {"type": "string", "format": "uuid"},
+                    "category": {"type": "string"}
+                }),
+                vec!["transaction_id", "category"],
+            ),
+            _ => (json!({}), Vec::new()),
+        };
+        json!({
+            "type": "object",
+            "properties": properties,
+            "required": required,
+            "additionalProperties": false,
+        })
+    }
+}
+
+/// Tool descriptions for a prompt, one per line
+///
+/// Without `share_data` only the tools that return aggregates are listed.
+pub fn prompt_descriptions(share_data: bool) -> String {
+    TOOLS
+        .iter()
+        .filter(|tool| share_data || !tool.rows)
+        .map(|tool| {
+            let confirm = if tool.write {
+                "; the user is asked to confirm"
+            } else {
+                ""
+            };
+            format!(
+                "{} {}: {}{}",
+                tool.name, tool.example, tool.description, confirm
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Where tools read the books and make changes
+#[async_trait]
+pub trait Books: Send + Sync {
+    async fn load(&self) -> Result<FinancialData>;
+
+    async fn set_category(&self, transaction_id: Uuid, category: &str) -> Result<()>;
+}
+
+#[async_trait]
+impl Books for FinancialAgent {
+    async fn load(&self) -> Result<FinancialData> {
+        self.load_data().await
+    }
+
+    async fn set_category(&self, transaction_id: Uuid, category: &str) -> Result<()> {
+        FinancialAgent::set_category(self, transaction_id, category).await
+    }
+}
+
+/// A tool call the model may make, with typed arguments
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "tool", content = "arguments", rename_all = "snake_case")]
+pub enum ToolCall {
+    GenerateMonthlyReport {
+        year: i32,
+        month: u32,
+    },
+    AnalyzeCategories {},
+    AnalyzeSpendingTrends {
+        months: usize,
+    },
+    DetectAnomalies {},
+    SearchTransactions {
+        query: String,
+        #[serde(default)]
+        limit: Option<usize>,
+    },
+    AccountBalances {},
+    SetCategory {
+        transaction_id: Uuid,
+        category: String,
+    },
+}
+
+impl ToolCall {
+    /// Tool call from a tool name and its JSON arguments
+    pub fn parse(name: &str, arguments: Value) -> Result<Self, serde_json::Error> {
+        serde_json::from_value(json!({"tool": name, "arguments": arguments}))
+    }
+
+    /// Tool name as a client writes it
+    pub fn name(&self) -> &'static str {
+        match self {
+            ToolCall::GenerateMonthlyReport { .. } => "generate_monthly_report",
+            ToolCall::AnalyzeCategories {} => "analyze_categories",
+            ToolCall::AnalyzeSpendingTrends { .. } => "analyze_spending_trends",
+            ToolCall::DetectAnomalies {} => "detect_anomalies",
+            ToolCall::SearchTransactions { .. } => "search_transactions",
+            ToolCall::AccountBalances {} => "account_balances",
+            ToolCall::SetCategory { .. } => "set_category",
+        }
+    }
+
+    /// Whether the call changes the books and needs the user's confirmation
+    pub fn is_write(&self) -> bool {
+        matches!(self, ToolCall::SetCategory { .. })
+    }
+
+    /// Whether the call reads or changes individual transactions
+    pub fn is_row_level(&self) -> bool {
+        matches!(
+            self,
+            ToolCall::DetectAnomalies {}
+                | ToolCall::SearchTransactions { .. }
+                | ToolCall::SetCategory { .. }
+        )
+    }
+
+    /// Run a read-only tool against `data`
+    pub fn read(&self, data: &FinancialData) -> Result<Value> {
+        Ok(match self {
+            ToolCall::GenerateMonthlyReport { year, month } => {
+                if !(1..=12).contains(month) {
+                    bail!("month must be between 1 and 12");
+                }
+                json!(AnalysisEngine::generate_monthly_report(
+                    data, *year, *month
+                )?)
+            }
+            ToolCall::AnalyzeCategories {} => {
+                let mut categories = AnalysisEngine::analyze_categories(data)?;
+                categories.sort_by_key(|c| std::cmp::Reverse(c.total_amount));
+                categories.truncate(MAX_TOOL_ROWS);
+                json!(categories)
+            }
+            ToolCall::AnalyzeSpendingTrends { months } => {
+                if !(1..=36).contains(months) {
+                    bail!("months must be between 1 and 36");
+                }
+                let mut trends = AnalysisEngine::analyze_spending_trends(data, *months)?;
+                trends.sort_by_key(|t| std::cmp::Reverse(t.average_monthly));
+                trends.truncate(MAX_TOOL_ROWS);
+                json!(trends)
+            }
+            ToolCall::DetectAnomalies {} => {
+                let anomalies = AnalysisEngine::detect_anomalies(data)?;
+                json!(rows(data, anomalies.into_iter()))
+            }
+            ToolCall::SearchTransactions { query, limit } => {
+                let mut parsed = SearchQuery::parse(query)?;
+                parsed.limit = Some(limit.or(parsed.limit).unwrap_or(20).min(MAX_TOOL_ROWS));
+                let hits = SearchEngine::search(data, &parsed);
+                json!(rows(data, hits.iter().map(|hit| &hit.transaction)))
+            }
+            ToolCall::AccountBalances {} => json!(AccountBalance::from_data(data)),
+            ToolCall::SetCategory { .. } => bail!("set_category changes the books"),
+        })
+    }
+
+    /// One-line description of a write, shown when asking for confirmation
+    pub fn describe(&self, data: &FinancialData) -> Result<String> {
+        match self {
+            ToolCall::SetCategory {
+                transaction_id,
+                category,
+            } => {
+                let Some(transaction) = data.transactions.iter().find(|t| t.id == *transaction_id)
+                else {
+                    bail!("no transaction with id {}", transaction_id);
+                };
+                if category.trim().is_empty() {
+                    bail!("category must not be empty");
+                }
+                Ok(format!(
+                    "Set the category of {} {} {} ({}) to {}",
+                    transaction.date.date_naive(),
+                    crate::categorize::payee_of(transaction),
+                    transaction.amount,
+                    transaction.category.as_deref().unwrap_or("uncategorized"),
+                    category
+                ))
+            }
+            other => Ok(format!("Run {}", other.name())),
+        }
+    }
+}
+
+/// Compact transaction rows, at most `MAX_TOOL_ROWS`
+fn rows<'a>(
+    data: &FinancialData,
+    transactions: impl Iterator<Item = &'a Transaction>,
+) -> Vec<Value> {
+    transactions
+        .take(MAX_TOOL_ROWS)
+        .map(|t| {
+            let account = data
+                .accounts
+                .iter()
+                .find(|a| a.id == t.account_id)
+                .map(|a| a.name.as_str());
+            json!({
+                "id": t.id,
+                "date": t.date.date_naive(),
+                "account": account,
+                "payee": crate::categorize::payee_of(t),
+                "category": t.category,
+                "amount": t.amount,
+                "type": t.transaction_type,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Account, AccountType, TransactionType};
+    use chrono::{TimeZone, Utc};
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_parses_and_runs_tools() {
+        let mut data = FinancialData::new();
+        let account = Account::new(
+            "Checking".to_string(),
+            AccountType::Checking,
+            dec!(900),
+            "USD".to_string(),
+        );
+        let mut t = Transaction::new(
+            account.id,
+            Utc.with_ymd_and_hms(2026, 9, 5, 12, 0, 0).unwrap(),
+            dec!(50.00),
+            "Grocer".to_string(),
+            TransactionType::Debit,
+        );
+        t.category = Some("Groceries".to_string());
+        let id = t.id;
+        data.add_transaction(t);
+        data.add_account(account);
+
+        let call = ToolCall::parse("search_transactions", json!({"query": "groc*"})).unwrap();
+        let result = call.read(&data).unwrap();
+        assert_eq!(result[0]["payee"], "Grocer");
+        assert_eq!(result[0]["account"], "Checking");
+
+        let report = ToolCall::GenerateMonthlyReport {
+            year: 2026,
+            month: 9,
+        };
+        assert_eq!(report.read(&data).unwrap()["total_expenses"], "50.00");
+        assert!(ToolCall::GenerateMonthlyReport {
+            year: 2026,
+            month: 13
+        }
+        .read(&data)
+        .is_err());
+        assert!(ToolCall::parse("drop_tables", json!({})).is_err());
+
+        let write = ToolCall::SetCategory {
+            transaction_id: id,
+            category: "Household".to_string(),
+        };
+        assert!(write.is_write() && write.read(&data).is_err());
+        assert_eq!(
+            write.describe(&data).unwrap(),
+            "Set the category of 2026-09-05 Grocer 50.00 (Groceries) to Household"
+        );
+    }
+
+    #[test]
+    fn test_specs_cover_every_tool() {
+        for spec in TOOLS {
+            let example: Value = serde_json::from_str(spec.example).unwrap();
+            let schema = spec.input_schema();
+            for key in example.as_object().unwrap().keys() {
+                assert!(schema["properties"].get(key).is_some(), "{}", spec.name);
+            }
+            if example.to_string().contains("...") {
+                continue;
+            }
+            let call = ToolCall::parse(spec.name, example).unwrap();
+            assert_eq!(call.name(), spec.name);
+            assert_eq!(call.is_write(), spec.write);
+            assert_eq!(call.is_row_level(), spec.rows);
+        }
+        assert!(prompt_descriptions(true).contains("set_category {\"transaction_id\""));
+        let aggregates = prompt_descriptions(false);
+        assert!(aggregates.contains("analyze_categories"));
+        assert!(!aggregates.contains("search_transactions"));
+    }
+}
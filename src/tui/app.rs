@@ -0,0 +1,587 @@
+use crate::data::{Account, FinancialData, Transaction, TransactionType};
+use chrono::Utc;
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+/// Maximum number of autocomplete suggestions offered while editing
+const MAX_SUGGESTIONS: usize = 8;
+
+/// Keys the TUI reacts to, independent of the terminal backend
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    Enter,
+    Esc,
+    Backspace,
+    Tab,
+    BackTab,
+    Up,
+    Down,
+    Left,
+    Right,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+}
+
+/// Pane receiving navigation keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Focus {
+    Accounts,
+    Register,
+}
+
+/// Transaction field being edited
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditField {
+    Category,
+    Payee,
+}
+
+/// In-progress edit of a category or payee with autocomplete
+#[derive(Debug, Clone, PartialEq)]
+pub struct Editor {
+    pub field: EditField,
+    pub input: String,
+    pub suggestions: Vec<String>,
+    pub selected: Option<usize>,
+}
+
+/// One line of the register: a transaction and the balance after it
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegisterRow {
+    pub index: usize,
+    pub balance: Decimal,
+}
+
+/// State of the account browser and transaction register
+///
+/// Holds no terminal types so key handling can be tested without a screen.
+/// Edited transactions are queued and collected with [`App::take_dirty`].
+pub struct App {
+    data: FinancialData,
+    focus: Focus,
+    account: usize,
+    selected: usize,
+    register: Vec<RegisterRow>,
+    editor: Option<Editor>,
+    dirty: Vec<Uuid>,
+    status: Option<String>,
+    quit: bool,
+}
+
+impl Editor {
+    fn new(field: EditField, input: String, options: &[String]) -> Self {
+        let mut editor = Self {
+            field,
+            input,
+            suggestions: Vec::new(),
+            selected: None,
+        };
+        editor.refresh(options);
+        editor
+    }
+
+    /// Recompute suggestions: prefix matches first, then substring matches
+    fn refresh(&mut self, options: &[String]) {
+        let needle = self.input.to_lowercase();
+        let (mut prefix, mut contains): (Vec<&String>, Vec<&String>) = options
+            .iter()
+            .filter(|o| o.to_lowercase().contains(&needle) && **o != self.input)
+            .partition(|o| o.to_lowercase().starts_with(&needle));
+        prefix.sort_by_key(|o| o.to_lowercase());
+        contains.sort_by_key(|o| o.to_lowercase());
+
+        self.suggestions = prefix
+            .into_iter()
+            .chain(contains)
+            .take(MAX_SUGGESTIONS)
+            .cloned()
+            .collect();
+        self.selected = None;
+    }
+
+    fn cycle(&mut self, forward: bool) {
+        let len = self.suggestions.len();
+        if len == 0 {
+            return;
+        }
+        self.selected = Some(match (self.selected, forward) {
+            (None, true) => 0,
+            (None, false) => len - 1,
+            (Some(i), true) => (i + 1) % len,
+            (Some(i), false) => (i + len - 1) % len,
+        });
+    }
+
+    /// Value that Enter would commit
+    pub fn value(&self) -> &str {
+        self.selected
+            .and_then(|i| self.suggestions.get(i))
+            .map(String::as_str)
+            .unwrap_or(&self.input)
+    }
+}
+
+impl App {
+    /// Create the browser over a copy of the books
+    pub fn new(data: FinancialData) -> Self {
+        let mut app = Self {
+            data,
+            focus: Focus::Accounts,
+            account: 0,
+            selected: 0,
+            register: Vec::new(),
+            editor: None,
+            dirty: Vec::new(),
+            status: None,
+            quit: false,
+        };
+        app.rebuild_register();
+        app.selected = app.register.len().saturating_sub(1);
+        app
+    }
+
+    /// Books as currently edited
+    pub fn data(&self) -> &FinancialData {
+        &self.data
+    }
+
+    /// Pane receiving navigation keys
+    pub fn focus(&self) -> Focus {
+        self.focus
+    }
+
+    /// Open category or payee editor, if any
+    pub fn editor(&self) -> Option<&Editor> {
+        self.editor.as_ref()
+    }
+
+    /// Message for the status line
+    pub fn status(&self) -> Option<&str> {
+        self.status.as_deref()
+    }
+
+    /// Whether the user asked to leave
+    pub fn should_quit(&self) -> bool {
+        self.quit
+    }
+
+    /// Set the status line message
+    pub fn set_status(&mut self, status: impl Into<String>) {
+        self.status = Some(status.into());
+    }
+
+    /// Index of the highlighted account
+    pub fn selected_account_index(&self) -> Option<usize> {
+        (!self.data.accounts.is_empty()).then_some(self.account)
+    }
+
+    /// Highlighted account
+    pub fn selected_account(&self) -> Option<&Account> {
+        self.data.accounts.get(self.account)
+    }
+
+    /// Register rows for the highlighted account, oldest first
+    pub fn register(&self) -> &[RegisterRow] {
+        &self.register
+    }
+
+    /// Position of the highlighted row in the register
+    pub fn selected_row(&self) -> Option<usize> {
+        (!self.register.is_empty()).then_some(self.selected)
+    }
+
+    /// Transaction on the highlighted register row
+    pub fn selected_transaction(&self) -> Option<&Transaction> {
+        self.register
+            .get(self.selected)
+            .map(|row| &self.data.transactions[row.index])
+    }
+
+    /// Transaction shown on a register row
+    pub fn transaction(&self, row: &RegisterRow) -> &Transaction {
+        &self.data.transactions[row.index]
+    }
+
+    /// Transactions edited since the last call, in their current state
+    pub fn take_dirty(&mut self) -> Vec<Transaction> {
+        let ids = std::mem::take(&mut self.dirty);
+        ids.iter()
+            .filter_map(|id| self.data.transactions.iter().find(|t| t.id == *id))
+            .cloned()
+            .collect()
+    }
+
+    /// React to a key press
+    pub fn handle_key(&mut self, key: Key) {
+        if self.editor.is_some() {
+            self.handle_edit_key(key);
+            return;
+        }
+
+        match key {
+            Key::Char('q') | Key::Esc => self.quit = true,
+            Key::Tab | Key::BackTab | Key::Left | Key::Right => {
+                self.focus = match self.focus {
+                    Focus::Accounts => Focus::Register,
+                    Focus::Register => Focus::Accounts,
+                };
+            }
+            Key::Enter if self.focus == Focus::Accounts => self.focus = Focus::Register,
+            Key::Up | Key::Char('k') => self.move_selection(-1),
+            Key::Down | Key::Char('j') => self.move_selection(1),
+            Key::PageUp => self.move_selection(-10),
+            Key::PageDown => self.move_selection(10),
+            Key::Home => self.move_selection(isize::MIN),
+            Key::End => self.move_selection(isize::MAX),
+            Key::Char('c') if self.focus == Focus::Register => self.begin_edit(EditField::Category),
+            Key::Char('p') if self.focus == Focus::Register => self.begin_edit(EditField::Payee),
+            Key::Char(' ') | Key::Char('x') if self.focus == Focus::Register => {
+                self.toggle_cleared()
+            }
+            Key::Char('r') if self.focus == Focus::Register => self.toggle_reconciled(),
+            _ => {}
+        }
+    }
+
+    fn handle_edit_key(&mut self, key: Key) {
+        let options = match self.editor.as_ref().map(|e| e.field) {
+            Some(EditField::Category) => self.data.categories.clone(),
+            Some(EditField::Payee) => self.data.payees.clone(),
+            None => return,
+        };
+        let Some(editor) = self.editor.as_mut() else {
+            return;
+        };
+
+        match key {
+            Key::Esc => {
+                self.editor = None;
+                self.status = Some("Edit cancelled".to_string());
+            }
+            Key::Enter => self.commit_edit(),
+            Key::Tab | Key::Down => editor.cycle(true),
+            Key::BackTab | Key::Up => editor.cycle(false),
+            Key::Right => {
+                // Accept the highlighted suggestion and keep typing
+                editor.input = editor.value().to_string();
+                editor.refresh(&options);
+            }
+            Key::Backspace => {
+                editor.input.pop();
+                editor.refresh(&options);
+            }
+            Key::Char(c) => {
+                editor.input.push(c);
+                editor.refresh(&options);
+            }
+            _ => {}
+        }
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        match self.focus {
+            Focus::Accounts => {
+                let next = step(self.account, delta, self.data.accounts.len());
+                if next != self.account {
+                    self.account = next;
+                    self.rebuild_register();
+                    self.selected = self.register.len().saturating_sub(1);
+                }
+            }
+            Focus::Register => {
+                self.selected = step(self.selected, delta, self.register.len());
+            }
+        }
+    }
+
+    fn begin_edit(&mut self, field: EditField) {
+        let Some(transaction) = self.selected_transaction() else {
+            return;
+        };
+        let current = match field {
+            EditField::Category => transaction.category.clone(),
+            EditField::Payee => transaction.payee.clone(),
+        }
+        .unwrap_or_default();
+        let options = match field {
+            EditField::Category => &self.data.categories,
+            EditField::Payee => &self.data.payees,
+        };
+
+        self.editor = Some(Editor::new(field, current, options));
+        self.status = None;
+    }
+
+    fn commit_edit(&mut self) {
+        let Some(editor) = self.editor.take() else {
+            return;
+        };
+        let Some(index) = self.register.get(self.selected).map(|row| row.index) else {
+            return;
+        };
+
+        let value = editor.value().trim().to_string();
+        let value = (!value.is_empty()).then_some(value);
+
+        let (known, label) = match editor.field {
+            EditField::Category => (&mut self.data.categories, "Category"),
+            EditField::Payee => (&mut self.data.payees, "Payee"),
+        };
+        if let Some(ref value) = value {
+            if !known.contains(value) {
+                known.push(value.clone());
+            }
+        }
+
+        let transaction = &mut self.data.transactions[index];
+        let target = match editor.field {
+            EditField::Category => &mut transaction.category,
+            EditField::Payee => &mut transaction.payee,
+        };
+        if *target == value {
+            return;
+        }
+
+        self.status = Some(match value {
+            Some(ref v) => format!("{} set to {}", label, v),
+            None => format!("{} cleared", label),
+        });
+        *target = value;
+        transaction.updated_at = Utc::now();
+        self.mark_dirty(index);
+    }
+
+    fn toggle_cleared(&mut self) {
+        let Some(index) = self.register.get(self.selected).map(|row| row.index) else {
+            return;
+        };
+        let transaction = &mut self.data.transactions[index];
+
+        if transaction.cleared {
+            // Unclearing also unreconciles, mirroring mark_reconciled implying cleared
+            transaction.cleared = false;
+            transaction.reconciled = false;
+            transaction.updated_at = Utc::now();
+        } else {
+            transaction.mark_cleared();
+        }
+        self.mark_dirty(index);
+    }
+
+    fn toggle_reconciled(&mut self) {
+        let Some(index) = self.register.get(self.selected).map(|row| row.index) else {
+            return;
+        };
+        let transaction = &mut self.data.transactions[index];
+
+        if transaction.reconciled {
+            transaction.reconciled = false;
+            transaction.updated_at = Utc::now();
+        } else {
+            transaction.mark_reconciled();
+        }
+        self.mark_dirty(index);
+    }
+
+    fn mark_dirty(&mut self, index: usize) {
+        let id = self.data.transactions[index].id;
+        if !self.dirty.contains(&id) {
+            self.dirty.push(id);
+        }
+    }
+
+    fn rebuild_register(&mut self) {
+        self.register.clear();
+        let Some(account_id) = self.selected_account().map(|a| a.id) else {
+            return;
+        };
+
+        let mut indices: Vec<usize> = self
+            .data
+            .transactions
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| t.account_id == account_id)
+            .map(|(i, _)| i)
+            .collect();
+        indices.sort_by_key(|&i| {
+            let t = &self.data.transactions[i];
+            (t.date, t.created_at)
+        });
+
+        // Same sign convention as FinancialData::calculate_account_balance
+        let mut balance = Decimal::ZERO;
+        for index in indices {
+            let transaction = &self.data.transactions[index];
+            balance += match transaction.transaction_type {
+                TransactionType::Debit => -transaction.amount,
+                _ => transaction.amount,
+            };
+            self.register.push(RegisterRow { index, balance });
+        }
+    }
+}
+
+fn step(current: usize, delta: isize, len: usize) -> usize {
+    if len == 0 {
+        return 0;
+    }
+    current.saturating_add_signed(delta).min(len - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::AccountType;
+    use chrono::TimeZone;
+    use rust_decimal_macros::dec;
+
+    fn books() -> FinancialData {
+        let mut data = FinancialData::new();
+        for name in ["Checking", "Savings"] {
+            data.add_account(Account::new(
+                name.to_string(),
+                AccountType::Checking,
+                Decimal::ZERO,
+                "USD".to_string(),
+            ));
+        }
+        let checking = data.accounts[0].id;
+
+        // Added out of date order to exercise register sorting
+        for (day, amount, kind, payee, category) in [
+            (
+                20,
+                dec!(40.00),
+                TransactionType::Debit,
+                "Grocer",
+                "Groceries",
+            ),
+            (
+                1,
+                dec!(1000.00),
+                TransactionType::Credit,
+                "Employer",
+                "Salary",
+            ),
+            (5, dec!(12.50), TransactionType::Debit, "Cafe", "Dining"),
+        ] {
+            let mut transaction = Transaction::new(
+                checking,
+                Utc.with_ymd_and_hms(2026, 9, day, 0, 0, 0).unwrap(),
+                amount,
+                payee.to_string(),
+                kind,
+            );
+            transaction.payee = Some(payee.to_string());
+            transaction.category = Some(category.to_string());
+            data.add_transaction(transaction);
+        }
+        data.categories.push("Gifts".to_string());
+        data
+    }
+
+    fn press(app: &mut App, keys: &str) {
+        for c in keys.chars() {
+            app.handle_key(Key::Char(c));
+        }
+    }
+
+    #[test]
+    fn test_register_running_balance() {
+        let mut app = App::new(books());
+
+        let balances: Vec<Decimal> = app.register().iter().map(|r| r.balance).collect();
+        assert_eq!(balances, vec![dec!(1000.00), dec!(987.50), dec!(947.50)]);
+        assert_eq!(app.selected_row(), Some(2));
+
+        app.handle_key(Key::Down);
+        assert_eq!(app.selected_account().unwrap().name, "Savings");
+        assert!(app.register().is_empty());
+        assert!(app.selected_transaction().is_none());
+    }
+
+    #[test]
+    fn test_category_autocomplete_and_save_queue() {
+        let mut app = App::new(books());
+        app.handle_key(Key::Tab);
+        assert_eq!(app.focus(), Focus::Register);
+
+        press(&mut app, "c");
+        let editor = app.editor().unwrap();
+        assert_eq!(editor.field, EditField::Category);
+        assert_eq!(editor.input, "Groceries");
+
+        for _ in 0.."Groceries".len() {
+            app.handle_key(Key::Backspace);
+        }
+        press(&mut app, "g");
+        // Prefix matches come before substring matches
+        assert_eq!(
+            app.editor().unwrap().suggestions,
+            vec!["Gifts", "Groceries", "Dining"]
+        );
+
+        app.handle_key(Key::Tab);
+        assert_eq!(app.editor().unwrap().value(), "Gifts");
+        app.handle_key(Key::Enter);
+
+        assert!(app.editor().is_none());
+        assert_eq!(
+            app.selected_transaction().unwrap().category.as_deref(),
+            Some("Gifts")
+        );
+
+        let dirty = app.take_dirty();
+        assert_eq!(dirty.len(), 1);
+        assert_eq!(dirty[0].category.as_deref(), Some("Gifts"));
+        assert!(app.take_dirty().is_empty());
+    }
+
+    #[test]
+    fn test_new_payee_and_cancel() {
+        let mut app = App::new(books());
+        app.handle_key(Key::Enter);
+
+        press(&mut app, "p");
+        app.handle_key(Key::Esc);
+        assert!(app.editor().is_none());
+        assert!(!app.should_quit());
+
+        press(&mut app, "p");
+        while !app.editor().unwrap().input.is_empty() {
+            app.handle_key(Key::Backspace);
+        }
+        press(&mut app, "Corner Store");
+        app.handle_key(Key::Enter);
+
+        assert!(app.data().payees.contains(&"Corner Store".to_string()));
+        assert_eq!(app.take_dirty()[0].payee.as_deref(), Some("Corner Store"));
+    }
+
+    #[test]
+    fn test_cleared_and_reconciled_toggles() {
+        let mut app = App::new(books());
+        app.handle_key(Key::Tab);
+
+        press(&mut app, "r");
+        let transaction = app.selected_transaction().unwrap();
+        assert!(transaction.reconciled && transaction.cleared);
+
+        press(&mut app, "x");
+        let transaction = app.selected_transaction().unwrap();
+        assert!(!transaction.reconciled && !transaction.cleared);
+
+        press(&mut app, "x");
+        assert!(app.selected_transaction().unwrap().cleared);
+
+        // Toggling the same transaction repeatedly queues a single save
+        assert_eq!(app.take_dirty().len(), 1);
+
+        press(&mut app, "q");
+        assert!(app.should_quit());
+    }
+}
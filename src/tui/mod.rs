@@ -0,0 +1,84 @@
+//! Full-screen terminal UI for the weekly review: browse accounts, walk the
+//! register and assign categories, payees and cleared/reconciled flags.
+
+mod app;
+#[cfg(feature = "tui")]
+mod ui;
+
+pub use app::{App, EditField, Editor, Focus, Key, RegisterRow};
+
+#[cfg(feature = "tui")]
+pub use terminal::run;
+
+#[cfg(feature = "tui")]
+mod terminal {
+    use super::{ui, App, Key};
+    use crate::storage::Database;
+    use anyhow::Result;
+    use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+    use ratatui::DefaultTerminal;
+    use std::time::Duration;
+
+    /// Run the TUI until the user quits, saving each edit as it is made
+    pub async fn run(database: &Database) -> Result<()> {
+        let data = database.load_data().await?;
+        let mut app = App::new(data);
+
+        let mut terminal = ratatui::init();
+        let result = event_loop(&mut terminal, &mut app, database).await;
+        ratatui::restore();
+        result
+    }
+
+    async fn event_loop(
+        terminal: &mut DefaultTerminal,
+        app: &mut App,
+        database: &Database,
+    ) -> Result<()> {
+        while !app.should_quit() {
+            terminal.draw(|frame| ui::draw(frame, app))?;
+
+            if event::poll(Duration::from_millis(250))? {
+                if let Event::Key(key) = event::read()? {
+                    let interrupt = key.modifiers.contains(KeyModifiers::CONTROL)
+                        && key.code == KeyCode::Char('c');
+                    if interrupt {
+                        break;
+                    }
+                    if key.kind == KeyEventKind::Press {
+                        if let Some(key) = map_key(key.code) {
+                            app.handle_key(key);
+                        }
+                    }
+                }
+            }
+
+            for transaction in app.take_dirty() {
+                if let Err(e) = database.upsert_transaction(&transaction).await {
+                    app.set_status(format!("Save failed: {:#}", e));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn map_key(code: KeyCode) -> Option<Key> {
+        Some(match code {
+            KeyCode::Char(c) => Key::Char(c),
+            KeyCode::Enter => Key::Enter,
+            KeyCode::Esc => Key::Esc,
+            KeyCode::Backspace => Key::Backspace,
+            KeyCode::Tab => Key::Tab,
+            KeyCode::BackTab => Key::BackTab,
+            KeyCode::Up => Key::Up,
+            KeyCode::Down => Key::Down,
+            KeyCode::Left => Key::Left,
+            KeyCode::Right => Key::Right,
+            KeyCode::PageUp => Key::PageUp,
+            KeyCode::PageDown => Key::PageDown,
+            KeyCode::Home => Key::Home,
+            KeyCode::End => Key::End,
+            _ => return None,
+        })
+    }
+}
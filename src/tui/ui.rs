@@ -0,0 +1,239 @@
+use super::app::{App, EditField, Focus};
+use crate::data::TransactionType;
+use crate::utils::format_currency;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{
+    Block, Borders, Cell, Clear, List, ListItem, ListState, Paragraph, Row, Table, TableState,
+};
+use ratatui::Frame;
+
+const HELP: &str = "Tab switch pane  ↑↓ move  c category  p payee  x cleared  r reconciled  q quit";
+const EDIT_HELP: &str = "Enter save  Tab/↑↓ choose suggestion  → accept  Esc cancel";
+
+/// Draw the whole screen from the application state
+pub fn draw(frame: &mut Frame, app: &App) {
+    let [main, status] =
+        Layout::vertical([Constraint::Min(3), Constraint::Length(1)]).areas(frame.area());
+    let [accounts, register] =
+        Layout::horizontal([Constraint::Length(32), Constraint::Min(40)]).areas(main);
+
+    draw_accounts(frame, app, accounts);
+    draw_register(frame, app, register);
+    draw_status(frame, app, status);
+
+    if app.editor().is_some() {
+        draw_editor(frame, app, register);
+    }
+}
+
+fn pane(title: &str, focused: bool) -> Block<'_> {
+    let style = if focused {
+        Style::default().fg(Color::Cyan)
+    } else {
+        Style::default()
+    };
+    Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(style)
+}
+
+fn draw_accounts(frame: &mut Frame, app: &App, area: Rect) {
+    let items: Vec<ListItem> = app
+        .data()
+        .accounts
+        .iter()
+        .map(|account| {
+            let balance = app.data().calculate_account_balance(&account.id);
+            ListItem::new(Line::from(vec![
+                Span::raw(format!("{:<18}", account.name)),
+                Span::raw(format!(
+                    "{:>12}",
+                    format_currency(balance, &account.currency)
+                )),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(pane(" Accounts ", app.focus() == Focus::Accounts))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    let mut state = ListState::default().with_selected(app.selected_account_index());
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+fn draw_register(frame: &mut Frame, app: &App, area: Rect) {
+    let currency = app
+        .selected_account()
+        .map(|a| a.currency.as_str())
+        .unwrap_or("USD");
+    let title = app
+        .selected_account()
+        .map(|a| format!(" Register: {} ", a.name))
+        .unwrap_or_else(|| " Register ".to_string());
+
+    let rows: Vec<Row> = app
+        .register()
+        .iter()
+        .map(|row| {
+            let transaction = app.transaction(row);
+            let flag = if transaction.reconciled {
+                "R"
+            } else if transaction.cleared {
+                "c"
+            } else {
+                " "
+            };
+            let amount = match transaction.transaction_type {
+                TransactionType::Debit => -transaction.amount,
+                _ => transaction.amount,
+            };
+            Row::new(vec![
+                Cell::from(transaction.date.format("%Y-%m-%d").to_string()),
+                Cell::from(
+                    transaction
+                        .payee
+                        .clone()
+                        .unwrap_or_else(|| transaction.description.clone()),
+                ),
+                Cell::from(transaction.category.clone().unwrap_or_default()),
+                Cell::from(flag),
+                Cell::from(Line::from(format_currency(amount, currency)).right_aligned()),
+                Cell::from(Line::from(format_currency(row.balance, currency)).right_aligned()),
+            ])
+        })
+        .collect();
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(10),
+            Constraint::Fill(2),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+            Constraint::Length(12),
+            Constraint::Length(12),
+        ],
+    )
+    .header(
+        Row::new(["Date", "Payee", "Category", "C", "Amount", "Balance"])
+            .style(Style::default().add_modifier(Modifier::BOLD)),
+    )
+    .block(pane(&title, app.focus() == Focus::Register))
+    .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+    let mut state = TableState::default().with_selected(app.selected_row());
+    frame.render_stateful_widget(table, area, &mut state);
+}
+
+fn draw_status(frame: &mut Frame, app: &App, area: Rect) {
+    let text = match (app.status(), app.editor()) {
+        (_, Some(_)) => EDIT_HELP.to_string(),
+        (Some(status), None) => format!("{}  |  {}", status, HELP),
+        (None, None) => HELP.to_string(),
+    };
+    frame.render_widget(
+        Paragraph::new(text).style(Style::default().fg(Color::DarkGray)),
+        area,
+    );
+}
+
+fn draw_editor(frame: &mut Frame, app: &App, register: Rect) {
+    let Some(editor) = app.editor() else {
+        return;
+    };
+    let title = match editor.field {
+        EditField::Category => " Category ",
+        EditField::Payee => " Payee ",
+    };
+
+    let height = (editor.suggestions.len() as u16 + 3).min(register.height);
+    let width = 40.min(register.width);
+    let area = Rect {
+        x: register.x + (register.width - width) / 2,
+        y: register.y + (register.height - height) / 2,
+        width,
+        height,
+    };
+
+    let mut lines = vec![Line::from(format!("> {}", editor.input))];
+    lines.extend(
+        editor
+            .suggestions
+            .iter()
+            .enumerate()
+            .map(|(i, suggestion)| {
+                let style = if editor.selected == Some(i) {
+                    Style::default().add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::default().fg(Color::DarkGray)
+                };
+                Line::styled(format!("  {}", suggestion), style)
+            }),
+    );
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(Paragraph::new(lines).block(pane(title, true)), area);
+    frame.set_cursor_position((area.x + 3 + editor.input.chars().count() as u16, area.y + 1));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Account, AccountType, FinancialData, Transaction};
+    use crate::tui::Key;
+    use chrono::{TimeZone, Utc};
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_draw_register_and_editor() {
+        let mut data = FinancialData::new();
+        let account = Account::new(
+            "Checking".to_string(),
+            AccountType::Checking,
+            dec!(0),
+            "USD".to_string(),
+        );
+        let mut transaction = Transaction::new(
+            account.id,
+            Utc.with_ymd_and_hms(2026, 9, 5, 0, 0, 0).unwrap(),
+            dec!(12.50),
+            "Cafe".to_string(),
+            TransactionType::Debit,
+        );
+        transaction.category = Some("Dining".to_string());
+        data.add_account(account);
+        data.add_transaction(transaction);
+
+        let mut app = App::new(data);
+        let mut terminal = Terminal::new(TestBackend::new(100, 12)).unwrap();
+        terminal.draw(|frame| draw(frame, &app)).unwrap();
+
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(screen.contains("Register: Checking"));
+        assert!(screen.contains("2026-09-05"));
+        assert!(screen.contains("$-12.50"));
+
+        app.handle_key(Key::Tab);
+        app.handle_key(Key::Char('c'));
+        terminal.draw(|frame| draw(frame, &app)).unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(screen.contains("> Dining"));
+    }
+}
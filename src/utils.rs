@@ -251,9 +251,9 @@ pub mod file_utils {
         matches!(get_file_extension(path).as_deref(), Some("qif"))
     }
 
-    /// Generate backup filename
-    pub fn generate_backup_filename(original: &Path) -> PathBuf {
-        let timestamp = Utc::now().format("%Y%m%d_%H%M%S");
+    /// Generate backup filename stamped with `now`
+    pub fn generate_backup_filename(original: &Path, now: DateTime<Utc>) -> PathBuf {
+        let timestamp = now.format("%Y%m%d_%H%M%S");
         let mut backup = original.to_path_buf();
         
         if let Some(stem) = original.file_stem() {
@@ -272,6 +272,22 @@ pub mod file_utils {
         backup
     }
 
+    /// `path` if nothing is there yet, otherwise the first free `stem~N.ext` beside it
+    pub fn unique_path(path: &Path) -> PathBuf {
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+        let ext = path.extension().map(|e| e.to_string_lossy().into_owned());
+        let mut candidate = path.to_path_buf();
+        let mut attempt = 1;
+        while candidate.exists() {
+            candidate.set_file_name(match &ext {
+                Some(ext) => format!("{}~{}.{}", stem, attempt, ext),
+                None => format!("{}~{}", stem, attempt),
+            });
+            attempt += 1;
+        }
+        candidate
+    }
+
     /// Ensure directory exists
     pub async fn ensure_dir_exists(path: &Path) -> Result<()> {
         if !path.exists() {
@@ -436,7 +452,9 @@ mod tests {
         assert!(is_qif_file(qif_path));
         assert!(!is_qif_file(txt_path));
         
-        let backup = generate_backup_filename(qif_path);
+        let now = Utc.with_ymd_and_hms(2026, 9, 5, 8, 30, 0).unwrap();
+        let backup = generate_backup_filename(qif_path, now);
+        assert_eq!(backup, Path::new("test_20260905_083000.qif"));
         assert!(backup.file_name().unwrap().to_string_lossy().starts_with("test_"));
         assert!(backup.file_name().unwrap().to_string_lossy().ends_with(".qif"));
     }
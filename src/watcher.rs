@@ -0,0 +1,337 @@
+use crate::config::QuickenConfig;
+use crate::utils::file_utils;
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use glob::Pattern;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+/// Subfolder receiving successfully imported files
+pub const PROCESSED_DIR: &str = "processed";
+
+/// Subfolder receiving files that failed to import
+pub const FAILED_DIR: &str = "failed";
+
+/// How long a file whose import hit a passing problem waits before the next attempt
+const RETRY_DELAY: Duration = Duration::from_secs(300);
+
+/// Last observed state of a file that is still being written
+#[derive(Debug, Clone, PartialEq)]
+struct PendingFile {
+    len: u64,
+    modified: Option<SystemTime>,
+    stable_since: Instant,
+}
+
+/// Watches the Quicken directory for new files ready to import
+///
+/// File system events (inotify on Linux) wake the watcher early; a periodic
+/// scan runs regardless, so the watcher keeps working by polling when events
+/// are unavailable or missed.
+pub struct ImportWatcher {
+    directory: PathBuf,
+    patterns: Vec<Pattern>,
+    settle: Duration,
+    poll_interval: Duration,
+    pending: HashMap<PathBuf, PendingFile>,
+    /// Files left in place after a failed attempt, with when to try them again
+    retry_at: HashMap<PathBuf, Instant>,
+    events: Option<(RecommendedWatcher, mpsc::UnboundedReceiver<()>)>,
+}
+
+impl ImportWatcher {
+    /// Create the `processed/` and `failed/` directories and start watching
+    ///
+    /// The watch directory itself must already exist, so a mistyped path is
+    /// reported instead of silently watching a new empty folder.
+    pub async fn new(config: &QuickenConfig) -> Result<Self> {
+        let patterns = config
+            .file_patterns
+            .iter()
+            .map(|p| Pattern::new(p).with_context(|| format!("Invalid file pattern '{}'", p)))
+            .collect::<Result<Vec<_>>>()?;
+
+        let directory = config.watch_directory.clone();
+        if !directory.is_dir() {
+            bail!("Watch directory {} does not exist", directory.display());
+        }
+        for dir in [directory.join(PROCESSED_DIR), directory.join(FAILED_DIR)] {
+            file_utils::ensure_dir_exists(&dir)
+                .await
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+
+        let events = match Self::start_events(&directory) {
+            Ok(events) => Some(events),
+            Err(e) => {
+                warn!(
+                    "File events unavailable for {} ({}); falling back to polling",
+                    directory.display(),
+                    e
+                );
+                None
+            }
+        };
+
+        info!(
+            "Watching {} for {:?}",
+            directory.display(),
+            config.file_patterns
+        );
+
+        Ok(Self {
+            directory,
+            patterns,
+            settle: Duration::from_millis(config.settle_ms),
+            poll_interval: Duration::from_millis(config.poll_interval_ms.max(1)),
+            pending: HashMap::new(),
+            retry_at: HashMap::new(),
+            events,
+        })
+    }
+
+    fn start_events(
+        directory: &Path,
+    ) -> notify::Result<(RecommendedWatcher, mpsc::UnboundedReceiver<()>)> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                if event.is_ok() {
+                    let _ = tx.send(());
+                }
+            })?;
+        watcher.watch(directory, RecursiveMode::NonRecursive)?;
+        Ok((watcher, rx))
+    }
+
+    /// Whether file system events are being received (otherwise polling only)
+    pub fn uses_events(&self) -> bool {
+        self.events.is_some()
+    }
+
+    /// Whether a file name matches one of the configured glob patterns
+    pub fn matches(&self, path: &Path) -> bool {
+        path.file_name()
+            .map(|name| {
+                let name = name.to_string_lossy();
+                self.patterns.iter().any(|p| p.matches(&name))
+            })
+            .unwrap_or(false)
+    }
+
+    /// Wait for a file system event or the next poll tick
+    pub async fn wait(&mut self) {
+        let tick = tokio::time::sleep(self.poll_interval);
+
+        match self.events {
+            Some((_, ref mut rx)) => {
+                tokio::select! {
+                    _ = tick => {}
+                    received = rx.recv() => {
+                        if received.is_none() {
+                            warn!("File event channel closed; falling back to polling");
+                            self.events = None;
+                        }
+                    }
+                }
+                // Coalesce bursts of events from a single write
+                if let Some((_, ref mut rx)) = self.events {
+                    while rx.try_recv().is_ok() {}
+                }
+            }
+            None => tick.await,
+        }
+    }
+
+    /// Scan the directory and return files whose size and modification time
+    /// have not changed for the settle period
+    pub async fn scan(&mut self) -> Result<Vec<PathBuf>> {
+        let now = Instant::now();
+        let mut seen = Vec::new();
+        let mut ready = Vec::new();
+
+        let mut entries = tokio::fs::read_dir(&self.directory)
+            .await
+            .with_context(|| format!("Failed to read {}", self.directory.display()))?;
+
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            let Ok(metadata) = entry.metadata().await else {
+                continue;
+            };
+            if !metadata.is_file() || !self.matches(&path) {
+                continue;
+            }
+
+            seen.push(path.clone());
+            let observed = PendingFile {
+                len: metadata.len(),
+                modified: metadata.modified().ok(),
+                stable_since: now,
+            };
+
+            match self.pending.get(&path) {
+                Some(previous)
+                    if previous.len == observed.len && previous.modified == observed.modified =>
+                {
+                    let waiting = self.retry_at.get(&path).is_some_and(|at| now < *at);
+                    if now.duration_since(previous.stable_since) >= self.settle && !waiting {
+                        ready.push(path);
+                    }
+                }
+                _ => {
+                    debug!("Waiting for {} to settle", path.display());
+                    self.pending.insert(path, observed);
+                }
+            }
+        }
+
+        self.pending.retain(|path, _| seen.contains(path));
+        self.retry_at.retain(|path, _| seen.contains(path));
+        for path in &ready {
+            self.pending.remove(path);
+        }
+        ready.sort();
+        Ok(ready)
+    }
+
+    /// Leave a file in place and offer it again once `RETRY_DELAY` has passed
+    pub fn retry_later(&mut self, path: &Path) {
+        self.retry_at
+            .insert(path.to_path_buf(), Instant::now() + RETRY_DELAY);
+    }
+
+    /// Move a handled file into `processed/` or `failed/`, returning its new path
+    pub async fn archive(&mut self, path: &Path, success: bool) -> Result<PathBuf> {
+        let folder = if success { PROCESSED_DIR } else { FAILED_DIR };
+        let file_name = path.file_name().context("Path has no file name")?;
+
+        let mut destination = self.directory.join(folder).join(file_name);
+        if destination.exists() {
+            destination = file_utils::unique_path(&file_utils::generate_backup_filename(
+                &destination,
+                Utc::now(),
+            ));
+        }
+
+        tokio::fs::rename(path, &destination)
+            .await
+            .with_context(|| format!("Failed to move {} to {}", path.display(), folder))?;
+        self.retry_at.remove(path);
+        Ok(destination)
+    }
+}
+
+/// Resolve when the process receives SIGINT or SIGTERM
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            warn!("Failed to listen for SIGINT: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                warn!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => info!("Received SIGINT"),
+        _ = terminate => info!("Received SIGTERM"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn watch_config(dir: &Path) -> QuickenConfig {
+        QuickenConfig {
+            watch_directory: dir.to_path_buf(),
+            file_patterns: vec!["*.qif".to_string(), "*.QIF".to_string()],
+            auto_import: true,
+            settle_ms: 0,
+            poll_interval_ms: 10,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_patterns_and_folders() {
+        let dir = tempdir().unwrap();
+        let watcher = ImportWatcher::new(&watch_config(dir.path())).await.unwrap();
+
+        assert!(dir.path().join(PROCESSED_DIR).is_dir());
+        assert!(dir.path().join(FAILED_DIR).is_dir());
+        assert!(watcher.matches(Path::new("/x/bank.qif")));
+        assert!(watcher.matches(Path::new("BANK.QIF")));
+        assert!(!watcher.matches(Path::new("notes.txt")));
+    }
+
+    #[tokio::test]
+    async fn test_scan_waits_for_file_to_settle() {
+        let dir = tempdir().unwrap();
+        let mut watcher = ImportWatcher::new(&watch_config(dir.path())).await.unwrap();
+        let file = dir.path().join("bank.qif");
+
+        std::fs::write(&file, "!Account\n").unwrap();
+        std::fs::write(dir.path().join("ignored.txt"), "x").unwrap();
+
+        // First sighting only records the file
+        assert!(watcher.scan().await.unwrap().is_empty());
+
+        // Still growing: the timer restarts
+        std::fs::write(&file, "!Account\nNChecking\n").unwrap();
+        assert!(watcher.scan().await.unwrap().is_empty());
+
+        // Unchanged since the last scan: ready
+        assert_eq!(watcher.scan().await.unwrap(), vec![file.clone()]);
+
+        let archived = watcher.archive(&file, false).await.unwrap();
+        assert_eq!(archived, dir.path().join(FAILED_DIR).join("bank.qif"));
+        assert!(!file.exists());
+
+        // Files of the same name arriving within one second all keep their contents
+        for n in 0..3 {
+            std::fs::write(&file, format!("copy {}", n)).unwrap();
+            watcher.archive(&file, false).await.unwrap();
+        }
+        assert_eq!(
+            std::fs::read_dir(dir.path().join(FAILED_DIR))
+                .unwrap()
+                .count(),
+            4
+        );
+    }
+
+    #[tokio::test]
+    async fn test_retry_later_holds_a_file_back() {
+        let dir = tempdir().unwrap();
+        let mut watcher = ImportWatcher::new(&watch_config(dir.path())).await.unwrap();
+        let file = dir.path().join("bank.qif");
+        std::fs::write(&file, "!Account\n").unwrap();
+
+        watcher.scan().await.unwrap();
+        assert_eq!(watcher.scan().await.unwrap(), vec![file.clone()]);
+        watcher.retry_later(&file);
+        watcher.scan().await.unwrap();
+        assert!(watcher.scan().await.unwrap().is_empty());
+        assert!(file.exists());
+    }
+}
@@ -0,0 +1,75 @@
+// Drives `qspec mcp` over stdio the way a desktop assistant would
+use qspec_fin_agent::Config;
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+const SAMPLE_QIF: &str =
+    "!Account\nNChecking\nTBank\n^\n!Type:Bank\nD9/5/2026\nT-50.00\nPGrocer\nLGroceries\n^\n";
+
+async fn write_config(dir: &Path) -> std::path::PathBuf {
+    let mut config = Config::default();
+    config.database.path = dir.join("books.db");
+    config.database.encryption.enabled = false;
+    config.database.backup.directory = Some(dir.join("backups"));
+    config.logging.file_logging = false;
+    let path = dir.join("config.toml");
+    config.save_to(&path).await.unwrap();
+    path
+}
+
+fn qspec(config: &Path) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_qspec"));
+    command.arg("--config").arg(config);
+    command
+}
+
+#[tokio::test]
+async fn test_scripted_stdio_client() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = write_config(dir.path()).await;
+    let qif = dir.path().join("sample.qif");
+    std::fs::write(&qif, SAMPLE_QIF).unwrap();
+    let status = qspec(&config).arg("import").arg(&qif).status().unwrap();
+    assert!(status.success());
+
+    let mut server = qspec(&config)
+        .arg("mcp")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    let mut stdin = server.stdin.take().unwrap();
+    let mut replies = BufReader::new(server.stdout.take().unwrap()).lines();
+    let mut call = move |message: Value| -> Value {
+        writeln!(stdin, "{}", message).unwrap();
+        stdin.flush().unwrap();
+        serde_json::from_str(&replies.next().unwrap().unwrap()).unwrap()
+    };
+
+    let init = call(json!({
+        "jsonrpc": "2.0", "id": 1, "method": "initialize",
+        "params": {"protocolVersion": "2024-11-05", "capabilities": {}, "clientInfo": {"name": "test"}}
+    }));
+    assert_eq!(init["result"]["serverInfo"]["name"], "qspec");
+
+    let accounts = call(json!({
+        "jsonrpc": "2.0", "id": 2, "method": "tools/call",
+        "params": {"name": "account_balances", "arguments": {}}
+    }));
+    assert_eq!(accounts["result"]["isError"], false);
+    let text = accounts["result"]["content"][0]["text"].as_str().unwrap();
+    assert!(text.contains("Checking"));
+
+    let resources = call(json!({"jsonrpc": "2.0", "id": 3, "method": "resources/list"}));
+    assert_eq!(
+        resources["result"]["resources"][0]["uri"],
+        "qspec://reports/monthly/2026-09"
+    );
+
+    // Closing stdin ends the session cleanly
+    drop(call);
+    assert!(server.wait().unwrap().success());
+}